@@ -0,0 +1,22 @@
+//! Records the git commit the binary is built from, for `version --verbose`.
+//! Packagers building from a tarball can set `RINGRING_GIT_HASH` instead.
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+    println!("cargo:rerun-if-env-changed=RINGRING_GIT_HASH");
+    if std::env::var_os("RINGRING_GIT_HASH").is_some() {
+        return;
+    }
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string());
+    if let Some(hash) = hash.filter(|h| !h.is_empty()) {
+        println!("cargo:rustc-env=RINGRING_GIT_HASH={hash}");
+    }
+}
@@ -0,0 +1,115 @@
+use rodio::{Decoder, Sink, Source};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, mpsc};
+
+/// Ambience volume multiplier while an alert is playing.
+const DUCK_FACTOR: f32 = 0.25;
+
+/// A looping track. The output stream lives on its own thread (it isn't
+/// `Send`); dropping `_stop` ends that thread and the playback.
+struct Track {
+    sink: Arc<Sink>,
+    volume: f32,
+    _stop: mpsc::Sender<()>,
+}
+
+/// Running ambience tracks by session id. Only the daemon ever fills this;
+/// hook processes exit long before a loop would be heard.
+static TRACKS: LazyLock<Mutex<HashMap<String, Track>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Number of alerts currently playing.
+static DUCKS: AtomicUsize = AtomicUsize::new(0);
+
+fn tracks() -> std::sync::MutexGuard<'static, HashMap<String, Track>> {
+    TRACKS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn effective_volume(volume: f32) -> f32 {
+    if DUCKS.load(Ordering::SeqCst) > 0 { volume * DUCK_FACTOR } else { volume }
+}
+
+/// Start looping `path` for a session, replacing any track it already has.
+pub fn start(session_id: &str, path: &Path, volume: f32) -> Result<(), Box<dyn std::error::Error>> {
+    let source = Decoder::new(BufReader::new(File::open(path)?))?.repeat_infinite();
+    let volume = volume.clamp(0.0, 1.0);
+
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let (stop_tx, stop_rx) = mpsc::channel::<()>();
+    std::thread::spawn(move || {
+        let mut stream = match rodio::OutputStreamBuilder::open_default_stream() {
+            Ok(stream) => stream,
+            Err(e) => {
+                let _ = ready_tx.send(Err(e.to_string()));
+                return;
+            }
+        };
+        stream.log_on_drop(false);
+        let sink = Arc::new(Sink::connect_new(stream.mixer()));
+        sink.set_volume(effective_volume(volume));
+        sink.append(source);
+        let _ = ready_tx.send(Ok(Arc::clone(&sink)));
+        // Returns once the Track (and its sender) is dropped
+        let _ = stop_rx.recv();
+        sink.stop();
+    });
+    let sink = ready_rx.recv()??;
+
+    tracks().insert(session_id.to_string(), Track { sink, volume, _stop: stop_tx });
+    Ok(())
+}
+
+/// Stop a session's ambience, if any. Returns whether one was playing.
+pub fn stop(session_id: &str) -> bool {
+    tracks().remove(session_id).is_some()
+}
+
+fn apply_volumes() {
+    for track in tracks().values() {
+        track.sink.set_volume(effective_volume(track.volume));
+    }
+}
+
+/// Lowers all ambience while held. Hold one around alert playback.
+pub struct Duck(());
+
+pub fn duck() -> Duck {
+    if DUCKS.fetch_add(1, Ordering::SeqCst) == 0 {
+        apply_volumes();
+    }
+    Duck(())
+}
+
+impl Drop for Duck {
+    fn drop(&mut self) {
+        if DUCKS.fetch_sub(1, Ordering::SeqCst) == 1 {
+            apply_volumes();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ducking_nests() {
+        assert_eq!(effective_volume(0.4), 0.4);
+        let outer = duck();
+        let inner = duck();
+        assert_eq!(effective_volume(0.4), 0.4 * DUCK_FACTOR);
+        drop(inner);
+        assert_eq!(effective_volume(0.4), 0.4 * DUCK_FACTOR);
+        drop(outer);
+        assert_eq!(effective_volume(0.4), 0.4);
+    }
+
+    #[test]
+    fn missing_file_does_not_start() {
+        assert!(start("s1", Path::new("/nonexistent/amb.ogg"), 0.1).is_err());
+        assert!(!stop("s1"));
+    }
+}
@@ -0,0 +1,157 @@
+//! Spoken project names: with several sessions running, say the project's
+//! name (text-to-speech) before its sound, so the sound alone tells which
+//! one finished.
+
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::audio::{Clip, Player};
+use crate::session;
+
+/// Speech programs tried in order when `command` isn't set. Each blocks
+/// until it has finished speaking.
+const SPEAKERS: [&str; 4] = ["spd-say --wait", "espeak-ng", "espeak", "say"];
+
+/// Sessions seen within this many seconds count as active, so one whose
+/// SessionEnd never arrived stops counting eventually.
+const ACTIVE_WITHIN: u64 = 60 * 60;
+
+/// config.json `announce`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnnounceConfig {
+    /// Speech command; `{text}` is replaced with the project name, which is
+    /// otherwise appended. Defaults to the first of spd-say, espeak-ng,
+    /// espeak and say found on PATH.
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Fewest active sessions (this one included) before names are spoken.
+    #[serde(default = "default_min_sessions")]
+    pub min_sessions: usize,
+}
+
+fn default_min_sessions() -> usize {
+    2
+}
+
+/// A prepared speech command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Speech {
+    program: String,
+    args: Vec<String>,
+}
+
+impl Speech {
+    /// `command` with `text` filled in. `None` when the command is empty.
+    fn new(command: &str, text: &str) -> Option<Self> {
+        let mut words = command.split_whitespace();
+        let program = words.next()?.to_string();
+        let mut args: Vec<String> = words.map(|w| w.replace("{text}", text)).collect();
+        if !command.contains("{text}") {
+            args.push(text.to_string());
+        }
+        Some(Self { program, args })
+    }
+
+    fn speak(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let status = std::process::Command::new(&self.program)
+            .args(&self.args)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()?;
+        if !status.success() {
+            return Err(format!("{} exited with {status}", self.program).into());
+        }
+        Ok(())
+    }
+}
+
+/// The announcement for `project`, if `announce` is configured, enough
+/// sessions are active and a speech program is available.
+pub fn for_project(cfg: &AnnounceConfig, project: &str) -> Option<Speech> {
+    if project.is_empty() || session::active_count(ACTIVE_WITHIN) < cfg.min_sessions {
+        return None;
+    }
+    let command = match &cfg.command {
+        Some(command) => command.clone(),
+        None => SPEAKERS.iter().find(|cmd| on_path(cmd.split(' ').next().unwrap_or(cmd)))?.to_string(),
+    };
+    Speech::new(&command, project)
+}
+
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|path| std::env::split_paths(&path).any(|dir| is_executable(&dir.join(program))))
+}
+
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+/// Speaks before handing the clips to `inner`. Runs wherever the sound
+/// does, so in hook mode the forked player process waits for the speech,
+/// not the hook.
+pub struct Announced<'a> {
+    pub speech: Speech,
+    pub inner: &'a dyn Player,
+}
+
+impl Player for Announced<'_> {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn play(&self, clip: Clip, volume: f32) -> Result<(), Box<dyn std::error::Error>> {
+        self.play_sequence(&[clip], volume)
+    }
+
+    fn play_sequence(&self, clips: &[Clip], volume: f32) -> Result<(), Box<dyn std::error::Error>> {
+        if let Err(e) = self.speech.speak() {
+            crate::log::write(&format!("announce: {e}"));
+        }
+        self.inner.play_sequence(clips, volume)
+    }
+
+    fn needs_device(&self) -> bool {
+        self.inner.needs_device()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_is_substituted_or_appended() {
+        let speech = Speech::new("espeak-ng -s 170", "dotfiles").unwrap();
+        assert_eq!(speech.program, "espeak-ng");
+        assert_eq!(speech.args, ["-s", "170", "dotfiles"]);
+        let speech = Speech::new("say -v Samantha {text} now", "api").unwrap();
+        assert_eq!(speech.args, ["-v", "Samantha", "api", "now"]);
+        assert!(Speech::new("  ", "api").is_none());
+    }
+
+    #[test]
+    fn speaks_before_playing() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("order");
+        let speech = Speech::new(&format!("sh -c {{text}}>>{}", out.display()), "echo").unwrap();
+        // sh -c 'echo>>file' appends an empty line; the player appends "played"
+        struct Recorder<'a>(&'a Path);
+        impl Player for Recorder<'_> {
+            fn name(&self) -> &'static str {
+                "recorder"
+            }
+            fn play(&self, _clip: Clip, _volume: f32) -> Result<(), Box<dyn std::error::Error>> {
+                use std::io::Write;
+                let mut file = std::fs::OpenOptions::new().append(true).create(true).open(self.0)?;
+                Ok(writeln!(file, "played")?)
+            }
+        }
+        let recorder = Recorder(&out);
+        let announced = Announced { speech, inner: &recorder };
+        announced.play(Clip::File(Path::new("x.wav")), 1.0).unwrap();
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "\nplayed\n");
+        assert_eq!(announced.name(), "recorder");
+    }
+}
@@ -1,39 +1,353 @@
-use rodio::{Decoder, Sink};
+use rodio::{Decoder, Sink, Source};
 use std::fs::File;
-use std::io::BufReader;
-use std::path::Path;
+use std::io::{BufReader, Cursor};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
-/// Play a sound file in a detached background process so the caller can exit
-/// immediately. The child process lives only as long as the audio lasts.
-pub fn play_sound(path: &Path, volume: f32) -> Result<(), Box<dyn std::error::Error>> {
+use crate::config::Config;
+use crate::limiter::Limited;
+use crate::sandbox::{self, Limits};
+use crate::{log, paths};
+
+static IN_PROCESS: AtomicBool = AtomicBool::new(false);
+
+/// Make `play` block in the calling thread instead of forking. Used by the
+/// long-running daemon, which waits for playback itself on shutdown.
+pub fn set_in_process(enabled: bool) {
+    IN_PROCESS.store(enabled, Ordering::SeqCst);
+}
+
+/// Whether playback happens in this process (daemon mode).
+pub fn in_process() -> bool {
+    IN_PROCESS.load(Ordering::SeqCst)
+}
+
+/// A sound to play: a file on disk or bytes compiled into the binary.
+#[derive(Debug, Clone, Copy)]
+pub enum Clip<'a> {
+    File(&'a Path),
+    Embedded { name: &'a str, bytes: &'static [u8] },
+}
+
+impl std::fmt::Display for Clip<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Clip::File(path) => write!(f, "{}", path.display()),
+            Clip::Embedded { name, .. } => write!(f, "embedded:{name}"),
+        }
+    }
+}
+
+/// Something that can turn a clip into sound. `play` blocks until done.
+pub trait Player: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn play(&self, clip: Clip, volume: f32) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Play clips back to back.
+    fn play_sequence(&self, clips: &[Clip], volume: f32) -> Result<(), Box<dyn std::error::Error>> {
+        clips.iter().try_for_each(|clip| self.play(*clip, volume))
+    }
+
+    /// Whether `play` takes long enough that hook mode should fork first.
+    fn detach(&self) -> bool {
+        true
+    }
+
+    /// Whether playing needs a local audio output (skipped when none is found).
+    fn needs_device(&self) -> bool {
+        true
+    }
+}
+
+/// Pick the player from `RINGRING_PLAYER` or config `player`: `rodio`
+/// (default), `command` (runs `player_command`) or `null`.
+pub fn player(cfg: &Config) -> Box<dyn Player> {
+    let rodio = || Box::new(RodioPlayer { max_level: cfg.max_output_level, limits: cfg.playback_limits });
+    match cfg.player().as_str() {
+        "null" => Box::new(NullPlayer),
+        "command" => match cfg.player_command.as_deref().and_then(ExternalCommandPlayer::parse) {
+            Some(player) => Box::new(ExternalCommandPlayer { limits: cfg.playback_limits, ..player }),
+            None => {
+                log::write("player 'command' needs player_command; using rodio");
+                rodio()
+            }
+        },
+        _ => rodio(),
+    }
+}
+
+/// Player for one sound category: the `players` command for that category
+/// if configured, otherwise the global player.
+pub fn player_for(cfg: &Config, category: &str) -> Box<dyn Player> {
+    match cfg.players.get(category).and_then(|cmd| ExternalCommandPlayer::parse(cmd)) {
+        Some(player) => Box::new(ExternalCommandPlayer { limits: cfg.playback_limits, ..player }),
+        None => player(cfg),
+    }
+}
+
+/// Play in a detached background process so the caller can exit immediately.
+/// The child process lives only as long as the audio lasts.
+pub fn play(player: &dyn Player, clips: &[Clip], volume: f32) -> Result<(), Box<dyn std::error::Error>> {
+    if in_process() || !player.detach() {
+        return player.play_sequence(clips, volume);
+    }
     // Fork: parent returns immediately, child plays the sound and exits.
     match unsafe { libc::fork() } {
         -1 => Err("fork failed".into()),
         0 => {
             // Child — detach from parent's process group so we survive parent exit.
             unsafe { libc::setsid() };
-            let _ = play_blocking(path, volume);
+            let _ = player.play_sequence(clips, volume);
             std::process::exit(0);
         }
         _child_pid => Ok(()), // Parent — returns immediately.
     }
 }
 
-/// Play a sound file and block until playback completes.
-pub fn play_sound_blocking(path: &Path, volume: f32) -> Result<(), Box<dyn std::error::Error>> {
-    play_blocking(path, volume)
+/// Decodes and plays through the default output device with rodio.
+pub struct RodioPlayer {
+    /// Peak cap (0.0–1.0) applied after volume; `None` disables the limiter.
+    pub max_level: Option<f32>,
+    /// Resource limits applied while decoding theme files.
+    pub limits: Option<Limits>,
+}
+
+impl Player for RodioPlayer {
+    fn name(&self) -> &'static str {
+        "rodio"
+    }
+
+    fn play(&self, clip: Clip, volume: f32) -> Result<(), Box<dyn std::error::Error>> {
+        self.play_sequence(&[clip], volume)
+    }
+
+    /// All clips go into one sink, so they play without gaps.
+    fn play_sequence(&self, clips: &[Clip], volume: f32) -> Result<(), Box<dyn std::error::Error>> {
+        // Decode everything first so a bad file doesn't open the device
+        let sources = clips.iter().map(|clip| decode(*clip, self.limits.as_ref())).collect::<Result<Vec<_>, _>>()?;
+
+        let stream = rodio::OutputStreamBuilder::open_default_stream()?;
+        let sink = Sink::connect_new(stream.mixer());
+        let volume = volume.clamp(0.0, 1.0);
+        let Some(cap) = self.max_level else {
+            sink.set_volume(volume);
+            for source in sources {
+                sink.append(source);
+            }
+            sink.sleep_until_end();
+            return Ok(());
+        };
+
+        // The limiter must see the final level, so apply volume before it
+        let mut stats = Vec::new();
+        for source in sources {
+            let (limited, s) = Limited::new(source.amplify(volume), cap);
+            sink.append(limited);
+            stats.push(s);
+        }
+        sink.sleep_until_end();
+        for (clip, s) in clips.iter().zip(&stats) {
+            if s.limited() > 0 {
+                log::write(&format!(
+                    "limiter: {clip} peaked at {:.2}, capped to {cap:.2} ({} samples)",
+                    s.peak(),
+                    s.limited()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Embedded clips are trusted; files are opened within `limits` when set.
+fn decode(clip: Clip, limits: Option<&Limits>) -> Result<Box<dyn Source + Send>, Box<dyn std::error::Error>> {
+    Ok(match clip {
+        Clip::File(path) if let Some(limits) = limits => sandbox::open(path, limits)?,
+        Clip::File(path) => Box::new(Decoder::new(BufReader::new(File::open(path)?))?),
+        Clip::Embedded { bytes, .. } => Box::new(Decoder::new(Cursor::new(bytes))?),
+    })
 }
 
-fn play_blocking(path: &Path, volume: f32) -> Result<(), Box<dyn std::error::Error>> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    let source = Decoder::try_from(reader)?;
+/// Runs an external program (`paplay`, `afplay`, `mpv --no-video`, ...).
+/// `{file}` and `{volume}` (0.0–1.0) in the arguments are substituted; without
+/// `{file}` the sound file is appended as the last argument. The volume is
+/// also passed in `RINGRING_VOLUME`.
+pub struct ExternalCommandPlayer {
+    program: String,
+    args: Vec<String>,
+    /// Only the type and size limits apply; the program does its own decoding.
+    limits: Option<Limits>,
+}
+
+impl ExternalCommandPlayer {
+    /// Split a command template on whitespace. `None` when empty.
+    pub fn parse(command: &str) -> Option<Self> {
+        let mut words = command.split_whitespace().map(str::to_string);
+        Some(Self { program: words.next()?, args: words.collect(), limits: None })
+    }
 
-    let stream = rodio::OutputStreamBuilder::open_default_stream()?;
+    fn command_args(&self, file: &str, volume: &str) -> Vec<String> {
+        let mut args: Vec<String> = self
+            .args
+            .iter()
+            .map(|a| a.replace("{file}", file).replace("{volume}", volume))
+            .collect();
+        if !self.args.iter().any(|a| a.contains("{file}")) {
+            args.push(file.to_string());
+        }
+        args
+    }
+}
+
+impl Player for ExternalCommandPlayer {
+    fn name(&self) -> &'static str {
+        "command"
+    }
+
+    fn play(&self, clip: Clip, volume: f32) -> Result<(), Box<dyn std::error::Error>> {
+        let path = match clip {
+            Clip::File(path) => {
+                if let Some(limits) = &self.limits {
+                    sandbox::check_file(path, limits)?;
+                }
+                path.to_path_buf()
+            }
+            Clip::Embedded { name, bytes } => materialize(name, bytes)?,
+        };
+        let volume = format!("{:.2}", volume.clamp(0.0, 1.0));
+        let status = std::process::Command::new(&self.program)
+            .args(self.command_args(&path.to_string_lossy(), &volume))
+            .env("RINGRING_VOLUME", &volume)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()?;
+        if !status.success() {
+            return Err(format!("{} exited with {status}", self.program).into());
+        }
+        Ok(())
+    }
+
+    /// The command decides where the sound goes (local device, network, ...).
+    fn needs_device(&self) -> bool {
+        false
+    }
+}
+
+/// External players need a path; embedded clips are written once to the
+/// runtime dir (tmpfs, so this works with a read-only home).
+fn materialize(name: &str, bytes: &[u8]) -> std::io::Result<PathBuf> {
+    let path = paths::runtime_dir().join("embedded").join(name);
+    if std::fs::metadata(&path).is_ok_and(|m| m.len() == bytes.len() as u64) {
+        return Ok(path);
+    }
+    std::fs::create_dir_all(path.parent().unwrap_or(Path::new("/")))?;
+    std::fs::write(&path, bytes)?;
+    Ok(path)
+}
+
+/// Plays nothing; records each intended play in the log. For CI and
+/// machines without sound hardware.
+pub struct NullPlayer;
+
+impl Player for NullPlayer {
+    fn name(&self) -> &'static str {
+        "null"
+    }
+
+    fn play(&self, clip: Clip, volume: f32) -> Result<(), Box<dyn std::error::Error>> {
+        log::write(&format!("null player: {clip} (volume {volume:.2})"));
+        Ok(())
+    }
+
+    fn detach(&self) -> bool {
+        false
+    }
+
+    fn needs_device(&self) -> bool {
+        false
+    }
+}
+
+/// Held while `ringring test` previews sounds, so two previews don't play
+/// over each other. Released when the returned file is dropped.
+pub fn preview_lock() -> Result<File, Box<dyn std::error::Error>> {
+    use std::os::fd::AsRawFd;
+    let path = paths::runtime_dir().join("preview.lock");
+    std::fs::create_dir_all(path.parent().unwrap_or(Path::new("/")))?;
+    let file = std::fs::OpenOptions::new().create(true).truncate(false).write(true).open(&path)?;
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+        return Err("another `ringring test` is already playing".into());
+    }
+    Ok(file)
+}
+
+/// Open the default output device and play 20 ms of silence, to check that
+/// audio output works without making a sound.
+pub fn probe() -> Result<(), Box<dyn std::error::Error>> {
+    let mut stream = rodio::OutputStreamBuilder::open_default_stream()?;
+    stream.log_on_drop(false);
     let sink = Sink::connect_new(stream.mixer());
-    sink.set_volume(volume.clamp(0.0, 1.0));
-    sink.append(source);
+    sink.append(rodio::source::Zero::new(1, 44_100).take_duration(Duration::from_millis(20)));
     sink.sleep_until_end();
-
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn player_selection_follows_config() {
+        let cfg = |json: &str| serde_json::from_str::<Config>(json).unwrap();
+        assert_eq!(player(&Config::default()).name(), "rodio");
+        assert_eq!(player(&cfg(r#"{"player": "null"}"#)).name(), "null");
+        assert_eq!(player(&cfg(r#"{"player": "command", "player_command": "paplay"}"#)).name(), "command");
+        // No command configured: fall back rather than go silent
+        assert_eq!(player(&cfg(r#"{"player": "command"}"#)).name(), "rodio");
+    }
+
+    #[test]
+    fn parse_external_command() {
+        let p = ExternalCommandPlayer::parse("mpv --no-video --really-quiet").unwrap();
+        assert_eq!(p.program, "mpv");
+        assert_eq!(p.args, vec!["--no-video", "--really-quiet"]);
+        assert!(ExternalCommandPlayer::parse("  ").is_none());
+    }
+
+    #[test]
+    fn per_category_players_override_global() {
+        let cfg: Config = serde_json::from_str(
+            r#"{"player": "null", "players": {"complete": "mpv --no-video --volume={volume} {file}"}}"#,
+        )
+        .unwrap();
+        assert_eq!(player_for(&cfg, "complete").name(), "command");
+        assert_eq!(player_for(&cfg, "greeting").name(), "null");
+    }
+
+    #[test]
+    fn command_template_placeholders() {
+        let p = ExternalCommandPlayer::parse("mpv --no-video --volume={volume} {file} --end").unwrap();
+        assert_eq!(p.command_args("/s/a.wav", "0.50"), vec!["--no-video", "--volume=0.50", "/s/a.wav", "--end"]);
+        let p = ExternalCommandPlayer::parse("paplay").unwrap();
+        assert_eq!(p.command_args("/s/a.wav", "0.50"), vec!["/s/a.wav"]);
+    }
+
+    #[test]
+    fn external_command_receives_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let sound = dir.path().join("a.wav");
+        std::fs::write(&sound, b"RIFF").unwrap();
+        let ok = ExternalCommandPlayer::parse("test -f").unwrap();
+        assert!(ok.play(Clip::File(&sound), 1.0).is_ok());
+        assert!(ok.play(Clip::File(&dir.path().join("missing.wav")), 1.0).is_err());
+    }
+
+    #[test]
+    fn null_player_plays_in_place() {
+        assert!(!NullPlayer.detach());
+        assert!(play(&NullPlayer, &[Clip::Embedded { name: "x.wav", bytes: b"" }], 0.5).is_ok());
+    }
+}
@@ -0,0 +1,79 @@
+//! What this build supports, for `version --verbose` and for `doctor` to
+//! check installed themes and config.json against.
+
+use crate::manifest::Manifest;
+use crate::sandbox;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Commit the binary was built from (see build.rs), or "unknown".
+pub const GIT_HASH: &str = match option_env!("RINGRING_GIT_HASH") {
+    Some(hash) => hash,
+    None => "unknown",
+};
+
+/// Newest manifest.json `schema` this build understands.
+pub const MANIFEST_SCHEMA: u32 = 1;
+
+/// Newest config.json `schema` this build understands.
+pub const CONFIG_SCHEMA: u32 = 1;
+
+/// Sound file types the player decodes.
+pub const CODECS: &[&str] = sandbox::ALLOWED_EXTENSIONS;
+
+/// `player` values.
+pub const PLAYERS: [&str; 3] = ["rodio", "command", "null"];
+
+/// Notification backends compiled in.
+pub const NOTIFIERS: [&str; 7] = ["desktop", "terminal", "log", "haptic", "slack", "telegram", "webhook"];
+
+/// Cargo features this binary was built with.
+pub fn features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "async") {
+        features.push("async");
+    }
+    features
+}
+
+/// Why `manifest` won't fully work with this build: a newer schema, or
+/// sounds in a format the player can't decode.
+pub fn manifest_problems(manifest: &Manifest) -> Vec<String> {
+    let mut problems = Vec::new();
+    if manifest.schema > MANIFEST_SCHEMA {
+        problems.push(format!("manifest schema {} is newer than this build's {MANIFEST_SCHEMA}", manifest.schema));
+    }
+    let unsupported: Vec<&str> = manifest
+        .files()
+        .into_iter()
+        .filter(|file| {
+            let ext = std::path::Path::new(file).extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+            !CODECS.contains(&ext.as_str())
+        })
+        .collect();
+    if !unsupported.is_empty() {
+        problems.push(format!("no codec for {}", unsupported.join(", ")));
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newer_schemas_and_unknown_formats_are_flagged() {
+        let manifest = |schema: &str, file: &str| -> Manifest {
+            serde_json::from_str(&format!(
+                r#"{{"name": "t", "display_name": "T", {schema} "categories": {{"complete": {{"sounds": [{{"file": "{file}"}}]}}}}}}"#
+            ))
+            .unwrap()
+        };
+        assert!(manifest_problems(&manifest("", "done.ogg")).is_empty());
+        assert!(manifest_problems(&manifest(r#""schema": 1,"#, "done.FLAC")).is_empty());
+        assert_eq!(
+            manifest_problems(&manifest(r#""schema": 2,"#, "done.opus")),
+            ["manifest schema 2 is newer than this build's 1", "no codec for done.opus"]
+        );
+    }
+}
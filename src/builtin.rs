@@ -0,0 +1,184 @@
+use std::path::{Path, PathBuf};
+
+use crate::audio::{Clip, Player};
+use crate::manifest::Manifest;
+use crate::{audio, config};
+
+/// Name of the theme compiled into the binary.
+pub const NAME: &str = "earcon";
+
+static MANIFEST: &str = include_str!("../assets/earcon/manifest.json");
+
+static SOUNDS: &[(&str, &[u8])] = &[
+    ("greeting.wav", include_bytes!("../assets/earcon/sounds/greeting.wav")),
+    ("complete.wav", include_bytes!("../assets/earcon/sounds/complete.wav")),
+    ("permission.wav", include_bytes!("../assets/earcon/sounds/permission.wav")),
+    ("annoyed.wav", include_bytes!("../assets/earcon/sounds/annoyed.wav")),
+    ("acknowledge.wav", include_bytes!("../assets/earcon/sounds/acknowledge.wav")),
+    ("resource_limit.wav", include_bytes!("../assets/earcon/sounds/resource_limit.wav")),
+];
+
+pub fn manifest() -> Manifest {
+    serde_json::from_str(MANIFEST).expect("embedded earcon manifest is valid")
+}
+
+fn sound(file: &str) -> Option<&'static [u8]> {
+    SOUNDS.iter().find(|(name, _)| *name == file).map(|(_, bytes)| *bytes)
+}
+
+/// Where a theme's sound files live.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sounds {
+    /// `{theme_dir}/sounds/` on disk.
+    Dir(PathBuf),
+    /// Compiled into the binary; no filesystem access.
+    Embedded,
+}
+
+fn embedded(file: &str) -> Result<Clip<'_>, Box<dyn std::error::Error>> {
+    let bytes = sound(file).ok_or_else(|| format!("no embedded sound '{file}'"))?;
+    Ok(Clip::Embedded { name: file, bytes })
+}
+
+/// Resolved prefix/suffix sound files for the current theme.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Stingers {
+    pub prefix: Option<PathBuf>,
+    pub suffix: Option<PathBuf>,
+}
+
+impl Stingers {
+    /// The theme's stinger (relative to its sounds dir) wins over the global
+    /// one from config (relative to the data dir). Missing files are dropped.
+    pub fn resolve(sounds_dir: &Path, cfg: &config::Config, manifest: &Manifest, sounds: &Sounds) -> Self {
+        let pick = |theme: &Option<String>, global: &Option<String>| {
+            let path = match (theme, sounds) {
+                (Some(file), Sounds::Dir(dir)) => dir.join(file),
+                _ => sounds_dir.join(global.as_ref()?),
+            };
+            path.is_file().then_some(path)
+        };
+        Self {
+            prefix: pick(&manifest.stinger.prefix, &cfg.stinger.prefix),
+            suffix: pick(&manifest.stinger.suffix, &cfg.stinger.suffix),
+        }
+    }
+}
+
+impl Sounds {
+    /// Play `file` wrapped in the stingers, in the background (see `audio::play`).
+    pub fn play(&self, player: &dyn Player, file: &str, stingers: &Stingers, volume: f32) -> Result<(), Box<dyn std::error::Error>> {
+        let path;
+        let line = match self {
+            Sounds::Dir(dir) => {
+                path = dir.join(file);
+                Clip::File(&path)
+            }
+            Sounds::Embedded => embedded(file)?,
+        };
+        let clips: Vec<Clip> = stingers
+            .prefix
+            .as_deref()
+            .map(Clip::File)
+            .into_iter()
+            .chain([line])
+            .chain(stingers.suffix.as_deref().map(Clip::File))
+            .collect();
+        audio::play(player, &clips, volume)
+    }
+
+    pub fn play_blocking(&self, player: &dyn Player, file: &str, volume: f32) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Sounds::Dir(dir) => player.play(Clip::File(&dir.join(file)), volume),
+            Sounds::Embedded => player.play(embedded(file)?, volume),
+        }
+    }
+}
+
+/// Load a theme from the data dir, falling back to the embedded copy for the
+/// built-in theme. An installed theme of the same name takes precedence.
+pub fn load_theme(sounds_dir: &Path, theme: &str) -> Option<(Manifest, Sounds)> {
+    load_theme_with(sounds_dir, theme, Manifest::load)
+}
+
+/// `load_theme` reading only the split categories in `only`.
+pub fn load_theme_categories(sounds_dir: &Path, theme: &str, only: &[&str]) -> Option<(Manifest, Sounds)> {
+    load_theme_with(sounds_dir, theme, |dir| Manifest::load_categories(dir, only))
+}
+
+fn load_theme_with(sounds_dir: &Path, theme: &str, load: impl FnOnce(&Path) -> Option<Manifest>) -> Option<(Manifest, Sounds)> {
+    let theme_dir = config::theme_dir(sounds_dir, theme).ok()?;
+    if let Some(manifest) = load(&theme_dir) {
+        return Some((manifest, Sounds::Dir(theme_dir.join("sounds"))));
+    }
+    (theme == NAME).then(|| (manifest(), Sounds::Embedded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_manifest_sound_is_embedded() {
+        let manifest = manifest();
+        assert_eq!(manifest.name, NAME);
+        for category in manifest.categories.values() {
+            for s in &category.sounds {
+                let bytes = sound(&s.file).unwrap_or_else(|| panic!("{} not embedded", s.file));
+                assert!(bytes.starts_with(b"RIFF"));
+            }
+        }
+    }
+
+    #[test]
+    fn embedded_sounds_decode() {
+        for (name, bytes) in SOUNDS {
+            assert!(rodio::Decoder::new(std::io::Cursor::new(*bytes)).is_ok(), "{name}");
+        }
+    }
+
+    #[test]
+    fn installed_theme_overrides_builtin() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, sounds) = load_theme(dir.path(), NAME).unwrap();
+        assert_eq!(sounds, Sounds::Embedded);
+        assert!(load_theme(dir.path(), "peon").is_none());
+
+        let theme_dir = dir.path().join(NAME);
+        std::fs::create_dir_all(&theme_dir).unwrap();
+        std::fs::write(
+            theme_dir.join("manifest.json"),
+            r#"{"name": "earcon", "display_name": "Mine", "categories": {}}"#,
+        )
+        .unwrap();
+        let (manifest, sounds) = load_theme(dir.path(), NAME).unwrap();
+        assert_eq!(manifest.display_name, "Mine");
+        assert_eq!(sounds, Sounds::Dir(theme_dir.join("sounds")));
+    }
+
+    #[test]
+    fn theme_stinger_overrides_global() {
+        let dir = tempfile::tempdir().unwrap();
+        let theme_sounds = dir.path().join("peon/sounds");
+        std::fs::create_dir_all(&theme_sounds).unwrap();
+        for f in [dir.path().join("ping.wav"), dir.path().join("bye.wav"), theme_sounds.join("horn.wav")] {
+            std::fs::write(f, b"RIFF").unwrap();
+        }
+        let cfg: config::Config =
+            serde_json::from_str(r#"{"stinger": {"prefix": "ping.wav", "suffix": "bye.wav"}}"#).unwrap();
+        let manifest: Manifest = serde_json::from_str(
+            r#"{"name": "peon", "display_name": "Peon", "categories": {},
+                "stinger": {"prefix": "horn.wav", "suffix": "missing.wav"}}"#,
+        )
+        .unwrap();
+
+        let s = Stingers::resolve(dir.path(), &cfg, &manifest, &Sounds::Dir(theme_sounds.clone()));
+        assert_eq!(s.prefix, Some(theme_sounds.join("horn.wav")));
+        assert_eq!(s.suffix, None);
+
+        // The built-in theme has no files of its own, only the global stinger applies
+        let s = Stingers::resolve(dir.path(), &cfg, &manifest, &Sounds::Embedded);
+        assert_eq!(s.prefix, Some(dir.path().join("ping.wav")));
+        assert_eq!(s.suffix, Some(dir.path().join("bye.wav")));
+    }
+}
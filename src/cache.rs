@@ -0,0 +1,267 @@
+//! The daemon's parsed config.json and theme manifests, so the events it
+//! handles neither read nor parse them. A watcher thread compares the files'
+//! stamps every second and swaps in fresh copies. Readers never lock or
+//! touch the disk: they load the current `Arc` atomically, and a theme that
+//! isn't loaded yet is left to the watcher's next check.
+
+use arc_swap::ArcSwap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::builtin::{self, Sounds};
+use crate::config::Config;
+use crate::fastpath::Stamp;
+use crate::manifest::{self, Manifest};
+use crate::{degraded, log, paths};
+
+/// How often the watcher looks for changed files.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Lookups since the daemon started, for `daemon status`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Counts {
+    /// Themes served from memory.
+    pub hits: u64,
+    /// Themes asked for before they were loaded (e.g. installed since the
+    /// last check); the event went on without them.
+    pub misses: u64,
+    /// Times a changed config.json or manifest was picked up.
+    pub reloads: u64,
+}
+
+struct Settings {
+    config: Config,
+    degraded: Option<String>,
+    /// config.json and its includes, as of loading.
+    stamps: Vec<Option<Stamp>>,
+}
+
+struct Entry {
+    /// manifest.json and its split category files.
+    stamps: Vec<Option<Stamp>>,
+    /// `None` for a theme that isn't installed, so asking again is a hit.
+    theme: Option<(Manifest, Sounds)>,
+}
+
+pub struct Cache {
+    data_dir: PathBuf,
+    settings: ArcSwap<Settings>,
+    themes: ArcSwap<HashMap<String, Arc<Entry>>>,
+    /// Themes events missed, for the next refresh to load.
+    wanted: Sender<String>,
+    /// Held while refreshing, so the watcher and `daemon reload` don't
+    /// swap in copies built from each other's stale state.
+    refreshing: Mutex<Receiver<String>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    reloads: AtomicU64,
+}
+
+fn stamp(path: &Path) -> Option<Stamp> {
+    std::fs::metadata(path).ok().map(|meta| Stamp::of(&meta))
+}
+
+impl Cache {
+    /// Load config.json and every installed theme from `data_dir`.
+    pub fn new(data_dir: PathBuf) -> Arc<Self> {
+        let settings = Self::load_settings(&data_dir);
+        let (wanted, missed) = mpsc::channel();
+        let cache = Arc::new(Self {
+            data_dir,
+            settings: ArcSwap::from_pointee(settings),
+            themes: ArcSwap::default(),
+            wanted,
+            refreshing: Mutex::new(missed),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            reloads: AtomicU64::new(0),
+        });
+        cache.refresh();
+        cache
+    }
+
+    fn load_settings(data_dir: &Path) -> Settings {
+        let degraded = degraded::problem(data_dir);
+        let config = match degraded {
+            Some(_) => Config { headless: Some(true), ..Default::default() },
+            None => Config::load(data_dir),
+        };
+        let stamps = Self::config_files(data_dir, &config).iter().map(|p| stamp(p)).collect();
+        Settings { config, degraded, stamps }
+    }
+
+    /// config.json and the includes it names directly.
+    fn config_files(data_dir: &Path, config: &Config) -> Vec<PathBuf> {
+        std::iter::once(data_dir.join("config.json")).chain(config.include.iter().map(|p| data_dir.join(p))).collect()
+    }
+
+    /// The config, and why the data dir can't be read if it can't.
+    pub fn config(&self) -> (Config, Option<String>) {
+        let settings = self.settings.load();
+        (settings.config.clone(), settings.degraded.clone())
+    }
+
+    /// `builtin::load_theme`, from memory. A theme the watcher hasn't
+    /// loaded yet counts as not installed until its next check.
+    pub fn theme(&self, name: &str) -> Option<(Manifest, Sounds)> {
+        if let Some(entry) = self.themes.load().get(name) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return entry.theme.clone();
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let _ = self.wanted.send(name.to_string());
+        None
+    }
+
+    fn theme_stamps(&self, name: &str) -> Vec<Option<Stamp>> {
+        let dir = self.data_dir.join(name);
+        let split = manifest::split_categories(&dir).into_iter().map(|(_, path)| path);
+        std::iter::once(dir.join("manifest.json")).chain(split).map(|p| stamp(&p)).collect()
+    }
+
+    fn load_theme(&self, name: &str) -> Entry {
+        Entry { stamps: self.theme_stamps(name), theme: builtin::load_theme(&self.data_dir, name) }
+    }
+
+    pub fn counts(&self) -> Counts {
+        Counts {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            reloads: self.reloads.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Pick up changed, new and removed files, and load the themes events
+    /// missed since the last refresh.
+    pub fn refresh(&self) {
+        let missed = self.refreshing.lock().unwrap_or_else(|e| e.into_inner());
+        let current = self.settings.load_full();
+        let stamps: Vec<Option<Stamp>> = Self::config_files(&self.data_dir, &current.config).iter().map(|p| stamp(p)).collect();
+        if stamps != current.stamps || current.degraded.is_some() != degraded::problem(&self.data_dir).is_some() {
+            self.settings.store(Arc::new(Self::load_settings(&self.data_dir)));
+            self.reloads.fetch_add(1, Ordering::Relaxed);
+            log::write("daemon: config.json changed, reloaded");
+        }
+        self.refresh_themes(missed.try_iter());
+    }
+
+    /// Load installed themes that are new or whose manifest changed, and
+    /// the `missed` ones (remembered as not installed if they aren't), then
+    /// swap in the new set.
+    fn refresh_themes(&self, missed: impl Iterator<Item = String>) {
+        let current = self.themes.load_full();
+        let mut names: Vec<String> = std::fs::read_dir(&self.data_dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().join("manifest.json").is_file())
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .collect();
+        names.extend(current.keys().cloned());
+        names.extend(missed);
+        names.push(builtin::NAME.to_string());
+        names.sort_unstable();
+        names.dedup();
+
+        let mut themes = (*current).clone();
+        let mut changed = false;
+        for name in names {
+            let known = current.get(&name).map(|e| &e.stamps);
+            if known == Some(&self.theme_stamps(&name)) {
+                continue;
+            }
+            let entry = self.load_theme(&name);
+            if known.is_some() {
+                self.reloads.fetch_add(1, Ordering::Relaxed);
+                log::write(&format!("daemon: theme '{name}' changed, reloaded"));
+            }
+            themes.insert(name, Arc::new(entry));
+            changed = true;
+        }
+        if changed {
+            self.themes.store(Arc::new(themes));
+        }
+    }
+
+    /// Refresh every `WATCH_INTERVAL` on a background thread, mirroring the
+    /// counts to `counts_path()` for `daemon status`.
+    pub fn watch(self: &Arc<Self>) {
+        let cache = Arc::clone(self);
+        std::thread::spawn(move || {
+            let mut written = None;
+            loop {
+                std::thread::sleep(WATCH_INTERVAL);
+                cache.refresh();
+                let counts = cache.counts();
+                if written != Some(counts)
+                    && let Ok(json) = serde_json::to_string(&counts)
+                    && std::fs::write(counts_path(), json).is_ok()
+                {
+                    written = Some(counts);
+                }
+            }
+        });
+    }
+
+    /// Remove the counts file when the daemon stops.
+    pub fn retire(&self) {
+        let _ = std::fs::remove_file(counts_path());
+    }
+}
+
+fn counts_path() -> PathBuf {
+    paths::runtime_dir().join("daemon-cache.json")
+}
+
+/// Cache counts of the running daemon, as of its last second.
+pub fn load_counts() -> Option<Counts> {
+    serde_json::from_str(&std::fs::read_to_string(counts_path()).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_theme(dir: &Path, display_name: &str) {
+        std::fs::create_dir_all(dir.join("sounds")).unwrap();
+        let manifest = serde_json::json!({"name": "t", "display_name": display_name, "categories": {}});
+        std::fs::write(dir.join("manifest.json"), manifest.to_string()).unwrap();
+    }
+
+    #[test]
+    fn serves_from_memory_until_files_change() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("config.json"), r#"{"theme": "first"}"#).unwrap();
+        write_theme(&tmp.path().join("chime"), "Chime");
+        let cache = Cache::new(tmp.path().to_path_buf());
+
+        assert_eq!(cache.config().0.theme.as_deref(), Some("first"));
+        assert_eq!(cache.theme("chime").unwrap().0.display_name, "Chime");
+        assert!(cache.theme("earcon").is_some());
+        // Unknown names are left to the next refresh, which remembers them
+        assert!(cache.theme("nope").is_none());
+        cache.refresh();
+        assert!(cache.theme("nope").is_none());
+        assert_eq!(cache.counts(), Counts { hits: 3, misses: 1, reloads: 0 });
+
+        // Different sizes, so the stamps differ even within one mtime tick
+        std::fs::write(tmp.path().join("config.json"), r#"{"theme": "second"}  "#).unwrap();
+        write_theme(&tmp.path().join("chime"), "Chime 2");
+        write_theme(&tmp.path().join("nope"), "Nope");
+        assert_eq!(cache.config().0.theme.as_deref(), Some("first"));
+        cache.refresh();
+        assert_eq!(cache.config().0.theme.as_deref(), Some("second"));
+        assert_eq!(cache.theme("chime").unwrap().0.display_name, "Chime 2");
+        assert_eq!(cache.theme("nope").unwrap().0.display_name, "Nope");
+        assert_eq!(cache.counts().reloads, 3);
+
+        std::fs::remove_dir_all(tmp.path().join("chime")).unwrap();
+        cache.refresh();
+        assert!(cache.theme("chime").is_none());
+    }
+}
@@ -0,0 +1,202 @@
+//! Raw event capture (config `capture_events`): every hook payload, with its
+//! text redacted, appended to a JSONL file, and `events summarize` to spot
+//! event names and notification types ringring doesn't map yet. Config
+//! `record_events` keeps the payloads unredacted in a file of the user's
+//! choosing, for `replay <file>` to run through the pipeline again.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::{event, log, paths};
+
+/// Fields whose values say what kind of event it is rather than what it's
+/// about; kept as they are. Every other string is redacted.
+const KEPT: [&str; 6] = ["hook_event_name", "notification_type", "source", "reason", "trigger", "tool_name"];
+
+/// One line of the capture file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Captured {
+    pub timestamp: u64,
+    pub input: serde_json::Value,
+}
+
+pub fn capture_path() -> PathBuf {
+    paths::state_dir().join("capture.jsonl")
+}
+
+/// `raw` with the same shape, but every string outside `KEPT` replaced by
+/// its length, so captures can be shared without paths, prompts or ids.
+pub fn redact(raw: &serde_json::Value) -> serde_json::Value {
+    redact_field(None, raw)
+}
+
+fn redact_field(key: Option<&str>, value: &serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        Value::String(_) if key.is_some_and(|k| KEPT.contains(&k)) => value.clone(),
+        Value::String(s) => Value::String(format!("<{} chars>", s.chars().count())),
+        Value::Array(items) => Value::Array(items.iter().map(|v| redact_field(None, v)).collect()),
+        Value::Object(fields) => Value::Object(fields.iter().map(|(k, v)| (k.clone(), redact_field(Some(k), v))).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Append `raw`, redacted, to the capture file. Failures are ignored.
+pub fn append(raw: &serde_json::Value) {
+    let _ = append_to(&capture_path(), &Captured { timestamp: log::now_secs(), input: redact(raw) });
+}
+
+fn append_to(path: &Path, captured: &Captured) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let line = serde_json::to_string(captured)?;
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{line}")
+}
+
+/// Append `raw` as it is to `path` (config `record_events`). Failures are
+/// ignored.
+pub fn record(path: &Path, raw: &serde_json::Value) {
+    let _ = append_to(path, &Captured { timestamp: log::now_secs(), input: raw.clone() });
+}
+
+/// The payloads in a capture or record file, oldest first. A line may also
+/// be a bare hook payload, which gets timestamp 0. Lines that are neither
+/// are skipped.
+pub fn load_file(path: &Path) -> std::io::Result<Vec<Captured>> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(text
+        .lines()
+        .filter_map(|l| {
+            serde_json::from_str(l).ok().or_else(|| {
+                let input: serde_json::Value = serde_json::from_str(l).ok()?;
+                input.get("hook_event_name")?;
+                Some(Captured { timestamp: 0, input })
+            })
+        })
+        .collect())
+}
+
+/// Captured events, oldest first. Malformed lines are skipped.
+pub fn load() -> Vec<Captured> {
+    load_from(&capture_path())
+}
+
+fn load_from(path: &Path) -> Vec<Captured> {
+    std::fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect()
+}
+
+/// What `events summarize` reports.
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    pub total: usize,
+    /// First and last capture time.
+    pub span: Option<(u64, u64)>,
+    /// Hook event name → count.
+    pub events: BTreeMap<String, usize>,
+    /// Notification type → count.
+    pub notification_types: BTreeMap<String, usize>,
+}
+
+impl Summary {
+    /// Event names `map_event` has no mapping for.
+    pub fn new_events(&self) -> Vec<&str> {
+        self.events.keys().map(String::as_str).filter(|e| !event::KNOWN_EVENTS.contains(e)).collect()
+    }
+
+    /// Notification types `map_event` has no mapping for.
+    pub fn new_notification_types(&self) -> Vec<&str> {
+        self.notification_types
+            .keys()
+            .map(String::as_str)
+            .filter(|t| !event::KNOWN_NOTIFICATION_TYPES.contains(t))
+            .collect()
+    }
+}
+
+pub fn summarize(captured: &[Captured]) -> Summary {
+    let mut summary = Summary { total: captured.len(), ..Default::default() };
+    for c in captured {
+        let name = c.input["hook_event_name"].as_str().unwrap_or("(none)");
+        *summary.events.entry(name.to_string()).or_default() += 1;
+        if let Some(kind) = c.input["notification_type"].as_str() {
+            *summary.notification_types.entry(kind.to_string()).or_default() += 1;
+        }
+        let (first, last) = summary.span.unwrap_or((c.timestamp, c.timestamp));
+        summary.span = Some((first.min(c.timestamp), last.max(c.timestamp)));
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_text_but_keeps_the_shape() {
+        let raw = serde_json::json!({
+            "hook_event_name": "Notification",
+            "notification_type": "idle_prompt",
+            "message": "Claude is waiting",
+            "cwd": "/home/me/secret",
+            "stop_hook_active": false,
+            "tool_input": {"command": "ls", "tool_name": "Bash"},
+        });
+        assert_eq!(
+            redact(&raw),
+            serde_json::json!({
+                "hook_event_name": "Notification",
+                "notification_type": "idle_prompt",
+                "message": "<17 chars>",
+                "cwd": "<15 chars>",
+                "stop_hook_active": false,
+                "tool_input": {"command": "<2 chars>", "tool_name": "Bash"},
+            })
+        );
+    }
+
+    #[test]
+    fn summary_flags_unmapped_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capture.jsonl");
+        for (timestamp, input) in [
+            (10, serde_json::json!({"hook_event_name": "Stop"})),
+            (20, serde_json::json!({"hook_event_name": "Notification", "notification_type": "idle_prompt"})),
+            (30, serde_json::json!({"hook_event_name": "Notification", "notification_type": "tea_break"})),
+            (40, serde_json::json!({"hook_event_name": "PreCompact"})),
+        ] {
+            append_to(&path, &Captured { timestamp, input }).unwrap();
+        }
+
+        let summary = summarize(&load_from(&path));
+        assert_eq!(summary.total, 4);
+        assert_eq!(summary.span, Some((10, 40)));
+        assert_eq!(summary.events["Notification"], 2);
+        assert_eq!(summary.new_events(), ["PreCompact"]);
+        assert_eq!(summary.new_notification_types(), ["tea_break"]);
+    }
+
+    #[test]
+    fn recorded_payloads_load_with_bare_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("record.jsonl");
+        record(&path, &serde_json::json!({"hook_event_name": "Stop", "cwd": "/home/me/app"}));
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, r#"{{"hook_event_name": "Notification", "notification_type": "tea_break"}}"#).unwrap();
+        writeln!(file, "not json\n{{\"message\": \"no event name\"}}").unwrap();
+
+        let loaded = load_file(&path).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].input["cwd"], "/home/me/app");
+        assert!(loaded[0].timestamp > 0);
+        assert_eq!((loaded[1].timestamp, loaded[1].input["notification_type"].as_str()), (0, Some("tea_break")));
+        assert!(load_file(&dir.path().join("missing.jsonl")).is_err());
+    }
+}
@@ -0,0 +1,348 @@
+//! Command-line parsing for the `ringring` binary: a table of commands with
+//! their flags and positional arguments, `--help` text generated from it, and
+//! errors for anything that doesn't fit instead of silent guesses.
+
+use std::fmt;
+
+/// A (sub)command and everything it accepts.
+#[derive(Debug)]
+pub struct Command {
+    /// Words that select it, e.g. `["theme", "install"]`.
+    pub path: &'static [&'static str],
+    /// Positional arguments as shown in the usage line, e.g. `"<source>"`.
+    pub args: &'static str,
+    /// Most positional arguments it takes.
+    pub max_args: usize,
+    pub about: &'static str,
+    pub flags: &'static [Flag],
+}
+
+/// A `--long` (and optionally `-s`) option, with a value when `value` names one.
+#[derive(Debug)]
+pub struct Flag {
+    pub long: &'static str,
+    pub short: Option<char>,
+    pub value: Option<&'static str>,
+    pub help: &'static str,
+}
+
+impl Flag {
+    /// A switch without a value.
+    pub const fn switch(long: &'static str, help: &'static str) -> Self {
+        Self { long, short: None, value: None, help }
+    }
+
+    /// An option taking the value shown as `<value>` in help.
+    pub const fn value(long: &'static str, value: &'static str, help: &'static str) -> Self {
+        Self { long, short: None, value: Some(value), help }
+    }
+
+    pub const fn short(self, short: char) -> Self {
+        Self { short: Some(short), ..self }
+    }
+
+    fn usage(&self) -> String {
+        let short = self.short.map(|s| format!("-{s}, ")).unwrap_or_default();
+        match self.value {
+            Some(value) => format!("{short}--{} <{value}>", self.long),
+            None => format!("{short}--{}", self.long),
+        }
+    }
+}
+
+/// What the command line asks for.
+#[derive(Debug)]
+pub enum Parsed<'a> {
+    /// No command: handle a hook event from stdin.
+    Hook,
+    /// `--help` for one command, or for the program when `None`.
+    Help(Option<&'a Command>),
+    Version,
+    Run(Matches<'a>),
+}
+
+/// A command with the flags and arguments it was given.
+#[derive(Debug)]
+pub struct Matches<'a> {
+    pub command: &'a Command,
+    pub args: Vec<String>,
+    values: Vec<(&'static str, Option<String>)>,
+}
+
+impl Matches<'_> {
+    pub fn is(&self, path: &[&str]) -> bool {
+        self.command.path == path
+    }
+
+    /// Whether `--long` was given.
+    pub fn flag(&self, long: &str) -> bool {
+        self.values.iter().any(|(name, _)| *name == long)
+    }
+
+    /// Value of the last `--long <value>`.
+    pub fn value(&self, long: &str) -> Option<String> {
+        self.values.iter().rev().find(|(name, _)| *name == long).and_then(|(_, value)| value.clone())
+    }
+
+    /// Values of every `--long <value>`, in order.
+    pub fn values(&self, long: &str) -> Vec<String> {
+        self.values.iter().filter(|(name, _)| *name == long).filter_map(|(_, value)| value.clone()).collect()
+    }
+
+    /// The `i`th positional argument.
+    pub fn arg(&self, i: usize) -> Option<String> {
+        self.args.get(i).cloned()
+    }
+
+    /// Value of `--long` parsed as a number.
+    pub fn number<T: std::str::FromStr>(&self, long: &str) -> Result<Option<T>, Error> {
+        self.value(long)
+            .map(|v| v.parse().map_err(|_| self.error(format!("--{long} expects a number, got '{v}'"))))
+            .transpose()
+    }
+
+    /// An error about this command's arguments.
+    pub fn error(&self, message: String) -> Error {
+        Error::new(message, Some(self.command))
+    }
+}
+
+/// A command line that doesn't fit the table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub message: String,
+    /// Usage line of the command involved, if one was recognized.
+    pub usage: Option<String>,
+}
+
+impl Error {
+    fn new(message: String, command: Option<&Command>) -> Self {
+        Self { message, usage: command.map(usage_line) }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Match `args` (without the program name) against `commands`.
+pub fn parse<'a>(commands: &'a [Command], args: &[String]) -> Result<Parsed<'a>, Error> {
+    let Some(first) = args.first() else {
+        return Ok(Parsed::Hook);
+    };
+    match first.as_str() {
+        "-h" | "--help" => return Ok(Parsed::Help(None)),
+        "-V" | "--version" => return Ok(Parsed::Version),
+        "help" => return help_for(commands, &args[1..]),
+        _ => {}
+    }
+
+    let words = args.iter().take_while(|a| !a.starts_with('-')).count();
+    let Some(command) = commands
+        .iter()
+        .filter(|c| c.path.len() <= words && c.path.iter().zip(args).all(|(w, a)| w == a))
+        .max_by_key(|c| c.path.len())
+    else {
+        return Err(unknown_command(commands, args));
+    };
+
+    let mut matches = Matches { command, args: Vec::new(), values: Vec::new() };
+    let mut rest = args[command.path.len()..].iter();
+    while let Some(arg) = rest.next() {
+        if arg == "--" {
+            matches.args.extend(rest.by_ref().cloned());
+            break;
+        }
+        if arg == "-h" || arg == "--help" {
+            return Ok(Parsed::Help(Some(command)));
+        }
+        let flag = if let Some(long) = arg.strip_prefix("--") {
+            let (name, inline) = match long.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (long, None),
+            };
+            let flag = command.flags.iter().find(|f| f.long == name);
+            flag.map(|f| (f, inline))
+        } else if let Some(short) = arg.strip_prefix('-').filter(|s| s.chars().count() == 1) {
+            let flag = command.flags.iter().find(|f| f.short.is_some_and(|c| short.starts_with(c)));
+            flag.map(|f| (f, None))
+        } else {
+            matches.args.push(arg.clone());
+            continue;
+        };
+        let Some((flag, inline)) = flag else {
+            return Err(Error::new(format!("unknown flag '{arg}' for '{}'", command.path.join(" ")), Some(command)));
+        };
+        let value = match (flag.value, inline) {
+            (None, Some(_)) => {
+                return Err(Error::new(format!("--{} doesn't take a value", flag.long), Some(command)));
+            }
+            (None, None) => None,
+            (Some(_), Some(value)) => Some(value),
+            (Some(name), None) => match rest.next() {
+                Some(value) => Some(value.clone()),
+                None => return Err(Error::new(format!("--{} needs a value <{name}>", flag.long), Some(command))),
+            },
+        };
+        matches.values.push((flag.long, value));
+    }
+
+    if let Some(extra) = matches.args.get(command.max_args) {
+        return Err(Error::new(format!("unexpected argument '{extra}' for '{}'", command.path.join(" ")), Some(command)));
+    }
+    Ok(Parsed::Run(matches))
+}
+
+/// `help [<command>...]`
+fn help_for<'a>(commands: &'a [Command], words: &[String]) -> Result<Parsed<'a>, Error> {
+    if words.is_empty() {
+        return Ok(Parsed::Help(None));
+    }
+    match commands.iter().find(|c| c.path.iter().eq(words.iter())) {
+        Some(command) => Ok(Parsed::Help(Some(command))),
+        None => Err(unknown_command(commands, words)),
+    }
+}
+
+fn unknown_command(commands: &[Command], args: &[String]) -> Error {
+    let first = &args[0];
+    if first.starts_with('-') {
+        return Error::new(format!("unexpected argument '{first}'"), None);
+    }
+    let subcommands: Vec<&str> =
+        commands.iter().filter(|c| c.path.len() > 1 && c.path[0] == first).map(|c| c.path[1]).collect();
+    if subcommands.is_empty() {
+        return Error::new(format!("unknown command '{first}'"), None);
+    }
+    match args.get(1) {
+        Some(sub) if !sub.starts_with('-') => {
+            Error::new(format!("unknown command '{first} {sub}' (expected one of: {})", subcommands.join(", ")), None)
+        }
+        _ => Error::new(format!("'{first}' needs a command: {}", subcommands.join(", ")), None),
+    }
+}
+
+fn usage_line(command: &Command) -> String {
+    let mut usage = format!("ringring {}", command.path.join(" "));
+    if !command.flags.is_empty() {
+        usage.push_str(" [options]");
+    }
+    if !command.args.is_empty() {
+        usage.push(' ');
+        usage.push_str(command.args);
+    }
+    usage
+}
+
+/// `--help` for one command.
+pub fn command_help(command: &Command) -> String {
+    let mut out = format!("{}\n\nUsage: {}\n", command.about, usage_line(command));
+    if !command.flags.is_empty() {
+        out.push_str("\nOptions:\n");
+        let rows: Vec<(String, &str)> = command.flags.iter().map(|f| (f.usage(), f.help)).collect();
+        push_rows(&mut out, &rows);
+    }
+    out
+}
+
+/// `--help` for the whole program: `intro`, the commands, then `globals`.
+pub fn program_help(commands: &[Command], intro: &str, globals: &[Flag]) -> String {
+    let mut out = format!("{intro}\n\nCommands:\n");
+    let rows: Vec<(String, &str)> = commands
+        .iter()
+        .map(|c| (format!("{} {}", c.path.join(" "), c.args).trim_end().to_string(), c.about))
+        .collect();
+    push_rows(&mut out, &rows);
+    out.push_str("\nOptions:\n");
+    let rows: Vec<(String, &str)> = globals.iter().map(|f| (f.usage(), f.help)).collect();
+    push_rows(&mut out, &rows);
+    out
+}
+
+fn push_rows(out: &mut String, rows: &[(String, &str)]) {
+    let width = rows.iter().map(|(left, _)| left.chars().count()).max().unwrap_or(0);
+    for (left, help) in rows {
+        out.push_str(&format!("  {left:<width$}  {help}\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMANDS: &[Command] = &[
+        Command { path: &["list"], args: "", max_args: 0, about: "List themes", flags: &[Flag::switch("debug", "More")] },
+        Command {
+            path: &["theme", "pack"],
+            args: "<dir>",
+            max_args: 1,
+            about: "Pack a theme",
+            flags: &[Flag::value("output", "zip", "Where to write it").short('o'), Flag::switch("force", "Overwrite")],
+        },
+        Command { path: &["theme", "check"], args: "<theme>", max_args: 1, about: "Check a theme", flags: &[] },
+    ];
+
+    fn parse_words(line: &str) -> Result<Parsed<'static>, Error> {
+        let args: Vec<String> = line.split_whitespace().map(String::from).collect();
+        parse(COMMANDS, &args)
+    }
+
+    fn run(line: &str) -> Matches<'static> {
+        match parse_words(line) {
+            Ok(Parsed::Run(matches)) => matches,
+            other => panic!("{line}: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn flags_and_arguments_in_any_order() {
+        let m = run("theme pack -o out.zip ./dir --force");
+        assert!(m.is(&["theme", "pack"]));
+        assert_eq!(m.value("output").as_deref(), Some("out.zip"));
+        assert!(m.flag("force"));
+        assert_eq!(m.args, ["./dir"]);
+
+        let m = run("theme pack --output=a.zip -- --odd-dir");
+        assert_eq!(m.value("output").as_deref(), Some("a.zip"));
+        assert_eq!(m.args, ["--odd-dir"]);
+        assert!(!m.flag("force"));
+
+        let m = run("theme pack -o a.zip --output b.zip dir");
+        assert_eq!(m.value("output").as_deref(), Some("b.zip"));
+        assert_eq!(m.values("output"), ["a.zip", "b.zip"]);
+    }
+
+    #[test]
+    fn mistakes_are_errors() {
+        let message = |line: &str| parse_words(line).unwrap_err().message;
+        assert_eq!(message("lst"), "unknown command 'lst'");
+        assert_eq!(message("theme"), "'theme' needs a command: pack, check");
+        assert_eq!(message("theme fetch x"), "unknown command 'theme fetch' (expected one of: pack, check)");
+        assert_eq!(message("list --verbose"), "unknown flag '--verbose' for 'list'");
+        assert_eq!(message("list extra"), "unexpected argument 'extra' for 'list'");
+        assert_eq!(message("theme pack --output"), "--output needs a value <zip>");
+        assert_eq!(message("list --debug=yes"), "--debug doesn't take a value");
+        assert_eq!(message("--bogus"), "unexpected argument '--bogus'");
+        assert_eq!(parse_words("list --verbose").unwrap_err().usage.as_deref(), Some("ringring list [options]"));
+    }
+
+    #[test]
+    fn bare_invocation_help_and_version() {
+        assert!(matches!(parse_words(""), Ok(Parsed::Hook)));
+        assert!(matches!(parse_words("--version"), Ok(Parsed::Version)));
+        assert!(matches!(parse_words("-h"), Ok(Parsed::Help(None))));
+        let Ok(Parsed::Help(Some(command))) = parse_words("theme pack --help") else { panic!("no help") };
+        let help = command_help(command);
+        assert!(help.starts_with("Pack a theme\n\nUsage: ringring theme pack [options] <dir>\n"));
+        assert!(help.contains("  -o, --output <zip>  Where to write it\n"));
+        assert!(matches!(parse_words("help theme check"), Ok(Parsed::Help(Some(c))) if c.about == "Check a theme"));
+
+        let help = program_help(COMMANDS, "ringring", &[Flag::switch("no-color", "Plain output")]);
+        assert!(help.contains("  theme pack <dir>     Pack a theme\n  theme check <theme>  Check a theme\n"));
+        assert!(help.ends_with("  --no-color  Plain output\n"));
+    }
+}
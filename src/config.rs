@@ -3,26 +3,363 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Default)]
 pub struct Config {
+    /// config.json format version, for `doctor` (see `buildinfo::CONFIG_SCHEMA`).
+    #[serde(default)]
+    pub schema: Option<u32>,
+    /// Config files merged in before this one (relative to the data dir),
+    /// e.g. `["work.json", "peon/config.json"]`; see `load_merged`.
+    #[serde(default)]
+    pub include: Vec<String>,
     #[serde(default)]
     pub mode: Option<String>,
     #[serde(default)]
     pub theme: Option<String>,
     #[serde(default)]
     pub random_pool: Vec<String>,
+    /// How recently another session must have been active in the same
+    /// terminal and directory for a new one to keep its `random` theme
+    /// (default 30m; "0" turns it off).
+    #[serde(default)]
+    pub sticky_theme_window: Option<String>,
+    #[serde(default)]
+    pub workspaces: HashMap<String, Pin>,
+    /// Application id used for the desktop entry, icon name and GTK notifications.
+    #[serde(default)]
+    pub app_id: Option<String>,
+    /// Application name shown by the notification daemon.
+    #[serde(default)]
+    pub app_name: Option<String>,
+    /// Path to a custom notification icon (PNG).
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Global audio switch. Overridden by `CLAUDE_SOUND_ENABLED`.
+    #[serde(default)]
+    pub sound_enabled: Option<bool>,
+    /// Global desktop notification switch. Overridden by `CLAUDE_NOTIFICATIONS_ENABLED`.
+    #[serde(default)]
+    pub notifications_enabled: Option<bool>,
+    /// Force headless (`true`) or desktop (`false`) mode; auto-detected when unset.
+    #[serde(default)]
+    pub headless: Option<bool>,
+    /// Desktop notification API on Linux; `auto` uses GTK only on GNOME.
+    #[serde(default)]
+    pub notification_backend: crate::notify::DesktopBackend,
+    /// Remote notification backends, used in addition to the local ones.
+    #[serde(default)]
+    pub remote: RemoteConfig,
+    /// Shared secret for `ringring forward` / `ringring daemon`. Overridden by `RINGRING_TOKEN`.
+    #[serde(default)]
+    pub forward_token: Option<String>,
+    /// Append every hook payload, redacted, to the capture file for
+    /// `events summarize`.
+    #[serde(default)]
+    pub capture_events: bool,
+    /// Append every hook payload as received, unredacted, to this JSONL
+    /// file (relative to the data dir) for `replay <file>`.
+    #[serde(default)]
+    pub record_events: Option<String>,
+    /// Most hook processes handling events at once (default 8, 0 for no
+    /// limit); the rest only record their event to history.
+    #[serde(default)]
+    pub max_processes: Option<usize>,
+    /// Per hook event switch (e.g. `{"Notification": false}`); unlisted events are on.
+    #[serde(default)]
+    pub events: HashMap<String, bool>,
+    /// Per hook event settings.json matcher written by `install`, e.g.
+    /// `{"PostToolUse": "Bash"}`; a listed event is registered even if
+    /// ringring doesn't hook it by default.
+    #[serde(default)]
+    pub hook_matchers: HashMap<String, String>,
+    /// Audio backend: `rodio` (default), `command` or `null`. Overridden by `RINGRING_PLAYER`.
+    #[serde(default)]
+    pub player: Option<String>,
+    /// Command line for the `command` player; the sound file is appended.
+    #[serde(default)]
+    pub player_command: Option<String>,
+    /// Per category external command templates (`{file}`, `{volume}`), used instead of `player`.
+    #[serde(default)]
+    pub players: HashMap<String, String>,
+    /// Command that focuses a session's terminal when its notification is
+    /// clicked (`{window}` = X11 window id); default is xdotool.
+    #[serde(default)]
+    pub focus_command: Option<String>,
+    /// Global prefix/suffix sounds (paths, relative ones to the data dir); a theme's own stinger wins.
+    #[serde(default)]
+    pub stinger: crate::manifest::Stinger,
+    /// Per category title prefix, e.g. `{"complete": "✅"}`.
+    #[serde(default)]
+    pub badges: HashMap<String, String>,
+    /// Longest notification body (in characters) for backends without a `body_limits` entry.
+    #[serde(default)]
+    pub max_body_length: Option<usize>,
+    /// Per notification backend body limits (`desktop`, `slack`, ...).
+    #[serde(default)]
+    pub body_limits: HashMap<String, usize>,
+    /// Hard cap on output peaks (0.0–1.0) for the built-in player; unset means no limiter.
+    #[serde(default)]
+    pub max_output_level: Option<f32>,
+    /// Resource limits for theme sounds (size, type, length, decode time); unset means none.
+    #[serde(default)]
+    pub playback_limits: Option<crate::sandbox::Limits>,
+    /// Where `theme publish` sends themes.
+    #[serde(default)]
+    pub registry: crate::publish::RegistryConfig,
+    /// Which notification backends get events from which workspaces and categories.
+    #[serde(default)]
+    pub routes: Vec<crate::routes::Route>,
+    /// Daemon event queue size and drop policy per category.
+    #[serde(default)]
+    pub daemon_queue: crate::queue::QueueConfig,
+    /// Per category quiet period after an event, e.g. `{"annoyed": "5m"}`.
+    #[serde(default)]
+    pub cooldowns: HashMap<String, String>,
+    /// Per category probability (0.0–1.0) that an event plays its sound,
+    /// e.g. `{"complete": 0.3}`; replaces the theme's category `chance`.
+    #[serde(default)]
+    pub chances: HashMap<String, f32>,
+    /// Master volume, 0–100 (default 100); scales every theme's own volume.
+    #[serde(default)]
+    pub volume: Option<u8>,
+    /// Per theme volume, 0–100, on top of `volume`, e.g. `{"peon": 40}` to
+    /// tame a theme mastered louder than the rest.
+    #[serde(default)]
+    pub theme_volumes: HashMap<String, u8>,
+    /// `meta` alerts when a category fires too often, e.g. 10 `permission` events in `15m`.
+    #[serde(default)]
+    pub thresholds: Vec<crate::threshold::Threshold>,
+    /// Celebrate the first and every so many completed tasks of the day.
+    #[serde(default)]
+    pub milestones: Option<crate::milestone::MilestoneConfig>,
+    /// Force-feedback rumble per category on an evdev device.
     #[serde(default)]
-    pub workspaces: HashMap<String, String>,
+    pub haptic: Option<crate::haptic::HapticConfig>,
+    /// Flash a Home Assistant or Hue light in a per category color.
+    #[serde(default)]
+    pub light: Option<crate::light::LightConfig>,
+    /// Color a Stream Deck key per category.
+    #[serde(default)]
+    pub streamdeck: Option<crate::controller::StreamDeckConfig>,
+    /// Light a MIDI pad per category.
+    #[serde(default)]
+    pub midi: Option<crate::controller::MidiConfig>,
+    /// Speak the project name before its sound while several sessions run.
+    #[serde(default)]
+    pub announce: Option<crate::announce::AnnounceConfig>,
+}
+
+/// A `workspaces` entry: a theme name, or `{"theme": "x", "quiet": true}`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Pin {
+    Theme(String),
+    Detailed {
+        #[serde(default)]
+        theme: String,
+        /// Play no sounds here; notifications are still sent.
+        #[serde(default)]
+        quiet: bool,
+    },
+}
+
+impl Pin {
+    pub fn theme(&self) -> &str {
+        match self {
+            Pin::Theme(theme) | Pin::Detailed { theme, .. } => theme,
+        }
+    }
+
+    pub fn quiet(&self) -> bool {
+        matches!(self, Pin::Detailed { quiet: true, .. })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct RemoteConfig {
+    #[serde(default)]
+    pub slack: Option<SlackConfig>,
+    #[serde(default)]
+    pub telegram: Option<TelegramConfig>,
+    #[serde(default)]
+    pub webhook: Option<WebhookConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlackConfig {
+    pub webhook_url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelegramConfig {
+    pub bot_token: String,
+    pub chat_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
 }
 
 impl Config {
     pub fn load(sounds_dir: &Path) -> Self {
-        let path = sounds_dir.join("config.json");
-        fs::read_to_string(&path)
+        load_merged(sounds_dir, false)
             .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
+            .flatten()
+            .and_then(|root| serde_json::from_value(root).ok())
             .unwrap_or_default()
     }
+
+    /// Parse config.json and its includes, reporting errors instead of
+    /// falling back to defaults or skipping the include. `Ok(None)` when the
+    /// file doesn't exist.
+    pub fn load_strict(sounds_dir: &Path) -> Result<Option<Self>, String> {
+        let Some(root) = load_merged(sounds_dir, true)? else {
+            return Ok(None);
+        };
+        serde_json::from_value(root).map(Some).map_err(|e| e.to_string())
+    }
+
+    /// Whether sounds should play at all (env var, then config, default on).
+    pub fn sound_enabled(&self) -> bool {
+        env_flag("CLAUDE_SOUND_ENABLED")
+            .or(self.sound_enabled)
+            .unwrap_or(true)
+    }
+
+    /// Whether notifications should be sent at all (env var, then config, default on).
+    pub fn notifications_enabled(&self) -> bool {
+        env_flag("CLAUDE_NOTIFICATIONS_ENABLED")
+            .or(self.notifications_enabled)
+            .unwrap_or(true)
+    }
+
+    /// Whether the given hook event should be handled at all.
+    pub fn event_enabled(&self, event: &str) -> bool {
+        self.events.get(event).copied().unwrap_or(true)
+    }
+
+    /// Workspace pin covering `cwd`: its own, else the nearest parent
+    /// directory's. Returns the pinned directory and theme.
+    pub fn workspace_pin(&self, cwd: &str) -> Option<(&str, &str)> {
+        Path::new(cwd).ancestors().find_map(|dir| {
+            let (dir, pin) = self.workspaces.get_key_value(dir.to_str()?)?;
+            (!pin.theme().is_empty()).then_some((dir.as_str(), pin.theme()))
+        })
+    }
+
+    /// Whether the nearest pin covering `cwd` is marked quiet.
+    pub fn workspace_quiet(&self, cwd: &str) -> bool {
+        Path::new(cwd)
+            .ancestors()
+            .find_map(|dir| self.workspaces.get(dir.to_str()?))
+            .is_some_and(Pin::quiet)
+    }
+
+    /// Notification title with the category's badge in front, if one is configured.
+    pub fn badged_title(&self, category: &str, title: &str) -> String {
+        match self.badges.get(category).map(|b| b.trim()).filter(|b| !b.is_empty()) {
+            Some(badge) => format!("{badge} {title}"),
+            None => title.to_string(),
+        }
+    }
+
+    /// Cooldown configured for `category`; unparsable values count as none.
+    pub fn cooldown(&self, category: &str) -> Option<std::time::Duration> {
+        parse_duration(self.cooldowns.get(category)?).filter(|d| !d.is_zero())
+    }
+
+    /// What `theme`'s own volume is scaled by (0.0–1.0): `volume` times its
+    /// `theme_volumes` entry. Values above 100 count as 100.
+    pub fn volume_scale(&self, theme: &str) -> f32 {
+        let percent = |v: Option<u8>| f32::from(v.unwrap_or(100).min(100)) / 100.0;
+        percent(self.volume) * percent(self.theme_volumes.get(theme).copied())
+    }
+
+    /// `max_processes`, or the default of 8.
+    pub fn max_processes(&self) -> usize {
+        self.max_processes.unwrap_or(8)
+    }
+
+    /// `sticky_theme_window`, `None` when turned off. Unparsable values
+    /// count as the default.
+    pub fn sticky_theme_window(&self) -> Option<std::time::Duration> {
+        let window = self.sticky_theme_window.as_deref().and_then(parse_duration);
+        Some(window.unwrap_or(std::time::Duration::from_secs(30 * 60))).filter(|d| !d.is_zero())
+    }
+
+    /// Audio backend name (env var, then config, default `rodio`).
+    pub fn player(&self) -> String {
+        std::env::var("RINGRING_PLAYER")
+            .ok()
+            .or_else(|| self.player.clone())
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| "rodio".to_string())
+    }
+
+    /// Shared forwarding token (env var, then config). Empty values count as unset.
+    pub fn forward_token(&self) -> Option<String> {
+        std::env::var("RINGRING_TOKEN")
+            .ok()
+            .or_else(|| self.forward_token.clone())
+            .filter(|t| !t.is_empty())
+    }
+}
+
+/// Parse a boolean-ish env var. Unset or unrecognised values yield `None`.
+fn env_flag(name: &str) -> Option<bool> {
+    parse_flag(&std::env::var(name).ok()?)
+}
+
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parse a duration like `90s`, `5m`, `2h` or `30d`; a bare number is seconds.
+pub fn parse_duration(value: &str) -> Option<std::time::Duration> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (n, unit) = value.split_at(split);
+    let n: u64 = n.parse().ok()?;
+    let secs = match unit {
+        "" | "s" => n,
+        "m" => n.checked_mul(60)?,
+        "h" => n.checked_mul(3600)?,
+        "d" => n.checked_mul(86_400)?,
+        _ => return None,
+    };
+    Some(std::time::Duration::from_secs(secs))
+}
+
+/// The largest whole unit of `duration`, in `parse_duration`'s format.
+pub fn format_duration(duration: std::time::Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        s if s > 0 && s.is_multiple_of(86_400) => format!("{}d", s / 86_400),
+        s if s > 0 && s.is_multiple_of(3600) => format!("{}h", s / 3600),
+        s if s > 0 && s.is_multiple_of(60) => format!("{}m", s / 60),
+        s => format!("{s}s"),
+    }
+}
+
+/// Whether a theme name from `source` is usable; bad ones are logged and skipped.
+fn accept(source: &str, theme: &str) -> bool {
+    validate_theme_name(theme)
+        .inspect_err(|e| crate::log::write(&format!("ignoring {source}: {e}")))
+        .is_ok()
+}
+
+/// A pool index fixed by the session id, so every event of a session picks
+/// the same theme even when the session cache is gone or two events race.
+fn session_index(session_id: &str, len: usize) -> usize {
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(session_id.as_bytes());
+    let seed = u64::from_be_bytes(digest[..8].try_into().unwrap_or_default());
+    (seed % len as u64) as usize
 }
 
 pub struct ThemeResolver<'a> {
@@ -32,44 +369,102 @@ pub struct ThemeResolver<'a> {
     pub cwd: String,
 }
 
+/// The step of the resolution chain that chose a theme.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ThemeSource {
+    Env,
+    /// Pinned on this directory.
+    WorkspacePin(String),
+    SessionCache,
+    /// Kept from the previous session in the same terminal.
+    Terminal,
+    Pool,
+    Config,
+    Legacy,
+    Fallback,
+}
+
+impl std::fmt::Display for ThemeSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ThemeSource::Env => f.write_str("CLAUDE_SOUND_THEME"),
+            ThemeSource::WorkspacePin(dir) => write!(f, "workspace pin for {dir}"),
+            ThemeSource::SessionCache => f.write_str("session cache"),
+            ThemeSource::Terminal => f.write_str("previous session in this terminal"),
+            ThemeSource::Pool => f.write_str("random_pool"),
+            ThemeSource::Config => f.write_str("config theme"),
+            ThemeSource::Legacy => f.write_str("legacy theme file"),
+            ThemeSource::Fallback => f.write_str("fallback"),
+        }
+    }
+}
+
 impl<'a> ThemeResolver<'a> {
     /// Resolve theme using the priority chain:
     /// 1. CLAUDE_SOUND_THEME env var
     /// 2. Workspace pin (config.json workspaces map)
     /// 3. Session cache (/tmp/.claude-theme-{session_id})
-    /// 4. Pick from pool (mode=random or mode=sequential)
+    /// 4. Pick from pool (mode=random or mode=sequential); random keeps the
+    ///    theme of a session that just ran in the same terminal
+    ///    (`sticky_theme_window`)
     /// 5. config.json "theme" field
     /// 6. Legacy ~/.claude/sounds/theme file
     /// 7. Fallback "peon"
     pub fn resolve(&self) -> String {
+        self.resolve_from(true).0
+    }
+
+    /// `resolve`, with the step that picked the theme.
+    pub fn resolve_with_source(&self) -> (String, ThemeSource) {
+        self.resolve_from(true)
+    }
+
+    /// The theme `resolve` would pick and the step that picked it, without
+    /// moving the sequential pool on.
+    pub fn explain(&self) -> (String, ThemeSource) {
+        self.resolve_from(false)
+    }
+
+    fn resolve_from(&self, advance: bool) -> (String, ThemeSource) {
         // 1. Env var
         if let Ok(theme) = std::env::var("CLAUDE_SOUND_THEME")
             && !theme.is_empty()
+            && accept("CLAUDE_SOUND_THEME", &theme)
         {
-            return theme;
+            return (theme, ThemeSource::Env);
         }
 
         // 2. Workspace pin
-        if let Some(theme) = self.config.workspaces.get(&self.cwd)
-            && !theme.is_empty()
+        if let Some((dir, theme)) = self.config.workspace_pin(&self.cwd)
+            && accept(&format!("workspace pin for {dir}"), theme)
         {
-            return theme.clone();
+            return (theme.to_string(), ThemeSource::WorkspacePin(dir.to_string()));
         }
 
         // 3. Session cache
         if !self.session_id.is_empty()
             && let Some(cached) = self.read_non_empty(&self.session_theme_file())
+            && accept("session cache", &cached)
         {
-            return cached;
+            return (cached, ThemeSource::SessionCache);
         }
 
         // 3b. Pick from pool (random or sequential)
-        if !self.config.random_pool.is_empty() {
+        let pool: Vec<&String> =
+            self.config.random_pool.iter().filter(|theme| accept("random_pool", theme)).collect();
+        if !pool.is_empty() {
             match self.config.mode.as_deref() {
                 Some("random") => {
-                    use rand::Rng;
-                    let idx = rand::rng().random_range(0..self.config.random_pool.len());
-                    return self.config.random_pool[idx].clone();
+                    if let Some(theme) = self.sticky_theme(&pool) {
+                        return (theme, ThemeSource::Terminal);
+                    }
+                    let idx = if self.session_id.is_empty() {
+                        use rand::Rng;
+                        rand::rng().random_range(0..pool.len())
+                    } else {
+                        session_index(self.session_id, pool.len())
+                    };
+                    return (pool[idx].clone(), ThemeSource::Pool);
                 }
                 Some("sequential") => {
                     let idx_file = self.sounds_dir.join(".sequence_index");
@@ -77,9 +472,11 @@ impl<'a> ThemeResolver<'a> {
                         .ok()
                         .and_then(|s| s.trim().parse::<usize>().ok())
                         .unwrap_or(0)
-                        % self.config.random_pool.len();
-                    let _ = fs::write(&idx_file, (idx + 1).to_string());
-                    return self.config.random_pool[idx].clone();
+                        % pool.len();
+                    if advance {
+                        let _ = fs::write(&idx_file, (idx + 1).to_string());
+                    }
+                    return (pool[idx].clone(), ThemeSource::Pool);
                 }
                 _ => {}
             }
@@ -88,17 +485,29 @@ impl<'a> ThemeResolver<'a> {
         // 4. Config theme
         if let Some(theme) = &self.config.theme
             && !theme.is_empty()
+            && accept("config theme", theme)
         {
-            return theme.clone();
+            return (theme.clone(), ThemeSource::Config);
         }
 
         // 5. Legacy theme file
-        if let Some(legacy) = self.read_non_empty(&self.sounds_dir.join("theme")) {
-            return legacy;
+        if let Some(legacy) = self.read_non_empty(&self.sounds_dir.join("theme"))
+            && accept("legacy theme file", &legacy)
+        {
+            return (legacy, ThemeSource::Legacy);
         }
 
         // 6. Fallback
-        "peon".to_string()
+        ("peon".to_string(), ThemeSource::Fallback)
+    }
+
+    /// The theme of a session that ran in this terminal and directory just
+    /// before, so a restart keeps its voice, if it's still in the pool.
+    fn sticky_theme(&self, pool: &[&String]) -> Option<String> {
+        let window = self.config.sticky_theme_window()?;
+        let current = crate::session::load(self.session_id)?;
+        let previous = crate::session::previous_in_terminal(&current, crate::log::now_secs(), window.as_secs())?;
+        previous.theme.filter(|theme| pool.contains(&theme))
     }
 
     /// Read a file and return its trimmed content if non-empty.
@@ -109,7 +518,7 @@ impl<'a> ThemeResolver<'a> {
     }
 
     pub fn session_theme_file(&self) -> PathBuf {
-        PathBuf::from(format!("/tmp/.claude-theme-{}", self.session_id))
+        crate::paths::tmp_dir().join(format!(".claude-theme-{}", self.session_id))
     }
 
     /// Persist resolved theme for this session.
@@ -120,8 +529,290 @@ impl<'a> ThemeResolver<'a> {
     }
 }
 
-pub fn theme_dir(sounds_dir: &Path, theme: &str) -> PathBuf {
-    sounds_dir.join(theme)
+/// Theme names become directory names under the data dir, so they must be a
+/// single ordinary path component.
+pub fn validate_theme_name(theme: &str) -> Result<(), String> {
+    let problem = if theme.is_empty() {
+        "it is empty"
+    } else if theme == "." || theme == ".." {
+        "it refers to a parent or current directory"
+    } else if theme.contains(['/', '\\']) {
+        "it contains a path separator"
+    } else if theme.starts_with('.') {
+        "it starts with a dot"
+    } else if theme.chars().any(char::is_control) {
+        "it contains control characters"
+    } else {
+        return Ok(());
+    };
+    Err(format!("invalid theme name '{}': {problem}", theme.escape_debug()))
+}
+
+pub fn theme_dir(sounds_dir: &Path, theme: &str) -> Result<PathBuf, String> {
+    validate_theme_name(theme)?;
+    Ok(sounds_dir.join(theme))
+}
+
+/// The raw config.json, `{}` when there is none.
+pub fn raw_config(sounds_dir: &Path) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let config_path = sounds_dir.join("config.json");
+    let content = fs::read_to_string(&config_path).unwrap_or_else(|_| "{}".to_string());
+    serde_json::from_str(&content).map_err(|e| format!("{}: {e}", config_path.display()).into())
+}
+
+/// Apply `f` to the raw config.json, keeping fields we don't model.
+pub fn edit_config(sounds_dir: &std::path::Path, f: impl FnOnce(&mut serde_json::Value)) -> Result<(), Box<dyn std::error::Error>> {
+    let mut root = raw_config(sounds_dir)?;
+    f(&mut root);
+    fs::create_dir_all(sounds_dir)?;
+    fs::write(sounds_dir.join("config.json"), serde_json::to_string_pretty(&root)?)?;
+    Ok(())
+}
+
+/// Most levels of nested `include`s followed; deeper ones (a file including
+/// itself, say) are an error.
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// config.json with its `include` files merged in: each include (nested ones
+/// too, all relative to the data dir) in listed order, then the including
+/// file's own keys. Unreadable includes are logged and skipped, or with
+/// `strict` returned as errors. `Ok(None)` when there is no config.json.
+fn load_merged(sounds_dir: &Path, strict: bool) -> Result<Option<serde_json::Value>, String> {
+    let Ok(content) = fs::read_to_string(sounds_dir.join("config.json")) else {
+        return Ok(None);
+    };
+    let root = parse_fragment(&content, strict).map_err(|e| e.to_string())?;
+    with_includes(sounds_dir, root, strict, 0).map(Some)
+}
+
+/// One config file as JSON; with `strict`, it must also load as a `Config`
+/// on its own, so errors point at a line of the file they are in.
+fn parse_fragment(content: &str, strict: bool) -> Result<serde_json::Value, serde_json::Error> {
+    if strict {
+        serde_json::from_str::<Config>(content)?;
+    }
+    serde_json::from_str(content)
+}
+
+fn with_includes(sounds_dir: &Path, own: serde_json::Value, strict: bool, depth: usize) -> Result<serde_json::Value, String> {
+    let includes: Vec<String> = own.get("include").and_then(|v| serde_json::from_value(v.clone()).ok()).unwrap_or_default();
+    let mut merged = serde_json::json!({});
+    for include in includes {
+        let fragment = if depth >= MAX_INCLUDE_DEPTH {
+            Err("includes nested too deeply".to_string())
+        } else {
+            fs::read_to_string(sounds_dir.join(&include))
+                .map_err(|e| e.to_string())
+                .and_then(|content| parse_fragment(&content, strict).map_err(|e| e.to_string()))
+                .and_then(|fragment| with_includes(sounds_dir, fragment, strict, depth + 1))
+        };
+        match fragment {
+            Ok(fragment) => merge(&mut merged, fragment),
+            Err(e) if strict => return Err(format!("include {include}: {e}")),
+            Err(e) => crate::log::write(&format!("ignoring include {include}: {e}")),
+        }
+    }
+    merge(&mut merged, own);
+    Ok(merged)
+}
+
+/// Lay `layer` over `base`: objects merge key by key, arrays append, and
+/// anything else is replaced.
+fn merge(base: &mut serde_json::Value, layer: serde_json::Value) {
+    use serde_json::Value;
+    match (base, layer) {
+        (Value::Object(base), Value::Object(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(base), Value::Array(layer)) => base.extend(layer),
+        (base, layer) => *base = layer,
+    }
+}
+
+/// Values `mode` accepts.
+pub const MODES: [&str; 2] = ["random", "sequential"];
+
+/// Top-level config.json fields, as `Config`'s deserializer names them, so
+/// the list can't drift from the struct.
+pub fn known_keys() -> &'static [&'static str] {
+    fields_of::<Config>()
+}
+
+/// The field names a derived `Deserialize` struct accepts, captured from
+/// its `deserialize_struct` call. Empty for anything that isn't a struct.
+pub fn fields_of<T: serde::de::DeserializeOwned>() -> &'static [&'static str] {
+    use serde::de::{self, Visitor};
+
+    struct Fields<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> de::Deserializer<'de> for Fields<'_> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("not a struct"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(de::Error::custom("fields captured"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit
+            unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(Fields(&mut fields));
+    fields
+}
+
+/// Split `key` into a top-level field and, for `field.name`, an entry of
+/// that (map) field, e.g. `workspaces./home/me/api`.
+fn split_key(key: &str) -> Result<(&str, Option<&str>), String> {
+    let (field, entry) = match key.split_once('.') {
+        Some((field, entry)) => (field, Some(entry).filter(|e| !e.is_empty())),
+        None => (key, None),
+    };
+    if !known_keys().contains(&field) {
+        return Err(format!("unknown key '{field}'"));
+    }
+    Ok((field, entry))
+}
+
+/// `key` in the raw config, as `split_key` reads it. `None` when unset.
+pub fn get_key<'a>(root: &'a serde_json::Value, key: &str) -> Result<Option<&'a serde_json::Value>, String> {
+    let (field, entry) = split_key(key)?;
+    let value = root.get(field);
+    Ok(match entry {
+        Some(entry) => value.and_then(|v| v.get(entry)),
+        None => value,
+    })
+}
+
+/// Set `key` to `value` (or with `None`, remove it) and write config.json,
+/// keeping other fields. Nothing is written unless the result still loads
+/// as a `Config`. Returns the previous value.
+pub fn set_key(
+    sounds_dir: &Path,
+    key: &str,
+    value: Option<serde_json::Value>,
+) -> Result<Option<serde_json::Value>, Box<dyn std::error::Error>> {
+    let (field, entry) = split_key(key)?;
+    if let Some(value) = &value {
+        check_value(field, entry, value)?;
+    }
+    let mut root = raw_config(sounds_dir)?;
+    let Some(object) = root.as_object_mut() else {
+        return Err("config.json is not a JSON object".into());
+    };
+    let previous = match (entry, value) {
+        (None, Some(value)) => object.insert(field.to_string(), value),
+        (None, None) => object.remove(field),
+        (Some(entry), Some(value)) => {
+            let map = object.entry(field).or_insert_with(|| serde_json::json!({}));
+            let Some(map) = map.as_object_mut() else {
+                return Err(format!("'{field}' is not an object").into());
+            };
+            map.insert(entry.to_string(), value)
+        }
+        (Some(entry), None) => object.get_mut(field).and_then(|map| map.as_object_mut()).and_then(|map| map.remove(entry)),
+    };
+    serde_json::from_value::<Config>(root.clone()).map_err(|e| format!("{key}: {e}"))?;
+    fs::create_dir_all(sounds_dir)?;
+    fs::write(sounds_dir.join("config.json"), serde_json::to_string_pretty(&root)?)?;
+    Ok(previous)
+}
+
+/// Checks beyond the types: values that parse but would be ignored.
+fn check_value(field: &str, entry: Option<&str>, value: &serde_json::Value) -> Result<(), String> {
+    match (field, entry, value.as_str()) {
+        ("mode", None, Some(mode)) if !MODES.contains(&mode) => {
+            Err(format!("unknown mode '{mode}', expected: {}", MODES.join(", ")))
+        }
+        ("theme", None, Some(theme)) => validate_theme_name(theme),
+        _ => Ok(()),
+    }
+}
+
+/// Set (or with `None`, remove) the workspace pin for `dir`. A quiet pin
+/// stays quiet.
+pub fn write_pin(sounds_dir: &std::path::Path, dir: &str, theme: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    edit_config(sounds_dir, |root| {
+        if !root["workspaces"].is_object() {
+            root["workspaces"] = serde_json::json!({});
+        }
+        match theme {
+            Some(theme) if root["workspaces"][dir].is_object() => {
+                root["workspaces"][dir]["theme"] = serde_json::Value::String(theme.to_string());
+            }
+            Some(theme) => root["workspaces"][dir] = serde_json::Value::String(theme.to_string()),
+            None => {
+                if let Some(pins) = root["workspaces"].as_object_mut() {
+                    pins.remove(dir);
+                }
+            }
+        }
+    })?;
+    if theme.is_some() {
+        crate::suggest::forget(dir);
+    }
+    Ok(())
+}
+
+/// Drop every reference to a removed `theme`: its workspace pins (a quiet pin
+/// stays, without the theme) and its `random_pool` entry. Returns the
+/// directories whose pins changed.
+pub fn forget_theme(sounds_dir: &std::path::Path, theme: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut unpinned = Vec::new();
+    edit_config(sounds_dir, |root| {
+        if let Some(pins) = root["workspaces"].as_object_mut() {
+            pins.retain(|dir, pin| {
+                let quiet = pin["quiet"].as_bool() == Some(true);
+                if pin.as_str() == Some(theme) || pin["theme"].as_str() == Some(theme) {
+                    unpinned.push(dir.clone());
+                    if quiet && let Some(pin) = pin.as_object_mut() {
+                        pin.remove("theme");
+                    }
+                    return quiet;
+                }
+                true
+            });
+        }
+        if let Some(pool) = root["random_pool"].as_array_mut() {
+            pool.retain(|entry| entry.as_str() != Some(theme));
+        }
+    })?;
+    Ok(unpinned)
+}
+
+/// Mark the pin for `dir` quiet, creating a theme-less pin if there is none.
+pub fn write_quiet_pin(sounds_dir: &std::path::Path, dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+    edit_config(sounds_dir, |root| {
+        if !root["workspaces"].is_object() {
+            root["workspaces"] = serde_json::json!({});
+        }
+        let pin = &mut root["workspaces"][dir];
+        if let Some(theme) = pin.as_str().map(str::to_string) {
+            *pin = serde_json::json!({"theme": theme});
+        } else if !pin.is_object() {
+            *pin = serde_json::json!({});
+        }
+        pin["quiet"] = serde_json::Value::Bool(true);
+    })
 }
 
 #[cfg(test)]
@@ -133,6 +824,55 @@ mod tests {
         tempfile::tempdir().unwrap()
     }
 
+    #[test]
+    fn keys_are_set_and_checked_before_writing() {
+        let dir = temp_sounds_dir();
+        fs::write(dir.path().join("config.json"), r#"{"theme": "peon", "custom": 1}"#).unwrap();
+        assert!(known_keys().contains(&"random_pool"));
+        assert!(known_keys().contains(&"workspaces"));
+
+        assert_eq!(set_key(dir.path(), "mode", Some(serde_json::json!("random"))).unwrap(), None);
+        assert!(set_key(dir.path(), "mode", Some(serde_json::json!("shuffle"))).is_err());
+        assert!(set_key(dir.path(), "random_pool", Some(serde_json::json!("icq"))).is_err());
+        assert!(set_key(dir.path(), "colour", Some(serde_json::json!("red"))).is_err());
+        set_key(dir.path(), "workspaces./home/me/api", Some(serde_json::json!("icq"))).unwrap();
+        assert_eq!(set_key(dir.path(), "theme", None).unwrap(), Some(serde_json::json!("peon")));
+
+        let root = raw_config(dir.path()).unwrap();
+        assert_eq!(root, serde_json::json!({"custom": 1, "mode": "random", "workspaces": {"/home/me/api": "icq"}}));
+        assert_eq!(get_key(&root, "workspaces./home/me/api").unwrap(), Some(&serde_json::json!("icq")));
+        assert_eq!(get_key(&root, "theme").unwrap(), None);
+        assert!(get_key(&root, "nope").is_err());
+    }
+
+    #[test]
+    fn forgetting_a_theme_drops_its_pins() {
+        let dir = temp_sounds_dir();
+        fs::write(
+            dir.path().join("config.json"),
+            r#"{"theme": "peon", "random_pool": ["peon", "icq"], "workspaces": {
+                "/a": "peon", "/b": "icq", "/c": {"theme": "peon", "quiet": true}, "/d": {"theme": "peon"}
+            }}"#,
+        )
+        .unwrap();
+        let mut unpinned = forget_theme(dir.path(), "peon").unwrap();
+        unpinned.sort();
+        assert_eq!(unpinned, ["/a", "/c", "/d"]);
+        let cfg = Config::load(dir.path());
+        assert_eq!(cfg.random_pool, ["icq"]);
+        assert_eq!(cfg.workspaces.len(), 2);
+        assert_eq!(cfg.workspaces["/c"], Pin::Detailed { theme: String::new(), quiet: true });
+        assert_eq!(cfg.theme.as_deref(), Some("peon"));
+    }
+
+    #[test]
+    fn badge_prefixes_title() {
+        let cfg: Config = serde_json::from_str(r#"{"badges": {"complete": "✅", "annoyed": " "}}"#).unwrap();
+        assert_eq!(cfg.badged_title("complete", "Hotovo"), "✅ Hotovo");
+        assert_eq!(cfg.badged_title("annoyed", "Čekám"), "Čekám");
+        assert_eq!(cfg.badged_title("permission", "Hmm"), "Hmm");
+    }
+
     #[test]
     fn fallback_to_peon() {
         let dir = temp_sounds_dir();
@@ -162,6 +902,36 @@ mod tests {
         assert_eq!(resolver.resolve(), "aoe2");
     }
 
+    #[test]
+    fn theme_names_are_single_components() {
+        for ok in ["peon", "aoe2-villager", "ICQ 2000", "a.b"] {
+            assert_eq!(validate_theme_name(ok), Ok(()), "{ok}");
+        }
+        for bad in ["", ".", "..", "../../etc", "a/b", "a\\b", ".hidden", "x\ny"] {
+            assert!(validate_theme_name(bad).is_err(), "{bad:?}");
+        }
+        assert!(theme_dir(Path::new("/data"), "../etc").unwrap_err().contains("path separator"));
+    }
+
+    #[test]
+    fn invalid_theme_names_are_skipped() {
+        let dir = temp_sounds_dir();
+        fs::write(dir.path().join("theme"), "icq\n").unwrap();
+        let config = Config {
+            theme: Some("../../etc".to_string()),
+            random_pool: vec!["..".to_string()],
+            mode: Some("random".to_string()),
+            ..Default::default()
+        };
+        let resolver = ThemeResolver {
+            sounds_dir: dir.path(),
+            config: &config,
+            session_id: "",
+            cwd: "/tmp".to_string(),
+        };
+        assert_eq!(resolver.resolve(), "icq");
+    }
+
     #[test]
     fn legacy_theme_file() {
         let dir = temp_sounds_dir();
@@ -180,7 +950,7 @@ mod tests {
     fn workspace_pin_overrides_config() {
         let dir = temp_sounds_dir();
         let mut workspaces = HashMap::new();
-        workspaces.insert("/home/user/project".to_string(), "aoe3".to_string());
+        workspaces.insert("/home/user/project".to_string(), Pin::Theme("aoe3".to_string()));
         let config = Config {
             theme: Some("peon".to_string()),
             workspaces,
@@ -195,6 +965,42 @@ mod tests {
         assert_eq!(resolver.resolve(), "aoe3");
     }
 
+    #[test]
+    fn workspace_pin_covers_subdirectories() {
+        let mut workspaces = HashMap::new();
+        workspaces.insert("/home/user/project".to_string(), Pin::Theme("aoe3".to_string()));
+        workspaces.insert("/home/user/project/docs".to_string(), Pin::Theme("icq".to_string()));
+        let config = Config { workspaces, ..Default::default() };
+        assert_eq!(config.workspace_pin("/home/user/project/src/bin"), Some(("/home/user/project", "aoe3")));
+        assert_eq!(config.workspace_pin("/home/user/project/docs"), Some(("/home/user/project/docs", "icq")));
+        assert_eq!(config.workspace_pin("/home/user/projects"), None);
+    }
+
+    #[test]
+    fn detailed_pins_parse_and_quiet_applies_below() {
+        let config: Config = serde_json::from_str(
+            r#"{"workspaces": {"/work/mono": {"theme": "peon", "quiet": true}, "/work/mono/tools": "icq", "/oss": {"quiet": true}}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.workspace_pin("/work/mono/app"), Some(("/work/mono", "peon")));
+        assert!(config.workspace_quiet("/work/mono/app"));
+        // The nearest pin decides
+        assert!(!config.workspace_quiet("/work/mono/tools"));
+        // A quiet pin without a theme leaves resolution alone
+        assert_eq!(config.workspace_pin("/oss/lib"), None);
+        assert!(config.workspace_quiet("/oss/lib"));
+    }
+
+    #[test]
+    fn repinning_keeps_quiet() {
+        let dir = temp_sounds_dir();
+        write_pin(dir.path(), "/work", Some("peon")).unwrap();
+        write_quiet_pin(dir.path(), "/work").unwrap();
+        write_pin(dir.path(), "/work", Some("icq")).unwrap();
+        let config = Config::load(dir.path());
+        assert_eq!(config.workspaces["/work"], Pin::Detailed { theme: "icq".into(), quiet: true });
+    }
+
     #[test]
     fn env_var_highest_priority() {
         let dir = temp_sounds_dir();
@@ -214,8 +1020,25 @@ mod tests {
             cwd: "/tmp".to_string(),
         };
         let result = resolver.resolve();
+        let source = resolver.explain().1;
         unsafe { std::env::remove_var("CLAUDE_SOUND_THEME") };
         assert_eq!(result, "icq");
+        assert_eq!(source, ThemeSource::Env);
+    }
+
+    #[test]
+    fn random_mode_is_stable_per_session() {
+        let dir = temp_sounds_dir();
+        let pool: Vec<String> = ["alpha", "beta", "gamma", "delta"].map(String::from).to_vec();
+        let config = Config { mode: Some("random".to_string()), random_pool: pool.clone(), ..Default::default() };
+        let pick = |session_id: &str| {
+            ThemeResolver { sounds_dir: dir.path(), config: &config, session_id, cwd: "/tmp".to_string() }.resolve()
+        };
+        // Nothing is persisted here, so every call resolves from scratch
+        let first = pick("session-a");
+        assert!((0..5).all(|_| pick("session-a") == first));
+        let picked: std::collections::HashSet<String> = (0..40).map(|i| pick(&format!("s{i}"))).collect();
+        assert!(picked.len() > 1, "{picked:?}");
     }
 
     #[test]
@@ -238,6 +1061,23 @@ mod tests {
             results.push(resolver.resolve());
         }
         assert_eq!(results, vec!["alpha", "beta", "gamma", "alpha", "beta", "gamma"]);
+
+        let resolver = ThemeResolver { sounds_dir: dir.path(), config: &config, session_id: "", cwd: "/tmp".to_string() };
+        assert_eq!(resolver.explain(), ("alpha".to_string(), ThemeSource::Pool));
+        assert_eq!(resolver.explain().0, "alpha", "explaining doesn't advance the pool");
+    }
+
+    #[test]
+    fn explain_names_the_deciding_step() {
+        let dir = temp_sounds_dir();
+        let config: Config = serde_json::from_str(r#"{"theme": "icq", "workspaces": {"/src/api": "peon"}}"#).unwrap();
+        let resolver = |cwd: &str| ThemeResolver { sounds_dir: dir.path(), config: &config, session_id: "", cwd: cwd.to_string() };
+        assert_eq!(resolver("/src/api/lib").explain(), ("peon".to_string(), ThemeSource::WorkspacePin("/src/api".to_string())));
+        assert_eq!(resolver("/src/web").explain(), ("icq".to_string(), ThemeSource::Config));
+        assert_eq!(ThemeSource::WorkspacePin("/src/api".to_string()).to_string(), "workspace pin for /src/api");
+        let empty = Config::default();
+        let resolver = ThemeResolver { sounds_dir: dir.path(), config: &empty, session_id: "", cwd: "/".to_string() };
+        assert_eq!(resolver.explain().1, ThemeSource::Fallback);
     }
 
     #[test]
@@ -252,4 +1092,106 @@ mod tests {
         assert_eq!(config.mode.as_deref(), Some("random"));
         assert_eq!(config.random_pool.len(), 2);
     }
+
+    #[test]
+    fn load_strict_reports_parse_errors() {
+        let dir = temp_sounds_dir();
+        assert!(matches!(Config::load_strict(dir.path()), Ok(None)));
+        fs::write(dir.path().join("config.json"), r#"{"mode": 3}"#).unwrap();
+        let err = Config::load_strict(dir.path()).unwrap_err();
+        assert!(err.contains("line 1"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn includes_merge_in_order_under_config_json() {
+        let dir = temp_sounds_dir();
+        fs::create_dir(dir.path().join("peon")).unwrap();
+        fs::write(
+            dir.path().join("config.json"),
+            r#"{"include": ["work.json", "peon/config.json", "gone.json"], "theme": "icq", "badges": {"complete": "✅"}}"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("work.json"), r#"{"theme": "peon", "mode": "random", "routes": [{"backends": ["local"]}]}"#).unwrap();
+        fs::write(
+            dir.path().join("peon/config.json"),
+            r#"{"mode": "sequential", "badges": {"permission": "🔐"}, "routes": [{"backends": ["slack"]}]}"#,
+        )
+        .unwrap();
+
+        let cfg = Config::load(dir.path());
+        assert_eq!(cfg.theme.as_deref(), Some("icq"));
+        assert_eq!(cfg.mode.as_deref(), Some("sequential"));
+        assert_eq!(cfg.badges.len(), 2);
+        assert_eq!(cfg.routes.len(), 2);
+
+        let err = Config::load_strict(dir.path()).unwrap_err();
+        assert!(err.starts_with("include gone.json:"), "unexpected error: {err}");
+        fs::write(dir.path().join("gone.json"), r#"{"mode": 3}"#).unwrap();
+        let err = Config::load_strict(dir.path()).unwrap_err();
+        assert!(err.contains("line 1"), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn self_include_stops() {
+        let dir = temp_sounds_dir();
+        fs::write(dir.path().join("config.json"), r#"{"include": ["loop.json"]}"#).unwrap();
+        fs::write(dir.path().join("loop.json"), r#"{"include": ["loop.json"], "theme": "peon"}"#).unwrap();
+        assert_eq!(Config::load(dir.path()).theme.as_deref(), Some("peon"));
+        assert!(Config::load_strict(dir.path()).unwrap_err().contains("nested too deeply"));
+    }
+
+    #[test]
+    fn switches_default_to_enabled() {
+        let config = Config::default();
+        assert!(config.sound_enabled());
+        assert!(config.notifications_enabled());
+    }
+
+    #[test]
+    fn switches_read_from_config() {
+        let config: Config =
+            serde_json::from_str(r#"{"sound_enabled": false, "notifications_enabled": true}"#).unwrap();
+        assert!(!config.sound_enabled());
+        assert!(config.notifications_enabled());
+    }
+
+    #[test]
+    fn durations_roundtrip() {
+        use std::time::Duration;
+        assert_eq!(parse_duration("5m"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_duration("30d"), Some(Duration::from_secs(30 * 86_400)));
+        assert_eq!(parse_duration("45"), Some(Duration::from_secs(45)));
+        assert_eq!(parse_duration("5 min"), None);
+        assert_eq!(parse_duration("m"), None);
+        assert_eq!(format_duration(Duration::from_secs(300)), "5m");
+        assert_eq!(format_duration(Duration::from_secs(5400)), "90m");
+        assert_eq!(format_duration(Duration::from_secs(7200)), "2h");
+    }
+
+    #[test]
+    fn sticky_theme_window_defaults_to_half_an_hour() {
+        use std::time::Duration;
+        assert_eq!(Config::default().sticky_theme_window(), Some(Duration::from_secs(1800)));
+        let cfg: Config = serde_json::from_str(r#"{"sticky_theme_window": "2h"}"#).unwrap();
+        assert_eq!(cfg.sticky_theme_window(), Some(Duration::from_secs(7200)));
+        let cfg: Config = serde_json::from_str(r#"{"sticky_theme_window": "0"}"#).unwrap();
+        assert_eq!(cfg.sticky_theme_window(), None);
+    }
+
+    #[test]
+    fn volume_scale_multiplies_master_and_theme() {
+        assert_eq!(Config::default().volume_scale("peon"), 1.0);
+        let cfg: Config = serde_json::from_str(r#"{"volume": 50, "theme_volumes": {"peon": 40, "loud": 250}}"#).unwrap();
+        assert_eq!(cfg.volume_scale("peon"), 0.2);
+        assert_eq!(cfg.volume_scale("loud"), 0.5);
+        assert_eq!(cfg.volume_scale("other"), 0.5);
+    }
+
+    #[test]
+    fn parse_flag_values() {
+        assert_eq!(parse_flag("0"), Some(false));
+        assert_eq!(parse_flag("Off"), Some(false));
+        assert_eq!(parse_flag("true"), Some(true));
+        assert_eq!(parse_flag("maybe"), None);
+    }
 }
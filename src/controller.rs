@@ -0,0 +1,273 @@
+//! Hardware status displays: color an Elgato Stream Deck key or light a MIDI
+//! pad per category, so the latest Claude state shows on a physical button.
+//! Both talk to the raw Linux device files (`/dev/hidrawN`,
+//! `/dev/snd/midiCxDy`) with no driver library in between.
+
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+use crate::light::{self, Rgb};
+use crate::notify::Notifier;
+
+/// config.json `streamdeck`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StreamDeckConfig {
+    /// hidraw node of the deck, e.g. `/dev/hidraw3`.
+    pub device: String,
+    #[serde(default)]
+    pub model: DeckModel,
+    /// Key to color, counted from 0 at the top left.
+    #[serde(default)]
+    pub key: u8,
+    /// Per category `#rrggbb`, replacing the built-in colors; empty for none.
+    #[serde(default)]
+    pub colors: HashMap<String, String>,
+}
+
+/// Stream Deck models that take BMP key images.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeckModel {
+    /// The original 15-key deck.
+    #[default]
+    Original,
+    /// The 6-key Mini.
+    Mini,
+}
+
+impl DeckModel {
+    fn key_pixels(self) -> usize {
+        match self {
+            DeckModel::Original => 72,
+            DeckModel::Mini => 80,
+        }
+    }
+
+    fn key_count(self) -> u8 {
+        match self {
+            DeckModel::Original => 15,
+            DeckModel::Mini => 6,
+        }
+    }
+
+    fn report_len(self) -> usize {
+        match self {
+            DeckModel::Original => 8191,
+            DeckModel::Mini => 1024,
+        }
+    }
+
+    /// The key index the device uses; the original numbers columns right to left.
+    fn device_key(self, key: u8) -> u8 {
+        match self {
+            DeckModel::Original => {
+                let column = key % 5;
+                key - column + (4 - column)
+            }
+            DeckModel::Mini => key,
+        }
+    }
+
+    /// Page numbers start at 1 on the original and at 0 on the Mini.
+    fn first_page(self) -> u8 {
+        match self {
+            DeckModel::Original => 1,
+            DeckModel::Mini => 0,
+        }
+    }
+}
+
+const REPORT_HEADER_LEN: usize = 16;
+
+/// A `size`×`size` 24-bit BMP filled with `rgb`.
+fn solid_bmp(size: usize, rgb: Rgb) -> Vec<u8> {
+    let pixels = size * size * 3;
+    let mut bmp = Vec::with_capacity(54 + pixels);
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&(54 + pixels as u32).to_le_bytes());
+    bmp.extend_from_slice(&[0; 4]);
+    bmp.extend_from_slice(&54u32.to_le_bytes());
+    bmp.extend_from_slice(&40u32.to_le_bytes());
+    bmp.extend_from_slice(&(size as i32).to_le_bytes());
+    bmp.extend_from_slice(&(size as i32).to_le_bytes());
+    bmp.extend_from_slice(&1u16.to_le_bytes());
+    bmp.extend_from_slice(&24u16.to_le_bytes());
+    bmp.extend_from_slice(&0u32.to_le_bytes());
+    bmp.extend_from_slice(&(pixels as u32).to_le_bytes());
+    bmp.extend_from_slice(&3780u32.to_le_bytes());
+    bmp.extend_from_slice(&3780u32.to_le_bytes());
+    bmp.extend_from_slice(&[0; 8]);
+    let [r, g, b] = rgb;
+    for _ in 0..size * size {
+        bmp.extend_from_slice(&[b, g, r]);
+    }
+    bmp
+}
+
+/// Output reports that set `key` to `image`, each padded to the model's report size.
+fn image_reports(model: DeckModel, key: u8, image: &[u8]) -> Vec<Vec<u8>> {
+    let payload_len = model.report_len() - REPORT_HEADER_LEN;
+    let pages = image.len().div_ceil(payload_len);
+    image
+        .chunks(payload_len)
+        .enumerate()
+        .map(|(page, chunk)| {
+            let mut report = vec![0u8; model.report_len()];
+            report[..6].copy_from_slice(&[
+                0x02,
+                0x01,
+                model.first_page() + page as u8,
+                0,
+                u8::from(page + 1 == pages),
+                model.device_key(key) + 1,
+            ]);
+            report[REPORT_HEADER_LEN..REPORT_HEADER_LEN + chunk.len()].copy_from_slice(chunk);
+            report
+        })
+        .collect()
+}
+
+/// Colors one Stream Deck key; title and body are ignored.
+pub struct StreamDeck {
+    device: String,
+    reports: Vec<Vec<u8>>,
+}
+
+impl StreamDeck {
+    /// The key update for `category`, if it has a color.
+    pub fn for_category(cfg: &StreamDeckConfig, category: &str) -> Option<Self> {
+        let rgb = light::category_color(&cfg.colors, category)?;
+        let image = solid_bmp(cfg.model.key_pixels(), rgb);
+        let key = cfg.key.min(cfg.model.key_count() - 1);
+        Some(Self { device: cfg.device.clone(), reports: image_reports(cfg.model, key, &image) })
+    }
+}
+
+impl Notifier for StreamDeck {
+    fn name(&self) -> &'static str {
+        "streamdeck"
+    }
+
+    fn notify(&self, _title: &str, _body: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut device = std::fs::OpenOptions::new().write(true).open(&self.device).map_err(|e| format!("{}: {e}", self.device))?;
+        // hidraw takes one output report per write
+        for report in &self.reports {
+            device.write_all(report).map_err(|e| format!("{}: {e}", self.device))?;
+        }
+        Ok(())
+    }
+}
+
+/// config.json `midi`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MidiConfig {
+    /// Raw MIDI device, e.g. `/dev/snd/midiC1D0`.
+    pub device: String,
+    /// MIDI channel, 1–16.
+    #[serde(default = "default_channel")]
+    pub channel: u8,
+    /// Category → note number (0–127).
+    pub notes: BTreeMap<String, u8>,
+    /// Note-on velocity; pad controllers like the Launchpad pick the color from it.
+    #[serde(default = "default_velocity")]
+    pub velocity: u8,
+}
+
+fn default_channel() -> u8 {
+    1
+}
+
+fn default_velocity() -> u8 {
+    127
+}
+
+/// Note-off for the other categories' notes, then note-on for this one's,
+/// so exactly one pad shows the current state.
+fn midi_messages(cfg: &MidiConfig, category: &str) -> Option<Vec<u8>> {
+    let note = *cfg.notes.get(category)?;
+    let channel = cfg.channel.clamp(1, 16) - 1;
+    let mut bytes = Vec::new();
+    for &other in cfg.notes.values().filter(|&&n| n != note) {
+        bytes.extend_from_slice(&[0x80 | channel, other & 0x7f, 0]);
+    }
+    bytes.extend_from_slice(&[0x90 | channel, note & 0x7f, cfg.velocity.min(127)]);
+    Some(bytes)
+}
+
+/// Lights a MIDI pad; title and body are ignored.
+pub struct Midi {
+    device: String,
+    bytes: Vec<u8>,
+}
+
+impl Midi {
+    /// The notes for `category`, if it has one.
+    pub fn for_category(cfg: &MidiConfig, category: &str) -> Option<Self> {
+        Some(Self { device: cfg.device.clone(), bytes: midi_messages(cfg, category)? })
+    }
+}
+
+impl Notifier for Midi {
+    fn name(&self) -> &'static str {
+        "midi"
+    }
+
+    fn notify(&self, _title: &str, _body: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut device = std::fs::OpenOptions::new().write(true).open(&self.device).map_err(|e| format!("{}: {e}", self.device))?;
+        device.write_all(&self.bytes).map_err(|e| format!("{}: {e}", self.device))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bmp_is_a_solid_image() {
+        let bmp = solid_bmp(72, [255, 0, 0]);
+        assert_eq!(bmp.len(), 54 + 72 * 72 * 3);
+        assert_eq!(&bmp[..2], b"BM");
+        assert_eq!(u32::from_le_bytes(bmp[2..6].try_into().unwrap()) as usize, bmp.len());
+        assert_eq!(&bmp[54..57], &[0, 0, 255]);
+    }
+
+    #[test]
+    fn original_deck_gets_two_pages_with_mirrored_key() {
+        let cfg: StreamDeckConfig = serde_json::from_str(r#"{"device": "/dev/hidraw3", "key": 0}"#).unwrap();
+        let deck = StreamDeck::for_category(&cfg, "permission").unwrap();
+        assert_eq!(deck.reports.len(), 2);
+        assert!(deck.reports.iter().all(|r| r.len() == 8191));
+        assert_eq!(&deck.reports[0][..6], &[0x02, 0x01, 1, 0, 0, 5]);
+        assert_eq!(&deck.reports[1][..6], &[0x02, 0x01, 2, 0, 1, 5]);
+        assert_eq!(&deck.reports[0][16..18], b"BM");
+        assert!(StreamDeck::for_category(&cfg, "greeting").is_none());
+    }
+
+    #[test]
+    fn mini_pages_start_at_zero() {
+        let cfg: StreamDeckConfig =
+            serde_json::from_str(r##"{"device": "/dev/hidraw3", "model": "mini", "key": 9, "colors": {"complete": "#0000ff"}}"##).unwrap();
+        let deck = StreamDeck::for_category(&cfg, "complete").unwrap();
+        assert_eq!(deck.reports.len(), (54 + 80 * 80 * 3usize).div_ceil(1008));
+        assert_eq!(&deck.reports[0][..6], &[0x02, 0x01, 0, 0, 0, 6]);
+        assert_eq!(deck.reports.last().unwrap()[4], 1);
+    }
+
+    #[test]
+    fn midi_lights_one_pad() {
+        let cfg: MidiConfig =
+            serde_json::from_str(r#"{"device": "/dev/snd/midiC1D0", "channel": 2, "notes": {"permission": 36, "complete": 37}, "velocity": 5}"#)
+                .unwrap();
+        assert_eq!(midi_messages(&cfg, "permission"), Some(vec![0x81, 37, 0, 0x91, 36, 5]));
+        assert_eq!(midi_messages(&cfg, "annoyed"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let device = dir.path().join("midi");
+        std::fs::write(&device, "").unwrap();
+        let midi = Midi::for_category(&MidiConfig { device: device.to_string_lossy().into_owned(), ..cfg }, "complete").unwrap();
+        midi.notify("t", "b").unwrap();
+        assert_eq!(std::fs::read(&device).unwrap(), [0x81, 36, 0, 0x91, 37, 5]);
+    }
+}
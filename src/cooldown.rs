@@ -0,0 +1,48 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::paths;
+
+fn marker_dir() -> PathBuf {
+    paths::runtime_dir().join("cooldown")
+}
+
+/// Whether `category` went off less than `cooldown` ago, across all
+/// sessions. An event that gets through restarts the clock; dropped ones
+/// don't, so a steady stream still plays once per cooldown.
+pub fn cooling(category: &str, cooldown: Duration) -> bool {
+    cooling_in(&marker_dir(), category, cooldown, SystemTime::now())
+}
+
+/// `cooling` for any `key` under `dir`; threshold alerts and light flashes pace themselves with it too.
+pub(crate) fn cooling_in(dir: &Path, key: &str, cooldown: Duration, now: SystemTime) -> bool {
+    let path = dir.join(key);
+    let last = fs::metadata(&path).and_then(|m| m.modified());
+    if last.is_ok_and(|t| now.duration_since(t).unwrap_or_default() < cooldown) {
+        return true;
+    }
+    let _ = fs::create_dir_all(dir);
+    if let Ok(file) = File::create(&path) {
+        let _ = file.set_modified(now);
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_events_until_the_cooldown_runs_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let cooldown = Duration::from_secs(300);
+        let t0 = SystemTime::now();
+        assert!(!cooling_in(dir.path(), "annoyed", cooldown, t0));
+        assert!(cooling_in(dir.path(), "annoyed", cooldown, t0 + Duration::from_secs(60)));
+        assert!(!cooling_in(dir.path(), "complete", cooldown, t0 + Duration::from_secs(60)));
+        // Measured from the last event that played, not the last one dropped
+        assert!(!cooling_in(dir.path(), "annoyed", cooldown, t0 + Duration::from_secs(301)));
+        assert!(cooling_in(dir.path(), "annoyed", cooldown, t0 + Duration::from_secs(400)));
+    }
+}
@@ -0,0 +1,542 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::queue::Queues;
+use crate::{log, paths};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+pub(crate) const IO_TIMEOUT: Duration = Duration::from_secs(5);
+/// Hook payloads are small; anything bigger is not from us.
+pub(crate) const MAX_PAYLOAD: u64 = 1 << 20;
+/// How long shutdown waits for in-flight events to finish playing.
+pub(crate) const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where the daemon listens / the forwarder connects.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Endpoint {
+    Tcp(String),
+    Unix(PathBuf),
+}
+
+impl Endpoint {
+    /// `unix:<path>` or anything containing a `/` is a socket path, otherwise `host:port`.
+    pub fn parse(s: &str) -> Self {
+        if let Some(path) = s.strip_prefix("unix:") {
+            Endpoint::Unix(PathBuf::from(path))
+        } else if s.contains('/') {
+            Endpoint::Unix(PathBuf::from(s))
+        } else {
+            Endpoint::Tcp(s.to_string())
+        }
+    }
+
+    /// Default endpoint: a unix socket in the runtime dir.
+    pub fn default_socket() -> Self {
+        Endpoint::Unix(paths::runtime_dir().join("daemon.sock"))
+    }
+}
+
+impl std::fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Endpoint::Tcp(addr) => write!(f, "{addr}"),
+            Endpoint::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// Bumped whenever the message set or framing changes.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// How long the client waits for each daemon reply. Kept short so a hook
+/// talking to an incompatible daemon falls back quickly.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Protocol messages. Each is sent as one frame: a 4-byte big-endian length
+/// followed by that many bytes of JSON.
+///
+/// A session is `Hello` -> `Welcome`, then `Event` -> `Ack`. The daemon
+/// answers with `Error` and closes the connection on a version or token
+/// mismatch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum Message {
+    Hello {
+        version: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token: Option<String>,
+    },
+    Welcome { version: u32 },
+    Event { payload: serde_json::Value },
+    Ack,
+    Error { message: String },
+}
+
+fn write_frame<W: Write>(w: &mut W, message: &Message) -> std::io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    w.write_all(&(body.len() as u32).to_be_bytes())?;
+    w.write_all(&body)?;
+    w.flush()
+}
+
+fn read_frame<R: Read>(r: &mut R) -> std::io::Result<Message> {
+    let mut len = [0u8; 4];
+    r.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len) as u64;
+    if len > MAX_PAYLOAD {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "frame too large"));
+    }
+    let mut body = vec![0u8; len as usize];
+    r.read_exact(&mut body)?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// Compare tokens without short-circuiting on the first differing byte.
+pub(crate) fn token_matches(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// Send a raw hook payload to a listening daemon. Fails if the daemon speaks
+/// a different protocol version or rejects the token.
+pub fn forward(to: &Endpoint, token: Option<&str>, payload: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let payload: serde_json::Value = serde_json::from_str(payload)?;
+    match to {
+        Endpoint::Tcp(addr) => {
+            let sock_addr = std::net::ToSocketAddrs::to_socket_addrs(addr)?
+                .next()
+                .ok_or_else(|| format!("cannot resolve '{addr}'"))?;
+            let mut stream = TcpStream::connect_timeout(&sock_addr, CONNECT_TIMEOUT)?;
+            stream.set_write_timeout(Some(IO_TIMEOUT))?;
+            stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+            send_event(&mut stream, token, payload)
+        }
+        Endpoint::Unix(path) => {
+            let mut stream = UnixStream::connect(path)?;
+            stream.set_write_timeout(Some(IO_TIMEOUT))?;
+            stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+            send_event(&mut stream, token, payload)
+        }
+    }
+}
+
+/// Client side of a session.
+fn send_event<S: Read + Write>(
+    stream: &mut S,
+    token: Option<&str>,
+    payload: serde_json::Value,
+) -> Result<(), Box<dyn std::error::Error>> {
+    write_frame(stream, &Message::Hello { version: PROTOCOL_VERSION, token: token.map(str::to_string) })?;
+    match read_frame(stream)? {
+        Message::Welcome { version } if version == PROTOCOL_VERSION => {}
+        Message::Welcome { version } => {
+            return Err(format!("daemon speaks protocol v{version}, expected v{PROTOCOL_VERSION}").into());
+        }
+        Message::Error { message } => return Err(format!("daemon refused: {message}").into()),
+        other => return Err(format!("unexpected reply from daemon: {other:?}").into()),
+    }
+    write_frame(stream, &Message::Event { payload })?;
+    match read_frame(stream)? {
+        Message::Ack => Ok(()),
+        Message::Error { message } => Err(format!("daemon refused: {message}").into()),
+        other => Err(format!("unexpected reply from daemon: {other:?}").into()),
+    }
+}
+
+/// First file descriptor passed by systemd socket activation.
+const SD_LISTEN_FDS_START: i32 = 3;
+
+/// Whether systemd passed us a listening socket that nothing has taken yet.
+static ACTIVATED: AtomicBool = AtomicBool::new(false);
+
+/// Note whether we were socket-activated and clear systemd's `LISTEN_*`
+/// variables, so they don't leak into children. Call it before starting any
+/// thread: changing the environment races with threads reading it.
+pub fn claim_activation() {
+    let passed = || -> Option<bool> {
+        let pid: u32 = std::env::var("LISTEN_PID").ok()?.parse().ok()?;
+        let fds: i32 = std::env::var("LISTEN_FDS").ok()?.parse().ok()?;
+        Some(pid == std::process::id() && fds >= 1)
+    };
+    if passed() == Some(true) {
+        ACTIVATED.store(true, Ordering::SeqCst);
+    }
+    if std::env::var_os("LISTEN_PID").is_some() {
+        unsafe {
+            std::env::remove_var("LISTEN_PID");
+            std::env::remove_var("LISTEN_FDS");
+            std::env::remove_var("LISTEN_FDNAMES");
+        }
+    }
+}
+
+/// Take over the listening socket systemd passed, if `claim_activation`
+/// found one.
+pub(crate) fn activated_listener() -> Option<UnixListener> {
+    ACTIVATED.swap(false, Ordering::SeqCst).then(|| unsafe { UnixListener::from_raw_fd(SD_LISTEN_FDS_START) })
+}
+
+pub(crate) fn bind_unix(path: &std::path::Path) -> Result<UnixListener, Box<dyn std::error::Error>> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // A stale socket from a previous run would make bind fail
+    let _ = std::fs::remove_file(path);
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+trait Stream: Read + Write + Send {}
+impl<T: Read + Write + Send> Stream for T {}
+
+enum Listener {
+    Tcp(TcpListener),
+    Unix(UnixListener),
+}
+
+impl Listener {
+    fn fd(&self) -> RawFd {
+        match self {
+            Listener::Tcp(l) => l.as_raw_fd(),
+            Listener::Unix(l) => l.as_raw_fd(),
+        }
+    }
+
+    fn accept(&self) -> std::io::Result<Box<dyn Stream>> {
+        Ok(match self {
+            Listener::Tcp(l) => {
+                let (stream, _) = l.accept()?;
+                stream.set_read_timeout(Some(IO_TIMEOUT))?;
+                Box::new(stream)
+            }
+            Listener::Unix(l) => {
+                let (stream, _) = l.accept()?;
+                stream.set_read_timeout(Some(IO_TIMEOUT))?;
+                Box::new(stream)
+            }
+        })
+    }
+}
+
+/// Wait up to `timeout_ms` for a pending connection. Signals interrupt the wait.
+fn wait_readable(fd: RawFd, timeout_ms: i32) -> bool {
+    let mut pfd = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+    unsafe { libc::poll(&mut pfd, 1, timeout_ms) > 0 && pfd.revents & libc::POLLIN != 0 }
+}
+
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+static RELOAD: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_signal(sig: libc::c_int) {
+    if sig == libc::SIGHUP {
+        RELOAD.store(true, Ordering::SeqCst);
+    } else {
+        SHUTDOWN.store(true, Ordering::SeqCst);
+    }
+}
+
+fn install_signal_handlers() {
+    let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGTERM, handler);
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGHUP, handler);
+    }
+}
+
+/// Counts handler threads still running; decrements on drop.
+struct InFlight(Arc<AtomicUsize>);
+
+impl InFlight {
+    fn start(counter: &Arc<AtomicUsize>) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Self(Arc::clone(counter))
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Accept forwarded hook payloads until SIGTERM/SIGINT, submitting each
+/// authenticated payload to `queues`. `load_token` is called at startup and
+/// again on SIGHUP. On shutdown, waits for queued events (including their
+/// playback) to finish.
+pub fn serve(listen: &Endpoint, load_token: fn() -> Option<String>, queues: Arc<Queues>) -> Result<(), Box<dyn std::error::Error>> {
+    let token = load_token();
+    if matches!(listen, Endpoint::Tcp(_)) && token.is_none() {
+        return Err("refusing to listen on TCP without a token (set forward_token or RINGRING_TOKEN)".into());
+    }
+
+    let _lock = acquire_pidfile(&pidfile_path())?;
+
+    // Reap any children spawned by handlers (external players etc.)
+    unsafe { libc::signal(libc::SIGCHLD, libc::SIG_IGN) };
+    install_signal_handlers();
+
+    let mut bound_socket = None;
+    let listener = match listen {
+        Endpoint::Tcp(addr) => Listener::Tcp(TcpListener::bind(addr)?),
+        Endpoint::Unix(path) => match activated_listener() {
+            Some(listener) => Listener::Unix(listener),
+            None => {
+                let listener = bind_unix(path)?;
+                bound_socket = Some(path.clone());
+                Listener::Unix(listener)
+            }
+        },
+    };
+    log::write(&format!("daemon listening on {listen}"));
+    println!("ringring daemon listening on {listen}");
+
+    let token = Arc::new(RwLock::new(token));
+    let in_flight = Arc::new(AtomicUsize::new(0));
+
+    while !SHUTDOWN.load(Ordering::SeqCst) {
+        if RELOAD.swap(false, Ordering::SeqCst) {
+            *token.write().unwrap_or_else(|e| e.into_inner()) = load_token();
+            log::write("daemon: configuration reloaded");
+        }
+        if !wait_readable(listener.fd(), 500) {
+            continue;
+        }
+        let Ok(stream) = listener.accept() else { continue };
+        let token = Arc::clone(&token);
+        let guard = InFlight::start(&in_flight);
+        let queues = Arc::clone(&queues);
+        std::thread::spawn(move || {
+            let _guard = guard;
+            let token = token.read().unwrap_or_else(|e| e.into_inner()).clone();
+            handle_connection(stream, token.as_deref(), |payload| queues.submit(payload));
+        });
+    }
+
+    log::write("daemon: shutting down, draining in-flight events");
+    let deadline = Instant::now() + DRAIN_TIMEOUT;
+    while (in_flight.load(Ordering::SeqCst) > 0 || !queues.idle()) && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    queues.retire();
+    // An activated socket belongs to systemd; only clean up our own
+    if let Some(path) = bound_socket {
+        let _ = std::fs::remove_file(path);
+    }
+    log::write("daemon: stopped");
+    Ok(())
+}
+
+pub fn pidfile_path() -> PathBuf {
+    paths::runtime_dir().join("daemon.pid")
+}
+
+/// Take the exclusive daemon lock and record our pid. The lock lives as long
+/// as the returned file.
+pub(crate) fn acquire_pidfile(path: &Path) -> Result<File, Box<dyn std::error::Error>> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+        let pid = std::fs::read_to_string(path).unwrap_or_default();
+        return Err(format!("daemon already running (pid {})", pid.trim()).into());
+    }
+    file.set_len(0)?;
+    write!(file, "{}", std::process::id())?;
+    Ok(file)
+}
+
+/// Pid of the running daemon, if the pidfile lock is held.
+pub fn running_pid() -> Option<i32> {
+    running_pid_at(&pidfile_path())
+}
+
+fn running_pid_at(path: &Path) -> Option<i32> {
+    let file = File::open(path).ok()?;
+    // If we can take the lock, nobody holds it: the daemon isn't running
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_SH | libc::LOCK_NB) } == 0 {
+        return None;
+    }
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Send `signal` to the running daemon. Returns its pid.
+pub fn signal_daemon(signal: libc::c_int) -> Result<i32, Box<dyn std::error::Error>> {
+    let pid = running_pid().ok_or("daemon is not running")?;
+    if unsafe { libc::kill(pid, signal) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(pid)
+}
+
+/// Ask the daemon to shut down and wait until it has released its lock.
+pub fn stop() -> Result<i32, Box<dyn std::error::Error>> {
+    let pid = signal_daemon(libc::SIGTERM)?;
+    let deadline = Instant::now() + DRAIN_TIMEOUT + Duration::from_secs(1);
+    while running_pid().is_some() {
+        if Instant::now() > deadline {
+            return Err(format!("daemon (pid {pid}) did not stop in time").into());
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    Ok(pid)
+}
+
+/// Daemon's answer to a client's first frame: `Welcome`, or the `Error` to
+/// send before closing. `None` means close without a word.
+pub(crate) fn answer_hello(hello: Message, token: Option<&str>) -> Option<Message> {
+    let given = match hello {
+        Message::Hello { version, token } if version == PROTOCOL_VERSION => token,
+        Message::Hello { version, .. } => {
+            log::write(&format!("daemon: rejected client speaking protocol v{version}"));
+            let message = format!("protocol version mismatch: daemon v{PROTOCOL_VERSION}, client v{version}");
+            return Some(Message::Error { message });
+        }
+        _ => return None,
+    };
+    if let Some(expected) = token
+        && !token_matches(expected, given.as_deref().unwrap_or(""))
+    {
+        log::write("daemon: rejected payload with invalid token");
+        return Some(Message::Error { message: "invalid token".to_string() });
+    }
+    Some(Message::Welcome { version: PROTOCOL_VERSION })
+}
+
+/// Daemon side of a session. The event is acknowledged before the handler
+/// runs so the client never waits on playback.
+fn handle_connection<S: Read + Write>(mut stream: S, token: Option<&str>, handler: impl FnOnce(String)) {
+    let Ok(hello) = read_frame(&mut stream) else { return };
+    let Some(reply) = answer_hello(hello, token) else { return };
+    let welcomed = matches!(reply, Message::Welcome { .. });
+    if write_frame(&mut stream, &reply).is_err() || !welcomed {
+        return;
+    }
+    let Ok(Message::Event { payload }) = read_frame(&mut stream) else {
+        return;
+    };
+    let _ = write_frame(&mut stream, &Message::Ack);
+    drop(stream);
+    handler(payload.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn parse_endpoints() {
+        assert_eq!(Endpoint::parse("host.docker.internal:7878"), Endpoint::Tcp("host.docker.internal:7878".into()));
+        assert_eq!(Endpoint::parse("/run/ringring.sock"), Endpoint::Unix("/run/ringring.sock".into()));
+        assert_eq!(Endpoint::parse("unix:ringring.sock"), Endpoint::Unix("ringring.sock".into()));
+    }
+
+    #[test]
+    fn frame_roundtrip() {
+        let mut buf = Vec::new();
+        let hello = Message::Hello { version: 1, token: Some("s3cret".into()) };
+        write_frame(&mut buf, &hello).unwrap();
+        write_frame(&mut buf, &Message::Ack).unwrap();
+        let mut r = buf.as_slice();
+        assert_eq!(read_frame(&mut r).unwrap(), hello);
+        assert_eq!(read_frame(&mut r).unwrap(), Message::Ack);
+    }
+
+    #[test]
+    fn oversized_frame_is_rejected() {
+        let mut buf = (MAX_PAYLOAD as u32 + 1).to_be_bytes().to_vec();
+        buf.extend_from_slice(b"{}");
+        assert!(read_frame(&mut buf.as_slice()).is_err());
+    }
+
+    #[test]
+    fn token_comparison() {
+        assert!(token_matches("abc", "abc"));
+        assert!(!token_matches("abc", "abd"));
+        assert!(!token_matches("abc", "ab"));
+    }
+
+    static RECEIVED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    fn record(payload: String) {
+        RECEIVED.lock().unwrap().push(payload);
+    }
+
+    /// Run one session over a socket pair against `handle_connection`.
+    fn session(client_token: Option<&str>, daemon_token: Option<&'static str>, payload: serde_json::Value) -> Result<(), String> {
+        let (mut client, server) = UnixStream::pair().unwrap();
+        let daemon = std::thread::spawn(move || handle_connection(server, daemon_token, record));
+        let result = send_event(&mut client, client_token, payload).map_err(|e| e.to_string());
+        daemon.join().unwrap();
+        result
+    }
+
+    #[test]
+    fn connection_requires_matching_token() {
+        let err = session(Some("wrong"), Some("right"), serde_json::json!({"t": "bad"})).unwrap_err();
+        assert!(err.contains("invalid token"));
+        session(Some("right"), Some("right"), serde_json::json!({"t": "good"})).unwrap();
+        let received = RECEIVED.lock().unwrap();
+        assert!(received.contains(&r#"{"t":"good"}"#.to_string()));
+        assert!(!received.iter().any(|p| p.contains("bad")));
+    }
+
+    #[test]
+    fn version_mismatch_is_refused() {
+        let (mut client, server) = UnixStream::pair().unwrap();
+        let daemon = std::thread::spawn(move || handle_connection(server, None, record));
+        write_frame(&mut client, &Message::Hello { version: PROTOCOL_VERSION + 1, token: None }).unwrap();
+        let reply = read_frame(&mut client).unwrap();
+        daemon.join().unwrap();
+        assert!(matches!(reply, Message::Error { message } if message.contains("version mismatch")));
+    }
+
+    #[test]
+    fn client_rejects_newer_daemon() {
+        let (mut client, mut server) = UnixStream::pair().unwrap();
+        let daemon = std::thread::spawn(move || {
+            let _ = read_frame(&mut server);
+            write_frame(&mut server, &Message::Welcome { version: PROTOCOL_VERSION + 1 }).unwrap();
+        });
+        let err = send_event(&mut client, None, serde_json::json!({})).unwrap_err();
+        daemon.join().unwrap();
+        assert!(err.to_string().contains("protocol"));
+    }
+
+    #[test]
+    fn tcp_without_token_is_refused() {
+        let queues = Queues::new(Default::default(), |_| {});
+        let err = serve(&Endpoint::Tcp("127.0.0.1:0".into()), || None, queues).unwrap_err();
+        assert!(err.to_string().contains("token"));
+    }
+
+    #[test]
+    fn pidfile_lock_is_exclusive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.pid");
+        assert_eq!(running_pid_at(&path), None);
+        let lock = acquire_pidfile(&path).unwrap();
+        assert_eq!(running_pid_at(&path), Some(std::process::id() as i32));
+        let err = acquire_pidfile(&path).unwrap_err();
+        assert!(err.to_string().contains("already running"));
+        drop(lock);
+        assert_eq!(running_pid_at(&path), None);
+    }
+}
@@ -0,0 +1,259 @@
+//! The daemon on a tokio runtime (feature `async`). Connections, signals,
+//! the shutdown drain and remote notifier retries are tasks rather than a
+//! thread each; the pipeline itself stays synchronous and runs on the
+//! category queues' workers. The one-shot hook never touches this.
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, UnixListener};
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+use crate::daemon::{self, Endpoint, IO_TIMEOUT, MAX_PAYLOAD, Message};
+use crate::notify::Notifier;
+use crate::queue::Queues;
+use crate::{log, remote};
+
+/// Further attempts after a remote notifier fails, waiting twice as long
+/// before each.
+const RETRIES: u32 = 3;
+const RETRY_BACKOFF: Duration = Duration::from_secs(2);
+
+async fn write_frame<W: AsyncWrite + Unpin>(w: &mut W, message: &Message) -> std::io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    w.write_all(&(body.len() as u32).to_be_bytes()).await?;
+    w.write_all(&body).await?;
+    w.flush().await
+}
+
+async fn read_frame<R: AsyncRead + Unpin>(r: &mut R) -> std::io::Result<Message> {
+    let mut len = [0u8; 4];
+    r.read_exact(&mut len).await?;
+    let len = u32::from_be_bytes(len) as u64;
+    if len > MAX_PAYLOAD {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "frame too large"));
+    }
+    let mut body = vec![0u8; len as usize];
+    r.read_exact(&mut body).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// `read_frame` giving up after `IO_TIMEOUT`.
+async fn read_frame_timed<R: AsyncRead + Unpin>(r: &mut R) -> std::io::Result<Message> {
+    timeout(IO_TIMEOUT, read_frame(r)).await.map_err(|_| std::io::ErrorKind::TimedOut)?
+}
+
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+enum Listener {
+    Tcp(TcpListener),
+    Unix(UnixListener),
+}
+
+impl Listener {
+    async fn bind(listen: &Endpoint) -> Result<(Self, Option<std::path::PathBuf>), Box<dyn std::error::Error>> {
+        Ok(match listen {
+            Endpoint::Tcp(addr) => (Listener::Tcp(TcpListener::bind(addr).await?), None),
+            Endpoint::Unix(path) => {
+                let (listener, bound) = match daemon::activated_listener() {
+                    Some(listener) => (listener, None),
+                    None => (daemon::bind_unix(path)?, Some(path.clone())),
+                };
+                listener.set_nonblocking(true)?;
+                (Listener::Unix(UnixListener::from_std(listener)?), bound)
+            }
+        })
+    }
+
+    async fn accept(&self) -> std::io::Result<Box<dyn Stream>> {
+        Ok(match self {
+            Listener::Tcp(l) => Box::new(l.accept().await?.0),
+            Listener::Unix(l) => Box::new(l.accept().await?.0),
+        })
+    }
+}
+
+/// Same contract as `daemon::serve`, on a multi-threaded tokio runtime.
+pub fn serve(listen: &Endpoint, load_token: fn() -> Option<String>, queues: Arc<Queues>) -> Result<(), Box<dyn std::error::Error>> {
+    let token = load_token();
+    if matches!(listen, Endpoint::Tcp(_)) && token.is_none() {
+        return Err("refusing to listen on TCP without a token (set forward_token or RINGRING_TOKEN)".into());
+    }
+
+    let _lock = daemon::acquire_pidfile(&daemon::pidfile_path())?;
+
+    // Reap any children spawned by handlers (external players etc.)
+    unsafe { libc::signal(libc::SIGCHLD, libc::SIG_IGN) };
+
+    let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+    let result = runtime.block_on(run(listen, token, load_token, &queues));
+    // Handlers still playing after the drain timeout are abandoned
+    runtime.shutdown_timeout(Duration::from_secs(1));
+    result
+}
+
+async fn run(
+    listen: &Endpoint,
+    token: Option<String>,
+    load_token: fn() -> Option<String>,
+    queues: &Arc<Queues>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut hangup = signal(SignalKind::hangup())?;
+
+    let (listener, bound_socket) = Listener::bind(listen).await?;
+    log::write(&format!("daemon listening on {listen} (async)"));
+    println!("ringring daemon listening on {listen}");
+
+    let runtime = tokio::runtime::Handle::current();
+    remote::set_retry(Box::new(move |backend, title, body| {
+        runtime.spawn(retry(backend, title, body, RETRY_BACKOFF));
+    }));
+
+    let token = Arc::new(RwLock::new(token));
+    let mut tasks = JoinSet::new();
+    loop {
+        tokio::select! {
+            _ = terminate.recv() => break,
+            _ = interrupt.recv() => break,
+            _ = hangup.recv() => {
+                *token.write().unwrap_or_else(|e| e.into_inner()) = load_token();
+                log::write("daemon: configuration reloaded");
+            }
+            accepted = listener.accept() => {
+                let Ok(stream) = accepted else { continue };
+                let token = token.read().unwrap_or_else(|e| e.into_inner()).clone();
+                let queues = Arc::clone(queues);
+                tasks.spawn(handle_connection(stream, token, move |payload| queues.submit(payload)));
+            }
+            // Reap finished connections so the set doesn't grow
+            Some(_) = tasks.join_next(), if !tasks.is_empty() => {}
+        }
+    }
+
+    log::write("daemon: shutting down, draining in-flight events");
+    let _ = timeout(daemon::DRAIN_TIMEOUT, async {
+        while tasks.join_next().await.is_some() {}
+        // Queue workers are plain threads; poll until they run dry
+        while !queues.idle() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await;
+    queues.retire();
+    // An activated socket belongs to systemd; only clean up our own
+    if let Some(path) = bound_socket {
+        let _ = std::fs::remove_file(path);
+    }
+    log::write("daemon: stopped");
+    Ok(())
+}
+
+/// Daemon side of a session; see `daemon::handle_connection`. `handler` must
+/// not block: it is called on the runtime.
+async fn handle_connection<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, token: Option<String>, handler: impl FnOnce(String)) {
+    let Ok(hello) = read_frame_timed(&mut stream).await else { return };
+    let Some(reply) = daemon::answer_hello(hello, token.as_deref()) else { return };
+    let welcomed = matches!(reply, Message::Welcome { .. });
+    if write_frame(&mut stream, &reply).await.is_err() || !welcomed {
+        return;
+    }
+    let Ok(Message::Event { payload }) = read_frame_timed(&mut stream).await else {
+        return;
+    };
+    let _ = write_frame(&mut stream, &Message::Ack).await;
+    drop(stream);
+    handler(payload.to_string());
+}
+
+/// Try a failed remote send again, up to `RETRIES` times with doubling
+/// delays starting at `backoff`.
+async fn retry(backend: Arc<dyn Notifier + Send + Sync>, title: String, body: String, backoff: Duration) -> bool {
+    let mut delay = backoff;
+    let mut last_error = String::new();
+    for attempt in 1..=RETRIES {
+        tokio::time::sleep(delay).await;
+        let (send, title, body) = (Arc::clone(&backend), title.clone(), body.clone());
+        match tokio::task::spawn_blocking(move || send.notify(&title, &body).map_err(|e| e.to_string())).await {
+            Ok(Ok(())) => {
+                log::write(&format!("{} notification delivered on retry {attempt}", backend.name()));
+                return true;
+            }
+            Ok(Err(e)) => last_error = e,
+            Err(e) => last_error = e.to_string(),
+        }
+        delay *= 2;
+    }
+    log::write(&format!("{} notification failed after {RETRIES} retries: {last_error}", backend.name()));
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    static RECEIVED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    fn record(payload: String) {
+        RECEIVED.lock().unwrap().push(payload);
+    }
+
+    #[tokio::test]
+    async fn session_acks_then_runs_handler() {
+        let (mut client, server) = tokio::net::UnixStream::pair().unwrap();
+        let daemon = tokio::spawn(handle_connection(server, Some("right".into()), record));
+        let hello = Message::Hello { version: daemon::PROTOCOL_VERSION, token: Some("right".into()) };
+        write_frame(&mut client, &hello).await.unwrap();
+        assert!(matches!(read_frame(&mut client).await.unwrap(), Message::Welcome { .. }));
+        write_frame(&mut client, &Message::Event { payload: serde_json::json!({"t": "async"}) }).await.unwrap();
+        assert_eq!(read_frame(&mut client).await.unwrap(), Message::Ack);
+        daemon.await.unwrap();
+        assert!(RECEIVED.lock().unwrap().contains(&r#"{"t":"async"}"#.to_string()));
+    }
+
+    #[tokio::test]
+    async fn wrong_token_is_refused() {
+        let (mut client, server) = tokio::net::UnixStream::pair().unwrap();
+        let daemon = tokio::spawn(handle_connection(server, Some("right".into()), record));
+        let hello = Message::Hello { version: daemon::PROTOCOL_VERSION, token: Some("wrong".into()) };
+        write_frame(&mut client, &hello).await.unwrap();
+        let reply = read_frame(&mut client).await.unwrap();
+        daemon.await.unwrap();
+        assert!(matches!(reply, Message::Error { message } if message.contains("invalid token")));
+    }
+
+    /// Fails until it has been called `succeed_on` times.
+    struct Flaky {
+        calls: AtomicU32,
+        succeed_on: u32,
+    }
+
+    impl Notifier for Flaky {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        fn notify(&self, _title: &str, _body: &str) -> Result<(), Box<dyn std::error::Error>> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            if call >= self.succeed_on { Ok(()) } else { Err("unavailable".into()) }
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_until_delivered_or_exhausted() {
+        let flaky = Arc::new(Flaky { calls: AtomicU32::new(0), succeed_on: 2 });
+        assert!(retry(flaky.clone(), "t".into(), "b".into(), Duration::from_millis(1)).await);
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 2);
+
+        let down = Arc::new(Flaky { calls: AtomicU32::new(0), succeed_on: u32::MAX });
+        assert!(!retry(down.clone(), "t".into(), "b".into(), Duration::from_millis(1)).await);
+        assert_eq!(down.calls.load(Ordering::SeqCst), RETRIES);
+    }
+}
@@ -0,0 +1,106 @@
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::event::HookInput;
+use crate::paths;
+
+/// Identical events closer together than this are one event fired twice.
+const WINDOW: Duration = Duration::from_secs(1);
+
+fn marker_dir() -> PathBuf {
+    paths::runtime_dir().join("recent")
+}
+
+/// Content hash of the fields that make two notifications "the same".
+fn key(input: &HookInput) -> String {
+    let mut hasher = Sha256::new();
+    for field in [
+        Some(input.hook_event_name.as_str()),
+        Some(input.session_id.as_str()),
+        input.source.as_deref(),
+        input.notification_type.as_deref(),
+        input.message.as_deref(),
+    ] {
+        hasher.update(field.unwrap_or("\u{0}"));
+        hasher.update([0xff]);
+    }
+    hasher.finalize()[..16].iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Whether the same event was already handled within the last second. Each
+/// event leaves a marker file in the runtime dir; creating it is atomic, so
+/// of two hooks racing on one event only one gets through.
+pub fn is_repeat(input: &HookInput) -> bool {
+    is_repeat_in(&marker_dir(), &key(input), SystemTime::now())
+}
+
+fn is_repeat_in(dir: &Path, key: &str, now: SystemTime) -> bool {
+    let _ = fs::create_dir_all(dir);
+    sweep(dir, now);
+    let path = dir.join(key);
+    match File::create_new(&path) {
+        Ok(file) => {
+            let _ = file.set_modified(now);
+            false
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            if fresh(&path, now) {
+                return true;
+            }
+            if let Ok(file) = File::options().write(true).open(&path) {
+                let _ = file.set_modified(now);
+            }
+            false
+        }
+        // Can't tell; better a double notification than a lost one
+        Err(_) => false,
+    }
+}
+
+fn fresh(path: &Path, now: SystemTime) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .is_ok_and(|t| now.duration_since(t).unwrap_or_default() < WINDOW)
+}
+
+/// Drop markers that have expired.
+fn sweep(dir: &Path, now: SystemTime) {
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        if !fresh(&entry.path(), now) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(json: &str) -> HookInput {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn repeat_within_window_is_collapsed() {
+        let dir = tempfile::tempdir().unwrap();
+        let t0 = SystemTime::now();
+        assert!(!is_repeat_in(dir.path(), "k", t0));
+        assert!(is_repeat_in(dir.path(), "k", t0 + Duration::from_millis(400)));
+        assert!(!is_repeat_in(dir.path(), "other", t0 + Duration::from_millis(500)));
+        // A second later it is a new event again
+        assert!(!is_repeat_in(dir.path(), "k", t0 + Duration::from_millis(1500)));
+        assert!(is_repeat_in(dir.path(), "k", t0 + Duration::from_millis(1600)));
+    }
+
+    #[test]
+    fn key_covers_event_content() {
+        let a = input(r#"{"hook_event_name": "Notification", "session_id": "s", "message": "Claude needs your permission"}"#);
+        let b = input(r#"{"hook_event_name": "Notification", "session_id": "s", "message": "Claude is waiting for your input"}"#);
+        let c = input(r#"{"hook_event_name": "Notification", "session_id": "t", "message": "Claude needs your permission"}"#);
+        assert_eq!(key(&a), key(&input(r#"{"hook_event_name": "Notification", "session_id": "s", "message": "Claude needs your permission", "cwd": "/x"}"#)));
+        assert_ne!(key(&a), key(&b));
+        assert_ne!(key(&a), key(&c));
+    }
+}
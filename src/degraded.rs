@@ -0,0 +1,119 @@
+//! Degraded mode: when the data dir can't be read (permission denied, an
+//! explicitly chosen dir that doesn't exist, an unreadable config.json),
+//! hook events still play the built-in theme and notify through the
+//! terminal and remote backends. The reason is kept for `doctor`.
+
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::{log, paths};
+
+/// Why the last hook event ran degraded, in `paths::state_dir()/degraded.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Degraded {
+    pub timestamp: u64,
+    pub reason: String,
+}
+
+pub fn state_path() -> PathBuf {
+    paths::state_dir().join("degraded.json")
+}
+
+/// What keeps ringring from reading `data_dir`, if anything. A missing
+/// default dir is a fresh install, not a problem; a missing dir that was
+/// chosen with `--data-dir` or `RINGRING_DATA_DIR` is.
+pub fn problem(data_dir: &Path) -> Option<String> {
+    problem_with(data_dir, paths::data_dir_override().is_some())
+}
+
+fn problem_with(data_dir: &Path, chosen: bool) -> Option<String> {
+    match std::fs::read_dir(data_dir) {
+        Err(e) if e.kind() == ErrorKind::NotFound && !chosen => return None,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Some(format!("data dir {} does not exist", data_dir.display())),
+        Err(e) => return Some(format!("data dir {}: {e}", data_dir.display())),
+        Ok(_) => {}
+    }
+    let config = data_dir.join("config.json");
+    match std::fs::File::open(&config) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Some(format!("{}: {e}", config.display())),
+        _ => None,
+    }
+}
+
+/// Remember why this event ran degraded, or forget it once it doesn't.
+/// Failures are ignored: the state dir may be just as unwritable.
+pub fn record(reason: Option<&str>) {
+    record_at(&state_path(), reason, log::now_secs());
+}
+
+fn record_at(path: &Path, reason: Option<&str>, now: u64) {
+    let Some(reason) = reason else {
+        if path.exists() {
+            let _ = std::fs::remove_file(path);
+        }
+        return;
+    };
+    if last_at(path).is_some_and(|d| d.reason == reason) {
+        return;
+    }
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let degraded = Degraded { timestamp: now, reason: reason.to_string() };
+    if let Ok(json) = serde_json::to_string(&degraded) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+/// The recorded degradation, if the last hook event ran degraded.
+pub fn last() -> Option<Degraded> {
+    last_at(&state_path())
+}
+
+fn last_at(path: &Path) -> Option<Degraded> {
+    serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn missing_dir_is_only_a_problem_when_chosen() {
+        let tmp = tempfile::tempdir().unwrap();
+        let missing = tmp.path().join("nope");
+        assert_eq!(problem_with(&missing, false), None);
+        assert!(problem_with(&missing, true).unwrap().ends_with("does not exist"));
+        assert_eq!(problem_with(tmp.path(), true), None);
+
+        // Not a directory at all
+        let file = tmp.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        assert!(problem_with(&file, false).is_some());
+    }
+
+    #[test]
+    fn unreadable_config_is_a_problem() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = tmp.path().join("config.json");
+        std::fs::write(&config, "{}").unwrap();
+        std::fs::set_permissions(&config, std::fs::Permissions::from_mode(0o000)).unwrap();
+        // Root reads anything; there's nothing to check then
+        if std::fs::read(&config).is_err() {
+            assert!(problem_with(tmp.path(), false).unwrap().contains("config.json"));
+        }
+    }
+
+    #[test]
+    fn record_keeps_the_first_time_and_clears() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("state/degraded.json");
+        record_at(&path, Some("data dir /x: Permission denied"), 10);
+        record_at(&path, Some("data dir /x: Permission denied"), 20);
+        assert_eq!(last_at(&path), Some(Degraded { timestamp: 10, reason: "data dir /x: Permission denied".into() }));
+        record_at(&path, None, 30);
+        assert_eq!(last_at(&path), None);
+    }
+}
@@ -0,0 +1,101 @@
+//! Embedding API: the hook engine without the CLI around it.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::audio::Player;
+use crate::config::Config;
+use crate::event::HookInput;
+use crate::history::{self, Outcome};
+use crate::notify::Notifier;
+use crate::pipeline::{Middleware, Overrides, Pipeline};
+
+/// Configures a [`Ringring`]. Anything left unset behaves as it does for
+/// the `ringring` binary: the data dir from `RINGRING_DATA_DIR` or the
+/// platform default, its config.json, and the configured players and
+/// notification backends.
+#[derive(Default)]
+pub struct RingringBuilder {
+    overrides: Overrides,
+    middleware: Vec<Box<dyn Middleware + Send + Sync>>,
+}
+
+impl RingringBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Where themes and config.json live.
+    pub fn data_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.overrides.data_dir = Some(dir.into());
+        self
+    }
+
+    /// Use this config instead of reading config.json.
+    pub fn config(mut self, config: Config) -> Self {
+        self.overrides.config = Some(config);
+        self
+    }
+
+    /// Play every sound through `player`. Playback runs on the calling
+    /// thread when `player.detach()` is false; otherwise the process forks
+    /// like hook mode does, so embedders normally want false.
+    pub fn player(mut self, player: impl Player + 'static) -> Self {
+        self.overrides.player = Some(Arc::new(player));
+        self
+    }
+
+    /// Send every notification to `notifier` instead of the desktop and
+    /// remote backends.
+    pub fn notifier(mut self, notifier: impl Notifier + Send + Sync + 'static) -> Self {
+        self.overrides.notifier = Some(Arc::new(notifier));
+        self
+    }
+
+    /// Add middleware after the standard ones.
+    pub fn middleware(mut self, middleware: impl Middleware + Send + Sync + 'static) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Don't write session files, theme caches, dedup markers or history,
+    /// so events handled here don't show up in `ringring status` or collapse
+    /// with the hook's. Milestones and thresholds count from the history, so
+    /// a stateless engine never reaches them.
+    pub fn stateless(mut self, stateless: bool) -> Self {
+        self.overrides.stateless = stateless;
+        self
+    }
+
+    pub fn build(self) -> Ringring {
+        let record = !self.overrides.stateless;
+        let mut pipeline = Pipeline::with_overrides(self.overrides);
+        for middleware in self.middleware {
+            pipeline.push(middleware);
+        }
+        Ringring { pipeline, record }
+    }
+}
+
+/// A configured engine; see [`RingringBuilder`].
+pub struct Ringring {
+    pipeline: Pipeline,
+    /// Append handled events to the history, as the hook does.
+    record: bool,
+}
+
+impl Ringring {
+    /// Play and notify for one hook event, as `ringring` does on stdin.
+    pub fn handle_event(&self, input: &HookInput) -> Result<Outcome, Box<dyn std::error::Error>> {
+        let outcome = self.pipeline.run(input, false)?;
+        if self.record {
+            history::append(&serde_json::to_value(input)?, &outcome);
+        }
+        Ok(outcome)
+    }
+
+    /// What `handle_event` would do, without playing or sending anything.
+    pub fn preview(&self, input: &HookInput) -> Result<Outcome, Box<dyn std::error::Error>> {
+        self.pipeline.run(input, true)
+    }
+}
@@ -1,15 +1,32 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize)]
+use crate::locale::Text;
+
+/// Sound categories hook events map to.
+pub const CATEGORIES: [&str; 4] = ["greeting", "permission", "complete", "annoyed"];
+
+/// Hook events `map_event` has a mapping for; others get the generic one.
+pub const KNOWN_EVENTS: [&str; 6] = ["SessionStart", "PermissionRequest", "Stop", "SessionEnd", "UserPromptSubmit", "Notification"];
+
+/// Notification types `map_notification` tells apart.
+pub const KNOWN_NOTIFICATION_TYPES: [&str; 4] = ["permission_prompt", "idle_prompt", "auth_success", "elicitation_dialog"];
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct HookInput {
     #[serde(default = "default_unknown")]
     pub hook_event_name: String,
     #[serde(default)]
     pub session_id: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub source: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub notification_type: Option<String>,
+    /// Notification text, when the event carries one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Working directory of the Claude Code session, when provided.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
 }
 
 fn default_unknown() -> String {
@@ -22,18 +39,22 @@ pub struct EventAction {
     pub title: String,
     pub body: String,
     pub skip_notify: bool,
+    /// Which built-in text `title` and `body` are, for themes that declare
+    /// a language.
+    pub text: Option<Text>,
     /// For SessionStart: "startup", "resume", or other
     #[allow(dead_code)]
     pub session_start_type: Option<String>,
 }
 
 impl EventAction {
-    fn new(category: &str, title: &str, body: &str) -> Self {
+    fn new(category: &str, text: Text, title: &str, body: &str) -> Self {
         Self {
             category: Some(category.into()),
             title: title.into(),
             body: body.into(),
             skip_notify: false,
+            text: Some(text),
             session_start_type: None,
         }
     }
@@ -44,6 +65,7 @@ impl EventAction {
             title: String::new(),
             body: String::new(),
             skip_notify: true,
+            text: None,
             session_start_type: None,
         }
     }
@@ -64,29 +86,48 @@ pub fn map_event(input: &HookInput) -> EventAction {
         }
         "PermissionRequest" => EventAction {
             skip_notify: true,
-            ..EventAction::new("permission", "Potřebuju povolení", "Something need doing?")
+            ..EventAction::new("permission", Text::PermissionRequest, "Potřebuju povolení", "Something need doing?")
         },
-        "Stop" => EventAction::new("complete", "Hotovo", "Okie dokie."),
+        "Stop" => EventAction::new("complete", Text::Stop, "Hotovo", "Okie dokie."),
+        // Only ends the daemon's ambience track
+        "SessionEnd" => EventAction::silent(None),
+        // Only clears the session's attention flag
+        "UserPromptSubmit" => EventAction::silent(None),
         "Notification" => map_notification(input),
-        _ => EventAction::new("resource_limit", "Neznámá událost", "Why not?"),
+        _ => EventAction::new("resource_limit", Text::UnknownEvent, "Neznámá událost", "Why not?"),
+    }
+}
+
+/// What the session is waiting on the user for, if this event means it is
+/// blocked. Any other event counts as activity and clears the flag.
+pub fn attention_reason(input: &HookInput) -> Option<&'static str> {
+    match input.hook_event_name.as_str() {
+        "PermissionRequest" => Some("permission"),
+        "Notification" => match input.notification_type.as_deref() {
+            Some("permission_prompt") => Some("permission"),
+            Some("elicitation_dialog") => Some("question"),
+            Some("idle_prompt") => Some("input"),
+            _ => None,
+        },
+        _ => None,
     }
 }
 
 fn map_notification(input: &HookInput) -> EventAction {
     match input.notification_type.as_deref().unwrap_or("unknown") {
         "permission_prompt" => {
-            EventAction::new("permission", "Chtěl bych trochu pozornosti", "Hmm?")
+            EventAction::new("permission", Text::PermissionPrompt, "Chtěl bych trochu pozornosti", "Hmm?")
         }
         "idle_prompt" => {
-            EventAction::new("annoyed", "Čekám na tebe", "Nudím se, pojď makat.")
+            EventAction::new("annoyed", Text::IdlePrompt, "Čekám na tebe", "Nudím se, pojď makat.")
         }
         "auth_success" => {
-            EventAction::new("acknowledge", "Přihlášení úspěšné", "Be happy to.")
+            EventAction::new("acknowledge", Text::AuthSuccess, "Přihlášení úspěšné", "Be happy to.")
         }
         "elicitation_dialog" => {
-            EventAction::new("permission", "Mám otázku", "What you want?")
+            EventAction::new("permission", Text::Elicitation, "Mám otázku", "What you want?")
         }
-        _ => EventAction::new("greeting", "Chtěl bych trochu pozornosti", "Yes?"),
+        _ => EventAction::new("greeting", Text::UnknownNotification, "Chtěl bych trochu pozornosti", "Yes?"),
     }
 }
 
@@ -98,6 +139,16 @@ mod tests {
         serde_json::from_str(json).unwrap()
     }
 
+    #[test]
+    fn attention_set_by_prompts_only() {
+        let reason = |json: &str| attention_reason(&parse(json));
+        assert_eq!(reason(r#"{"hook_event_name": "PermissionRequest"}"#), Some("permission"));
+        assert_eq!(reason(r#"{"hook_event_name": "Notification", "notification_type": "idle_prompt"}"#), Some("input"));
+        assert_eq!(reason(r#"{"hook_event_name": "Notification", "notification_type": "auth_success"}"#), None);
+        assert_eq!(reason(r#"{"hook_event_name": "UserPromptSubmit"}"#), None);
+        assert!(map_event(&parse(r#"{"hook_event_name": "UserPromptSubmit"}"#)).category.is_none());
+    }
+
     #[test]
     fn stop_maps_to_complete() {
         let input = parse(r#"{"hook_event_name": "Stop", "session_id": "abc"}"#);
@@ -140,6 +191,13 @@ mod tests {
         assert_eq!(action.category.as_deref(), Some("greeting"));
     }
 
+    #[test]
+    fn session_end_is_silent() {
+        let action = map_event(&parse(r#"{"hook_event_name": "SessionEnd"}"#));
+        assert!(action.category.is_none());
+        assert!(action.skip_notify);
+    }
+
     #[test]
     fn unknown_event_maps_to_resource_limit() {
         let input = parse(r#"{"hook_event_name": "SomeFutureEvent"}"#);
@@ -0,0 +1,163 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::paths;
+
+/// Hook events with a bit in the compiled map. Anything else always takes
+/// the slow path, which checks `Config::event_enabled` itself.
+const EVENTS: [&str; 4] = ["SessionStart", "PermissionRequest", "Stop", "Notification"];
+
+/// Identifies one version of a file: modification time and size.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Stamp {
+    secs: i64,
+    nanos: i64,
+    len: u64,
+}
+
+impl Stamp {
+    pub(crate) fn of(meta: &fs::Metadata) -> Self {
+        Self { secs: meta.mtime(), nanos: meta.mtime_nsec(), len: meta.size() }
+    }
+}
+
+/// Cache layout: stamp of the config it was compiled from, then the bitmap.
+const CACHE_LEN: usize = 8 + 8 + 8 + 4;
+
+pub fn cache_path() -> PathBuf {
+    paths::runtime_dir().join("events.bin")
+}
+
+fn bit(event: &str) -> Option<u32> {
+    EVENTS.iter().position(|e| *e == event).map(|i| 1 << i)
+}
+
+/// Bitmap of the known events enabled by `cfg`. With `include`s every bit
+/// is set: edits to those files don't change config.json's stamp, so the
+/// slow path has to decide.
+fn compile(cfg: &Config) -> u32 {
+    if !cfg.include.is_empty() {
+        return (1 << EVENTS.len()) - 1;
+    }
+    EVENTS
+        .iter()
+        .enumerate()
+        .filter(|(_, name)| cfg.event_enabled(name))
+        .fold(0, |map, (i, _)| map | 1 << i)
+}
+
+fn read_cache(path: &Path) -> Option<(Stamp, u32)> {
+    let mut buf = [0u8; CACHE_LEN];
+    File::open(path).ok()?.read_exact(&mut buf).ok()?;
+    let word = |i: usize| i64::from_le_bytes(buf[i..i + 8].try_into().unwrap());
+    let stamp = Stamp { secs: word(0), nanos: word(8), len: word(16) as u64 };
+    Some((stamp, u32::from_le_bytes(buf[24..28].try_into().unwrap())))
+}
+
+fn write_cache(path: &Path, stamp: Stamp, map: u32) -> std::io::Result<()> {
+    let mut buf = [0u8; CACHE_LEN];
+    buf[0..8].copy_from_slice(&stamp.secs.to_le_bytes());
+    buf[8..16].copy_from_slice(&stamp.nanos.to_le_bytes());
+    buf[16..24].copy_from_slice(&stamp.len.to_le_bytes());
+    buf[24..28].copy_from_slice(&map.to_le_bytes());
+    write_atomic(path, &buf)
+}
+
+/// Replace `path` with `bytes`, creating its directory. Written aside and
+/// renamed so concurrent hooks never read a torn file.
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+    File::create(&tmp)?.write_all(bytes)?;
+    fs::rename(&tmp, path)
+}
+
+/// Pull `hook_event_name` out of the raw payload without parsing it.
+/// `None` when absent or escaped, leaving the decision to the full parser.
+fn event_name(raw: &str) -> Option<&str> {
+    const KEY: &str = "\"hook_event_name\"";
+    let rest = raw[raw.find(KEY)? + KEY.len()..].trim_start();
+    let rest = rest.strip_prefix(':')?.trim_start().strip_prefix('"')?;
+    let name = &rest[..rest.find('"')?];
+    (!name.contains('\\')).then_some(name)
+}
+
+/// Whether the event in `raw` may be enabled. Only returns false when the
+/// compiled map says the event is definitely disabled; the map is rebuilt
+/// from config.json whenever that file changes.
+pub fn event_enabled(sounds_dir: &Path, raw: &str) -> bool {
+    event_enabled_with(sounds_dir, &cache_path(), raw)
+}
+
+fn event_enabled_with(sounds_dir: &Path, cache: &Path, raw: &str) -> bool {
+    let Some(bit) = event_name(raw).and_then(bit) else {
+        return true;
+    };
+    // No config means everything is on
+    let Ok(meta) = fs::metadata(sounds_dir.join("config.json")) else {
+        return true;
+    };
+    let stamp = Stamp::of(&meta);
+    let map = match read_cache(cache) {
+        Some((cached, map)) if cached == stamp => map,
+        _ => {
+            let map = compile(&Config::load(sounds_dir));
+            let _ = write_cache(cache, stamp, map);
+            map
+        }
+    };
+    map & bit != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_event_name() {
+        assert_eq!(event_name(r#"{"session_id":"x","hook_event_name" : "Stop"}"#), Some("Stop"));
+        assert_eq!(event_name(r#"{"session_id":"x"}"#), None);
+        assert_eq!(event_name(r#"{"hook_event_name":"St\"op"}"#), None);
+    }
+
+    #[test]
+    fn compiles_enabled_events() {
+        let cfg: Config = serde_json::from_str(r#"{"events": {"Stop": false, "Notification": false}}"#).unwrap();
+        assert_eq!(compile(&cfg), 0b0011);
+        assert_eq!(compile(&Config::default()), 0b1111);
+        let cfg: Config = serde_json::from_str(r#"{"include": ["quiet.json"], "events": {"Stop": false}}"#).unwrap();
+        assert_eq!(compile(&cfg), 0b1111);
+    }
+
+    #[test]
+    fn cache_follows_config_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("run/events.bin");
+        let stop = r#"{"hook_event_name":"Stop"}"#;
+
+        assert!(event_enabled_with(dir.path(), &cache, stop));
+        assert!(!cache.exists());
+
+        fs::write(dir.path().join("config.json"), r#"{"events": {"Stop": false}}"#).unwrap();
+        assert!(!event_enabled_with(dir.path(), &cache, stop));
+        assert!(event_enabled_with(dir.path(), &cache, r#"{"hook_event_name":"Notification"}"#));
+        assert_eq!(read_cache(&cache).map(|(_, map)| map), Some(0b1011));
+
+        // Different size, so the stamp changes even within one mtime tick
+        fs::write(dir.path().join("config.json"), r#"{"events": {"Stop": true}}"#).unwrap();
+        assert!(event_enabled_with(dir.path(), &cache, stop));
+    }
+
+    #[test]
+    fn unknown_events_take_slow_path() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("config.json"), r#"{"events": {"SubagentStop": false}}"#).unwrap();
+        assert!(event_enabled_with(dir.path(), &dir.path().join("events.bin"), r#"{"hook_event_name":"SubagentStop"}"#));
+    }
+}
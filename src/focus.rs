@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use std::process::{Command, Stdio};
+
+use crate::{log, session};
+
+/// Identifies the terminal window a session runs in, as far as the hook's
+/// environment tells us.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Terminal {
+    /// X11 window id (`WINDOWID`, set by xterm, kitty, alacritty, ...).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_id: Option<String>,
+    /// Wayland activation token (`XDG_ACTIVATION_TOKEN` / `DESKTOP_STARTUP_ID`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub activation_token: Option<String>,
+}
+
+impl Terminal {
+    /// Read the terminal identity from the current process environment.
+    /// Must run in the hook process: the daemon has its own environment.
+    pub fn capture() -> Option<Self> {
+        Self::from_env(|key| std::env::var(key).ok())
+    }
+
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let non_empty = |key: &str| var(key).filter(|v| !v.is_empty());
+        let terminal = Self {
+            window_id: non_empty("WINDOWID"),
+            activation_token: non_empty("XDG_ACTIVATION_TOKEN").or_else(|| non_empty("DESKTOP_STARTUP_ID")),
+        };
+        (terminal != Self::default()).then_some(terminal)
+    }
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+/// Command that raises `terminal`: the `focus_command` template if set,
+/// otherwise xdotool for an X11 window id.
+fn focus_command(terminal: &Terminal, template: Option<&str>) -> Option<Command> {
+    let window = terminal.window_id.as_deref().unwrap_or("");
+    let mut words = match template {
+        Some(template) => template.split_whitespace().map(|w| w.replace("{window}", window)).collect::<Vec<_>>(),
+        None if !window.is_empty() => vec!["xdotool".into(), "windowactivate".into(), window.to_string()],
+        None => return None,
+    }
+    .into_iter();
+    let mut cmd = Command::new(words.next()?);
+    cmd.args(words).env("RINGRING_WINDOW_ID", window);
+    if let Some(token) = &terminal.activation_token {
+        cmd.env("XDG_ACTIVATION_TOKEN", token);
+    }
+    Some(cmd)
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+/// Bring the terminal of `session_id` to the front. Called when a desktop
+/// notification is clicked; failures only reach the log.
+pub fn activate(session_id: &str, template: Option<&str>) {
+    let Some(terminal) = session::load(session_id).and_then(|s| s.terminal) else {
+        log::write(&format!("focus: no terminal recorded for session {session_id}"));
+        return;
+    };
+    let Some(mut cmd) = focus_command(&terminal, template) else {
+        log::write("focus: no X11 window id and no focus_command configured");
+        return;
+    };
+    let status = cmd.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).status();
+    if !status.is_ok_and(|s| s.success()) {
+        log::write(&format!("focus: {cmd:?} failed"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_reads_window_and_token() {
+        let env = |pairs: &'static [(&'static str, &'static str)]| {
+            move |key: &str| pairs.iter().find(|(k, _)| *k == key).map(|(_, v)| v.to_string())
+        };
+        let t = Terminal::from_env(env(&[("WINDOWID", "4194317"), ("DESKTOP_STARTUP_ID", "tok")])).unwrap();
+        assert_eq!(t.window_id.as_deref(), Some("4194317"));
+        assert_eq!(t.activation_token.as_deref(), Some("tok"));
+        assert_eq!(Terminal::from_env(env(&[("WINDOWID", "")])), None);
+    }
+
+    #[test]
+    fn focus_command_defaults_to_xdotool() {
+        let x11 = Terminal { window_id: Some("42".into()), activation_token: None };
+        let cmd = focus_command(&x11, None).unwrap();
+        assert_eq!(cmd.get_program(), "xdotool");
+        assert_eq!(cmd.get_args().collect::<Vec<_>>(), ["windowactivate", "42"]);
+
+        let wayland = Terminal { window_id: None, activation_token: Some("tok".into()) };
+        assert!(focus_command(&wayland, None).is_none());
+        let cmd = focus_command(&wayland, Some("my-focus --id={window}")).unwrap();
+        assert_eq!(cmd.get_args().collect::<Vec<_>>(), ["--id="]);
+    }
+}
@@ -0,0 +1,137 @@
+//! The deferred-greeting flag. `SessionStart` (startup) drops a flag, waits
+//! a moment and greets only if the flag is still there; a `resume` right
+//! after removes it, so resuming a session doesn't greet twice.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{paths, session};
+
+/// A flag older than this is left over from a crash, not a greeting in
+/// progress.
+const STALE: Duration = Duration::from_secs(10);
+
+fn flag_dir() -> PathBuf {
+    paths::runtime_dir().join("greetings")
+}
+
+/// A greeting this process has announced and may still play.
+#[derive(Debug)]
+pub struct Pending {
+    path: PathBuf,
+    token: String,
+}
+
+/// Announce a greeting for `session_id`. `None` means another startup of
+/// the same session is already about to greet (Claude restarts can fire
+/// several), so this one stays quiet, as does a session id that can't be a
+/// file name.
+pub fn begin(session_id: &str) -> std::io::Result<Option<Pending>> {
+    begin_in(&flag_dir(), session_id, SystemTime::now())
+}
+
+fn begin_in(dir: &Path, session_id: &str, now: SystemTime) -> std::io::Result<Option<Pending>> {
+    if !session::valid_id(session_id) {
+        return Ok(None);
+    }
+    fs::create_dir_all(dir)?;
+    let path = dir.join(session_id);
+    let millis = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+    let token = format!("{millis} {}", std::process::id());
+    loop {
+        match File::create_new(&path) {
+            Ok(mut file) => {
+                file.write_all(token.as_bytes())?;
+                return Ok(Some(Pending { path, token }));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                let written = fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|s| s.split_whitespace().next()?.parse::<u64>().ok())
+                    .map(|ms| UNIX_EPOCH + Duration::from_millis(ms));
+                // Empty means the other process is between create and write
+                let fresh = written.is_none_or(|t| now.duration_since(t).unwrap_or_default() < STALE);
+                if fresh {
+                    return Ok(None);
+                }
+                match fs::remove_file(&path) {
+                    Ok(()) => continue,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+impl Pending {
+    /// Take the flag back. True if nothing cancelled the greeting, so it
+    /// should play; removal is atomic, so a racing `cancel` and this call
+    /// can't both win.
+    pub fn claim(self) -> bool {
+        if fs::read_to_string(&self.path).ok().as_deref() != Some(self.token.as_str()) {
+            return false;
+        }
+        fs::remove_file(&self.path).is_ok()
+    }
+}
+
+/// `resume`: call off any greeting still pending for the session.
+pub fn cancel(session_id: &str) {
+    cancel_in(&flag_dir(), session_id);
+}
+
+fn cancel_in(dir: &Path, session_id: &str) {
+    if session::valid_id(session_id) {
+        let _ = fs::remove_file(dir.join(session_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concurrent_startups_greet_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        let first = begin_in(dir.path(), "s", now).unwrap().unwrap();
+        assert!(begin_in(dir.path(), "s", now + Duration::from_millis(300)).unwrap().is_none());
+        assert!(first.claim());
+        // Once greeted, a later startup greets again
+        assert!(begin_in(dir.path(), "s", now + Duration::from_secs(2)).unwrap().unwrap().claim());
+    }
+
+    #[test]
+    fn cancelled_greeting_is_not_claimed() {
+        let dir = tempfile::tempdir().unwrap();
+        let pending = begin_in(dir.path(), "s", SystemTime::now()).unwrap().unwrap();
+        fs::remove_file(dir.path().join("s")).unwrap();
+        assert!(!pending.claim());
+    }
+
+    #[test]
+    fn stale_flag_is_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        let crashed = begin_in(dir.path(), "s", now - STALE * 2).unwrap().unwrap();
+        let pending = begin_in(dir.path(), "s", now).unwrap().unwrap();
+        assert!(!crashed.claim());
+        assert!(pending.claim());
+    }
+
+    #[test]
+    fn session_ids_stay_inside_the_flag_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("greetings");
+        let outside = root.path().join("victim");
+        fs::write(&outside, "keep").unwrap();
+        assert!(begin_in(&dir, "../escape", SystemTime::now()).unwrap().is_none());
+        assert!(!root.path().join("escape").exists());
+        cancel_in(&dir, "../victim");
+        assert!(outside.exists());
+    }
+}
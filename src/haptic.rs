@@ -0,0 +1,242 @@
+//! Haptic notifications: a force-feedback rumble per category on an evdev
+//! device (game controller, haptic wristband, ...), for users who'd rather
+//! feel an alert than hear or read it.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::notify::Notifier;
+
+/// Longest pattern played, so a typo can't rumble for minutes. Playback
+/// blocks the notifying process, because closing the device stops the effect.
+const MAX_PATTERN_MS: u32 = 3000;
+
+/// config.json `haptic`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HapticConfig {
+    /// evdev device with force feedback, e.g. `/dev/input/by-id/...-event-joystick`.
+    pub device: String,
+    /// Rumble strength, 0.0–1.0.
+    #[serde(default = "default_strength")]
+    pub strength: f32,
+    /// Per category pattern: milliseconds on, off, on, ... Replaces the
+    /// built-in pattern; an empty list keeps the category still.
+    #[serde(default)]
+    pub patterns: HashMap<String, Vec<u32>>,
+}
+
+fn default_strength() -> f32 {
+    0.75
+}
+
+impl HapticConfig {
+    /// The pattern for `category`: configured, else built in.
+    pub fn pattern(&self, category: &str) -> Option<Vec<u32>> {
+        let pattern = match self.patterns.get(category) {
+            Some(pattern) => pattern.clone(),
+            None => builtin_pattern(category)?.to_vec(),
+        };
+        (!pattern.is_empty()).then_some(pattern)
+    }
+}
+
+fn builtin_pattern(category: &str) -> Option<&'static [u32]> {
+    Some(match category {
+        "permission" => &[200, 100, 200],
+        "complete" => &[120],
+        "annoyed" => &[400],
+        "meta" => &[80, 80, 80, 80, 80],
+        _ => return None,
+    })
+}
+
+/// (on, off) steps of a pattern, cut to `MAX_PATTERN_MS` overall.
+fn steps(pattern: &[u32]) -> Vec<(u32, u32)> {
+    let mut steps = Vec::new();
+    let mut left = MAX_PATTERN_MS;
+    for pair in pattern.chunks(2) {
+        let on = pair[0].min(left);
+        left -= on;
+        let off = pair.get(1).copied().unwrap_or(0).min(left);
+        left -= off;
+        if on > 0 {
+            steps.push((on, off));
+        }
+        if left == 0 {
+            break;
+        }
+    }
+    steps
+}
+
+/// Rumbles the category's pattern; title and body are ignored.
+pub struct Haptic {
+    pub device: String,
+    pub strength: f32,
+    pub pattern: Vec<u32>,
+}
+
+impl Notifier for Haptic {
+    fn name(&self) -> &'static str {
+        "haptic"
+    }
+
+    fn notify(&self, _title: &str, _body: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let magnitude = (self.strength.clamp(0.0, 1.0) * f32::from(u16::MAX)) as u16;
+        rumble(&self.device, magnitude, &steps(&self.pattern))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn rumble(device: &str, magnitude: u16, steps: &[(u32, u32)]) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+    use std::os::fd::AsRawFd;
+
+    let mut file = std::fs::OpenOptions::new().read(true).write(true).open(device).map_err(|e| format!("{device}: {e}"))?;
+    let fd = file.as_raw_fd();
+    let mut effect = evdev::FfEffect::rumble(magnitude);
+    let mut result = Ok(());
+    for &(on, off) in steps {
+        // Re-uploading under the same id updates the effect's length
+        effect.replay.length = on.min(u32::from(u16::MAX)) as u16;
+        if unsafe { libc::ioctl(fd, evdev::EVIOCSFF, &mut effect as *mut evdev::FfEffect) } < 0 {
+            result = Err(format!("{device}: no force feedback ({})", std::io::Error::last_os_error()));
+            break;
+        }
+        if let Err(e) = file.write_all(&evdev::play(effect.id)) {
+            result = Err(format!("{device}: {e}"));
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(u64::from(on + off)));
+    }
+    if effect.id >= 0 {
+        unsafe { libc::ioctl(fd, evdev::EVIOCRMFF, libc::c_int::from(effect.id)) };
+    }
+    Ok(result?)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn rumble(_device: &str, _magnitude: u16, _steps: &[(u32, u32)]) -> Result<(), Box<dyn std::error::Error>> {
+    Err("haptic output needs Linux evdev".into())
+}
+
+/// The bits of `<linux/input.h>` force feedback needs.
+#[cfg(target_os = "linux")]
+mod evdev {
+    const EV_FF: u16 = 0x15;
+    const FF_RUMBLE: u16 = 0x50;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct FfReplay {
+        pub length: u16,
+        pub delay: u16,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct FfRumble {
+        strong_magnitude: u16,
+        weak_magnitude: u16,
+    }
+
+    /// Only there to give the union the kernel's size and alignment.
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct FfPeriodic {
+        waveform: u16,
+        period: u16,
+        magnitude: i16,
+        offset: i16,
+        phase: u16,
+        envelope: [u16; 4],
+        custom_len: u32,
+        custom_data: *mut i16,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    union FfUnion {
+        rumble: FfRumble,
+        periodic: FfPeriodic,
+    }
+
+    /// `struct ff_effect`.
+    #[repr(C)]
+    pub struct FfEffect {
+        kind: u16,
+        pub id: i16,
+        direction: u16,
+        trigger: [u16; 2],
+        pub replay: FfReplay,
+        u: FfUnion,
+    }
+
+    impl FfEffect {
+        /// A new rumble effect; the kernel assigns the id on upload.
+        pub fn rumble(magnitude: u16) -> Self {
+            Self {
+                kind: FF_RUMBLE,
+                id: -1,
+                direction: 0,
+                trigger: [0; 2],
+                replay: FfReplay { length: 0, delay: 0 },
+                u: FfUnion { rumble: FfRumble { strong_magnitude: magnitude, weak_magnitude: magnitude } },
+            }
+        }
+    }
+
+    /// `_IOW('E', nr, size)`.
+    const fn iow(nr: u64, size: usize) -> u64 {
+        (1 << 30) | ((size as u64) << 16) | ((b'E' as u64) << 8) | nr
+    }
+
+    pub const EVIOCSFF: u64 = iow(0x80, std::mem::size_of::<FfEffect>());
+    pub const EVIOCRMFF: u64 = iow(0x81, std::mem::size_of::<libc::c_int>());
+
+    /// `struct input_event` that starts effect `id` once.
+    pub fn play(id: i16) -> Vec<u8> {
+        let mut event = vec![0u8; std::mem::size_of::<libc::timeval>()];
+        event.extend_from_slice(&EV_FF.to_ne_bytes());
+        event.extend_from_slice(&(id as u16).to_ne_bytes());
+        event.extend_from_slice(&1i32.to_ne_bytes());
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configured_patterns_replace_builtin_ones() {
+        let cfg: HapticConfig =
+            serde_json::from_str(r#"{"device": "/dev/input/event5", "patterns": {"complete": [50, 50, 50], "permission": []}}"#).unwrap();
+        assert_eq!(cfg.pattern("complete"), Some(vec![50, 50, 50]));
+        assert_eq!(cfg.pattern("permission"), None);
+        assert_eq!(cfg.pattern("annoyed"), Some(vec![400]));
+        assert_eq!(cfg.pattern("greeting"), None);
+        assert_eq!(cfg.strength, 0.75);
+    }
+
+    #[test]
+    fn patterns_pair_up_and_stay_short() {
+        assert_eq!(steps(&[200, 100, 200]), [(200, 100), (200, 0)]);
+        assert_eq!(steps(&[2500, 400, 900]), [(2500, 400), (100, 0)]);
+        assert_eq!(steps(&[0, 100, 50]), [(50, 0)]);
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn ioctl_numbers_match_the_kernel() {
+        assert_eq!(evdev::EVIOCSFF, 0x4030_4580);
+        assert_eq!(evdev::EVIOCRMFF, 0x4004_4581);
+        assert_eq!(evdev::play(3).len(), 24);
+    }
+
+    #[test]
+    fn missing_device_is_an_error() {
+        let haptic = Haptic { device: "/nonexistent/event0".into(), strength: 1.0, pattern: vec![10] };
+        assert!(haptic.notify("t", "b").is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use std::path::Path;
+
+/// What the current process can reach: a graphical display, a D-Bus session
+/// bus, and an audio output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Environment {
+    pub display: bool,
+    pub dbus: bool,
+    pub audio: bool,
+}
+
+impl Environment {
+    /// No display and no session bus: desktop notifications cannot work.
+    pub fn is_headless(&self) -> bool {
+        !self.display && !self.dbus
+    }
+}
+
+/// Probe the environment. `force_headless` (config `headless`) overrides the
+/// display/bus detection when set.
+pub fn detect(force_headless: Option<bool>) -> Environment {
+    let mut env = Environment {
+        display: has_display(),
+        dbus: has_dbus(),
+        audio: has_audio(),
+    };
+    match force_headless {
+        Some(true) => {
+            env.display = false;
+            env.dbus = false;
+        }
+        Some(false) => {
+            env.display = true;
+            env.dbus = true;
+        }
+        None => {}
+    }
+    env
+}
+
+fn env_set(name: &str) -> bool {
+    std::env::var(name).is_ok_and(|v| !v.is_empty())
+}
+
+#[cfg(target_os = "macos")]
+fn has_display() -> bool {
+    true
+}
+
+#[cfg(not(target_os = "macos"))]
+fn has_display() -> bool {
+    env_set("DISPLAY") || env_set("WAYLAND_DISPLAY")
+}
+
+#[cfg(target_os = "macos")]
+fn has_dbus() -> bool {
+    true
+}
+
+#[cfg(not(target_os = "macos"))]
+fn has_dbus() -> bool {
+    if env_set("DBUS_SESSION_BUS_ADDRESS") {
+        return true;
+    }
+    // Many setups don't export the address but still run a bus at the default path
+    std::env::var("XDG_RUNTIME_DIR")
+        .is_ok_and(|dir| !dir.is_empty() && Path::new(&dir).join("bus").exists())
+}
+
+#[cfg(target_os = "macos")]
+fn has_audio() -> bool {
+    true
+}
+
+#[cfg(not(target_os = "macos"))]
+fn has_audio() -> bool {
+    if env_set("PULSE_SERVER") {
+        return true;
+    }
+    if let Ok(dir) = std::env::var("XDG_RUNTIME_DIR")
+        && !dir.is_empty()
+    {
+        let dir = Path::new(&dir);
+        if dir.join("pulse/native").exists() || dir.join("pipewire-0").exists() {
+            return true;
+        }
+    }
+    has_alsa_pcm(Path::new("/dev/snd"))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn has_alsa_pcm(dev_snd: &Path) -> bool {
+    dev_snd
+        .read_dir()
+        .map(|mut rd| rd.any(|e| e.is_ok_and(|e| e.file_name().to_string_lossy().starts_with("pcm"))))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_requires_no_display_and_no_bus() {
+        let env = Environment { display: false, dbus: false, audio: true };
+        assert!(env.is_headless());
+        let env = Environment { display: false, dbus: true, audio: true };
+        assert!(!env.is_headless());
+    }
+
+    #[test]
+    fn force_headless_overrides_detection() {
+        assert!(detect(Some(true)).is_headless());
+        assert!(!detect(Some(false)).is_headless());
+    }
+
+    #[test]
+    #[cfg(not(target_os = "macos"))]
+    fn alsa_pcm_detection() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!has_alsa_pcm(dir.path()));
+        std::fs::write(dir.path().join("pcmC0D0p"), "").unwrap();
+        assert!(has_alsa_pcm(dir.path()));
+    }
+}
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::{log, paths};
+
+/// What the hook did with an event.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Outcome {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    /// Why that theme: the resolution step that chose it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme_source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Sound file (relative to the theme's sounds/ dir) that was played.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sound: Option<String>,
+    #[serde(default)]
+    pub notified: bool,
+    /// Why the event was dropped before reaching playback/notification.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+}
+
+impl Outcome {
+    pub fn skipped(reason: impl Into<String>) -> Self {
+        Self { skipped: Some(reason.into()), ..Default::default() }
+    }
+}
+
+/// One line of the history store.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub id: String,
+    pub timestamp: u64,
+    /// The raw HookInput payload as received.
+    pub input: serde_json::Value,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+pub fn history_path() -> PathBuf {
+    paths::state_dir().join("history.jsonl")
+}
+
+/// Once the history grows past this, it moves to `history.1.jsonl`
+/// (replacing the one before) and a new file starts.
+const MAX_BYTES: u64 = 4 * 1024 * 1024;
+
+fn rotated_path(path: &Path) -> PathBuf {
+    path.with_extension("1.jsonl")
+}
+
+fn new_id() -> String {
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    format!("{millis:x}{:04x}", std::process::id() & 0xffff)
+}
+
+/// Append an event and its outcome to the history store. Failures are ignored.
+pub fn append(input: &serde_json::Value, outcome: &Outcome) {
+    let record = Record {
+        id: new_id(),
+        timestamp: log::now_secs(),
+        input: input.clone(),
+        outcome: outcome.clone(),
+    };
+    let _ = append_to(&history_path(), &record, MAX_BYTES);
+}
+
+fn append_to(path: &Path, record: &Record, max_bytes: u64) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if std::fs::metadata(path).is_ok_and(|m| m.len() >= max_bytes) {
+        std::fs::rename(path, rotated_path(path))?;
+    }
+    let line = serde_json::to_string(record)?;
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{line}")
+}
+
+/// Read all records, oldest first, the rotated file included. Malformed
+/// lines are skipped.
+pub fn load() -> Vec<Record> {
+    load_from(&history_path())
+}
+
+fn load_from(path: &Path) -> Vec<Record> {
+    [rotated_path(path), path.to_path_buf()]
+        .iter()
+        .flat_map(|p| std::fs::read_to_string(p).unwrap_or_default().lines().filter_map(|l| serde_json::from_str(l).ok()).collect::<Vec<_>>())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let record = Record {
+            id: "abc".to_string(),
+            timestamp: 42,
+            input: serde_json::json!({"hook_event_name": "Stop"}),
+            outcome: Outcome {
+                theme: Some("peon".to_string()),
+                theme_source: None,
+                category: Some("complete".to_string()),
+                sound: Some("jobsdone.wav".to_string()),
+                notified: true,
+                skipped: None,
+            },
+        };
+        append_to(&path, &record, MAX_BYTES).unwrap();
+        append_to(&path, &Record { id: "def".to_string(), ..record }, MAX_BYTES).unwrap();
+        std::fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"garbage\n").unwrap();
+
+        let records = load_from(&path);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].id, "abc");
+        assert_eq!(records[1].outcome.sound.as_deref(), Some("jobsdone.wav"));
+    }
+
+    #[test]
+    fn rotates_past_the_size_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        for id in ["a", "b", "c", "d", "e", "f"] {
+            let record = Record { id: id.to_string(), timestamp: 1, input: serde_json::json!({}), outcome: Outcome::default() };
+            append_to(&path, &record, 100).unwrap();
+        }
+        // Each line is over 50 bytes: two per file, and only one old file kept
+        assert!(dir.path().join("history.1.jsonl").is_file());
+        let ids: Vec<String> = load_from(&path).into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, ["c", "d", "e", "f"]);
+    }
+
+    #[test]
+    fn skipped_outcome_serializes_reason() {
+        let line = serde_json::to_string(&Outcome::skipped("session muted")).unwrap();
+        assert!(line.contains(r#""skipped":"session muted""#));
+        assert!(!line.contains("theme"));
+    }
+}
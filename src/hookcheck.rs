@@ -0,0 +1,133 @@
+//! Noticing lost hooks: when Claude Code's settings.json is reset or
+//! rewritten without ringring's entries, the hook still runs for the events
+//! that are left and reports the missing ones, once, so the user can run
+//! `ringring install --repair`.
+//!
+//! Kept cheap for hook mode: two `stat`s compared with a cached stamp, and
+//! settings.json is only hashed (and parsed) after it changed.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::fastpath::{self, Stamp};
+use crate::{config, install, paths};
+
+/// File in the data dir naming the settings.json `install` registered the
+/// hooks in. Without it nothing is checked.
+const MARKER: &str = "hooks-settings";
+
+fn stamp(path: &Path) -> Option<Stamp> {
+    fs::metadata(path).ok().map(|meta| Stamp::of(&meta))
+}
+
+/// What the last check saw.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct State {
+    settings: Option<Stamp>,
+    config: Option<Stamp>,
+    sha256: String,
+    /// Missing events already reported.
+    warned: Vec<String>,
+}
+
+pub fn cache_path() -> PathBuf {
+    paths::runtime_dir().join("hooks.json")
+}
+
+/// Remember that the hooks live in `settings_path`, for `check`.
+pub fn remember_settings(data_dir: &Path, settings_path: &Path) -> std::io::Result<()> {
+    let path = std::path::absolute(settings_path)?;
+    fs::write(data_dir.join(MARKER), path.to_string_lossy().as_bytes())
+}
+
+/// Whether `install` (or `assets install`) registered hooks for this user.
+pub fn installed(data_dir: &Path) -> bool {
+    data_dir.join(MARKER).exists()
+}
+
+/// Stop checking, after `uninstall`.
+pub fn forget_settings(data_dir: &Path) {
+    let _ = fs::remove_file(data_dir.join(MARKER));
+}
+
+/// Hook events that should have a ringring hook but don't, when that's news:
+/// `None` if nothing changed since the last check, nothing is missing, or
+/// the same events were reported before.
+pub fn check(data_dir: &Path) -> Option<Vec<String>> {
+    check_with(data_dir, &cache_path())
+}
+
+fn check_with(data_dir: &Path, cache: &Path) -> Option<Vec<String>> {
+    let settings_path = PathBuf::from(fs::read_to_string(data_dir.join(MARKER)).ok()?.trim());
+    let settings = stamp(&settings_path);
+    let config = stamp(&data_dir.join("config.json"));
+    let mut state: State = fs::read(cache).ok().and_then(|bytes| serde_json::from_slice(&bytes).ok()).unwrap_or_default();
+    if state.settings.is_some_and(|s| Some(s) == settings) && state.config == config {
+        return None;
+    }
+
+    let content = fs::read(&settings_path).unwrap_or_default();
+    let sha256 = {
+        use sha2::Digest;
+        format!("{:x}", sha2::Sha256::digest(&content))
+    };
+    if sha256 == state.sha256 && state.config == config {
+        // Touched, not changed
+        state.settings = settings;
+        save(cache, &state);
+        return None;
+    }
+    let root: serde_json::Value = if content.is_empty() {
+        serde_json::json!({})
+    } else {
+        // Half written, most likely; look again next time
+        serde_json::from_slice(&content).ok()?
+    };
+
+    let registered = install::registered_events(&root);
+    let missing: Vec<String> = install::hook_plan(&config::Config::load(data_dir), &[])
+        .into_iter()
+        .map(|hook| hook.event)
+        .filter(|event| !registered.contains(event))
+        .collect();
+    let news = !missing.is_empty() && missing != state.warned;
+    save(cache, &State { settings, config, sha256, warned: missing.clone() });
+    news.then_some(missing)
+}
+
+fn save(cache: &Path, state: &State) {
+    if let Ok(json) = serde_json::to_vec(state) {
+        let _ = fastpath::write_atomic(cache, &json);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lost_hooks_are_reported_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = dir.path().join("settings.json");
+        let cache = dir.path().join("run/hooks.json");
+        assert_eq!(check_with(dir.path(), &cache), None, "not installed, nothing to check");
+
+        remember_settings(dir.path(), &settings).unwrap();
+        fs::write(dir.path().join("config.json"), r#"{"events": {"SessionEnd": false, "UserPromptSubmit": false, "PermissionRequest": false}}"#)
+            .unwrap();
+        let hooks = r#"{"matcher": "", "hooks": [{"type": "command", "command": "ringring"}]}"#;
+        fs::write(&settings, format!(r#"{{"hooks": {{"SessionStart": [{hooks}], "Stop": [{hooks}], "Notification": [{hooks}]}}}}"#)).unwrap();
+        assert_eq!(check_with(dir.path(), &cache), None);
+
+        fs::write(&settings, format!(r#"{{"hooks": {{"Stop": [{hooks}]}}, "model": "opus"}}"#)).unwrap();
+        assert_eq!(check_with(dir.path(), &cache), Some(vec!["SessionStart".to_string(), "Notification".to_string()]));
+        assert_eq!(check_with(dir.path(), &cache), None);
+        // Rewritten with the same hooks missing: already reported
+        fs::write(&settings, format!(r#"{{"hooks": {{"Stop": [{hooks}]}}, "model": "sonnet"}}"#)).unwrap();
+        assert_eq!(check_with(dir.path(), &cache), None);
+
+        fs::remove_file(&settings).unwrap();
+        assert_eq!(check_with(dir.path(), &cache).map(|missing| missing.len()), Some(3));
+    }
+}
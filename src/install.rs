@@ -1,21 +1,92 @@
-use std::path::Path;
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
 
 /// Copy the running binary to `dest_dir/ringring` with executable permissions.
 pub fn install_binary(dest_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
     let exe = std::env::current_exe()?;
     std::fs::create_dir_all(dest_dir)?;
-    let dest = dest_dir.join("ringring");
-    std::fs::copy(&exe, &dest)?;
+    replace_binary(&dest_dir.join("ringring"), &std::fs::read(&exe)?)?;
+    Ok(())
+}
+
+/// Write `bytes` next to `dest`, make it executable and rename it over
+/// `dest`, so hooks starting meanwhile run either the old binary or the new
+/// one, and a running one isn't overwritten in place.
+pub fn replace_binary(dest: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let dir = dest.parent().unwrap_or(Path::new("."));
+    let mut tmp = tempfile::Builder::new().prefix(".ringring-").tempfile_in(dir)?;
+    std::io::Write::write_all(&mut tmp, bytes)?;
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
-        let mut perms = std::fs::metadata(&dest)?.permissions();
-        perms.set_mode(0o755);
-        std::fs::set_permissions(&dest, perms)?;
+        tmp.as_file().set_permissions(std::fs::Permissions::from_mode(0o755))?;
     }
+    tmp.persist(dest).map_err(|e| e.error)?;
     Ok(())
 }
 
+/// Where `update` puts a new release: the `ringring` the hooks run, else
+/// the running one.
+pub fn update_target() -> std::io::Result<PathBuf> {
+    on_path().map_or_else(std::env::current_exe, Ok)
+}
+
+/// The `ringring` the hooks would run: the first one on PATH.
+pub fn on_path() -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).map(|dir| dir.join("ringring")).find(|bin| bin.is_file())
+}
+
+/// Whether `exe` was put in place by a package manager rather than
+/// `install`: it lives outside `home`, so only the user-level setup
+/// (`assets install`) is missing.
+pub fn packaged(exe: &Path, home: &Path) -> bool {
+    !home.as_os_str().is_empty() && !exe.starts_with(home)
+}
+
+/// How an installed binary compares with the running one, for `repair`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryState {
+    Current,
+    Missing,
+    /// A different build, e.g. left over from an older install.
+    Stale,
+    NotExecutable,
+}
+
+/// Check the binary at `dest` against `exe`.
+pub fn binary_state(dest: &Path, exe: &Path) -> BinaryState {
+    let Some(installed) = file_hash(dest) else { return BinaryState::Missing };
+    if file_hash(exe).is_some_and(|running| running != installed) {
+        return BinaryState::Stale;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if std::fs::metadata(dest).is_ok_and(|m| m.permissions().mode() & 0o111 != 0o111) {
+            return BinaryState::NotExecutable;
+        }
+    }
+    BinaryState::Current
+}
+
+/// Make `path` executable by everyone, keeping its other permissions.
+pub fn make_executable(path: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = std::fs::metadata(path)?.permissions();
+        perms.set_mode(perms.mode() | 0o755);
+        std::fs::set_permissions(path, perms)?;
+    }
+    Ok(())
+}
+
+/// Whether the /ringring slash command in `claude_dir` is this version's.
+pub fn command_installed(claude_dir: &Path) -> bool {
+    std::fs::read_to_string(claude_dir.join("commands/ringring.md")).is_ok_and(|s| s == include_str!("../commands/ringring.md"))
+}
+
 /// Install the /ringring slash command into ~/.claude/commands/.
 pub fn install_command(claude_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
     let commands_dir = claude_dir.join("commands");
@@ -25,76 +96,631 @@ pub fn install_command(claude_dir: &Path) -> Result<(), Box<dyn std::error::Erro
     Ok(())
 }
 
-/// Merge ringring hook entries into the Claude Code settings.json at `settings_path`.
-pub fn register_hooks(settings_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    let content = std::fs::read_to_string(settings_path).unwrap_or_else(|_| "{}".to_string());
-    let mut root: serde_json::Value = serde_json::from_str(&content).unwrap_or(serde_json::json!({}));
+/// systemd user unit for the daemon's listening socket. `%t` is the user
+/// runtime dir, matching `paths::runtime_dir()`.
+const SOCKET_UNIT: &str = "[Unit]
+Description=ringring sound and notification daemon socket
 
-    if !root.is_object() {
-        root = serde_json::json!({});
+[Socket]
+ListenStream=%t/ringring/daemon.sock
+SocketMode=0600
+DirectoryMode=0700
+
+[Install]
+WantedBy=sockets.target
+";
+
+fn service_unit(exe: &Path) -> String {
+    format!(
+        "[Unit]
+Description=ringring sound and notification daemon
+Requires=ringring.socket
+After=ringring.socket
+
+[Service]
+ExecStart={} daemon
+Restart=on-failure
+
+[Install]
+WantedBy=default.target
+",
+        exe.display()
+    )
+}
+
+/// Write `ringring.service` and `ringring.socket` into the systemd user unit
+/// dir `units_dir`, running `exe`. Returns the paths written.
+pub fn install_service_units(units_dir: &Path, exe: &Path) -> Result<Vec<std::path::PathBuf>, Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(units_dir)?;
+    let service = units_dir.join("ringring.service");
+    let socket = units_dir.join("ringring.socket");
+    std::fs::write(&service, service_unit(exe))?;
+    std::fs::write(&socket, SOCKET_UNIT)?;
+    Ok(vec![service, socket])
+}
+
+/// Reload systemd and enable + start the socket unit.
+pub fn enable_service() -> Result<(), Box<dyn std::error::Error>> {
+    for args in [&["--user", "daemon-reload"][..], &["--user", "enable", "--now", "ringring.socket"][..]] {
+        let status = std::process::Command::new("systemctl").args(args).status()?;
+        if !status.success() {
+            return Err(format!("systemctl {} failed", args.join(" ")).into());
+        }
     }
-    if !root["hooks"].is_object() {
-        root["hooks"] = serde_json::json!({});
+    Ok(())
+}
+
+/// Hook events `install` registers unless config `events` switches them off.
+pub const HOOK_EVENTS: [&str; 6] = ["SessionStart", "SessionEnd", "Stop", "Notification", "PermissionRequest", "UserPromptSubmit"];
+
+/// Hook events registered only when config `events` switches them on.
+pub const OPTIONAL_EVENTS: [&str; 1] = ["SubagentStop"];
+
+/// Every event `install --events` can choose from.
+pub fn selectable_events() -> impl Iterator<Item = &'static str> {
+    HOOK_EVENTS.into_iter().chain(OPTIONAL_EVENTS)
+}
+
+/// A comma separated `--events` list, each one checked against
+/// `selectable_events`.
+pub fn parse_events(list: &str) -> Result<Vec<String>, String> {
+    let mut events = Vec::new();
+    for event in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        if !selectable_events().any(|known| known == event) {
+            let known: Vec<&str> = selectable_events().collect();
+            return Err(format!("unknown hook event '{event}' (expected: {})", known.join(", ")));
+        }
+        if !events.iter().any(|e| e == event) {
+            events.push(event.to_string());
+        }
+    }
+    if events.is_empty() {
+        return Err("no hook events given".to_string());
+    }
+    Ok(events)
+}
+
+/// Record a choice of hook events in raw config.json `events`, so the hook
+/// and later installs agree with it: chosen default events lose their
+/// switch (they are on anyway), chosen optional ones are switched on and
+/// every other selectable event off. Other `events` entries are kept.
+pub fn choose_events(root: &mut serde_json::Value, chosen: &[String]) {
+    if !root["events"].is_object() {
+        root["events"] = serde_json::json!({});
+    }
+    let switches = root["events"].as_object_mut().unwrap();
+    for event in selectable_events() {
+        let on = chosen.iter().any(|c| c == event);
+        if on && HOOK_EVENTS.contains(&event) {
+            switches.remove(event);
+        } else {
+            switches.insert(event.to_string(), serde_json::Value::Bool(on));
+        }
     }
+    if switches.is_empty()
+        && let Some(root) = root.as_object_mut()
+    {
+        root.remove("events");
+    }
+}
 
-    let events = ["SessionStart", "Stop", "Notification", "PermissionRequest"];
+/// A hook event to register ringring for, with the settings.json matcher
+/// that narrows it ("" for every occurrence).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hook {
+    pub event: String,
+    pub matcher: String,
+}
+
+/// The hooks to register: the default events config `events` leaves on,
+/// then any other event it switches on or gives a matcher. A `--matcher`
+/// from `overrides` (event, pattern) beats config `hook_matchers` and
+/// registers its event even when config switches it off.
+pub fn hook_plan(cfg: &crate::config::Config, overrides: &[(String, String)]) -> Vec<Hook> {
+    let mut events: Vec<&str> = HOOK_EVENTS.to_vec();
+    let switched_on = cfg.events.iter().filter(|(_, on)| **on).map(|(event, _)| event.as_str());
+    let mut extra: Vec<&str> = switched_on.chain(cfg.hook_matchers.keys().map(String::as_str)).collect();
+    extra.sort_unstable();
+    events.extend(extra);
+    events.extend(overrides.iter().map(|(event, _)| event.as_str()));
 
+    let mut hooks: Vec<Hook> = Vec::new();
     for event in events {
-        if !root["hooks"][event].is_array() {
-            root["hooks"][event] = serde_json::json!([]);
+        if hooks.iter().any(|h| h.event == event) {
+            continue;
         }
+        let matcher = match overrides.iter().rev().find(|(e, _)| e == event) {
+            Some((_, matcher)) => matcher.clone(),
+            None if cfg.event_enabled(event) => cfg.hook_matchers.get(event).cloned().unwrap_or_default(),
+            None => continue,
+        };
+        hooks.push(Hook { event: event.to_string(), matcher });
+    }
+    hooks
+}
 
-        // Remove legacy ~/.claude/ringring entries
-        if let Some(arr) = root["hooks"][event].as_array_mut() {
-            arr.retain(|entry| {
-                !entry["hooks"]
-                    .as_array()
-                    .map(|hooks| {
-                        hooks.iter().any(|h| {
-                            h["command"]
-                                .as_str()
-                                .is_some_and(|c| c.contains(".claude/ringring"))
-                        })
-                    })
-                    .unwrap_or(false)
-            });
+/// A pending change to the Claude Code settings.json, with what the file
+/// held when it was read.
+pub struct SettingsEdit {
+    pub path: PathBuf,
+    before: String,
+    /// sha256 of the file as read; `None` when it didn't exist.
+    read_hash: Option<Vec<u8>>,
+    after: String,
+    /// Hook events the change registers, or whose matcher it changes.
+    pub added: Vec<String>,
+    /// Hook events the change unregisters because they are no longer wanted.
+    pub removed: Vec<String>,
+}
+
+impl SettingsEdit {
+    /// Whether writing would change the file.
+    pub fn changes(&self) -> bool {
+        self.read_hash.is_none() || self.before != self.after
+    }
+
+    /// The change as a unified diff.
+    pub fn diff(&self) -> Vec<String> {
+        let path = self.path.display().to_string();
+        let from = if self.read_hash.is_some() { path.clone() } else { "/dev/null".to_string() };
+        crate::repair::unified_diff(&self.before, &self.after, &from, &path)
+    }
+
+    /// Write the new settings, unless the file changed since it was read
+    /// (e.g. Claude Code saved it meanwhile), which would lose that edit.
+    pub fn apply(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if file_hash(&self.path) != self.read_hash {
+            return Err(format!("{} changed while ringring was editing it; nothing written, run this again", self.path.display()).into());
         }
+        write_settings(&self.path, &self.after)
+    }
+}
 
-        let already = root["hooks"][event]
-            .as_array()
-            .map(|arr| {
-                arr.iter().any(|entry| {
-                    entry["hooks"]
-                        .as_array()
-                        .map(|hooks| hooks.iter().any(|h| h["command"] == "ringring"))
-                        .unwrap_or(false)
-                })
-            })
-            .unwrap_or(false);
+fn file_hash(path: &Path) -> Option<Vec<u8>> {
+    use sha2::Digest;
+    std::fs::read(path).ok().map(|bytes| sha2::Sha256::digest(bytes).to_vec())
+}
 
+/// Whether a settings.json hook entry holds a hook running `command` exactly.
+fn runs(entry: &serde_json::Value, command: impl Fn(&str) -> bool) -> bool {
+    entry["hooks"].as_array().is_some_and(|hooks| hooks.iter().any(|h| h["command"].as_str().is_some_and(&command)))
+}
+
+/// Drop the plain `ringring` hook from the entries `which` selects, then
+/// entries left without hooks. Returns whether any was dropped.
+fn drop_ringring(entries: &mut Vec<serde_json::Value>, which: impl Fn(&serde_json::Value) -> bool) -> bool {
+    let mut dropped = false;
+    for entry in entries.iter_mut().filter(|entry| which(entry)) {
+        if let Some(hooks) = entry.get_mut("hooks").and_then(|h| h.as_array_mut()) {
+            let before = hooks.len();
+            hooks.retain(|h| h["command"] != "ringring");
+            dropped |= hooks.len() < before;
+        }
+    }
+    entries.retain(|e| e["hooks"].as_array().is_none_or(|hooks| !hooks.is_empty()));
+    dropped
+}
+
+/// Events with a plain `ringring` hook in a parsed settings.json.
+pub fn registered_events(settings: &serde_json::Value) -> Vec<String> {
+    let Some(events) = settings["hooks"].as_object() else {
+        return Vec::new();
+    };
+    events
+        .iter()
+        .filter(|(_, entries)| entries.as_array().is_some_and(|entries| entries.iter().any(|e| runs(e, |c| c == "ringring"))))
+        .map(|(event, _)| event.clone())
+        .collect()
+}
+
+/// The settings.json at `settings_path` with ringring registered for exactly
+/// `hooks`: missing entries added, matchers brought up to date, and the
+/// plain `ringring` hook dropped from events no longer in the list.
+pub fn plan_hooks(settings_path: &Path, hooks: &[Hook]) -> Result<SettingsEdit, Box<dyn std::error::Error>> {
+    plan(settings_path, hooks, true)
+}
+
+/// `plan_hooks`, leaving events outside `hooks` alone unless `prune`.
+fn plan(settings_path: &Path, hooks: &[Hook], prune: bool) -> Result<SettingsEdit, Box<dyn std::error::Error>> {
+    let read_hash = file_hash(settings_path);
+    let before = std::fs::read_to_string(settings_path).unwrap_or_default();
+    let mut root: serde_json::Value = serde_json::from_str(&before).unwrap_or(serde_json::json!({}));
+    if !root.is_object() {
+        root = serde_json::json!({});
+    }
+    if !root["hooks"].is_object() {
+        root["hooks"] = serde_json::json!({});
+    }
+    let settings_hooks = root["hooks"].as_object_mut().unwrap();
+
+    let mut removed = Vec::new();
+    for (event, entries) in settings_hooks.iter_mut() {
+        let Some(entries) = entries.as_array_mut() else { continue };
+        // Remove legacy ~/.claude/ringring entries
+        entries.retain(|entry| !runs(entry, |c| c.contains(".claude/ringring")));
+        if prune && !hooks.iter().any(|h| &h.event == event) && drop_ringring(entries, |_| true) {
+            removed.push(event.clone());
+        }
+    }
+    settings_hooks.retain(|event, entries| !removed.contains(event) || entries.as_array().is_none_or(|e| !e.is_empty()));
+
+    let mut added = Vec::new();
+    for hook in hooks {
+        let entries = settings_hooks.entry(hook.event.clone()).or_insert_with(|| serde_json::json!([]));
+        if !entries.is_array() {
+            *entries = serde_json::json!([]);
+        }
+        let entries = entries.as_array_mut().unwrap();
+        drop_ringring(entries, |entry| entry["matcher"].as_str().unwrap_or_default() != hook.matcher);
+        let already = entries
+            .iter()
+            .any(|entry| entry["matcher"].as_str().unwrap_or_default() == hook.matcher && runs(entry, |c| c == "ringring"));
         if !already {
-            let entry = serde_json::json!({
-                "matcher": "",
+            entries.push(serde_json::json!({
+                "matcher": hook.matcher,
                 "hooks": [{"type": "command", "command": "ringring"}]
-            });
-            root["hooks"][event].as_array_mut().unwrap().push(entry);
+            }));
+            added.push(hook.event.clone());
         }
     }
 
-    // Atomic write: write to .tmp then rename
+    let after = serde_json::to_string_pretty(&root)?;
+    Ok(SettingsEdit { path: settings_path.to_path_buf(), before, read_hash, after, added, removed })
+}
+
+/// Register ringring for `hooks` in the Claude Code settings.json at
+/// `settings_path` (see `plan_hooks`). The diff is logged, and `approve`
+/// sees the edit before anything is written; when it says no, the file is
+/// left alone. Returns the edit, for its `added` and `removed` events.
+pub fn register_hooks(
+    settings_path: &Path,
+    hooks: &[Hook],
+    approve: impl FnOnce(&SettingsEdit) -> bool,
+) -> Result<SettingsEdit, Box<dyn std::error::Error>> {
+    confirm_edit(plan_hooks(settings_path, hooks)?, approve)
+}
+
+/// Add ringring back for those of `hooks` whose event has no ringring hook
+/// in `settings_path`, touching nothing else (`install --repair`). Like
+/// `register_hooks`, nothing is written unless `approve` agrees.
+pub fn repair_hooks(
+    settings_path: &Path,
+    hooks: &[Hook],
+    approve: impl FnOnce(&SettingsEdit) -> bool,
+) -> Result<SettingsEdit, Box<dyn std::error::Error>> {
+    let current: serde_json::Value =
+        std::fs::read_to_string(settings_path).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default();
+    let registered = registered_events(&current);
+    let missing: Vec<Hook> = hooks.iter().filter(|h| !registered.contains(&h.event)).cloned().collect();
+    confirm_edit(plan(settings_path, &missing, false)?, approve)
+}
+
+/// Log the diff of `edit` and write it if `approve` agrees.
+fn confirm_edit(edit: SettingsEdit, approve: impl FnOnce(&SettingsEdit) -> bool) -> Result<SettingsEdit, Box<dyn std::error::Error>> {
+    if !edit.changes() {
+        return Ok(edit);
+    }
+    crate::log::write(&format!("install: changes to {}:\n{}", edit.path.display(), edit.diff().join("\n")));
+    if !approve(&edit) {
+        return Err(format!("not confirmed; {} left unchanged", edit.path.display()).into());
+    }
+    edit.apply()?;
+    Ok(edit)
+}
+
+/// Whether a settings.json hook command runs ringring (plain, with
+/// arguments such as `forward`, by path, or the legacy `~/.claude/ringring`).
+fn is_ringring_command(command: &str) -> bool {
+    let program = command.split_whitespace().find(|word| !word.contains('='));
+    program.is_some_and(|p| Path::new(p).file_name().is_some_and(|name| name == "ringring")) || command.contains(".claude/ringring")
+}
+
+/// Remove ringring's hooks from the settings.json at `settings_path` (only
+/// for `events`, when given), keeping every other hook. Entries and event
+/// lists left empty are dropped. Returns the events that had a ringring hook.
+pub fn unregister_hooks(settings_path: &Path, events: Option<&[String]>) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let Ok(content) = std::fs::read_to_string(settings_path) else {
+        return Ok(Vec::new());
+    };
+    let mut root: serde_json::Value = serde_json::from_str(&content)?;
+    let Some(hooks) = root.get_mut("hooks").and_then(|h| h.as_object_mut()) else {
+        return Ok(Vec::new());
+    };
+
+    let mut removed = Vec::new();
+    for (event, entries) in hooks.iter_mut() {
+        let Some(entries) = entries.as_array_mut() else { continue };
+        if events.is_some_and(|events| !events.contains(event)) {
+            continue;
+        }
+        let before: usize = entries.iter().map(|e| e["hooks"].as_array().map_or(0, Vec::len)).sum();
+        for entry in entries.iter_mut() {
+            if let Some(list) = entry.get_mut("hooks").and_then(|h| h.as_array_mut()) {
+                list.retain(|h| !h["command"].as_str().is_some_and(is_ringring_command));
+            }
+        }
+        let after: usize = entries.iter().map(|e| e["hooks"].as_array().map_or(0, Vec::len)).sum();
+        if after < before {
+            entries.retain(|e| e["hooks"].as_array().is_none_or(|list| !list.is_empty()));
+            removed.push(event.clone());
+        }
+    }
+    hooks.retain(|event, entries| !removed.contains(event) || entries.as_array().is_none_or(|e| !e.is_empty()));
+
+    if !removed.is_empty() {
+        write_settings(settings_path, &serde_json::to_string_pretty(&root)?)?;
+    }
+    Ok(removed)
+}
+
+/// Atomic write: write to .tmp then rename
+fn write_settings(settings_path: &Path, content: &str) -> Result<(), Box<dyn std::error::Error>> {
     if let Some(parent) = settings_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
     let tmp_path = settings_path.with_extension("json.tmp");
-    let serialized = serde_json::to_string_pretty(&root)?;
-    std::fs::write(&tmp_path, serialized)?;
+    std::fs::write(&tmp_path, content)?;
     std::fs::rename(&tmp_path, settings_path)?;
-
     Ok(())
 }
 
+/// Per-session flag files ringring leaves in `tmp_dir` (theme cache, mute
+/// flag, pid map).
+pub fn session_files(tmp_dir: &Path) -> Vec<PathBuf> {
+    let prefixes = [".claude-theme-", ".claude-mute-", ".claude-ringring-cpid-"];
+    let Ok(entries) = std::fs::read_dir(tmp_dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| e.file_name().to_str().is_some_and(|name| prefixes.iter().any(|p| name.starts_with(p))))
+        .map(|e| e.path())
+        .collect()
+}
+
+/// A place themes can be installed from. `fetch` only has to put the files
+/// somewhere under the staging dir; `stage` validates the result, and
+/// `StagedTheme::install` moves it into the data dir.
+pub trait ThemeSource {
+    /// The source as the user gave it, for messages.
+    fn describe(&self) -> String;
+
+    /// Write the theme into `staging` (as `staging/<name>/...`).
+    fn fetch(&self, staging: &Path) -> Result<Fetched, Box<dyn std::error::Error>>;
+
+    /// sha256 of the archive the source would install now, for sources that
+    /// can tell without downloading the theme.
+    fn latest_sha256(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        Ok(None)
+    }
+}
+
+/// What a `ThemeSource` put into the staging dir.
+pub struct Fetched {
+    pub name: String,
+    /// sha256 of the archive it came from, when there was one.
+    pub sha256: Option<String>,
+}
+
+/// A theme source directory on disk; symlinks inside it are skipped.
+pub struct DirSource(pub PathBuf);
+
+impl ThemeSource for DirSource {
+    fn describe(&self) -> String {
+        self.0.display().to_string()
+    }
+
+    fn fetch(&self, staging: &Path) -> Result<Fetched, Box<dyn std::error::Error>> {
+        let name = crate::pack::theme_name(&self.0)?;
+        for (rel, bytes) in crate::pack::read_tree(&self.0)? {
+            let out = staging.join(&name).join(rel);
+            if let Some(parent) = out.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(out, bytes)?;
+        }
+        Ok(Fetched { name, sha256: None })
+    }
+}
+
+/// A zip with one top-level directory holding the theme.
+pub struct ZipSource(pub PathBuf);
+
+impl ThemeSource for ZipSource {
+    fn describe(&self) -> String {
+        self.0.display().to_string()
+    }
+
+    fn fetch(&self, staging: &Path) -> Result<Fetched, Box<dyn std::error::Error>> {
+        unpack_bytes(std::fs::read(&self.0)?, staging)
+    }
+}
+
+/// A theme zip at an http(s) URL.
+pub struct UrlSource(pub String);
+
+impl ThemeSource for UrlSource {
+    fn describe(&self) -> String {
+        self.0.clone()
+    }
+
+    fn fetch(&self, staging: &Path) -> Result<Fetched, Box<dyn std::error::Error>> {
+        unpack_bytes(download(&self.0)?, staging)
+    }
+}
+
+/// A git repository whose top level is the theme; named after the repo.
+pub struct GitSource(pub String);
+
+impl ThemeSource for GitSource {
+    fn describe(&self) -> String {
+        self.0.clone()
+    }
+
+    fn fetch(&self, staging: &Path) -> Result<Fetched, Box<dyn std::error::Error>> {
+        let url = self.0.strip_prefix("git+").unwrap_or(&self.0);
+        let name = url.trim_end_matches('/').rsplit(['/', ':']).next().unwrap_or_default().trim_end_matches(".git").to_string();
+        crate::config::validate_theme_name(&name)?;
+        let dest = staging.join(&name);
+        let _spinner = crate::ui::Spinner::start(&format!("cloning {url}"));
+        let status = std::process::Command::new("git")
+            .args(["clone", "--quiet", "--depth", "1", "--", url])
+            .arg(&dest)
+            .stdin(std::process::Stdio::null())
+            .status()
+            .map_err(|e| format!("git: {e}"))?;
+        if !status.success() {
+            return Err(format!("git clone {url} failed").into());
+        }
+        std::fs::remove_dir_all(dest.join(".git"))?;
+        Ok(Fetched { name, sha256: None })
+    }
+}
+
+/// A theme by name from the registry (see `registry`). The download must
+/// match the entry's sha256.
+pub struct RegistrySource {
+    pub name: String,
+    pub registry: crate::publish::RegistryConfig,
+}
+
+impl ThemeSource for RegistrySource {
+    fn describe(&self) -> String {
+        format!("registry theme '{}'", self.name)
+    }
+
+    fn fetch(&self, staging: &Path) -> Result<Fetched, Box<dyn std::error::Error>> {
+        let entry = crate::registry::find(&self.registry, &self.name)?;
+        let url = entry.url.as_deref().ok_or_else(|| format!("registry entry for '{}' has no url", self.name))?;
+        let zip = download(url)?;
+        if entry.sha256.as_deref() != Some(crate::publish::sha256_hex(&zip).as_str()) {
+            return Err(format!("{url} does not match the registry's sha256").into());
+        }
+        let fetched = unpack_bytes(zip, staging)?;
+        if fetched.name != self.name {
+            return Err(format!("registry theme '{}' unpacks as '{}'", self.name, fetched.name).into());
+        }
+        Ok(fetched)
+    }
+
+    fn latest_sha256(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        Ok(crate::registry::find(&self.registry, &self.name)?.sha256)
+    }
+}
+
+/// Pick the source for a `theme install` argument: `git+<url>` or a `.git`
+/// URL is a repository, other http(s) URLs a zip, an existing path a
+/// directory or zip, and anything else a registry name.
+pub fn source_for(spec: &str, registry: &crate::publish::RegistryConfig) -> Box<dyn ThemeSource> {
+    let path = Path::new(spec);
+    if spec.starts_with("git+") || spec.starts_with("git@") || spec.ends_with(".git") {
+        Box::new(GitSource(spec.to_string()))
+    } else if spec.starts_with("http://") || spec.starts_with("https://") {
+        Box::new(UrlSource(spec.to_string()))
+    } else if path.is_dir() {
+        Box::new(DirSource(path.to_path_buf()))
+    } else if path.exists() || spec.contains('/') || spec.ends_with(".zip") {
+        Box::new(ZipSource(path.to_path_buf()))
+    } else {
+        Box::new(RegistrySource { name: spec.to_string(), registry: registry.clone() })
+    }
+}
+
+/// A fetched theme that passed validation, waiting in a temp dir.
+pub struct StagedTheme {
+    pub name: String,
+    /// sha256 of the archive, for the install receipt.
+    pub sha256: Option<String>,
+    /// The theme's files, ready to be checked before installing.
+    pub dir: PathBuf,
+    _staging: tempfile::TempDir,
+}
+
+/// Fetch `source` into a temp dir next to `data_dir` (so installing is a
+/// rename) and make sure it is a theme: a valid name and a manifest.json.
+pub fn stage(source: &dyn ThemeSource, data_dir: &Path) -> Result<StagedTheme, Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(data_dir)?;
+    let staging = tempfile::Builder::new().prefix(".staging-").tempdir_in(data_dir)?;
+    let Fetched { name, sha256 } = source.fetch(staging.path())?;
+    crate::config::validate_theme_name(&name)?;
+    let dir = staging.path().join(&name);
+    if !dir.join("manifest.json").exists() {
+        return Err(format!("theme '{name}' has no manifest.json").into());
+    }
+    Ok(StagedTheme { name, sha256, dir, _staging: staging })
+}
+
+impl StagedTheme {
+    /// Move the theme into `data_dir`, replacing an installed one only with `force`.
+    pub fn install(self, data_dir: &Path, force: bool) -> Result<String, Box<dyn std::error::Error>> {
+        let dest = data_dir.join(&self.name);
+        if dest.exists() {
+            if !force {
+                return Err(format!("theme '{}' already exists; use --force to overwrite", self.name).into());
+            }
+            std::fs::remove_dir_all(&dest)?;
+        }
+        std::fs::rename(&self.dir, &dest)?;
+        Ok(self.name)
+    }
+}
+
+/// Stage and install in one go. Returns the theme name on success.
+pub fn theme_install(source: &dyn ThemeSource, data_dir: &Path, force: bool) -> Result<String, Box<dyn std::error::Error>> {
+    stage(source, data_dir)?.install(data_dir, force)
+}
+
+/// Largest download accepted. Theme zips, registry entries and release
+/// binaries are far smaller; anything bigger is a broken or hostile server.
+const MAX_DOWNLOAD: usize = 256 << 20;
+
+/// GET `url`, with a progress bar when the size is known and a spinner otherwise.
+pub fn download(url: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let response = ureq::get(url).call()?;
+    let total = response.header("Content-Length").and_then(|len| len.parse::<usize>().ok());
+    let too_big = || format!("{url} is larger than {} MB", MAX_DOWNLOAD >> 20);
+    if total.is_some_and(|total| total > MAX_DOWNLOAD) {
+        return Err(too_big().into());
+    }
+    // One byte past the limit tells a body that is too big from one that fits
+    let mut reader = response.into_reader().take(MAX_DOWNLOAD as u64 + 1);
+    let Some(total) = total else {
+        let _spinner = crate::ui::Spinner::start(&format!("downloading {url}"));
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        if bytes.len() > MAX_DOWNLOAD {
+            return Err(too_big().into());
+        }
+        return Ok(bytes);
+    };
+    let mut progress = crate::ui::Progress::bytes("downloading", total);
+    // The header is only a hint; don't let it decide how much memory to take
+    let mut bytes = Vec::with_capacity(total.min(16 << 20));
+    let mut chunk = [0u8; 16 * 1024];
+    loop {
+        let n = reader.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        bytes.extend_from_slice(&chunk[..n]);
+        if bytes.len() > MAX_DOWNLOAD {
+            return Err(too_big().into());
+        }
+        progress.add(n);
+    }
+    Ok(bytes)
+}
+
+fn unpack_bytes(zip: Vec<u8>, staging: &Path) -> Result<Fetched, Box<dyn std::error::Error>> {
+    let sha256 = crate::publish::sha256_hex(&zip);
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(zip))?;
+    let name = zip_theme_name(&mut archive)?;
+    extract_zip(&mut archive, staging)?;
+    Ok(Fetched { name, sha256: Some(sha256) })
+}
+
 /// Find the single top-level directory name in a zip archive.
-fn zip_theme_name(archive: &mut zip::ZipArchive<std::fs::File>) -> Result<String, Box<dyn std::error::Error>> {
+fn zip_theme_name<R: Read + Seek>(archive: &mut zip::ZipArchive<R>) -> Result<String, Box<dyn std::error::Error>> {
     let mut top_dirs: std::collections::HashSet<String> = std::collections::HashSet::new();
     for i in 0..archive.len() {
         let entry = archive.by_index(i)?;
@@ -121,12 +747,16 @@ fn zip_theme_name(archive: &mut zip::ZipArchive<std::fs::File>) -> Result<String
             }
         ).into());
     }
-    Ok(top_dirs.into_iter().next().unwrap())
+    let name = top_dirs.into_iter().next().unwrap();
+    crate::config::validate_theme_name(&name)?;
+    Ok(name)
 }
 
 /// Extract a zip archive into `dest_parent`. All entries placed relative to `dest_parent`.
-fn extract_zip(archive: &mut zip::ZipArchive<std::fs::File>, dest_parent: &Path) -> Result<(), Box<dyn std::error::Error>> {
+fn extract_zip<R: Read + Seek>(archive: &mut zip::ZipArchive<R>, dest_parent: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut progress = crate::ui::Progress::new("extracting", archive.len());
     for i in 0..archive.len() {
+        progress.inc();
         let mut entry = archive.by_index(i)?;
         let Some(rel_path) = entry.enclosed_name() else { continue };
         let out_path = dest_parent.join(&rel_path);
@@ -143,55 +773,10 @@ fn extract_zip(archive: &mut zip::ZipArchive<std::fs::File>, dest_parent: &Path)
     Ok(())
 }
 
-/// Install a theme from a local zip path or an http(s):// URL.
-/// Returns the theme name on success.
-pub fn theme_install(source: &str, data_dir: &Path, force: bool) -> Result<String, Box<dyn std::error::Error>> {
-    // Resolve to a local zip file (download if URL)
-    let tmp_file;
-    let zip_path: &Path = if source.starts_with("http://") || source.starts_with("https://") {
-        let tmp = tempfile::NamedTempFile::new()?;
-        let response = ureq::get(source).call()?;
-        let mut reader = response.into_reader();
-        let mut file = std::fs::File::create(tmp.path())?;
-        std::io::copy(&mut reader, &mut file)?;
-        tmp_file = tmp;
-        tmp_file.path()
-    } else {
-        std::path::Path::new(source)
-    };
-
-    let file = std::fs::File::open(zip_path)?;
-    let mut archive = zip::ZipArchive::new(file)?;
-    let theme_name = zip_theme_name(&mut archive)?;
-
-    let dest = data_dir.join(&theme_name);
-    if dest.exists() {
-        if !force {
-            return Err(format!(
-                "theme '{}' already exists; use --force to overwrite",
-                theme_name
-            )
-            .into());
-        }
-        std::fs::remove_dir_all(&dest)?;
-    }
-
-    extract_zip(&mut archive, data_dir)?;
-
-    // Validate manifest exists; clean up if not
-    if !dest.join("manifest.json").exists() {
-        let _ = std::fs::remove_dir_all(&dest);
-        return Err(format!("theme '{}' has no manifest.json", theme_name).into());
-    }
-
-    Ok(theme_name)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
-    use std::path::PathBuf;
 
     #[test]
     fn install_binary_copies_and_makes_executable() {
@@ -215,14 +800,153 @@ mod tests {
         assert!(dest.join("ringring").exists());
     }
 
+    #[test]
+    fn binary_state_spots_missing_stale_and_unexecutable() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let (exe, dest) = (dir.path().join("exe"), dir.path().join("ringring"));
+        fs::write(&exe, "new build").unwrap();
+        assert_eq!(binary_state(&dest, &exe), BinaryState::Missing);
+        fs::write(&dest, "old build").unwrap();
+        assert_eq!(binary_state(&dest, &exe), BinaryState::Stale);
+        fs::write(&dest, "new build").unwrap();
+        fs::set_permissions(&dest, fs::Permissions::from_mode(0o644)).unwrap();
+        assert_eq!(binary_state(&dest, &exe), BinaryState::NotExecutable);
+        make_executable(&dest).unwrap();
+        assert_eq!(fs::metadata(&dest).unwrap().permissions().mode() & 0o777, 0o755);
+        assert_eq!(binary_state(&dest, &exe), BinaryState::Current);
+    }
+
+    #[test]
+    fn replace_binary_swaps_the_file_whole() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("ringring");
+        fs::write(&dest, "old build").unwrap();
+        let old = fs::File::open(&dest).unwrap();
+        replace_binary(&dest, b"new build").unwrap();
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "new build");
+        assert_eq!(fs::metadata(&dest).unwrap().permissions().mode() & 0o777, 0o755);
+        // A process still running the old file keeps reading the old one
+        assert_eq!(std::io::read_to_string(old).unwrap(), "old build");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn packaged_means_outside_home() {
+        let home = Path::new("/home/ada");
+        assert!(packaged(Path::new("/usr/bin/ringring"), home));
+        assert!(!packaged(Path::new("/home/ada/.local/bin/ringring"), home));
+        assert!(!packaged(Path::new("/usr/bin/ringring"), Path::new("")));
+    }
+
+    fn default_hooks() -> Vec<Hook> {
+        hook_plan(&crate::config::Config::default(), &[])
+    }
+
+    #[test]
+    fn hook_plan_follows_config_and_overrides() {
+        let cfg: crate::config::Config = serde_json::from_str(
+            r#"{"events": {"SessionEnd": false, "UserPromptSubmit": false}, "hook_matchers": {"PostToolUse": "Bash", "Notification": "idle_prompt"}}"#,
+        )
+        .unwrap();
+        let plan = hook_plan(&cfg, &[("SessionEnd".to_string(), "clear".to_string()), ("Stop".to_string(), "x".to_string())]);
+        let pairs: Vec<(&str, &str)> = plan.iter().map(|h| (h.event.as_str(), h.matcher.as_str())).collect();
+        assert_eq!(
+            pairs,
+            [
+                ("SessionStart", ""),
+                ("SessionEnd", "clear"),
+                ("Stop", "x"),
+                ("Notification", "idle_prompt"),
+                ("PermissionRequest", ""),
+                ("PostToolUse", "Bash"),
+            ]
+        );
+    }
+
+    #[test]
+    fn chosen_events_are_recorded() {
+        assert_eq!(parse_events("Stop, SubagentStop,Stop").unwrap(), ["Stop", "SubagentStop"]);
+        assert!(parse_events("Stop,PreToolUse").unwrap_err().contains("unknown hook event 'PreToolUse'"));
+        assert!(parse_events(",").is_err());
+
+        let mut root = serde_json::json!({"events": {"Stop": false, "Custom": true}});
+        choose_events(&mut root, &["Stop".to_string(), "SubagentStop".to_string()]);
+        assert_eq!(
+            root["events"],
+            serde_json::json!({
+                "Custom": true, "SubagentStop": true, "SessionStart": false, "SessionEnd": false,
+                "Notification": false, "PermissionRequest": false, "UserPromptSubmit": false,
+            })
+        );
+        let cfg: crate::config::Config = serde_json::from_value(root.clone()).unwrap();
+        let events: Vec<String> = hook_plan(&cfg, &[]).into_iter().map(|h| h.event).collect();
+        assert_eq!(events, ["Stop", "Custom", "SubagentStop"]);
+
+        let mut root = serde_json::json!({"events": {"Stop": false}});
+        choose_events(&mut root, &selectable_events().map(String::from).collect::<Vec<_>>());
+        assert_eq!(root["events"], serde_json::json!({"SubagentStop": true}));
+    }
+
+    #[test]
+    fn register_hooks_updates_matchers_and_drops_unwanted_events() {
+        let tmp = tempfile::tempdir().unwrap();
+        let settings = tmp.path().join("settings.json");
+        fs::write(&settings, r#"{"hooks":{"Stop":[{"matcher":"","hooks":[{"type":"command","command":"cargo check"}]}]}}"#).unwrap();
+        register_hooks(&settings, &default_hooks(), |_| true).unwrap();
+
+        let hooks = [Hook { event: "Stop".to_string(), matcher: "".to_string() }, Hook { event: "PostToolUse".to_string(), matcher: "Bash".to_string() }];
+        let edit = register_hooks(&settings, &hooks, |_| true).unwrap();
+        assert_eq!(edit.added, ["PostToolUse"]);
+        let mut removed = edit.removed.clone();
+        removed.sort();
+        assert_eq!(removed, ["Notification", "PermissionRequest", "SessionEnd", "SessionStart", "UserPromptSubmit"]);
+        let v: serde_json::Value = serde_json::from_str(&fs::read_to_string(&settings).unwrap()).unwrap();
+        assert_eq!(v["hooks"].as_object().unwrap().len(), 2);
+        assert_eq!(v["hooks"]["PostToolUse"], serde_json::json!([{"matcher": "Bash", "hooks": [{"type": "command", "command": "ringring"}]}]));
+        assert_eq!(v["hooks"]["Stop"][0]["hooks"][0]["command"], "cargo check");
+
+        let hooks = [Hook { event: "Stop".to_string(), matcher: "y".to_string() }];
+        let edit = register_hooks(&settings, &hooks, |_| true).unwrap();
+        assert_eq!((edit.added.as_slice(), edit.removed.as_slice()), (&["Stop".to_string()][..], &["PostToolUse".to_string()][..]));
+        let v: serde_json::Value = serde_json::from_str(&fs::read_to_string(&settings).unwrap()).unwrap();
+        assert_eq!(
+            v["hooks"]["Stop"],
+            serde_json::json!([
+                {"matcher": "", "hooks": [{"type": "command", "command": "cargo check"}]},
+                {"matcher": "y", "hooks": [{"type": "command", "command": "ringring"}]},
+            ])
+        );
+    }
+
+    #[test]
+    fn repair_only_adds_missing_hooks() {
+        let tmp = tempfile::tempdir().unwrap();
+        let settings = tmp.path().join("settings.json");
+        let hooks = [Hook { event: "Stop".to_string(), matcher: "".to_string() }, Hook { event: "Notification".to_string(), matcher: "x".to_string() }];
+        fs::write(
+            &settings,
+            r#"{"hooks":{"Stop":[{"matcher":"y","hooks":[{"type":"command","command":"ringring"}]}],"SubagentStop":[{"matcher":"","hooks":[{"type":"command","command":"ringring"}]}]}}"#,
+        )
+        .unwrap();
+        let edit = repair_hooks(&settings, &hooks, |_| true).unwrap();
+        assert_eq!((edit.added.as_slice(), edit.removed.is_empty()), (&["Notification".to_string()][..], true));
+        let v: serde_json::Value = serde_json::from_str(&fs::read_to_string(&settings).unwrap()).unwrap();
+        assert_eq!(v["hooks"]["Stop"][0]["matcher"], "y");
+        assert_eq!(v["hooks"]["Notification"][0]["matcher"], "x");
+        assert_eq!(registered_events(&v), ["Notification", "Stop", "SubagentStop"]);
+        assert!(!repair_hooks(&settings, &hooks, |_| false).unwrap().changes());
+    }
+
     #[test]
     fn register_hooks_creates_settings_when_missing() {
         let tmp = tempfile::tempdir().unwrap();
         let settings = tmp.path().join("settings.json");
-        register_hooks(&settings).unwrap();
+        register_hooks(&settings, &default_hooks(), |_| true).unwrap();
         let content = fs::read_to_string(&settings).unwrap();
         let v: serde_json::Value = serde_json::from_str(&content).unwrap();
-        for event in ["SessionStart", "Stop", "Notification", "PermissionRequest"] {
+        for event in ["SessionStart", "SessionEnd", "Stop", "Notification", "PermissionRequest", "UserPromptSubmit"] {
             let arr = v["hooks"][event].as_array().unwrap();
             let has_ringring = arr.iter().any(|entry| {
                 entry["hooks"].as_array()
@@ -234,11 +958,12 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::len_zero)]
     fn register_hooks_preserves_existing_fields() {
         let tmp = tempfile::tempdir().unwrap();
         let settings = tmp.path().join("settings.json");
         fs::write(&settings, r#"{"hooks":{"PostToolUse":[{"matcher":"Edit","hooks":[{"type":"command","command":"cargo check"}]}]},"otherField":42}"#).unwrap();
-        register_hooks(&settings).unwrap();
+        register_hooks(&settings, &default_hooks(), |_| true).unwrap();
         let content = fs::read_to_string(&settings).unwrap();
         let v: serde_json::Value = serde_json::from_str(&content).unwrap();
         assert_eq!(v["otherField"], 42);
@@ -249,11 +974,11 @@ mod tests {
     fn register_hooks_is_idempotent() {
         let tmp = tempfile::tempdir().unwrap();
         let settings = tmp.path().join("settings.json");
-        register_hooks(&settings).unwrap();
-        register_hooks(&settings).unwrap();
+        assert_eq!(register_hooks(&settings, &default_hooks(), |_| true).unwrap().added.len(), 6);
+        assert!(!register_hooks(&settings, &default_hooks(), |_| true).unwrap().changes());
         let content = fs::read_to_string(&settings).unwrap();
         let v: serde_json::Value = serde_json::from_str(&content).unwrap();
-        for event in ["SessionStart", "Stop", "Notification", "PermissionRequest"] {
+        for event in ["SessionStart", "SessionEnd", "Stop", "Notification", "PermissionRequest", "UserPromptSubmit"] {
             let count = v["hooks"][event].as_array().unwrap().iter()
                 .filter(|entry| {
                     entry["hooks"].as_array()
@@ -265,6 +990,70 @@ mod tests {
         }
     }
 
+    #[test]
+    fn settings_edited_meanwhile_are_not_clobbered() {
+        let tmp = tempfile::tempdir().unwrap();
+        let settings = tmp.path().join("settings.json");
+        fs::write(&settings, "{\n  \"model\": \"opus\"\n}").unwrap();
+        let edit = plan_hooks(&settings, &default_hooks()).unwrap();
+        assert_eq!(&edit.diff()[..3], [format!("--- {}", settings.display()), format!("+++ {}", settings.display()), "@@ -1,3 +1,71 @@".to_string()]);
+
+        fs::write(&settings, "{\n  \"model\": \"sonnet\"\n}").unwrap();
+        assert!(edit.apply().unwrap_err().to_string().contains("changed while ringring was editing it"));
+        assert!(register_hooks(&settings, &default_hooks(), |_| false).is_err());
+        assert_eq!(fs::read_to_string(&settings).unwrap(), "{\n  \"model\": \"sonnet\"\n}");
+        register_hooks(&settings, &default_hooks(), |_| true).unwrap();
+        assert!(!plan_hooks(&settings, &default_hooks()).unwrap().changes());
+    }
+
+    #[test]
+    fn unregister_hooks_keeps_other_hooks() {
+        let tmp = tempfile::tempdir().unwrap();
+        let settings = tmp.path().join("settings.json");
+        fs::write(&settings, r#"{"hooks":{"Stop":[{"matcher":"","hooks":[{"type":"command","command":"cargo check"},{"type":"command","command":"RINGRING_TOKEN=x ringring forward --to h:1"}]}]},"model":"opus"}"#).unwrap();
+        register_hooks(&settings, &default_hooks(), |_| true).unwrap();
+        let mut removed = unregister_hooks(&settings, None).unwrap();
+        removed.sort();
+        assert_eq!(removed, ["Notification", "PermissionRequest", "SessionEnd", "SessionStart", "Stop", "UserPromptSubmit"]);
+
+        let v: serde_json::Value = serde_json::from_str(&fs::read_to_string(&settings).unwrap()).unwrap();
+        assert_eq!(v["model"], "opus");
+        assert_eq!(v["hooks"].as_object().unwrap().len(), 1);
+        assert_eq!(v["hooks"]["Stop"], serde_json::json!([{"matcher": "", "hooks": [{"type": "command", "command": "cargo check"}]}]));
+        assert!(unregister_hooks(&settings, None).unwrap().is_empty());
+        assert!(unregister_hooks(&tmp.path().join("missing.json"), None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn ringring_commands() {
+        assert!(is_ringring_command("ringring"));
+        assert!(is_ringring_command("/home/u/.local/bin/ringring forward --to host:7878"));
+        assert!(is_ringring_command("~/.claude/ringring/ringring.sh"));
+        assert!(!is_ringring_command("ringring-lint"));
+        assert!(!is_ringring_command("echo ringring"));
+    }
+
+    #[test]
+    fn session_files_match_prefixes() {
+        let tmp = tempfile::tempdir().unwrap();
+        for name in [".claude-theme-a", ".claude-mute-a", ".claude-ringring-cpid-7", "other"] {
+            fs::write(tmp.path().join(name), "").unwrap();
+        }
+        assert_eq!(session_files(tmp.path()).len(), 3);
+    }
+
+    #[test]
+    fn install_service_units_writes_both_units() {
+        let tmp = tempfile::tempdir().unwrap();
+        let units = tmp.path().join("systemd/user");
+        let written = install_service_units(&units, Path::new("/home/u/.local/bin/ringring")).unwrap();
+        assert_eq!(written.len(), 2);
+        let service = fs::read_to_string(units.join("ringring.service")).unwrap();
+        assert!(service.contains("ExecStart=/home/u/.local/bin/ringring daemon"));
+        let socket = fs::read_to_string(units.join("ringring.socket")).unwrap();
+        assert!(socket.contains("ListenStream=%t/ringring/daemon.sock"));
+    }
+
     fn make_theme_zip(tmp: &tempfile::TempDir, theme_name: &str) -> PathBuf {
         use std::io::Write;
         let zip_path = tmp.path().join("theme.zip");
@@ -287,7 +1076,7 @@ mod tests {
         let zip_path = make_theme_zip(&tmp, "mytheme");
         let data_dir = tmp.path().join("data");
         fs::create_dir_all(&data_dir).unwrap();
-        let name = theme_install(&zip_path.to_string_lossy(), &data_dir, false).unwrap();
+        let name = theme_install(&ZipSource(zip_path.clone()), &data_dir, false).unwrap();
         assert_eq!(name, "mytheme");
         assert!(data_dir.join("mytheme/manifest.json").exists());
         assert!(data_dir.join("mytheme/sounds/beep.wav").exists());
@@ -299,8 +1088,8 @@ mod tests {
         let zip_path = make_theme_zip(&tmp, "mytheme");
         let data_dir = tmp.path().join("data");
         fs::create_dir_all(&data_dir).unwrap();
-        theme_install(&zip_path.to_string_lossy(), &data_dir, false).unwrap();
-        let err = theme_install(&zip_path.to_string_lossy(), &data_dir, false).unwrap_err();
+        theme_install(&ZipSource(zip_path.clone()), &data_dir, false).unwrap();
+        let err = theme_install(&ZipSource(zip_path.clone()), &data_dir, false).unwrap_err();
         assert!(err.to_string().contains("already exists"), "expected 'already exists', got: {err}");
     }
 
@@ -310,8 +1099,8 @@ mod tests {
         let zip_path = make_theme_zip(&tmp, "mytheme");
         let data_dir = tmp.path().join("data");
         fs::create_dir_all(&data_dir).unwrap();
-        theme_install(&zip_path.to_string_lossy(), &data_dir, false).unwrap();
-        theme_install(&zip_path.to_string_lossy(), &data_dir, true).unwrap();
+        theme_install(&ZipSource(zip_path.clone()), &data_dir, false).unwrap();
+        theme_install(&ZipSource(zip_path.clone()), &data_dir, true).unwrap();
         assert!(data_dir.join("mytheme/manifest.json").exists());
     }
 
@@ -330,8 +1119,62 @@ mod tests {
 
         let data_dir = tmp.path().join("data");
         fs::create_dir_all(&data_dir).unwrap();
-        let err = theme_install(&zip_path.to_string_lossy(), &data_dir, false).unwrap_err();
+        let err = theme_install(&ZipSource(zip_path.clone()), &data_dir, false).unwrap_err();
         assert!(err.to_string().contains("manifest.json"));
         assert!(!data_dir.join("nomanifest").exists());
     }
+
+    fn make_theme_dir(root: &Path, name: &str) -> PathBuf {
+        let dir = root.join(name);
+        fs::create_dir_all(dir.join("sounds")).unwrap();
+        fs::write(dir.join("manifest.json"), r#"{"display_name":"Test","categories":{}}"#).unwrap();
+        fs::write(dir.join("sounds/beep.wav"), b"RIFF").unwrap();
+        dir
+    }
+
+    #[test]
+    fn sources_are_picked_by_spec() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = make_theme_dir(tmp.path(), "chime");
+        let registry = crate::publish::RegistryConfig::default();
+        let kind = |spec: &str| source_for(spec, &registry).describe();
+        assert_eq!(kind(&dir.to_string_lossy()), dir.display().to_string());
+        assert_eq!(kind("https://example.com/chime.zip"), "https://example.com/chime.zip");
+        assert_eq!(kind("git+https://example.com/chime"), "git+https://example.com/chime");
+        assert_eq!(kind("chime"), "registry theme 'chime'");
+        assert_eq!(kind("./missing.zip"), "./missing.zip");
+    }
+
+    #[test]
+    fn dir_source_stages_then_installs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = make_theme_dir(tmp.path(), "chime");
+        let data_dir = tmp.path().join("data");
+        let staged = stage(&DirSource(dir), &data_dir).unwrap();
+        assert!(staged.dir.join("sounds/beep.wav").exists());
+        assert!(!data_dir.join("chime").exists(), "staging must not touch the data dir");
+        assert_eq!(staged.install(&data_dir, false).unwrap(), "chime");
+        assert!(data_dir.join("chime/sounds/beep.wav").exists());
+        // The staging dir is gone again
+        assert_eq!(fs::read_dir(&data_dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn git_source_clones_without_history() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = make_theme_dir(tmp.path(), "bells");
+        let git = |args: &[&str]| {
+            let ok = std::process::Command::new("git").args(args).current_dir(&repo).output().is_ok_and(|o| o.status.success());
+            assert!(ok, "git {args:?}");
+        };
+        git(&["init", "--quiet"]);
+        git(&["add", "."]);
+        git(&["-c", "user.name=t", "-c", "user.email=t@t", "commit", "--quiet", "-m", "theme"]);
+
+        let data_dir = tmp.path().join("data");
+        let name = theme_install(&GitSource(format!("git+file://{}", repo.display())), &data_dir, false).unwrap();
+        assert_eq!(name, "bells");
+        assert!(data_dir.join("bells/manifest.json").exists());
+        assert!(!data_dir.join("bells/.git").exists());
+    }
 }
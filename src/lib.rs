@@ -0,0 +1,109 @@
+//! Theme sounds and notifications for Claude Code hook events.
+//!
+//! The `ringring` binary is a thin CLI over this library. To use the engine
+//! from another Rust program, build one with [`RingringBuilder`].
+
+pub use audio::{Clip, Player};
+pub use config::Config;
+pub use engine::{Ringring, RingringBuilder};
+pub use event::HookInput;
+pub use history::Outcome;
+pub use notify::Notifier;
+pub use pipeline::{Ctx, Delivery, Flow, Middleware};
+
+mod ambience;
+mod announce;
+mod controller;
+mod cooldown;
+mod dedup;
+mod engine;
+mod greeting;
+mod haptic;
+mod light;
+mod limiter;
+mod locale;
+mod milestone;
+mod remote;
+mod threshold;
+
+// The binary's internals. Public only so `main.rs` can reach them; they are
+// not part of the API and may change in any release.
+#[doc(hidden)]
+pub mod audio;
+#[doc(hidden)]
+pub mod buildinfo;
+#[doc(hidden)]
+pub mod builtin;
+#[doc(hidden)]
+pub mod cache;
+#[doc(hidden)]
+pub mod capture;
+#[doc(hidden)]
+pub mod cli;
+#[doc(hidden)]
+pub mod config;
+#[doc(hidden)]
+pub mod daemon;
+#[cfg(feature = "async")]
+#[doc(hidden)]
+pub mod daemon_async;
+#[doc(hidden)]
+pub mod degraded;
+#[doc(hidden)]
+pub mod event;
+#[doc(hidden)]
+pub mod fastpath;
+#[doc(hidden)]
+pub mod focus;
+#[doc(hidden)]
+pub mod headless;
+#[doc(hidden)]
+pub mod history;
+#[doc(hidden)]
+pub mod hookcheck;
+#[doc(hidden)]
+pub mod install;
+#[doc(hidden)]
+pub mod log;
+#[doc(hidden)]
+pub mod manifest;
+#[doc(hidden)]
+pub mod mute;
+#[doc(hidden)]
+pub mod notify;
+#[doc(hidden)]
+pub mod pack;
+#[doc(hidden)]
+pub mod paths;
+#[doc(hidden)]
+pub mod pipeline;
+#[doc(hidden)]
+pub mod publish;
+#[doc(hidden)]
+pub mod queue;
+#[doc(hidden)]
+pub mod registry;
+#[doc(hidden)]
+pub mod repair;
+#[doc(hidden)]
+pub mod routes;
+#[doc(hidden)]
+pub mod sandbox;
+#[doc(hidden)]
+pub mod session;
+#[doc(hidden)]
+pub mod setup;
+#[doc(hidden)]
+pub mod slots;
+#[doc(hidden)]
+pub mod stats;
+#[doc(hidden)]
+pub mod suggest;
+#[doc(hidden)]
+pub mod tail;
+#[doc(hidden)]
+pub mod ui;
+#[doc(hidden)]
+pub mod updates;
+#[doc(hidden)]
+pub mod validate;
@@ -0,0 +1,279 @@
+//! Smart light notifications: flash a Home Assistant light or a Philips Hue
+//! lamp in a per-category color (red for permission, green for complete).
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::notify::Notifier;
+use crate::{config, cooldown, paths};
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Fewest seconds between flashes unless `min_interval` says otherwise.
+const DEFAULT_MIN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// After a failed request the light is left alone this long, so an
+/// unreachable bridge doesn't add a timeout to every event.
+const BACKOFF: Duration = Duration::from_secs(60);
+
+/// config.json `light`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LightConfig {
+    #[serde(default)]
+    pub home_assistant: Option<HomeAssistantConfig>,
+    #[serde(default)]
+    pub hue: Option<HueConfig>,
+    /// Per category color as `#rrggbb`, replacing the built-in one; an empty
+    /// string leaves the light alone for that category.
+    #[serde(default)]
+    pub colors: HashMap<String, String>,
+    /// Shortest time between two flashes, e.g. `"30s"`; defaults to 10s.
+    #[serde(default)]
+    pub min_interval: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HomeAssistantConfig {
+    /// Base URL, e.g. `http://homeassistant.local:8123`.
+    pub url: String,
+    /// Long-lived access token.
+    pub token: String,
+    /// e.g. `light.desk`.
+    pub entity_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HueConfig {
+    /// Bridge host or IP.
+    pub bridge: String,
+    /// API user created on the bridge.
+    pub username: String,
+    /// Light id on the bridge.
+    pub light: String,
+}
+
+impl LightConfig {
+    /// The color for `category`: configured, else built in.
+    pub fn color(&self, category: &str) -> Option<Rgb> {
+        category_color(&self.colors, category)
+    }
+
+    /// Like `color`, with the theme's color for the category (`#rrggbb`)
+    /// ahead of the built-in one.
+    pub fn theme_color(&self, category: &str, theme: Option<&str>) -> Option<Rgb> {
+        match theme {
+            Some(hex) if !self.colors.contains_key(category) => parse_hex(hex),
+            _ => self.color(category),
+        }
+    }
+
+    fn min_interval(&self) -> Duration {
+        self.min_interval.as_deref().and_then(config::parse_duration).unwrap_or(DEFAULT_MIN_INTERVAL)
+    }
+}
+
+pub type Rgb = [u8; 3];
+
+/// `category`'s color from `colors` (`#rrggbb`, empty for none), else the
+/// built-in one. Stream Deck keys use the same scheme.
+pub(crate) fn category_color(colors: &HashMap<String, String>, category: &str) -> Option<Rgb> {
+    match colors.get(category) {
+        Some(hex) => parse_hex(hex),
+        None => builtin_color(category),
+    }
+}
+
+fn builtin_color(category: &str) -> Option<Rgb> {
+    Some(match category {
+        "permission" => [255, 0, 0],
+        "complete" => [0, 255, 0],
+        "annoyed" => [255, 160, 0],
+        "meta" => [0, 96, 255],
+        _ => return None,
+    })
+}
+
+pub(crate) fn parse_hex(hex: &str) -> Option<Rgb> {
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// CIE xy for the Hue API, using the sRGB gamma and wide gamut D65
+/// conversion Philips documents.
+fn rgb_to_xy(rgb: Rgb) -> [f64; 2] {
+    let linear = |c: u8| {
+        let c = f64::from(c) / 255.0;
+        if c > 0.04045 { ((c + 0.055) / 1.055).powf(2.4) } else { c / 12.92 }
+    };
+    let [r, g, b] = rgb.map(linear);
+    let x = r * 0.664_511 + g * 0.154_324 + b * 0.162_028;
+    let y = r * 0.283_881 + g * 0.668_433 + b * 0.047_685;
+    let z = r * 0.000_088 + g * 0.072_310 + b * 0.986_039;
+    let sum = x + y + z;
+    if sum == 0.0 {
+        return [0.0, 0.0];
+    }
+    let round = |v: f64| (v * 10_000.0).round() / 10_000.0;
+    [round(x / sum), round(y / sum)]
+}
+
+/// An HTTP request that flashes the light.
+#[derive(Debug, Clone, PartialEq)]
+struct Request {
+    method: &'static str,
+    url: String,
+    token: Option<String>,
+    body: serde_json::Value,
+}
+
+impl HomeAssistantConfig {
+    /// `light.turn_on` with a short flash in `rgb`.
+    fn request(&self, rgb: Rgb) -> Request {
+        Request {
+            method: "POST",
+            url: format!("{}/api/services/light/turn_on", self.url.trim_end_matches('/')),
+            token: Some(self.token.clone()),
+            body: serde_json::json!({"entity_id": self.entity_id, "rgb_color": rgb, "flash": "short"}),
+        }
+    }
+}
+
+impl HueConfig {
+    /// One breathe cycle (`alert: select`) in `rgb`.
+    fn request(&self, rgb: Rgb) -> Request {
+        Request {
+            method: "PUT",
+            url: format!("http://{}/api/{}/lights/{}/state", self.bridge, self.username, self.light),
+            token: None,
+            body: serde_json::json!({"on": true, "xy": rgb_to_xy(rgb), "alert": "select"}),
+        }
+    }
+}
+
+/// Flashes the configured light; title and body are ignored.
+pub struct Light {
+    requests: Vec<Request>,
+    min_interval: Duration,
+}
+
+impl Light {
+    /// The light backend for `category`, if it has a color (configured or
+    /// the theme's, see `theme_color`) and a light is configured.
+    pub fn for_theme_category(cfg: &LightConfig, category: &str, theme_color: Option<&str>) -> Option<Self> {
+        let rgb = cfg.theme_color(category, theme_color)?;
+        let requests: Vec<Request> =
+            cfg.home_assistant.iter().map(|ha| ha.request(rgb)).chain(cfg.hue.iter().map(|hue| hue.request(rgb))).collect();
+        (!requests.is_empty()).then(|| Self { requests, min_interval: cfg.min_interval() })
+    }
+}
+
+impl Notifier for Light {
+    fn name(&self) -> &'static str {
+        "light"
+    }
+
+    fn notify(&self, _title: &str, _body: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = marker_dir();
+        if !ready(&dir, self.min_interval, SystemTime::now()) {
+            return Ok(());
+        }
+        let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+        for request in &self.requests {
+            let mut call = agent.request(request.method, &request.url).set("Content-Type", "application/json");
+            if let Some(token) = &request.token {
+                call = call.set("Authorization", &format!("Bearer {token}"));
+            }
+            if let Err(e) = call.send_string(&request.body.to_string()) {
+                back_off(&dir, SystemTime::now());
+                return Err(e.into());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn marker_dir() -> PathBuf {
+    paths::runtime_dir().join("light")
+}
+
+/// Whether a flash may go out now: not within `min_interval` of the last
+/// one, nor within `BACKOFF` of a failure. Claims the slot when it may.
+fn ready(dir: &Path, min_interval: Duration, now: SystemTime) -> bool {
+    let failed = fs::metadata(dir.join("failed")).and_then(|m| m.modified());
+    if failed.is_ok_and(|t| now.duration_since(t).unwrap_or_default() < BACKOFF) {
+        return false;
+    }
+    !cooldown::cooling_in(dir, "flash", min_interval, now)
+}
+
+fn back_off(dir: &Path, now: SystemTime) {
+    let _ = fs::create_dir_all(dir);
+    if let Ok(file) = File::create(dir.join("failed")) {
+        let _ = file.set_modified(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg(json: &str) -> LightConfig {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn colors_and_requests() {
+        let cfg = cfg(r##"{
+            "home_assistant": {"url": "http://ha.local:8123/", "token": "t0k", "entity_id": "light.desk"},
+            "hue": {"bridge": "10.0.0.2", "username": "me", "light": "3"},
+            "colors": {"complete": "#0000ff", "annoyed": ""}
+        }"##);
+        assert_eq!(cfg.color("permission"), Some([255, 0, 0]));
+        assert_eq!(cfg.color("complete"), Some([0, 0, 255]));
+        assert_eq!(cfg.color("annoyed"), None);
+        assert_eq!(cfg.theme_color("permission", Some("#102030")), Some([16, 32, 48]));
+        assert_eq!(cfg.theme_color("complete", Some("#102030")), Some([0, 0, 255]));
+        assert_eq!(cfg.theme_color("annoyed", Some("#102030")), None);
+        assert!(Light::for_theme_category(&cfg, "greeting", None).is_none());
+
+        let light = Light::for_theme_category(&cfg, "permission", None).unwrap();
+        assert_eq!(light.min_interval, DEFAULT_MIN_INTERVAL);
+        assert_eq!(light.requests[0].url, "http://ha.local:8123/api/services/light/turn_on");
+        assert_eq!(light.requests[0].body["rgb_color"], serde_json::json!([255, 0, 0]));
+        assert_eq!(light.requests[1].method, "PUT");
+        assert_eq!(light.requests[1].url, "http://10.0.0.2/api/me/lights/3/state");
+        assert_eq!(light.requests[1].body["xy"], serde_json::json!([0.7006, 0.2993]));
+
+        assert!(Light::for_theme_category(&self::cfg(r#"{"min_interval": "1m"}"#), "permission", None).is_none());
+    }
+
+    #[test]
+    fn hex_colors() {
+        assert_eq!(parse_hex("#FFa500"), Some([255, 165, 0]));
+        assert_eq!(parse_hex("00ff00"), Some([0, 255, 0]));
+        assert_eq!(parse_hex("#fff"), None);
+        assert_eq!(parse_hex("#gg0000"), None);
+        assert_eq!(rgb_to_xy([0, 0, 0]), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn flashes_are_rate_limited_and_back_off_after_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let t0 = SystemTime::now();
+        let interval = Duration::from_secs(10);
+        assert!(ready(dir.path(), interval, t0));
+        assert!(!ready(dir.path(), interval, t0 + Duration::from_secs(5)));
+        assert!(ready(dir.path(), interval, t0 + Duration::from_secs(11)));
+
+        back_off(dir.path(), t0 + Duration::from_secs(20));
+        assert!(!ready(dir.path(), interval, t0 + Duration::from_secs(40)));
+        assert!(ready(dir.path(), interval, t0 + Duration::from_secs(81)));
+    }
+}
@@ -0,0 +1,146 @@
+use rodio::Source;
+use rodio::source::SeekError;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Per-sample gain recovery after a peak (roughly 50 ms at 44.1 kHz).
+const RELEASE: f32 = 0.0005;
+
+/// What a limiter did, readable after the source has been consumed.
+#[derive(Debug, Default)]
+pub struct Stats {
+    /// Highest input magnitude seen, as `f32` bits.
+    peak: AtomicU32,
+    /// Samples that had to be pulled down to the cap.
+    limited: AtomicUsize,
+}
+
+impl Stats {
+    pub fn peak(&self) -> f32 {
+        f32::from_bits(self.peak.load(Ordering::Relaxed))
+    }
+
+    pub fn limited(&self) -> usize {
+        self.limited.load(Ordering::Relaxed)
+    }
+}
+
+/// Peak limiter with instant attack and smooth release. Output never
+/// exceeds `cap` in magnitude.
+struct PeakLimiter {
+    cap: f32,
+    gain: f32,
+    peak: f32,
+    limited: usize,
+}
+
+impl PeakLimiter {
+    fn new(cap: f32) -> Self {
+        Self { cap: cap.clamp(0.0, 1.0), gain: 1.0, peak: 0.0, limited: 0 }
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let magnitude = x.abs();
+        self.peak = self.peak.max(magnitude);
+        if magnitude * self.gain > self.cap {
+            self.gain = self.cap / magnitude;
+            self.limited += 1;
+        } else {
+            self.gain += (1.0 - self.gain) * RELEASE;
+        }
+        x * self.gain
+    }
+}
+
+/// Wraps a source in a `PeakLimiter`, publishing its stats when done.
+pub struct Limited<S> {
+    input: S,
+    limiter: PeakLimiter,
+    stats: Arc<Stats>,
+}
+
+impl<S: Source> Limited<S> {
+    pub fn new(input: S, cap: f32) -> (Self, Arc<Stats>) {
+        let stats = Arc::new(Stats::default());
+        (Self { input, limiter: PeakLimiter::new(cap), stats: Arc::clone(&stats) }, stats)
+    }
+
+    fn publish(&self) {
+        self.stats.peak.store(self.limiter.peak.to_bits(), Ordering::Relaxed);
+        self.stats.limited.store(self.limiter.limited, Ordering::Relaxed);
+    }
+}
+
+impl<S: Source> Iterator for Limited<S> {
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.input.next() {
+            Some(x) => Some(self.limiter.process(x)),
+            None => {
+                self.publish();
+                None
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<S: Source> Source for Limited<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    fn channels(&self) -> rodio::ChannelCount {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> rodio::SampleRate {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_never_exceeds_cap() {
+        let mut limiter = PeakLimiter::new(0.5);
+        for x in [0.1, 0.9, -1.0, 0.6, 0.2, -0.7] {
+            assert!(limiter.process(x).abs() <= 0.5 + f32::EPSILON, "{x}");
+        }
+        assert_eq!(limiter.peak, 1.0);
+        assert!(limiter.limited >= 2);
+    }
+
+    #[test]
+    fn quiet_input_passes_through() {
+        let mut limiter = PeakLimiter::new(0.8);
+        assert_eq!(limiter.process(0.3), 0.3);
+        assert_eq!(limiter.process(-0.8), -0.8);
+        assert_eq!(limiter.limited, 0);
+    }
+
+    #[test]
+    fn stats_published_at_end() {
+        let loud = rodio::buffer::SamplesBuffer::new(1, 44_100, vec![0.2, 1.0, -0.9]);
+        let (limited, stats) = Limited::new(loud, 0.5);
+        let out: Vec<f32> = limited.collect();
+        assert!(out.iter().all(|s| s.abs() <= 0.5 + f32::EPSILON));
+        assert_eq!(stats.peak(), 1.0);
+        assert!(stats.limited() > 0);
+    }
+}
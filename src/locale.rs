@@ -0,0 +1,83 @@
+//! Built-in notification texts in the language a theme declares
+//! (`"language": "cs"` in manifest.json). They stand in wherever the theme
+//! has no title or line of its own, so a Czech theme's notifications read
+//! in Czech too. Themes without a language keep the default texts.
+
+/// Languages with built-in texts.
+pub const LANGUAGES: [&str; 3] = ["cs", "de", "en"];
+
+/// What a built-in text is for: the hook event or notification type it
+/// maps, or the milestone celebration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Text {
+    PermissionRequest,
+    Stop,
+    UnknownEvent,
+    PermissionPrompt,
+    IdlePrompt,
+    AuthSuccess,
+    Elicitation,
+    UnknownNotification,
+    Milestone,
+}
+
+/// The primary subtag of a language tag: `cs` for `cs-CZ` or `cs_CZ`.
+fn primary(language: &str) -> String {
+    language.split(['-', '_']).next().unwrap_or_default().trim().to_ascii_lowercase()
+}
+
+/// Whether `language` has built-in texts.
+pub fn supported(language: &str) -> bool {
+    LANGUAGES.contains(&primary(language).as_str())
+}
+
+/// Title and body of `text` in `language`, if it has built-in texts.
+pub fn text(language: &str, text: Text) -> Option<(&'static str, &'static str)> {
+    use Text::*;
+    Some(match (primary(language).as_str(), text) {
+        ("en", PermissionRequest) => ("Permission needed", "Claude wants to use a tool"),
+        ("en", Stop) => ("Done", "Task complete"),
+        ("en", UnknownEvent) => ("Unknown event", "Something happened"),
+        ("en", PermissionPrompt) => ("Needs your attention", "Claude needs your permission"),
+        ("en", IdlePrompt) => ("Waiting for you", "Claude is waiting for input"),
+        ("en", AuthSuccess) => ("Signed in", "Authentication succeeded"),
+        ("en", Elicitation) => ("A question", "Claude has a question for you"),
+        ("en", UnknownNotification) => ("Needs your attention", "Claude has something for you"),
+        ("en", Milestone) => ("Milestone", ""),
+
+        ("cs", PermissionRequest) => ("Potřebuju povolení", "Claude chce použít nástroj"),
+        ("cs", Stop) => ("Hotovo", "Úkol dokončen"),
+        ("cs", UnknownEvent) => ("Neznámá událost", "Něco se stalo"),
+        ("cs", PermissionPrompt) => ("Chtěl bych trochu pozornosti", "Claude potřebuje tvé svolení"),
+        ("cs", IdlePrompt) => ("Čekám na tebe", "Nudím se, pojď makat."),
+        ("cs", AuthSuccess) => ("Přihlášení úspěšné", "Ověření proběhlo v pořádku"),
+        ("cs", Elicitation) => ("Mám otázku", "Claude se tě na něco ptá"),
+        ("cs", UnknownNotification) => ("Chtěl bych trochu pozornosti", "Claude pro tebe něco má"),
+        ("cs", Milestone) => ("Milník", ""),
+
+        ("de", PermissionRequest) => ("Berechtigung nötig", "Claude möchte ein Werkzeug verwenden"),
+        ("de", Stop) => ("Fertig", "Aufgabe erledigt"),
+        ("de", UnknownEvent) => ("Unbekanntes Ereignis", "Etwas ist passiert"),
+        ("de", PermissionPrompt) => ("Aufmerksamkeit gefragt", "Claude braucht deine Erlaubnis"),
+        ("de", IdlePrompt) => ("Ich warte auf dich", "Claude wartet auf eine Eingabe"),
+        ("de", AuthSuccess) => ("Angemeldet", "Anmeldung erfolgreich"),
+        ("de", Elicitation) => ("Eine Frage", "Claude hat eine Frage an dich"),
+        ("de", UnknownNotification) => ("Aufmerksamkeit gefragt", "Claude hat etwas für dich"),
+        ("de", Milestone) => ("Meilenstein", ""),
+
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_and_case_are_ignored() {
+        assert_eq!(text("cs-CZ", Text::Stop), Some(("Hotovo", "Úkol dokončen")));
+        assert_eq!(text("DE_at", Text::Stop), text("de", Text::Stop));
+        assert_eq!(text("xx", Text::Stop), None);
+        assert!(supported("en_GB") && !supported("pl"));
+    }
+}
@@ -1,128 +1,759 @@
-mod audio;
-mod config;
-mod event;
-mod manifest;
-mod notify;
-mod paths;
-mod install;
+use rust_ringring::{
+    audio, buildinfo, builtin, cache, capture, cli, config, daemon, degraded, event, fastpath, focus, headless, history, hookcheck, install, log, manifest, mute, notify, pack, paths,
+    pipeline, publish, queue, registry, repair, routes, sandbox, session, setup, slots, stats, suggest, tail, ui, updates, validate,
+};
 
 use std::fs;
 use std::path::PathBuf;
 
 enum Cmd {
     Hook,
-    Test { theme: String, category: Option<String> },
-    List { debug: bool },
-    Install,
-    ThemeInstall { source: String, force: bool },
+    Help(String),
+    Version { verbose: bool },
+    Invalid(cli::Error),
+    Test { theme: String, category: Option<String>, preview: Preview },
+    TestAll { category: String, preview: Preview },
+    List { debug: bool, json: bool, page: usize },
+    Install { bin_dir: Option<String>, settings: Option<String>, yes: bool, matchers: Vec<(String, String)>, events: EventChoice, repair: bool },
+    AssetsInstall { settings: Option<String>, yes: bool, matchers: Vec<(String, String)>, events: EventChoice },
+    Repair { bin_dir: Option<String>, settings: Option<String>, yes: bool },
+    Uninstall { purge: bool, yes: bool, bin_dir: Option<String>, settings: Option<String>, events: Option<Vec<String>> },
+    ThemeInstall { source: String, force: bool, check: bool },
+    ThemeSearch { query: String },
+    ThemeCheck { theme: String },
+    ThemeValidate { target: String },
+    ThemeStats { theme: String },
+    ThemeRemove { theme: String, force: bool },
+    ThemeNew { theme: String },
+    ThemeSet { theme: Option<String>, migrate: bool },
+    ThemePack { dir: String, output: Option<String> },
+    ThemeVerify { zip: String, against: String },
+    ThemePublish { dir: String, url: Option<String>, output: Option<String> },
+    ThemeUse { theme: String, session: Option<String>, here: bool, pin: bool },
     SessionTheme { session_id: String, theme: String },
     SessionMute { session_id: String },
     SessionUnmute { session_id: String },
+    Mute { duration: Option<std::time::Duration>, all: bool },
+    Unmute,
     Mode { mode: String },
+    Pin { theme: String, quiet: bool, path: Option<String> },
+    Unpin { path: Option<String> },
+    Pins,
+    VolumeGet { theme: Option<String> },
+    VolumeSet { level: String, theme: Option<String> },
+    ConfigValidate { fix: bool },
+    ConfigGet { key: Option<String> },
+    ConfigSet { key: String, value: String },
+    ConfigUnset { key: String },
+    ConfigExport { output: Option<String> },
+    ConfigImport { file: String, yes: bool, skip_themes: bool },
+    RoutesTest { event: String },
     Status { session_id: Option<String> },
     DetectSession,
+    Sessions,
+    Pending { json: bool },
+    Doctor,
+    Forward { to: String },
+    Daemon { listen: Option<String> },
+    DaemonInstallService,
+    DaemonStop,
+    DaemonReload,
+    DaemonStatus,
+    Replay { file: Option<String>, last: Option<usize>, id: Option<String>, dry_run: bool },
+    Simulate { simulation: Simulation, dry_run: bool },
+    Play { target: String, volume: Option<f32> },
+    History { lines: usize, json: bool },
+    Tail { lines: usize, log: bool, follow: bool },
+    EventsSummarize,
+    Stats { suggest: bool, apply: bool },
+    StatsExport { format: String, since: Option<String> },
+    Healthcheck,
+    CheckUpdates { notify: bool },
+    Update { force: bool },
 }
 
-fn parse_args(args: &[String]) -> Cmd {
-    match args.get(1).map(|s| s.as_str()) {
-        Some("test") => {
-            let theme = args.get(2).cloned().unwrap_or_default();
-            let category = args.get(3..).unwrap_or(&[])
-                .windows(2)
-                .find(|w| w[0] == "--category")
-                .map(|w| w[1].clone());
-            Cmd::Test { theme, category }
-        }
-        Some("list") => {
-            let debug = args.get(2..).unwrap_or(&[]).iter().any(|a| a == "--debug");
-            Cmd::List { debug }
-        }
-        Some("install") => Cmd::Install,
-        Some("theme") => {
-            match args.get(2).map(|s| s.as_str()) {
-                Some("install") => {
-                    let rest = args.get(3..).unwrap_or(&[]);
-                    let force = rest.iter().any(|a| a == "--force");
-                    let source = rest.iter()
-                        .find(|a| *a != "--force")
-                        .cloned()
-                        .unwrap_or_default();
-                    Cmd::ThemeInstall { source, force }
+/// Which hook events `install` registers.
+#[derive(Debug, Clone, PartialEq)]
+enum EventChoice {
+    /// Those config.json `events` leaves on.
+    Config,
+    /// These, recorded in config.json.
+    List(Vec<String>),
+    /// Chosen from a checklist, then recorded in config.json.
+    Pick,
+}
+
+/// Pacing and selection for `ringring test`.
+#[derive(Debug, Clone, PartialEq)]
+struct Preview {
+    /// Silence between sounds.
+    gap: std::time::Duration,
+    /// Play at most this many sounds per category (per theme with `--all`).
+    limit: Option<usize>,
+    shuffle: bool,
+    /// Send each sound's notification too.
+    notify: bool,
+    /// With `--pick`, draw this many sounds per category at random, the way
+    /// the hook does, instead of going through them all.
+    picks: Option<usize>,
+}
+
+impl Preview {
+    const DEFAULT_GAP_MS: u64 = 300;
+    /// Without `--limit`, play at most this many sounds of one category.
+    const LARGE_CATEGORY: usize = 50;
+
+    fn parse(m: &cli::Matches) -> Result<Self, cli::Error> {
+        let gap = m.number("gap")?.unwrap_or(Self::DEFAULT_GAP_MS);
+        Ok(Self {
+            gap: std::time::Duration::from_millis(gap),
+            limit: m.number("limit")?,
+            shuffle: m.flag("shuffle"),
+            notify: m.flag("notify"),
+            picks: match m.number("count")? {
+                Some(0) => return Err(m.error("--count must be at least 1".to_string())),
+                Some(n) => Some(n),
+                None => m.flag("pick").then_some(1),
+            },
+        })
+    }
+
+    /// The sounds to play from `category`: with `--pick`, `pick_sound`'s
+    /// draws; otherwise every sound, as `select` orders and limits them.
+    fn sounds(&self, manifest: &manifest::Manifest, category: &str, cat: &manifest::Category) -> Vec<manifest::Sound> {
+        match self.picks {
+            Some(n) => (0..n)
+                .filter_map(|_| manifest::pick_sound(manifest, category))
+                .map(|pick| manifest::Sound { file: pick.file, line: pick.line, chance: pick.chance })
+                .collect(),
+            None => {
+                let mut sounds: Vec<manifest::Sound> = self.select(&cat.sounds).into_iter().cloned().collect();
+                if self.limit.is_none() && sounds.len() > Self::LARGE_CATEGORY {
+                    println!(
+                        "{} {category} has {} sounds; playing the first {} (use --limit to choose)",
+                        ui::paint(ui::Style::Warn, "!"),
+                        sounds.len(),
+                        Self::LARGE_CATEGORY
+                    );
+                    sounds.truncate(Self::LARGE_CATEGORY);
                 }
-                _ => Cmd::Hook,
+                sounds
             }
         }
-        Some("session") => {
-            let raw_id = args.get(2).cloned().unwrap_or_default();
-            let session_id = if raw_id.is_empty() {
-                detect_session_id().unwrap_or_default()
-            } else {
-                raw_id
-            };
-            match args.get(3).map(|s| s.as_str()) {
-                Some("theme") => {
-                    let theme = args.get(4).cloned().unwrap_or_default();
-                    Cmd::SessionTheme { session_id, theme }
-                }
-                Some("mute") => Cmd::SessionMute { session_id },
-                Some("unmute") => Cmd::SessionUnmute { session_id },
-                _ => Cmd::Status { session_id: Some(session_id).filter(|s| !s.is_empty()) },
+    }
+
+    /// After more than one `--pick` draw, how often each file came up.
+    fn print_tally(&self, sounds: &[manifest::Sound]) {
+        if self.picks.is_none_or(|n| n < 2) || sounds.is_empty() {
+            return;
+        }
+        let mut counts: std::collections::BTreeMap<&str, usize> = Default::default();
+        for sound in sounds {
+            *counts.entry(&sound.file).or_default() += 1;
+        }
+        let mut counts: Vec<(&str, usize)> = counts.into_iter().collect();
+        counts.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+        for (file, n) in counts {
+            println!("  {n:>4}×  {:>3}%  {file}", n * 100 / sounds.len());
+        }
+    }
+
+    /// The items to play, shuffled and cut to the limit as requested.
+    fn select<'a, T>(&self, items: &'a [T]) -> Vec<&'a T> {
+        let mut picked: Vec<&T> = items.iter().collect();
+        if self.shuffle {
+            use rand::seq::SliceRandom;
+            picked.shuffle(&mut rand::rng());
+        }
+        picked.truncate(self.limit.unwrap_or(usize::MAX));
+        picked
+    }
+
+    /// With `--notify`, send what the hook would show alongside `sound`: the
+    /// category's title (badged) and the sound's line, else the category's
+    /// body.
+    fn notify(&self, cfg: &config::Config, category: &str, cat: &manifest::Category, sound: &manifest::Sound) {
+        if !self.notify {
+            return;
+        }
+        let title = cfg.badged_title(category, cat.title.as_deref().unwrap_or(category));
+        let body = sound.line.clone().or_else(|| cat.body.clone()).unwrap_or_default();
+        let env = headless::detect(cfg.headless);
+        notify::Dispatcher::new(cfg, notify::Branding::from_config(cfg), &env, None, None).send(&title, &body);
+    }
+}
+
+/// A hook event for `ringring simulate`, as Claude Code would send it.
+#[derive(Debug, Clone, PartialEq)]
+struct Simulation {
+    event: String,
+    session_id: String,
+    notification_type: Option<String>,
+    source: Option<String>,
+    message: Option<String>,
+    cwd: Option<String>,
+}
+
+impl Simulation {
+    fn parse(m: &cli::Matches) -> Result<Self, cli::Error> {
+        let Some(event) = m.arg(0) else {
+            return Err(m.error("simulate needs a hook event name, e.g. Stop".to_string()));
+        };
+        Ok(Self {
+            event,
+            session_id: m.value("session-id").unwrap_or_default(),
+            notification_type: m.value("type"),
+            source: m.value("source"),
+            message: m.value("message"),
+            cwd: m.value("cwd"),
+        })
+    }
+
+    /// The HookInput JSON. A SessionStart is a `startup` unless `--source`
+    /// says otherwise, and the cwd defaults to ours.
+    fn input(&self) -> serde_json::Value {
+        let mut input = serde_json::json!({
+            "hook_event_name": self.event,
+            "session_id": self.session_id,
+            "cwd": self.cwd.clone().unwrap_or_else(|| std::env::current_dir().unwrap_or_default().to_string_lossy().into_owned()),
+        });
+        let source = self.source.clone().or_else(|| (self.event == "SessionStart").then(|| "startup".to_string()));
+        for (key, value) in [("source", source), ("notification_type", self.notification_type.clone()), ("message", self.message.clone())] {
+            if let Some(value) = value {
+                input[key] = serde_json::Value::String(value);
+            }
+        }
+        input
+    }
+}
+
+const INTRO: &str = "ringring: sounds and notifications for Claude Code hooks
+
+Usage: ringring [options] [<command>]
+
+With no command, ringring handles the hook event JSON on stdin.";
+
+const GLOBAL_FLAGS: &[cli::Flag] = &[
+    cli::Flag::value("data-dir", "dir", "Use this data dir instead of ~/.claude/sounds"),
+    cli::Flag::switch("no-color", "Don't color output"),
+    cli::Flag::switch("help", "Show help (also `<command> --help`)").short('h'),
+    cli::Flag::switch("version", "Show the version").short('V'),
+];
+
+const SESSION_FLAG: cli::Flag = cli::Flag::value("session", "id", "Session (default: the one this shell runs in)");
+
+const THEME_FLAG: cli::Flag = cli::Flag::value("theme", "name", "This theme's volume instead, on top of the master volume");
+
+const PATH_FLAG: cli::Flag = cli::Flag::value("path", "dir", "This directory instead of the current git checkout or directory");
+
+const PIN_FLAGS: &[cli::Flag] = &[cli::Flag::switch("quiet", "No sounds here, notifications only"), PATH_FLAG];
+
+const COMMANDS: &[cli::Command] = &[
+    cli::Command { path: &["version"], args: "", max_args: 0, about: "Print the version", flags: &[
+        cli::Flag::switch("verbose", "Also the commit, features, codecs, backends and schema versions").short('v'),
+    ] },
+    cli::Command { path: &["list"], args: "", max_args: 0, about: "List installed themes", flags: &[
+        cli::Flag::switch("debug", "Show every category and sound"),
+        cli::Flag::value("page", "n", "With --debug, which page of sounds to show per category"),
+        cli::Flag::switch("json", "Print JSON, with every category and sound"),
+    ] },
+    cli::Command { path: &["test"], args: "[<theme>]", max_args: 1, about: "Play a theme's sounds", flags: &[
+        cli::Flag::value("category", "name", "Only this category"),
+        cli::Flag::switch("all", "One sound from every installed theme"),
+        cli::Flag::value("gap", "ms", "Silence between sounds (default 300)"),
+        cli::Flag::value("limit", "n", "Sounds per category, or per theme with --all"),
+        cli::Flag::switch("shuffle", "Random order"),
+        cli::Flag::switch("notify", "Also send the notification each sound would come with"),
+        cli::Flag::switch("pick", "Pick sounds at random like the hook does, instead of playing all"),
+        cli::Flag::value("count", "n", "Picks per category (implies --pick), then show how often each file came up"),
+    ] },
+    cli::Command { path: &["install"], args: "", max_args: 0, about: "Install the binary, hooks and slash command", flags: &[
+        cli::Flag::switch("yes", "Change settings.json without asking").short('y'),
+        cli::Flag::value("matcher", "event=pattern", "Register the hook for event only where pattern matches (repeatable)"),
+        cli::Flag::value("events", "list", "Hook only these events (comma separated), recording the choice in config.json"),
+        cli::Flag::switch("pick-events", "Choose the events to hook from a checklist"),
+        cli::Flag::switch("repair", "Only re-add hooks missing from settings.json"),
+        cli::Flag::value("bin-dir", "dir", "Where to copy the binary (default ~/.local/bin)"),
+        cli::Flag::value("settings", "file", "Claude Code settings to register the hooks in (default ~/.claude/settings.json)"),
+    ] },
+    cli::Command { path: &["assets", "install"], args: "", max_args: 0, about: "Set up a packaged ringring for this user: data dir, config, desktop entry, hooks", flags: &[
+        cli::Flag::switch("yes", "Change settings.json without asking").short('y'),
+        cli::Flag::value("matcher", "event=pattern", "Register the hook for event only where pattern matches (repeatable)"),
+        cli::Flag::value("events", "list", "Hook only these events (comma separated), recording the choice in config.json"),
+        cli::Flag::switch("pick-events", "Choose the events to hook from a checklist"),
+        cli::Flag::value("settings", "file", "Claude Code settings to register the hooks in (default ~/.claude/settings.json)"),
+    ] },
+    cli::Command { path: &["repair"], args: "", max_args: 0, about: "Fix a broken install without reinstalling: binary, hooks, directories, desktop entry", flags: &[
+        cli::Flag::switch("yes", "Change settings.json without asking").short('y'),
+        cli::Flag::value("bin-dir", "dir", "Where the binary is installed (default ~/.local/bin)"),
+        cli::Flag::value("settings", "file", "Claude Code settings holding the hooks (default ~/.claude/settings.json)"),
+    ] },
+    cli::Command { path: &["uninstall"], args: "", max_args: 0, about: "Remove the hooks, slash command and session files", flags: &[
+        cli::Flag::switch("purge", "Also delete the binary, themes, config, history and daemon units"),
+        cli::Flag::switch("yes", "With --purge, delete a data dir you chose yourself without asking").short('y'),
+        cli::Flag::value("events", "list", "Only unhook these events (comma separated) and switch them off in config.json"),
+        cli::Flag::value("bin-dir", "dir", "Where the binary was installed (default ~/.local/bin)"),
+        cli::Flag::value("settings", "file", "Claude Code settings to remove the hooks from (default ~/.claude/settings.json)"),
+    ] },
+    cli::Command { path: &["theme", "install"], args: "<source>", max_args: 1, about: "Install a theme from a zip, dir, URL, git repo or the registry", flags: &[
+        cli::Flag::switch("force", "Replace an installed theme of the same name"),
+        cli::Flag::switch("check", "Decode every sound first and refuse the theme if one fails"),
+    ] },
+    cli::Command { path: &["theme", "search"], args: "[<query>]", max_args: 1, about: "Search the theme registry by name or description", flags: &[] },
+    cli::Command { path: &["theme", "check"], args: "<theme>", max_args: 1, about: "Decode every sound of a theme within the playback limits", flags: &[] },
+    cli::Command { path: &["theme", "stats"], args: "<theme>", max_args: 1, about: "Count how often each sound of a theme played, to find clips that never do", flags: &[] },
+    cli::Command { path: &["theme", "validate"], args: "<name|dir|zip>", max_args: 1, about: "Check a theme's manifest, categories and sound files before sharing it", flags: &[] },
+    cli::Command { path: &["theme", "remove"], args: "<theme>", max_args: 1, about: "Delete an installed theme and its pins", flags: &[
+        cli::Flag::switch("force", "Remove it even if it is the configured theme"),
+    ] },
+    cli::Command { path: &["theme", "new"], args: "<name>", max_args: 1, about: "Start a theme: a manifest with every category and an empty sounds/", flags: &[] },
+    cli::Command { path: &["theme", "pack"], args: "<dir|theme>", max_args: 1, about: "Pack a theme dir or installed theme into a reproducible zip", flags: &[
+        cli::Flag::value("output", "zip", "Where to write it (default <name>.zip)").short('o'),
+    ] },
+    cli::Command { path: &["theme", "verify"], args: "<zip>", max_args: 1, about: "Compare a packed theme with its source dir", flags: &[
+        cli::Flag::value("against", "dir", "Source dir"),
+    ] },
+    cli::Command { path: &["theme", "publish"], args: "<dir|theme>", max_args: 1, about: "Pack a theme and publish its registry entry", flags: &[
+        cli::Flag::value("url", "url", "Where the zip will be downloaded from"),
+        cli::Flag::value("output", "zip", "Where to write the zip").short('o'),
+    ] },
+    cli::Command { path: &["theme", "set"], args: "[<theme>]", max_args: 1, about: "Set the default theme in config.json", flags: &[
+        cli::Flag::switch("migrate", "Move the legacy theme file into config.json"),
+    ] },
+    cli::Command { path: &["theme", "use"], args: "<theme>", max_args: 1, about: "Switch a session's theme", flags: &[
+        SESSION_FLAG,
+        cli::Flag::switch("here", "The newest session in this directory"),
+        cli::Flag::switch("pin", "Also pin the theme to this directory"),
+    ] },
+    cli::Command { path: &["theme", "pin"], args: "[<theme>]", max_args: 1, about: "Same as pin", flags: PIN_FLAGS },
+    cli::Command { path: &["theme", "unpin"], args: "", max_args: 0, about: "Same as unpin", flags: &[PATH_FLAG] },
+    cli::Command { path: &["session"], args: "[<id>] [<action>]", max_args: 3, about: "Show a session, or change it: theme <name>, mute, unmute", flags: &[] },
+    cli::Command { path: &["sessions"], args: "", max_args: 0, about: "List active sessions", flags: &[] },
+    cli::Command { path: &["pending"], args: "", max_args: 0, about: "Sessions waiting on you", flags: &[
+        cli::Flag::switch("json", "Print JSON"),
+    ] },
+    cli::Command { path: &["mute"], args: "", max_args: 0, about: "Mute every session, or one with --session", flags: &[
+        cli::Flag::value("for", "duration", "Unmute by itself after this long, e.g. 30m"),
+        cli::Flag::switch("all", "Hold back notifications too"),
+        cli::Flag::value("session", "id", "Mute only this session"),
+    ] },
+    cli::Command { path: &["unmute"], args: "", max_args: 0, about: "Lift the mute, or one session's with --session", flags: &[
+        cli::Flag::value("session", "id", "Unmute only this session"),
+    ] },
+    cli::Command { path: &["status"], args: "[<session>]", max_args: 1, about: "Show the theme the hook would use here, and why", flags: &[
+        cli::Flag::value("session-id", "id", "Resolve for this session (same as the argument)"),
+    ] },
+    cli::Command { path: &["detect-session"], args: "", max_args: 0, about: "Print the id of the session this shell runs in", flags: &[] },
+    cli::Command { path: &["mode"], args: "<random|sequential>", max_args: 1, about: "Pick themes for new sessions from the pool", flags: &[] },
+    cli::Command { path: &["pin"], args: "[<theme>]", max_args: 1, about: "Pin a theme to this git checkout or directory", flags: PIN_FLAGS },
+    cli::Command { path: &["unpin"], args: "", max_args: 0, about: "Remove this directory's pin", flags: &[PATH_FLAG] },
+    cli::Command { path: &["pins"], args: "", max_args: 0, about: "List workspace pins", flags: &[] },
+    cli::Command { path: &["volume", "get"], args: "", max_args: 0, about: "Print the master volume (0–100)", flags: &[THEME_FLAG] },
+    cli::Command { path: &["volume", "set"], args: "<0-100>", max_args: 1, about: "Set the master volume in config.json", flags: &[THEME_FLAG] },
+    cli::Command { path: &["config", "validate"], args: "", max_args: 0, about: "Report stale pins and pool entries", flags: &[
+        cli::Flag::switch("fix", "Repair them, showing a diff first"),
+    ] },
+    cli::Command { path: &["config", "get"], args: "[<key>]", max_args: 1, about: "Print a config.json value (all of it without a key)", flags: &[] },
+    cli::Command { path: &["config", "set"], args: "<key> <value>", max_args: 2, about: "Set a config.json value, checking its type", flags: &[] },
+    cli::Command { path: &["config", "unset"], args: "<key>", max_args: 1, about: "Remove a config.json value", flags: &[] },
+    cli::Command { path: &["config", "export"], args: "", max_args: 0, about: "Write config.json, its includes and the installed themes as one JSON document", flags: &[
+        cli::Flag::value("output", "file", "Where to write it (default stdout)").short('o'),
+    ] },
+    cli::Command { path: &["config", "import"], args: "<file|->", max_args: 1, about: "Restore a config export: config.json, includes and missing themes", flags: &[
+        cli::Flag::switch("yes", "Replace config.json without asking").short('y'),
+        cli::Flag::switch("skip-themes", "Only restore the config, installing no themes"),
+    ] },
+    cli::Command { path: &["routes", "test"], args: "<event.json|->", max_args: 1, about: "Show which backends a hook event would reach", flags: &[] },
+    cli::Command { path: &["doctor"], args: "", max_args: 0, about: "Show detected environment, config and notifiers", flags: &[] },
+    cli::Command { path: &["healthcheck"], args: "", max_args: 0, about: "Check config, theme, audio and notifications; exit 1 on failure", flags: &[] },
+    cli::Command { path: &["check-updates"], args: "", max_args: 0, about: "Look for new ringring releases and theme versions", flags: &[
+        cli::Flag::switch("no-notify", "Don't send a notification"),
+    ] },
+    cli::Command { path: &["update"], args: "", max_args: 0, about: "Install the newest ringring release over this one", flags: &[
+        cli::Flag::switch("force", "Reinstall even when this is the newest release"),
+    ] },
+    cli::Command { path: &["history"], args: "", max_args: 0, about: "Show what the hook did with the latest events", flags: &[
+        cli::Flag::value("lines", "n", "How many events (default 50)").short('n'),
+        cli::Flag::switch("json", "Print them as a JSON array"),
+    ] },
+    cli::Command { path: &["tail"], args: "", max_args: 0, about: "Follow handled events and the log live, with what the pipeline decided", flags: &[
+        cli::Flag::value("lines", "n", "How many past events to show first (default 10)").short('n'),
+        cli::Flag::switch("no-log", "Show only the events, not the log lines between them"),
+        cli::Flag::switch("no-follow", "Exit after the past events instead of following"),
+    ] },
+    cli::Command { path: &["replay"], args: "[<file>]", max_args: 1, about: "Re-run past events, or the payloads in a record file, through the current config", flags: &[
+        cli::Flag::value("last", "n", "Replay the last n events (default 1, every payload of a file)"),
+        cli::Flag::value("id", "id", "Replay one event from the history"),
+        cli::Flag::switch("dry-run", "Show what would happen, without sound or notifications"),
+    ] },
+    cli::Command { path: &["play"], args: "<file|theme:category>", max_args: 1, about: "Play one sound file, or a random sound of a theme's category", flags: &[
+        cli::Flag::value("volume", "0.0-1.0", "Volume (default: the theme's, or 1.0 for a file)"),
+    ] },
+    cli::Command { path: &["simulate"], args: "<event>", max_args: 1, about: "Run a made-up hook event through the full pipeline", flags: &[
+        cli::Flag::value("type", "name", "Notification type, e.g. idle_prompt or permission_prompt"),
+        cli::Flag::value("session-id", "id", "Session the event belongs to"),
+        cli::Flag::value("source", "name", "SessionStart source: startup (default) or resume"),
+        cli::Flag::value("message", "text", "Notification message"),
+        cli::Flag::value("cwd", "dir", "Session working directory (default: the current one)"),
+        cli::Flag::switch("dry-run", "Show what would happen, without sound or notifications"),
+    ] },
+    cli::Command { path: &["events", "summarize"], args: "", max_args: 0, about: "Report captured event names and notification types ringring doesn't map", flags: &[] },
+    cli::Command { path: &["stats"], args: "", max_args: 0, about: "Count the last week's events per category", flags: &[
+        cli::Flag::switch("suggest", "Propose cooldowns for noisy categories"),
+        cli::Flag::switch("apply", "Write the proposed cooldowns to config.json"),
+    ] },
+    cli::Command { path: &["stats", "export"], args: "", max_args: 0, about: "Write the history as CSV or JSON", flags: &[
+        cli::Flag::value("format", "csv|json", "Output format (default csv)"),
+        cli::Flag::value("since", "duration", "Only events this recent, e.g. 30d"),
+    ] },
+    cli::Command { path: &["forward"], args: "", max_args: 0, about: "Hook mode: send the event to a daemon on another host", flags: &[
+        cli::Flag::value("to", "addr", "host:port or unix socket path"),
+    ] },
+    cli::Command { path: &["daemon"], args: "", max_args: 0, about: "Run the background daemon in the foreground", flags: &[
+        cli::Flag::value("listen", "addr", "host:port or unix socket path"),
+    ] },
+    cli::Command { path: &["daemon", "install-service"], args: "", max_args: 0, about: "Write and enable systemd user units", flags: &[] },
+    cli::Command { path: &["daemon", "status"], args: "", max_args: 0, about: "Show the running daemon; exit 3 when none", flags: &[] },
+    cli::Command { path: &["daemon", "reload"], args: "", max_args: 0, about: "Make the daemon re-read its config", flags: &[] },
+    cli::Command { path: &["daemon", "stop"], args: "", max_args: 0, about: "Stop the daemon after in-flight sounds", flags: &[] },
+];
+
+fn parse_args(args: &[String]) -> Cmd {
+    match cli::parse(COMMANDS, args.get(1..).unwrap_or(&[])) {
+        Ok(cli::Parsed::Hook) => Cmd::Hook,
+        Ok(cli::Parsed::Help(None)) => Cmd::Help(cli::program_help(COMMANDS, INTRO, GLOBAL_FLAGS)),
+        Ok(cli::Parsed::Help(Some(command))) => Cmd::Help(cli::command_help(command)),
+        Ok(cli::Parsed::Version) => Cmd::Version { verbose: false },
+        Ok(cli::Parsed::Run(m)) => command(&m).unwrap_or_else(Cmd::Invalid),
+        Err(e) => Cmd::Invalid(e),
+    }
+}
+
+/// `--matcher` and `--events`/`--pick-events`, shared by `install` and
+/// `assets install`.
+fn install_choices(m: &cli::Matches) -> Result<(Vec<(String, String)>, EventChoice), cli::Error> {
+    let mut matchers = Vec::new();
+    for value in m.values("matcher") {
+        let Some((event, pattern)) = value.split_once('=').filter(|(event, _)| !event.is_empty()) else {
+            return Err(m.error(format!("--matcher expects event=pattern, got '{value}'")));
+        };
+        matchers.push((event.to_string(), pattern.to_string()));
+    }
+    let events = match (m.value("events"), m.flag("pick-events")) {
+        (Some(_), true) => return Err(m.error("--events and --pick-events don't go together".to_string())),
+        (Some(list), false) => EventChoice::List(install::parse_events(&list).map_err(|e| m.error(e))?),
+        (None, true) => EventChoice::Pick,
+        (None, false) => EventChoice::Config,
+    };
+    Ok((matchers, events))
+}
+
+fn command(m: &cli::Matches) -> Result<Cmd, cli::Error> {
+    let arg = |i| m.arg(i).unwrap_or_default();
+    Ok(match m.command.path {
+        ["test"] if m.flag("all") => {
+            let category = m.value("category").unwrap_or_else(|| "complete".to_string());
+            let mut preview = Preview::parse(m)?;
+            preview.limit.get_or_insert(1);
+            Cmd::TestAll { category, preview }
+        }
+        ["test"] => Cmd::Test { theme: arg(0), category: m.value("category"), preview: Preview::parse(m)? },
+        ["version"] => Cmd::Version { verbose: m.flag("verbose") },
+        ["list"] => Cmd::List { debug: m.flag("debug"), json: m.flag("json"), page: m.number("page")?.unwrap_or(1).max(1) },
+        ["install"] => {
+            let (matchers, events) = install_choices(m)?;
+            if m.flag("repair") && events != EventChoice::Config {
+                return Err(m.error("--repair re-adds the configured events; drop --events/--pick-events".to_string()));
             }
+            let (bin_dir, settings, yes, repair) = (m.value("bin-dir"), m.value("settings"), m.flag("yes"), m.flag("repair"));
+            Cmd::Install { bin_dir, settings, yes, matchers, events, repair }
+        }
+        ["assets", "install"] => {
+            let (matchers, events) = install_choices(m)?;
+            Cmd::AssetsInstall { settings: m.value("settings"), yes: m.flag("yes"), matchers, events }
         }
-        Some("mode") => {
-            let mode = args.get(2).cloned().unwrap_or_default();
-            Cmd::Mode { mode }
+        ["repair"] => Cmd::Repair { bin_dir: m.value("bin-dir"), settings: m.value("settings"), yes: m.flag("yes") },
+        ["uninstall"] => {
+            let events = m.value("events").map(|list| install::parse_events(&list)).transpose().map_err(|e| m.error(e))?;
+            if events.is_some() && m.flag("purge") {
+                return Err(m.error("--events and --purge don't go together".to_string()));
+            }
+            Cmd::Uninstall { purge: m.flag("purge"), yes: m.flag("yes"), bin_dir: m.value("bin-dir"), settings: m.value("settings"), events }
         }
-        Some("status") => {
-            let session_id = args.get(2).cloned();
-            Cmd::Status { session_id }
+        ["theme", "install"] => Cmd::ThemeInstall { source: arg(0), force: m.flag("force"), check: m.flag("check") },
+        ["theme", "search"] => Cmd::ThemeSearch { query: arg(0) },
+        ["theme", "check"] => Cmd::ThemeCheck { theme: arg(0) },
+        ["theme", "validate"] => Cmd::ThemeValidate { target: arg(0) },
+        ["theme", "stats"] => Cmd::ThemeStats { theme: arg(0) },
+        ["theme", "remove"] => Cmd::ThemeRemove { theme: arg(0), force: m.flag("force") },
+        ["theme", "new"] => Cmd::ThemeNew { theme: arg(0) },
+        ["theme", "set"] => Cmd::ThemeSet { theme: m.arg(0), migrate: m.flag("migrate") },
+        ["theme", "pack"] => Cmd::ThemePack { dir: arg(0), output: m.value("output") },
+        ["theme", "verify"] => Cmd::ThemeVerify { zip: arg(0), against: m.value("against").unwrap_or_default() },
+        ["theme", "publish"] => Cmd::ThemePublish { dir: arg(0), url: m.value("url"), output: m.value("output") },
+        ["theme", "use"] => {
+            Cmd::ThemeUse { theme: arg(0), session: m.value("session"), here: m.flag("here"), pin: m.flag("pin") }
         }
-        Some("detect-session") => Cmd::DetectSession,
-        Some("mute") => {
-            let session_id = detect_session_id().unwrap_or_default();
-            Cmd::SessionMute { session_id }
+        ["session"] => {
+            let session_id = m.arg(0).or_else(detect_session_id).unwrap_or_default();
+            match m.arg(1).as_deref() {
+                Some("theme") => Cmd::SessionTheme { session_id, theme: arg(2) },
+                Some("mute") => Cmd::SessionMute { session_id },
+                Some("unmute") => Cmd::SessionUnmute { session_id },
+                Some(other) => return Err(m.error(format!("unknown session command '{other}' (expected theme, mute or unmute)"))),
+                None => Cmd::Status { session_id: Some(session_id).filter(|s| !s.is_empty()) },
+            }
         }
-        Some("unmute") => {
-            let session_id = detect_session_id().unwrap_or_default();
-            Cmd::SessionUnmute { session_id }
+        ["config", "validate"] => Cmd::ConfigValidate { fix: m.flag("fix") },
+        ["config", "get"] => Cmd::ConfigGet { key: m.arg(0) },
+        ["config", "set"] => match (m.arg(0), m.arg(1)) {
+            (Some(key), Some(value)) => Cmd::ConfigSet { key, value },
+            _ => return Err(m.error("config set needs a key and a value".to_string())),
+        },
+        ["config", "unset"] => Cmd::ConfigUnset { key: arg(0) },
+        ["config", "export"] => Cmd::ConfigExport { output: m.value("output") },
+        ["config", "import"] => Cmd::ConfigImport { file: arg(0), yes: m.flag("yes"), skip_themes: m.flag("skip-themes") },
+        ["routes", "test"] => match m.arg(0) {
+            Some(event) => Cmd::RoutesTest { event },
+            None => return Err(m.error("routes test needs a hook event file (or - for stdin)".to_string())),
+        },
+        ["pin"] | ["theme", "pin"] => Cmd::Pin { theme: arg(0), quiet: m.flag("quiet"), path: m.value("path") },
+        ["unpin"] | ["theme", "unpin"] => Cmd::Unpin { path: m.value("path") },
+        ["pins"] => Cmd::Pins,
+        ["volume", "get"] => Cmd::VolumeGet { theme: m.value("theme") },
+        ["volume", "set"] => Cmd::VolumeSet { level: arg(0), theme: m.value("theme") },
+        ["mode"] => Cmd::Mode { mode: arg(0) },
+        ["status"] => Cmd::Status { session_id: m.value("session-id").or_else(|| m.arg(0)) },
+        ["detect-session"] => Cmd::DetectSession,
+        ["sessions"] => Cmd::Sessions,
+        ["pending"] => Cmd::Pending { json: m.flag("json") },
+        ["doctor"] => Cmd::Doctor,
+        ["healthcheck"] => Cmd::Healthcheck,
+        ["check-updates"] => Cmd::CheckUpdates { notify: !m.flag("no-notify") },
+        ["update"] => Cmd::Update { force: m.flag("force") },
+        ["forward"] => Cmd::Forward { to: m.value("to").unwrap_or_default() },
+        ["history"] => Cmd::History { lines: m.number("lines")?.unwrap_or(50), json: m.flag("json") },
+        ["tail"] => Cmd::Tail { lines: m.number("lines")?.unwrap_or(10), log: !m.flag("no-log"), follow: !m.flag("no-follow") },
+        ["replay"] => Cmd::Replay { file: m.arg(0), last: m.number("last")?, id: m.value("id"), dry_run: m.flag("dry-run") },
+        ["events", "summarize"] => Cmd::EventsSummarize,
+        ["play"] => Cmd::Play { target: arg(0), volume: m.number("volume")? },
+        ["simulate"] => Cmd::Simulate { simulation: Simulation::parse(m)?, dry_run: m.flag("dry-run") },
+        ["stats", "export"] => {
+            Cmd::StatsExport { format: m.value("format").unwrap_or_else(|| "csv".to_string()), since: m.value("since") }
         }
-        _ => Cmd::Hook,
+        ["stats"] => Cmd::Stats { suggest: m.flag("suggest") || m.flag("apply"), apply: m.flag("apply") },
+        ["daemon", "install-service"] => Cmd::DaemonInstallService,
+        ["daemon", "stop"] => Cmd::DaemonStop,
+        ["daemon", "reload"] => Cmd::DaemonReload,
+        ["daemon", "status"] => Cmd::DaemonStatus,
+        ["daemon"] => Cmd::Daemon { listen: m.value("listen") },
+        ["mute"] => match m.value("session") {
+            Some(_) if m.flag("for") || m.flag("all") => {
+                return Err(m.error("--for and --all mute every session; leave out --session".to_string()));
+            }
+            Some(session_id) => Cmd::SessionMute { session_id },
+            None => {
+                let duration = m.value("for").map(|d| config::parse_duration(&d).ok_or(d)).transpose();
+                let duration = duration.map_err(|d| m.error(format!("--for expects a duration like 30m or 2h, got '{d}'")))?;
+                Cmd::Mute { duration, all: m.flag("all") }
+            }
+        },
+        ["unmute"] => match m.value("session") {
+            Some(session_id) => Cmd::SessionUnmute { session_id },
+            None => Cmd::Unmute,
+        },
+        path => unreachable!("no handler for command {path:?}"),
+    })
+}
+
+/// Remove a global `--data-dir <path>` (or `--data-dir=<path>`) from `args`.
+fn take_data_dir_flag(args: &mut Vec<String>) -> Option<String> {
+    let pos = args.iter().position(|a| a == "--data-dir" || a.starts_with("--data-dir="))?;
+    let flag = args.remove(pos);
+    match flag.strip_prefix("--data-dir=") {
+        Some(dir) => Some(dir.to_string()),
+        None => (pos < args.len()).then(|| args.remove(pos)),
     }
 }
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    match parse_args(&args) {
-        Cmd::Test { theme, category } => {
-            if let Err(e) = run_test(&theme, category.as_deref()) {
+    let mut args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|a| a == "--no-color") {
+        ui::disable_color();
+        args.retain(|a| a != "--no-color");
+    }
+    // Exported so the daemon and preview children see the same sandbox
+    if let Some(dir) = take_data_dir_flag(&mut args) {
+        unsafe { std::env::set_var("RINGRING_DATA_DIR", dir) };
+    }
+    let cmd = parse_args(&args);
+    if !matches!(
+        cmd,
+        Cmd::Hook
+            | Cmd::Help(_)
+            | Cmd::Version { .. }
+            | Cmd::Invalid(_)
+            | Cmd::Install { .. }
+            | Cmd::AssetsInstall { .. }
+            | Cmd::Repair { .. }
+            | Cmd::Uninstall { .. }
+            | Cmd::Forward { .. }
+            | Cmd::Daemon { .. }
+            | Cmd::Healthcheck
+            | Cmd::CheckUpdates { .. }
+            | Cmd::Update { .. }
+    ) {
+        suggest_setup();
+    }
+    match cmd {
+        Cmd::Help(help) => print!("{help}"),
+        Cmd::Version { verbose } => run_version(verbose),
+        Cmd::Invalid(e) => {
+            eprintln!("ringring: {e}");
+            if let Some(usage) = &e.usage {
+                eprintln!("\nUsage: {usage}");
+            }
+            eprintln!("\nRun 'ringring --help' for more.");
+            std::process::exit(2);
+        }
+        Cmd::Test { theme, category, preview } => {
+            if let Err(e) = run_test(&theme, category.as_deref(), &preview) {
+                eprintln!("ringring test: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::TestAll { category, preview } => {
+            if let Err(e) = run_test_all(&category, &preview) {
                 eprintln!("ringring test: {e}");
                 std::process::exit(1);
             }
         }
-        Cmd::List { debug } => {
-            run_list(debug);
+        Cmd::List { debug, json, page } => {
+            run_list(debug, json, page);
         }
         Cmd::Hook => {
             let _ = run();
         }
-        Cmd::Install => {
-            if let Err(e) = run_install() {
+        Cmd::Install { bin_dir, settings, yes, matchers, events, repair } => {
+            let result = if repair {
+                run_repair_hooks(settings.as_deref(), yes, &matchers)
+            } else {
+                run_install(Some(bin_dir.as_deref()), settings.as_deref(), yes, &matchers, events)
+            };
+            if let Err(e) = result {
                 eprintln!("ringring install: {e}");
                 std::process::exit(1);
             }
         }
-        Cmd::ThemeInstall { source, force } => {
-            if let Err(e) = run_theme_install(&source, force) {
+        Cmd::AssetsInstall { settings, yes, matchers, events } => {
+            if let Err(e) = run_install(None, settings.as_deref(), yes, &matchers, events) {
+                eprintln!("ringring assets install: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::Repair { bin_dir, settings, yes } => {
+            if let Err(e) = run_repair(bin_dir.as_deref(), settings.as_deref(), yes) {
+                eprintln!("ringring repair: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::Uninstall { purge, yes, bin_dir, settings, events } => {
+            if let Err(e) = run_uninstall(purge, yes, bin_dir.as_deref(), settings.as_deref(), events.as_deref()) {
+                eprintln!("ringring uninstall: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::ThemeInstall { source, force, check } => {
+            if let Err(e) = run_theme_install(&source, force, check) {
                 eprintln!("ringring theme install: {e}");
                 std::process::exit(1);
             }
         }
+        Cmd::ThemeSearch { query } => {
+            if let Err(e) = run_theme_search(&query) {
+                eprintln!("ringring theme search: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::ThemeCheck { theme } => {
+            if let Err(e) = run_theme_check(&theme) {
+                eprintln!("ringring theme check: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::ThemeValidate { target } => {
+            if let Err(e) = run_theme_validate(&target) {
+                eprintln!("ringring theme validate: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::ThemeStats { theme } => {
+            if let Err(e) = run_theme_stats(&theme) {
+                eprintln!("ringring theme stats: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::ThemeRemove { theme, force } => {
+            if let Err(e) = run_theme_remove(&theme, force) {
+                eprintln!("ringring theme remove: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::ThemeNew { theme } => {
+            if let Err(e) = run_theme_new(&theme) {
+                eprintln!("ringring theme new: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::ThemeSet { theme, migrate } => {
+            if let Err(e) = run_theme_set(theme.as_deref(), migrate) {
+                eprintln!("ringring theme set: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::ThemePack { dir, output } => {
+            if let Err(e) = run_theme_pack(&dir, output.as_deref()) {
+                eprintln!("ringring theme pack: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::ThemePublish { dir, url, output } => {
+            if let Err(e) = run_theme_publish(&dir, url.as_deref(), output.as_deref()) {
+                eprintln!("ringring theme publish: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::ThemeVerify { zip, against } => {
+            if let Err(e) = run_theme_verify(&zip, &against) {
+                eprintln!("ringring theme verify: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::ThemeUse { theme, session, here, pin } => {
+            if let Err(e) = run_theme_use(&theme, session.as_deref(), here, pin) {
+                eprintln!("ringring theme use: {e}");
+                std::process::exit(1);
+            }
+        }
         Cmd::SessionTheme { session_id, theme } => {
             if let Err(e) = run_session_theme(&session_id, &theme) {
                 eprintln!("ringring session theme: {e}");
                 std::process::exit(1);
             }
         }
+        Cmd::Mute { duration, all } => {
+            if let Err(e) = run_mute(duration, all) {
+                eprintln!("ringring mute: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::Unmute => {
+            if mute::clear(log::now_secs()) {
+                ui::ok("unmuted");
+            } else {
+                ui::ok("wasn't muted");
+            }
+        }
         Cmd::SessionMute { session_id } => {
             if let Err(e) = run_session_mute(&session_id, true) {
                 eprintln!("ringring session mute: {e}");
@@ -141,6 +772,75 @@ fn main() {
                 std::process::exit(1);
             }
         }
+        Cmd::Pin { theme, quiet, path } => {
+            if let Err(e) = run_pin(&theme, quiet, path.as_deref()) {
+                eprintln!("ringring pin: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::Unpin { path } => {
+            if let Err(e) = run_unpin(path.as_deref()) {
+                eprintln!("ringring unpin: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::Pins => run_pins(),
+        Cmd::VolumeGet { theme } => {
+            if let Err(e) = run_volume_get(theme.as_deref()) {
+                eprintln!("ringring volume get: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::VolumeSet { level, theme } => {
+            if let Err(e) = run_volume_set(&level, theme.as_deref()) {
+                eprintln!("ringring volume set: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::ConfigValidate { fix } => match run_config_validate(fix) {
+            Ok(true) => {}
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("ringring config validate: {e}");
+                std::process::exit(1);
+            }
+        },
+        Cmd::ConfigGet { key } => {
+            if let Err(e) = run_config_get(key.as_deref()) {
+                eprintln!("ringring config get: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::ConfigSet { key, value } => {
+            if let Err(e) = run_config_set(&key, Some(&value)) {
+                eprintln!("ringring config set: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::ConfigUnset { key } => {
+            if let Err(e) = run_config_set(&key, None) {
+                eprintln!("ringring config unset: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::ConfigExport { output } => {
+            if let Err(e) = run_config_export(output.as_deref()) {
+                eprintln!("ringring config export: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::ConfigImport { file, yes, skip_themes } => {
+            if let Err(e) = run_config_import(&file, yes, skip_themes) {
+                eprintln!("ringring config import: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::RoutesTest { event } => {
+            if let Err(e) = run_routes_test(&event) {
+                eprintln!("ringring routes test: {e}");
+                std::process::exit(1);
+            }
+        }
         Cmd::Status { session_id } => {
             let session_id = session_id.or_else(detect_session_id);
             run_status(session_id.as_deref());
@@ -150,87 +850,301 @@ fn main() {
                 println!("{sid}");
             }
         }
+        Cmd::Sessions => run_sessions(),
+        Cmd::Pending { json } => run_pending(json),
+        Cmd::Doctor => {
+            run_doctor();
+        }
+        Cmd::Forward { to } => {
+            // Runs as a hook inside the container: report but never fail.
+            if let Err(e) = run_forward(&to) {
+                eprintln!("ringring forward: {e}");
+            }
+        }
+        Cmd::Healthcheck => {
+            if !run_healthcheck() {
+                std::process::exit(1);
+            }
+        }
+        Cmd::CheckUpdates { notify } => {
+            if let Err(e) = run_check_updates(notify) {
+                eprintln!("ringring check-updates: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::Update { force } => {
+            if let Err(e) = run_update(force) {
+                eprintln!("ringring update: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::Replay { file, last, id, dry_run } => {
+            let result = match (file, id) {
+                (Some(_), Some(_)) => Err("--id replays from the history, not from a file".into()),
+                (Some(file), None) => run_replay_file(&file, last, dry_run),
+                (None, id) => run_replay(last, id.as_deref(), dry_run),
+            };
+            if let Err(e) = result {
+                eprintln!("ringring replay: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::History { lines, json } => {
+            if let Err(e) = run_history(lines, json) {
+                eprintln!("ringring history: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::Tail { lines, log, follow } => {
+            if let Err(e) = run_tail(lines, log, follow) {
+                eprintln!("ringring tail: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::Play { target, volume } => {
+            if let Err(e) = run_play(&target, volume) {
+                eprintln!("ringring play: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::Simulate { simulation, dry_run } => {
+            if let Err(e) = run_simulate(&simulation, dry_run) {
+                eprintln!("ringring simulate: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::EventsSummarize => {
+            if let Err(e) = run_events_summarize() {
+                eprintln!("ringring events summarize: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::Stats { suggest, apply } => {
+            if let Err(e) = run_stats(suggest, apply) {
+                eprintln!("ringring stats: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::StatsExport { format, since } => {
+            if let Err(e) = run_stats_export(&format, since.as_deref()) {
+                eprintln!("ringring stats export: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::DaemonInstallService => {
+            if let Err(e) = run_daemon_install_service() {
+                eprintln!("ringring daemon install-service: {e}");
+                std::process::exit(1);
+            }
+        }
+        Cmd::DaemonStop => match daemon::stop() {
+            Ok(pid) => println!("daemon (pid {pid}) stopped"),
+            Err(e) => {
+                eprintln!("ringring daemon stop: {e}");
+                std::process::exit(1);
+            }
+        },
+        Cmd::DaemonReload => match daemon::signal_daemon(libc::SIGHUP) {
+            Ok(pid) => println!("daemon (pid {pid}) reloading"),
+            Err(e) => {
+                eprintln!("ringring daemon reload: {e}");
+                std::process::exit(1);
+            }
+        },
+        Cmd::DaemonStatus => {
+            if !run_daemon_status() {
+                std::process::exit(3);
+            }
+        }
+        Cmd::Daemon { listen } => {
+            if let Err(e) = run_daemon(listen.as_deref()) {
+                eprintln!("ringring daemon: {e}");
+                std::process::exit(1);
+            }
+        }
     }
 }
 
 fn run() -> Result<(), Box<dyn std::error::Error>> {
     let input_str = std::io::read_to_string(std::io::stdin())?;
 
-    let hook_input: event::HookInput = serde_json::from_str(&input_str)?;
+    // Cheapest possible exit for events switched off in config
+    if !fastpath::event_enabled(&paths::data_dir(), &input_str) {
+        return Ok(());
+    }
+
+    let raw: serde_json::Value = serde_json::from_str(&input_str)?;
+    let cfg = config::Config::load(&paths::data_dir());
+    if cfg.capture_events {
+        capture::append(&raw);
+    }
+    if let Some(path) = &cfg.record_events {
+        capture::record(&paths::data_dir().join(path), &raw);
+    }
+    let hook_input: event::HookInput = serde_json::from_value(raw.clone())?;
 
     // Write PID-to-session mapping so detect-session can find us
     if !hook_input.session_id.is_empty() {
         let ppid = std::os::unix::process::parent_id();
         let _ = fs::write(
-            format!("/tmp/.claude-ringring-cpid-{ppid}"),
+            paths::tmp_dir().join(format!(".claude-ringring-cpid-{ppid}")),
             &hook_input.session_id,
         );
     }
 
-    let sounds_dir = paths::data_dir();
-
-    let cfg = config::Config::load(&sounds_dir);
-    let cwd = std::env::current_dir().unwrap_or_default();
-    let resolver = config::ThemeResolver {
-        sounds_dir: &sounds_dir,
-        config: &cfg,
-        session_id: &hook_input.session_id,
-        cwd: cwd.to_string_lossy().into_owned(),
-    };
-    // Check mute flag
-    if !hook_input.session_id.is_empty() {
-        let mute_file = PathBuf::from(format!("/tmp/.claude-mute-{}", hook_input.session_id));
-        if mute_file.exists() {
-            return Ok(());
+    // The daemon can't see our environment, so capture the terminal and any
+    // theme chosen by env var here (events run with one are handled here
+    // anyway, see `LOCAL_ENV`)
+    if hook_input.hook_event_name == "SessionStart" {
+        if let Some(terminal) = focus::Terminal::capture() {
+            session::record_terminal(&hook_input.session_id, terminal);
+        }
+        if let Some(tty) = session::controlling_tty() {
+            session::record_tty(&hook_input.session_id, tty);
+        }
+        if let Ok(theme) = std::env::var("CLAUDE_SOUND_THEME")
+            && !theme.is_empty()
+        {
+            let cwd = hook_input.cwd.clone().map(PathBuf::from).or_else(|| std::env::current_dir().ok());
+            if let Some(cwd) = cwd {
+                suggest::record(&paths::workspace_root(&cwd).to_string_lossy(), &theme);
+            }
         }
     }
 
-    let theme = resolver.resolve();
-    resolver.persist_session_theme(&theme);
-    let theme_dir = config::theme_dir(&sounds_dir, &theme);
+    if let Some(missing) = hookcheck::check(&paths::data_dir()) {
+        report_missing_hooks(&missing, hook_input.cwd.as_deref().unwrap_or_default());
+    }
 
-    let Some(manifest) = manifest::Manifest::load(&theme_dir) else {
+    if forward_to_daemon(&raw) {
         return Ok(());
-    };
-
-    if hook_input.hook_event_name == "SessionStart" {
-        return handle_session_start(&hook_input, &resolver, &theme, &theme_dir, &manifest);
     }
 
-    let action = event::map_event(&hook_input);
-
-    if let Some(ref category) = action.category {
-        let pick = manifest::pick_sound(&manifest, category);
-        let (cat_title, cat_body) = manifest::category_text(&manifest, category);
+    // Under an event storm, keep the event but leave handling it to others
+    let Some(_slot) = slots::acquire(cfg.max_processes()) else {
+        let reason = format!("{} hook processes already running", cfg.max_processes());
+        log::write(&format!("{}: {reason}, recorded only", hook_input.hook_event_name));
+        history::append(&raw, &history::Outcome::skipped(reason));
+        return Ok(());
+    };
+    let outcome = pipeline::handle(&hook_input, false)?;
+    history::append(&raw, &outcome);
+    Ok(())
+}
 
-        let title = cat_title.unwrap_or(action.title);
-        let body = pick
-            .as_ref()
-            .and_then(|p| p.line.clone())
-            .or(cat_body)
-            .unwrap_or(action.body);
+/// Tell the user, once, that Claude Code's settings lost some of the hooks.
+fn report_missing_hooks(missing: &[String], cwd: &str) {
+    let body = format!("No ringring hook for {} any more; run `ringring install --repair`", missing.join(", "));
+    log::write(&format!("hooks: {body}"));
+    let cfg = config::Config::load(&paths::data_dir());
+    let env = headless::detect(cfg.headless);
+    notify::Dispatcher::new(&cfg, notify::Branding::from_config(&cfg), &env, None, None)
+        .route(&cfg.routes, cwd, Some("meta"))
+        .send("Claude Code hooks missing", &body);
+}
 
-        if !action.skip_notify {
-            notify::send_notification(&title, &body);
+/// Per-invocation overrides the daemon resolves from its own environment
+/// instead; an event run with one of them set is handled in-process.
+const LOCAL_ENV: &[&str] = &["CLAUDE_SOUND_THEME", "CLAUDE_SOUND_ENABLED", "CLAUDE_NOTIFICATIONS_ENABLED", "RINGRING_PLAYER"];
+
+/// Hand the event to a running (or socket-activated) daemon if its socket
+/// exists. Returns false when the event must be handled in-process, including
+/// when the daemon speaks a different protocol version (e.g. after an update).
+fn forward_to_daemon(raw: &serde_json::Value) -> bool {
+    // A sandboxed data dir must not leak into the user's real daemon
+    if std::env::var_os("RINGRING_DATA_DIR").is_some_and(|d| !d.is_empty()) {
+        return false;
+    }
+    if LOCAL_ENV.iter().any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty())) {
+        return false;
+    }
+    let endpoint = daemon::Endpoint::default_socket();
+    let daemon::Endpoint::Unix(ref socket) = endpoint else {
+        return false;
+    };
+    if !socket.exists() {
+        return false;
+    }
+    // The daemon runs elsewhere; pin the event to our working directory
+    let mut raw = raw.clone();
+    if raw.get("cwd").is_none()
+        && let Ok(cwd) = std::env::current_dir()
+    {
+        raw["cwd"] = serde_json::Value::String(cwd.to_string_lossy().into_owned());
+    }
+    let cfg = config::Config::load(&paths::data_dir());
+    match daemon::forward(&endpoint, cfg.forward_token().as_deref(), &raw.to_string()) {
+        Ok(()) => true,
+        Err(e) => {
+            log::write(&format!("daemon handoff failed, handling in-process: {e}"));
+            false
         }
+    }
+}
 
-        if let Some(ref pick) = pick {
-            let sound_path = theme_dir.join("sounds").join(&pick.file);
-            let _ = audio::play_sound(&sound_path, manifest.volume);
+/// The daemon's config and manifests, set up once by `run_daemon`.
+static DAEMON_CACHE: std::sync::OnceLock<std::sync::Arc<cache::Cache>> = std::sync::OnceLock::new();
+
+/// Forwarded payloads arrive here in daemon mode.
+fn handle_forwarded(payload: &str) {
+    let parsed = serde_json::from_str::<serde_json::Value>(payload).and_then(|raw| {
+        let hook_input = serde_json::from_value::<event::HookInput>(raw.clone())?;
+        Ok((raw, hook_input))
+    });
+    match parsed {
+        Ok((raw, hook_input)) => {
+            let overrides = pipeline::Overrides { cache: DAEMON_CACHE.get().cloned(), ..Default::default() };
+            if let Ok(outcome) = pipeline::Pipeline::with_overrides(overrides).run(&hook_input, false) {
+                history::append(&raw, &outcome);
+            }
         }
-    } else if !action.skip_notify {
-        notify::send_notification(&action.title, &action.body);
+        Err(e) => log::write(&format!("daemon: invalid payload: {e}")),
     }
+}
 
-    Ok(())
+fn run_version(verbose: bool) {
+    println!("ringring {}", buildinfo::VERSION);
+    if !verbose {
+        return;
+    }
+    let features = buildinfo::features();
+    println!("{:<16} {}", "commit", buildinfo::GIT_HASH);
+    println!("{:<16} {}", "features", if features.is_empty() { "none".to_string() } else { features.join(", ") });
+    println!("{:<16} {}", "codecs", buildinfo::CODECS.join(", "));
+    println!("{:<16} {}", "players", buildinfo::PLAYERS.join(", "));
+    println!("{:<16} {}", "notifiers", buildinfo::NOTIFIERS.join(", "));
+    println!("{:<16} {}", "manifest schema", buildinfo::MANIFEST_SCHEMA);
+    println!("{:<16} {}", "config schema", buildinfo::CONFIG_SCHEMA);
 }
 
-fn run_list(debug: bool) {
+fn run_list(debug: bool, json: bool, page: usize) {
     let sounds_dir = paths::data_dir();
+    let themes = installed_themes(&sounds_dir);
+    if json {
+        let items: Vec<serde_json::Value> = themes
+            .iter()
+            .map(|(name, manifest)| {
+                let builtin = name == builtin::NAME && manifest::Manifest::load(&sounds_dir.join(name)).is_none();
+                theme_json(name, manifest, builtin)
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&serde_json::Value::Array(items)).unwrap_or_default());
+        return;
+    }
+    for (name, manifest) in &themes {
+        if debug {
+            print_theme_debug(name, manifest, page);
+        } else {
+            println!("{}\t{}", name, manifest.display_name);
+        }
+    }
+}
 
-    let Ok(entries) = std::fs::read_dir(&sounds_dir) else { return };
-
-    let mut themes: Vec<(String, manifest::Manifest)> = entries
+/// Every theme with a valid manifest plus the built-in one, sorted by name.
+fn installed_themes(sounds_dir: &std::path::Path) -> Vec<(String, manifest::Manifest)> {
+    let mut themes: Vec<(String, manifest::Manifest)> = std::fs::read_dir(sounds_dir)
+        .into_iter()
+        .flatten()
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
         .filter_map(|e| {
@@ -239,45 +1153,135 @@ fn run_list(debug: bool) {
             Some((name, m))
         })
         .collect();
+    if !themes.iter().any(|(name, _)| name == builtin::NAME) {
+        themes.push((builtin::NAME.to_string(), builtin::manifest()));
+    }
 
     themes.sort_by(|(a, _), (b, _)| a.cmp(b));
+    themes
+}
 
-    for (name, manifest) in &themes {
-        if debug {
-            print_theme_debug(name, manifest);
-        } else {
-            println!("{}\t{}", name, manifest.display_name);
-        }
-    }
+/// One theme of `list --json`: its categories, variants and sounds, with
+/// counts so a consumer needn't walk them.
+fn theme_json(name: &str, manifest: &manifest::Manifest, builtin: bool) -> serde_json::Value {
+    let sounds = |sounds: &[manifest::Sound]| -> Vec<serde_json::Value> {
+        sounds.iter().map(|s| serde_json::json!({ "file": s.file, "line": s.line })).collect()
+    };
+    let categories: serde_json::Map<String, serde_json::Value> = manifest
+        .categories
+        .iter()
+        .map(|(cat_name, cat)| {
+            let variants: serde_json::Map<String, serde_json::Value> = cat
+                .variants
+                .iter()
+                .map(|(variant, v)| (variant.clone(), serde_json::json!({ "after": v.after, "sounds": sounds(&v.sounds) })))
+                .collect();
+            let value = serde_json::json!({
+                "title": cat.title,
+                "body": cat.body,
+                "sound_count": cat.sounds.len() + cat.variants.values().map(|v| v.sounds.len()).sum::<usize>(),
+                "sounds": sounds(&cat.sounds),
+                "variants": variants,
+            });
+            (cat_name.clone(), value)
+        })
+        .collect();
+    serde_json::json!({
+        "name": name,
+        "display_name": manifest.display_name,
+        "builtin": builtin,
+        "volume": manifest.volume,
+        "sound_count": manifest.files().len(),
+        "categories": categories,
+    })
 }
 
-fn print_theme_debug(name: &str, manifest: &manifest::Manifest) {
+fn print_theme_debug(name: &str, manifest: &manifest::Manifest, page: usize) {
     println!("=== {} ({}) ===", name, manifest.display_name);
     let mut categories: Vec<(&str, &manifest::Category)> =
         manifest.categories.iter().map(|(k, v)| (k.as_str(), v)).collect();
     categories.sort_by_key(|(k, _)| *k);
     for (cat_name, cat) in categories {
         println!("  {}:", cat_name);
-        for sound in &cat.sounds {
-            if let Some(ref line) = sound.line {
-                println!("    - {}  \"{}\"", sound.file, line);
-            } else {
-                println!("    - {}", sound.file);
+        print_sounds("    ", &cat.sounds, page);
+        for (variant, v) in &cat.variants {
+            println!("    {variant} (after {}):", v.after);
+            print_sounds("      ", &v.sounds, page);
+        }
+    }
+}
+
+/// Sounds `list --debug` shows per category and page.
+const DEBUG_PAGE_SIZE: usize = 50;
+
+/// One page of `sounds`, with a note on where the rest are when they don't fit.
+fn print_sounds(indent: &str, sounds: &[manifest::Sound], page: usize) {
+    let start = (page - 1).saturating_mul(DEBUG_PAGE_SIZE).min(sounds.len());
+    let end = (start + DEBUG_PAGE_SIZE).min(sounds.len());
+    for sound in &sounds[start..end] {
+        if let Some(ref line) = sound.line {
+            println!("{indent}- {}  \"{}\"", sound.file, line);
+        } else {
+            println!("{indent}- {}", sound.file);
+        }
+    }
+    if sounds.len() > DEBUG_PAGE_SIZE {
+        let pages = sounds.len().div_ceil(DEBUG_PAGE_SIZE);
+        let shown = if start == end { "none".to_string() } else { format!("{}–{end}", start + 1) };
+        let next = if page < pages { format!("; --page {} for more", page + 1) } else { String::new() };
+        println!("{indent}({shown} of {}, page {page} of {pages}{next})", sounds.len());
+    }
+}
+
+/// Play a file, or a sound picked the hook's way from `theme:category`,
+/// through the configured player and its limits. Blocks until it's done.
+fn run_play(target: &str, volume: Option<f32>) -> Result<(), Box<dyn std::error::Error>> {
+    if target.is_empty() {
+        return Err("usage: ringring play <file|theme:category> [--volume <0.0-1.0>]".into());
+    }
+    if let Some(volume) = volume.filter(|v| !(0.0..=1.0).contains(v)) {
+        return Err(format!("--volume {volume} is outside 0.0–1.0").into());
+    }
+    let sounds_dir = paths::data_dir();
+    let cfg = config::Config::load(&sounds_dir);
+    let path = std::path::Path::new(target);
+    let _lock = audio::preview_lock()?;
+    match target.split_once(':') {
+        Some((theme, category)) if !path.exists() => {
+            config::validate_theme_name(theme)?;
+            let (manifest, sounds) = builtin::load_theme(&sounds_dir, theme).ok_or_else(|| format!("theme '{theme}' not found"))?;
+            if !manifest.categories.contains_key(category) {
+                let mut known: Vec<&str> = manifest.categories.keys().map(String::as_str).collect();
+                known.sort_unstable();
+                return Err(format!("category '{category}' not found in theme '{theme}' (has: {})", known.join(", ")).into());
             }
+            let pick = manifest::pick_sound(&manifest, category).ok_or_else(|| format!("category '{category}' has no sounds"))?;
+            println!("{} {}", ui::paint(ui::Style::Bold, &format!("[{category}]")), pick.file);
+            sounds.play_blocking(&*audio::player_for(&cfg, category), &pick.file, volume.unwrap_or(manifest.volume * cfg.volume_scale(theme)))
+        }
+        _ => {
+            if !path.is_file() {
+                return Err(format!("{target}: no such file (or use theme:category)").into());
+            }
+            println!("{}", path.display());
+            audio::player(&cfg).play(audio::Clip::File(path), volume.unwrap_or(1.0))
         }
     }
 }
 
-fn run_test(theme: &str, category: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+fn run_test(theme: &str, category: Option<&str>, preview: &Preview) -> Result<(), Box<dyn std::error::Error>> {
     if theme.is_empty() {
-        return Err("usage: ringring test <theme> [--category <cat>]".into());
+        return Err("usage: ringring test <theme> [--category <cat>] [--gap <ms>] [--limit <n>] [--shuffle] [--notify] [--pick] [--count <n>]".into());
     }
 
+    config::validate_theme_name(theme)?;
     let sounds_dir = paths::data_dir();
-    let theme_dir = config::theme_dir(&sounds_dir, theme);
-
-    let manifest = manifest::Manifest::load(&theme_dir)
-        .ok_or_else(|| format!("no manifest found for theme '{theme}'"))?;
+    let loaded = match category {
+        Some(cat) => builtin::load_theme_categories(&sounds_dir, theme, &[cat]),
+        None => builtin::load_theme(&sounds_dir, theme),
+    };
+    let (manifest, sounds) = loaded.ok_or_else(|| format!("no manifest found for theme '{theme}'"))?;
+    let cfg = config::Config::load(&sounds_dir);
 
     let categories: Vec<(&str, &manifest::Category)> = if let Some(cat) = category {
         let entry = manifest.categories.get(cat)
@@ -290,67 +1294,509 @@ fn run_test(theme: &str, category: Option<&str>) -> Result<(), Box<dyn std::erro
         pairs
     };
 
+    if manifest.is_visual() {
+        // Nothing to hear: show each category's notification instead
+        let env = headless::detect(cfg.headless);
+        for (cat_name, cat) in &categories {
+            let title = cfg.badged_title(cat_name, cat.title.as_deref().unwrap_or(cat_name));
+            let body = cat.body.clone().unwrap_or_default();
+            println!("{} {title}: {body}", ui::paint(ui::Style::Bold, &format!("[{cat_name}]")));
+            let mut branding = notify::Branding::from_config(&cfg);
+            if let builtin::Sounds::Dir(dir) = &sounds
+                && let Some(icon) = dir.parent().and_then(|theme_dir| manifest.icon(theme_dir, cat_name))
+            {
+                branding.icon = Some(icon);
+            }
+            notify::Dispatcher::new(&cfg, branding, &env, None, None).send(&title, &body);
+        }
+        return Ok(());
+    }
+
+    let _lock = audio::preview_lock()?;
+    let mut first = true;
     for (cat_name, cat) in &categories {
-        // Preview mode: play every sound in the category, not a random pick.
-        for sound in &cat.sounds {
-            println!("[{cat_name}] {}", sound.file);
-            let sound_path = theme_dir.join("sounds").join(&sound.file);
-            let _ = audio::play_sound_blocking(&sound_path, manifest.volume);
+        // Preview mode: play every sound in the category unless --pick asks
+        // for the hook's random choice.
+        let player = audio::player_for(&cfg, cat_name);
+        let picked = preview.sounds(&manifest, cat_name, cat);
+        for sound in &picked {
+            if !std::mem::take(&mut first) {
+                std::thread::sleep(preview.gap);
+            }
+            println!("{} {}", ui::paint(ui::Style::Bold, &format!("[{cat_name}]")), sound.file);
+            preview.notify(&cfg, cat_name, cat, sound);
+            let _ = sounds.play_blocking(&*player, &sound.file, manifest.volume * cfg.volume_scale(theme));
         }
+        preview.print_tally(&picked);
     }
 
     Ok(())
 }
 
-fn run_install() -> Result<(), Box<dyn std::error::Error>> {
-    let home = std::env::var("HOME").map_err(|_| "HOME not set")?;
-    let bin_dir = PathBuf::from(&home).join(".local/bin");
+/// Play one sound of `category` from every installed theme, naming each
+/// theme first, to compare them side by side.
+fn run_test_all(category: &str, preview: &Preview) -> Result<(), Box<dyn std::error::Error>> {
+    let sounds_dir = paths::data_dir();
+    let cfg = config::Config::load(&sounds_dir);
+    let player = audio::player_for(&cfg, category);
+    let _lock = audio::preview_lock()?;
+    let mut played = 0;
+    for (name, manifest) in installed_themes(&sounds_dir) {
+        let (Some(cat), Some((_, sounds))) = (manifest.categories.get(category), builtin::load_theme(&sounds_dir, &name)) else {
+            continue;
+        };
+        let picked = preview.sounds(&manifest, category, cat);
+        for sound in &picked {
+            if played > 0 {
+                std::thread::sleep(preview.gap);
+            }
+            println!("{} {}  {}", ui::paint(ui::Style::Bold, &name), manifest.display_name, sound.file);
+            preview.notify(&cfg, category, cat, sound);
+            let _ = sounds.play_blocking(&*player, &sound.file, manifest.volume * cfg.volume_scale(&name));
+            played += 1;
+        }
+        preview.print_tally(&picked);
+    }
+    if played == 0 {
+        return Err(format!("no installed theme has a '{category}' category").into());
+    }
+    Ok(())
+}
+
+/// Install the binary into `bin_dir` (default `~/.local/bin`) and register
+/// the hooks in `settings` (default `~/.claude/settings.json`); the slash
+/// command goes next to the settings file. Without a `bin_dir` at all
+/// (`assets install`) the binary stays where the package manager put it.
+fn run_install(
+    bin_dir: Option<Option<&str>>,
+    settings: Option<&str>,
+    yes: bool,
+    matchers: &[(String, String)],
+    events: EventChoice,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let home = || std::env::var("HOME").map(PathBuf::from).map_err(|_| "HOME not set");
+    let bin_dir = match bin_dir {
+        Some(Some(dir)) => Some(PathBuf::from(dir)),
+        Some(None) => Some(home()?.join(".local/bin")),
+        None => None,
+    };
+    let settings_path = match settings {
+        Some(file) => PathBuf::from(file),
+        None => home()?.join(".claude/settings.json"),
+    };
+    let claude_dir = settings_path.parent().map(PathBuf::from).unwrap_or_default();
     let config_dir = paths::config_dir();
     let data_dir = paths::data_dir();
 
+    let chosen = match events {
+        EventChoice::Config => None,
+        EventChoice::List(list) => Some(list),
+        EventChoice::Pick => {
+            let current = install::hook_plan(&config::Config::load(&data_dir), matchers);
+            Some(pick_events(&current.into_iter().map(|h| h.event).collect::<Vec<_>>())?)
+        }
+    };
+    let cfg = match &chosen {
+        Some(chosen) => {
+            let mut raw = config::raw_config(&data_dir)?;
+            install::choose_events(&mut raw, chosen);
+            serde_json::from_value(raw)?
+        }
+        None => config::Config::load(&data_dir),
+    };
+
     if !config_dir.exists() {
         std::fs::create_dir_all(&config_dir)?;
-        println!("created {}", config_dir.display());
+        ui::ok(&format!("created {}", config_dir.display()));
     }
 
     if !data_dir.exists() {
         std::fs::create_dir_all(&data_dir)?;
-        println!("created {}", data_dir.display());
+        ui::ok(&format!("created {}", data_dir.display()));
     }
 
-    install::install_binary(&bin_dir)?;
-    println!("installed binary to {}", bin_dir.join("ringring").display());
+    match &bin_dir {
+        Some(bin_dir) => {
+            install::install_binary(bin_dir)?;
+            ui::ok(&format!("installed binary to {}", bin_dir.join("ringring").display()));
+            // The hooks run plain `ringring`
+            let on_path = std::env::var_os("PATH").is_some_and(|path| std::env::split_paths(&path).any(|dir| dir == *bin_dir));
+            if !on_path {
+                ui::warn(&format!("{} is not on PATH; add it so Claude Code can run the hooks", bin_dir.display()));
+            }
+        }
+        None => {
+            if install::on_path().is_none() {
+                ui::warn("ringring is not on PATH; Claude Code won't be able to run the hooks");
+            }
+            let config_path = data_dir.join("config.json");
+            if !config_path.exists() {
+                fs::write(&config_path, format!("{{\n  \"theme\": \"{}\"\n}}\n", builtin::NAME))?;
+                ui::ok(&format!("created {} with the {} theme", config_path.display(), builtin::NAME));
+            }
+            #[cfg(target_os = "linux")]
+            {
+                let branding = notify::Branding::from_config(&cfg);
+                if !notify::install_desktop_entry(&branding, &home()?).is_empty() {
+                    ui::ok(&format!("installed desktop entry {}.desktop", branding.app_id));
+                }
+            }
+        }
+    }
 
-    let claude_dir = PathBuf::from(&home).join(".claude");
-    let settings_path = claude_dir.join("settings.json");
-    install::register_hooks(&settings_path)?;
-    println!("registered hooks in {}", settings_path.display());
+    let hooks = install::hook_plan(&cfg, matchers);
+    let edit = install::register_hooks(&settings_path, &hooks, |edit| approve_settings(edit, yes))?;
+    hookcheck::remember_settings(&data_dir, &settings_path)?;
+    if let Some(chosen) = &chosen {
+        config::edit_config(&data_dir, |root| install::choose_events(root, chosen))?;
+        ui::ok(&format!("hooking {} (recorded in config.json events)", chosen.join(", ")));
+    }
+    if edit.added.is_empty() && edit.removed.is_empty() {
+        ui::ok(&format!("hooks already registered in {}", settings_path.display()));
+    }
+    if !edit.added.is_empty() {
+        ui::ok(&format!("registered hooks for {} in {}", edit.added.join(", "), settings_path.display()));
+    }
+    if !edit.removed.is_empty() {
+        ui::ok(&format!("removed hooks for {} (switched off in config)", edit.removed.join(", ")));
+    }
 
     install::install_command(&claude_dir)?;
-    println!("installed /ringring command to {}", claude_dir.join("commands/ringring.md").display());
+    ui::ok(&format!("installed /ringring command to {}", claude_dir.join("commands/ringring.md").display()));
+
+    Ok(())
+}
+
+/// `install --repair`: put back the hooks Claude Code's settings.json lost,
+/// leaving everything else as it is.
+fn run_repair_hooks(settings: Option<&str>, yes: bool, matchers: &[(String, String)]) -> Result<(), Box<dyn std::error::Error>> {
+    let settings_path = match settings {
+        Some(file) => PathBuf::from(file),
+        None => PathBuf::from(std::env::var("HOME").map_err(|_| "HOME not set")?).join(".claude/settings.json"),
+    };
+    let data_dir = paths::data_dir();
+    let hooks = install::hook_plan(&config::Config::load(&data_dir), matchers);
+    let edit = install::repair_hooks(&settings_path, &hooks, |edit| approve_settings(edit, yes))?;
+    fs::create_dir_all(&data_dir)?;
+    hookcheck::remember_settings(&data_dir, &settings_path)?;
+    if edit.added.is_empty() {
+        ui::ok(&format!("no hooks missing in {}", settings_path.display()));
+    } else {
+        ui::ok(&format!("re-added hooks for {} in {}", edit.added.join(", "), settings_path.display()));
+    }
+    Ok(())
+}
+
+/// The first time a command runs before the hooks were set up for this user,
+/// say how: `assets install` for a binary a package manager installed
+/// outside the home dir, `install` otherwise.
+fn suggest_setup() {
+    let hint = paths::state_dir().join("setup-hint");
+    if hookcheck::installed(&paths::data_dir()) || hint.exists() {
+        return;
+    }
+    let _ = fs::create_dir_all(paths::state_dir());
+    let _ = fs::write(&hint, "");
+    let home = PathBuf::from(std::env::var("HOME").unwrap_or_default());
+    let packaged = std::env::current_exe().is_ok_and(|exe| install::packaged(&exe, &home));
+    if packaged {
+        ui::warn("ringring isn't set up for this user yet; run `ringring assets install` to add the hooks, config and desktop entry");
+    } else {
+        ui::warn("ringring isn't set up yet; run `ringring install` to copy the binary and add the hooks");
+    }
+}
+
+/// `repair`: put back whatever of an install went missing or stale, leaving
+/// the rest alone. Safe to run any number of times.
+fn run_repair(bin_dir: Option<&str>, settings: Option<&str>, yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let home = PathBuf::from(std::env::var("HOME").map_err(|_| "HOME not set")?);
+    let bin_dir = bin_dir.map_or_else(|| home.join(".local/bin"), PathBuf::from);
+    let settings_path = settings.map_or_else(|| home.join(".claude/settings.json"), PathBuf::from);
+    let claude_dir = settings_path.parent().map(PathBuf::from).unwrap_or_default();
+    let data_dir = paths::data_dir();
+    let mut fixed = 0;
+    let mut fix = |what: String| {
+        ui::ok(&what);
+        fixed += 1;
+    };
+
+    for dir in [paths::config_dir(), data_dir.clone()] {
+        if !dir.exists() {
+            fs::create_dir_all(&dir)?;
+            fix(format!("created {}", dir.display()));
+        }
+    }
+
+    let dest = bin_dir.join("ringring");
+    match install::binary_state(&dest, &std::env::current_exe()?) {
+        install::BinaryState::Current => {}
+        install::BinaryState::Missing => {
+            install::install_binary(&bin_dir)?;
+            fix(format!("installed missing binary to {}", dest.display()));
+        }
+        install::BinaryState::Stale => {
+            install::install_binary(&bin_dir)?;
+            fix(format!("replaced stale binary at {}", dest.display()));
+        }
+        install::BinaryState::NotExecutable => {
+            install::make_executable(&dest)?;
+            fix(format!("made {} executable", dest.display()));
+        }
+    }
+
+    let hooks = install::hook_plan(&config::Config::load(&data_dir), &[]);
+    let edit = install::repair_hooks(&settings_path, &hooks, |edit| approve_settings(edit, yes))?;
+    hookcheck::remember_settings(&data_dir, &settings_path)?;
+    if !edit.added.is_empty() {
+        fix(format!("re-added hooks for {} in {}", edit.added.join(", "), settings_path.display()));
+    }
+
+    if !install::command_installed(&claude_dir) {
+        install::install_command(&claude_dir)?;
+        fix(format!("reinstalled /ringring command to {}", claude_dir.join("commands/ringring.md").display()));
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let branding = notify::Branding::from_config(&config::Config::load(&data_dir));
+        for file in notify::install_desktop_entry(&branding, &home) {
+            fix(format!("rewrote {}", file.display()));
+        }
+    }
+
+    if fixed == 0 {
+        ui::ok("nothing to repair");
+    }
+    Ok(())
+}
+
+/// Let the user toggle hook events on a numbered checklist, starting from
+/// `current`.
+fn pick_events(current: &[String]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    use std::io::Write;
+    if !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        return Err("--pick-events needs a terminal; use --events instead".into());
+    }
+    let events: Vec<&str> = install::selectable_events().collect();
+    let mut on: Vec<bool> = events.iter().map(|event| current.iter().any(|c| c == event)).collect();
+    loop {
+        for (i, (event, on)) in events.iter().zip(&on).enumerate() {
+            println!("  {}. [{}] {event}", i + 1, if *on { "x" } else { " " });
+        }
+        print!("Toggle by number (e.g. 2 5), Enter to accept: ");
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if answer.trim().is_empty() {
+            break;
+        }
+        for word in answer.split([' ', ',']).map(str::trim).filter(|w| !w.is_empty()) {
+            match word.parse::<usize>().ok().filter(|n| (1..=events.len()).contains(n)) {
+                Some(n) => on[n - 1] = !on[n - 1],
+                None => ui::warn(&format!("no event number {word}")),
+            }
+        }
+    }
+    let picked: Vec<String> = events.iter().zip(&on).filter(|(_, on)| **on).map(|(event, _)| event.to_string()).collect();
+    if picked.is_empty() {
+        return Err("no hook events chosen".into());
+    }
+    Ok(picked)
+}
+
+/// Show the settings.json diff and ask before writing it, unless `yes`.
+/// Without a terminal to ask on, the answer is no.
+fn approve_settings(edit: &install::SettingsEdit, yes: bool) -> bool {
+    for line in edit.diff() {
+        let style = match line.chars().next() {
+            Some('+') if !line.starts_with("+++") => Some(ui::Style::Good),
+            Some('-') if !line.starts_with("---") => Some(ui::Style::Bad),
+            Some('@') => Some(ui::Style::Bold),
+            _ => None,
+        };
+        println!("{}", style.map_or(line.clone(), |style| ui::paint(style, &line)));
+    }
+    if yes {
+        return true;
+    }
+    if !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        ui::warn("not asking without a terminal; pass --yes to apply the changes");
+        return false;
+    }
+    confirm(&format!("apply these changes to {}?", edit.path.display())).unwrap_or(false)
+}
+
+/// Undo `install`: hooks, slash command and per-session files; with `purge`
+/// also the binary, data dir (themes and config.json), state, runtime and
+/// config dirs, and the daemon's systemd units.
+fn run_uninstall(
+    purge: bool,
+    yes: bool,
+    bin_dir: Option<&str>,
+    settings: Option<&str>,
+    events: Option<&[String]>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let home = || std::env::var("HOME").map(PathBuf::from).map_err(|_| "HOME not set");
+    let settings_path = match settings {
+        Some(file) => PathBuf::from(file),
+        None => home()?.join(".claude/settings.json"),
+    };
+    let claude_dir = settings_path.parent().map(PathBuf::from).unwrap_or_default();
+
+    if let Some(events) = events {
+        // Just these events: switch them off so the next install agrees
+        let removed = install::unregister_hooks(&settings_path, Some(events))?;
+        let data_dir = paths::data_dir();
+        let keep: Vec<String> = install::hook_plan(&config::Config::load(&data_dir), &[])
+            .into_iter()
+            .map(|hook| hook.event)
+            .filter(|event| !events.contains(event))
+            .collect();
+        config::edit_config(&data_dir, |root| install::choose_events(root, &keep))?;
+        if removed.is_empty() {
+            ui::ok(&format!("no ringring hooks for {} in {}", events.join(", "), settings_path.display()));
+        } else {
+            ui::ok(&format!("removed hooks for {} from {}", removed.join(", "), settings_path.display()));
+        }
+        ui::ok(&format!("switched off {} in config.json", events.join(", ")));
+        return Ok(());
+    }
+
+    let removed = install::unregister_hooks(&settings_path, None)?;
+    hookcheck::forget_settings(&paths::data_dir());
+    if removed.is_empty() {
+        ui::ok(&format!("no ringring hooks in {}", settings_path.display()));
+    } else {
+        ui::ok(&format!("removed hooks for {} from {}", removed.join(", "), settings_path.display()));
+    }
+
+    let command = claude_dir.join("commands/ringring.md");
+    if command.exists() {
+        fs::remove_file(&command)?;
+        ui::ok(&format!("removed {}", command.display()));
+    }
+
+    let session_files = install::session_files(&paths::tmp_dir());
+    for file in &session_files {
+        let _ = fs::remove_file(file);
+    }
+    if !session_files.is_empty() {
+        ui::ok(&format!("removed {} session files from {}", session_files.len(), paths::tmp_dir().display()));
+    }
+
+    if !purge {
+        return Ok(());
+    }
 
+    if let Ok(pid) = daemon::stop() {
+        ui::ok(&format!("stopped daemon (pid {pid})"));
+    }
+    let units = ["ringring.socket", "ringring.service"].map(|unit| paths::systemd_user_dir().join(unit));
+    if units.iter().any(|unit| unit.exists()) {
+        let _ = std::process::Command::new("systemctl")
+            .args(["--user", "disable", "--now", "ringring.socket"])
+            .stderr(std::process::Stdio::null())
+            .status();
+        for unit in units.iter().filter(|unit| unit.exists()) {
+            fs::remove_file(unit)?;
+            ui::ok(&format!("removed {}", unit.display()));
+        }
+    }
+
+    let binary = match bin_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => home()?.join(".local/bin"),
+    }
+    .join("ringring");
+    if binary.exists() {
+        fs::remove_file(&binary)?;
+        ui::ok(&format!("removed {}", binary.display()));
+    }
+
+    // The data dir may be pointed to from the config dir, so resolve it first
+    let data_dir = paths::data_dir();
+    let mut dirs = vec![paths::state_dir(), paths::runtime_dir(), paths::config_dir()];
+    if data_dir.exists() && !paths::owns_data_dir(&data_dir) && !confirm_purge(&data_dir, yes)? {
+        ui::ok(&format!("kept {}", data_dir.display()));
+    } else {
+        dirs.insert(0, data_dir);
+    }
+    for dir in dirs {
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+            ui::ok(&format!("removed {}", dir.display()));
+        }
+    }
     Ok(())
 }
 
-fn run_theme_install(source: &str, force: bool) -> Result<(), Box<dyn std::error::Error>> {
-    if source.is_empty() {
-        return Err("usage: ringring theme install [--force] <path|url>".into());
+/// A data dir the user chose may hold more than ringring's files: show
+/// what's in it and ask before deleting it.
+fn confirm_purge(dir: &std::path::Path, yes: bool) -> std::io::Result<bool> {
+    println!("{} was chosen as the data dir; it holds:", dir.display());
+    let mut names: Vec<String> = fs::read_dir(dir)?.filter_map(|e| e.ok()).map(|e| e.file_name().to_string_lossy().into_owned()).collect();
+    names.sort();
+    for name in &names {
+        println!("  {name}");
+    }
+    if yes {
+        return Ok(true);
+    }
+    if !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        ui::warn("not asking without a terminal; pass --yes to delete it");
+        return Ok(false);
+    }
+    confirm(&format!("delete {} and everything in it?", dir.display()))
+}
+
+fn run_theme_install(spec: &str, force: bool, check: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if spec.is_empty() {
+        return Err("usage: ringring theme install [--force] [--check] <path|url|git url|name>".into());
     }
     let data_dir = paths::data_dir();
-    let theme_name = install::theme_install(source, &data_dir, force)?;
-    println!("installed theme '{theme_name}' to {}", data_dir.join(&theme_name).display());
+    let cfg = config::Config::load(&data_dir);
+    let source = install::source_for(spec, &cfg.registry);
+    let staged = install::stage(&*source, &data_dir)?;
+    if check {
+        let manifest = manifest::Manifest::load(&staged.dir).ok_or("manifest.json does not parse")?;
+        let limits = cfg.playback_limits.unwrap_or_default();
+        check_sounds(&staged.dir, &manifest, &limits).map_err(|e| format!("not installing theme '{}': {e}", staged.name))?;
+    }
+    let sha256 = staged.sha256.clone();
+    let theme_name = staged.install(&data_dir, force)?;
+    let receipt = updates::Receipt { source: spec.to_string(), sha256, installed: log::now_secs() };
+    if let Err(e) = updates::record_receipt(&data_dir, &theme_name, receipt) {
+        ui::warn(&format!("could not record the install receipt: {e}"));
+    }
+    ui::ok(&format!("installed theme '{theme_name}' from {} to {}", source.describe(), data_dir.join(&theme_name).display()));
+    if let Some(manifest) = manifest::Manifest::load(&data_dir.join(&theme_name)) {
+        print_categories(&manifest);
+    }
     Ok(())
 }
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn data_dir_flag_removed_from_args() {
+        let mut args: Vec<String> = ["ringring", "--data-dir", "/sb", "list"].map(String::from).to_vec();
+        assert_eq!(take_data_dir_flag(&mut args).as_deref(), Some("/sb"));
+        assert_eq!(args, ["ringring", "list"]);
+
+        let mut args: Vec<String> = ["ringring", "list", "--data-dir=/sb"].map(String::from).to_vec();
+        assert_eq!(take_data_dir_flag(&mut args).as_deref(), Some("/sb"));
+        assert_eq!(args, ["ringring", "list"]);
+        assert_eq!(take_data_dir_flag(&mut args), None);
+    }
+
     #[test]
     fn parse_test_args_theme_only() {
         let args = vec!["ringring".to_string(), "test".to_string(), "peon".to_string()];
         let cmd = parse_args(&args);
-        assert!(matches!(cmd, Cmd::Test { theme, category: None } if theme == "peon"));
+        assert!(matches!(cmd, Cmd::Test { theme, category: None, .. } if theme == "peon"));
     }
 
     #[test]
@@ -363,12 +1809,12 @@ mod tests {
             "greeting".to_string(),
         ];
         let cmd = parse_args(&args);
-        assert!(matches!(cmd, Cmd::Test { theme, category: Some(cat) } if theme == "peon" && cat == "greeting"));
+        assert!(matches!(cmd, Cmd::Test { theme, category: Some(cat), .. } if theme == "peon" && cat == "greeting"));
     }
 
     #[test]
     fn parse_test_args_category_flag_not_confused_with_theme() {
-        // No theme given; --category should NOT be picked up as a category value
+        // No theme given; --category is a flag, not the theme name
         let args = vec![
             "ringring".to_string(),
             "test".to_string(),
@@ -376,8 +1822,62 @@ mod tests {
             "greeting".to_string(),
         ];
         let cmd = parse_args(&args);
-        // theme is "--category" (args[2]), no --category flag in args[3..]
-        assert!(matches!(cmd, Cmd::Test { ref theme, category: None } if theme == "--category"));
+        assert!(matches!(cmd, Cmd::Test { ref theme, category: Some(ref cat), .. } if theme.is_empty() && cat == "greeting"));
+    }
+
+    #[test]
+    fn parse_rejects_unknown_commands_and_flags() {
+        let invalid = |line: &str| match parse_args(&line.split(' ').map(String::from).collect::<Vec<_>>()) {
+            Cmd::Invalid(e) => e.message,
+            _ => panic!("{line} parsed"),
+        };
+        assert_eq!(invalid("ringring lsit"), "unknown command 'lsit'");
+        assert_eq!(invalid("ringring theme instal x.zip"), "unknown command 'theme instal' (expected one of: install, search, check, stats, validate, remove, new, pack, verify, publish, set, use, pin, unpin)");
+        assert_eq!(invalid("ringring list --debgu"), "unknown flag '--debgu' for 'list'");
+        assert_eq!(invalid("ringring replay --last many"), "--last expects a number, got 'many'");
+        assert_eq!(invalid("ringring session abc snooze"), "unknown session command 'snooze' (expected theme, mute or unmute)");
+        assert_eq!(invalid("ringring routes test"), "routes test needs a hook event file (or - for stdin)");
+    }
+
+    #[test]
+    fn parse_help_and_version() {
+        let args: Vec<String> = ["ringring", "--help"].iter().map(|s| s.to_string()).collect();
+        let Cmd::Help(help) = parse_args(&args) else { panic!("no help") };
+        assert!(help.contains("theme install <source>"));
+        assert!(help.contains("--data-dir <dir>"));
+        let args: Vec<String> = ["ringring", "stats", "export", "-h"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::Help(ref h) if h.contains("--format <csv|json>")));
+        let args: Vec<String> = ["ringring", "-V"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::Version { verbose: false }));
+        let args: Vec<String> = ["ringring", "version", "-v"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::Version { verbose: true }));
+    }
+
+    #[test]
+    fn parse_test_all() {
+        let args: Vec<String> = ["ringring", "test", "--all", "--category", "permission"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::TestAll { ref category, .. } if category == "permission"));
+        let args: Vec<String> = ["ringring", "test", "--all"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::TestAll { ref category, ref preview } if category == "complete" && preview.limit == Some(1)));
+    }
+
+    #[test]
+    fn parse_test_preview_options() {
+        let args: Vec<String> = ["ringring", "test", "peon", "--gap", "50", "--limit", "2", "--shuffle", "--notify"].iter().map(|s| s.to_string()).collect();
+        let Cmd::Test { preview, .. } = parse_args(&args) else { panic!("not a test command") };
+        assert_eq!(preview, Preview { gap: std::time::Duration::from_millis(50), limit: Some(2), shuffle: true, notify: true, picks: None });
+
+        let sounds = [1, 2, 3, 4];
+        assert_eq!(preview.select(&sounds).len(), 2);
+        let in_order = Preview { limit: Some(3), shuffle: false, ..preview };
+        assert_eq!(in_order.select(&sounds), vec![&1, &2, &3]);
+
+        let args: Vec<String> = ["ringring", "test", "peon", "--count", "20"].iter().map(|s| s.to_string()).collect();
+        let Cmd::Test { preview, .. } = parse_args(&args) else { panic!("not a test command") };
+        assert_eq!(preview.picks, Some(20));
+        let args: Vec<String> = ["ringring", "test", "peon", "--pick"].iter().map(|s| s.to_string()).collect();
+        let Cmd::Test { preview, .. } = parse_args(&args) else { panic!("not a test command") };
+        assert_eq!(preview.picks, Some(1));
     }
 
     #[test]
@@ -399,87 +1899,964 @@ mod tests {
     fn parse_list_no_flags() {
         let args = vec!["ringring".to_string(), "list".to_string()];
         let cmd = parse_args(&args);
-        assert!(matches!(cmd, Cmd::List { debug: false }));
+        assert!(matches!(cmd, Cmd::List { debug: false, json: false, .. }));
     }
 
     #[test]
     fn parse_list_with_debug() {
         let args = vec!["ringring".to_string(), "list".to_string(), "--debug".to_string()];
         let cmd = parse_args(&args);
-        assert!(matches!(cmd, Cmd::List { debug: true }));
+        assert!(matches!(cmd, Cmd::List { debug: true, .. }));
+        let args = vec!["ringring".to_string(), "list".to_string(), "--json".to_string()];
+        assert!(matches!(parse_args(&args), Cmd::List { debug: false, json: true, .. }));
     }
 
     #[test]
     fn parse_install() {
         let args = vec!["ringring".to_string(), "install".to_string()];
-        assert!(matches!(parse_args(&args), Cmd::Install));
+        assert!(matches!(parse_args(&args), Cmd::Install { bin_dir: None, settings: None, yes: false, ref matchers, events: EventChoice::Config, repair: false } if matchers.is_empty()));
+        let args: Vec<String> = ["ringring", "install", "--bin-dir", "/opt/bin", "--settings", "s.json", "-y"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::Install { bin_dir: Some(ref b), settings: Some(ref s), yes: true, .. } if b == "/opt/bin" && s == "s.json"));
+        let args: Vec<String> = ["ringring", "install", "--matcher", "PostToolUse=Bash", "--matcher", "Notification=permission_prompt|idle_prompt"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(matches!(parse_args(&args), Cmd::Install { ref matchers, .. } if matchers == &[
+            ("PostToolUse".to_string(), "Bash".to_string()),
+            ("Notification".to_string(), "permission_prompt|idle_prompt".to_string()),
+        ]));
+        let args: Vec<String> = ["ringring", "install", "--matcher", "Bash"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::Invalid(_)));
+        let args: Vec<String> = ["ringring", "install", "--events", "Stop,SubagentStop"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::Install { events: EventChoice::List(ref e), .. } if e == &["Stop", "SubagentStop"]));
+        let args: Vec<String> = ["ringring", "install", "--events", "Stop", "--pick-events"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::Invalid(_)));
+        let args: Vec<String> = ["ringring", "install", "--repair", "-y"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::Install { repair: true, yes: true, .. }));
+        let args: Vec<String> = ["ringring", "install", "--repair", "--events", "Stop"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::Invalid(_)));
+        let args: Vec<String> = ["ringring", "assets", "install", "-y", "--events", "Stop"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::AssetsInstall { yes: true, events: EventChoice::List(ref e), .. } if e == &["Stop"]));
+        let args: Vec<String> = ["ringring", "assets", "install", "--bin-dir", "/opt/bin"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::Invalid(_)));
+        let args: Vec<String> = ["ringring", "repair", "-y", "--bin-dir", "/opt/bin"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::Repair { bin_dir: Some(ref b), settings: None, yes: true } if b == "/opt/bin"));
+    }
+
+    #[test]
+    fn parse_theme_install_local() {
+        let args = vec!["ringring".to_string(), "theme".to_string(), "install".to_string(), "/tmp/foo.zip".to_string()];
+        assert!(matches!(parse_args(&args), Cmd::ThemeInstall { ref source, force: false, .. } if source == "/tmp/foo.zip"));
+    }
+
+    #[test]
+    fn parse_theme_install_force() {
+        let args = vec!["ringring".to_string(), "theme".to_string(), "install".to_string(), "--force".to_string(), "https://example.com/t.zip".to_string()];
+        assert!(matches!(parse_args(&args), Cmd::ThemeInstall { ref source, force: true, .. } if source == "https://example.com/t.zip"));
+    }
+
+    #[test]
+    fn parse_theme_pack_and_verify() {
+        let args: Vec<String> = ["ringring", "theme", "pack", "-o", "out.zip", "themes/peon"].map(String::from).to_vec();
+        assert!(matches!(parse_args(&args), Cmd::ThemePack { ref dir, output: Some(ref o) } if dir == "themes/peon" && o == "out.zip"));
+        let args: Vec<String> = ["ringring", "theme", "verify", "--against", "src", "peon.zip"].map(String::from).to_vec();
+        assert!(matches!(parse_args(&args), Cmd::ThemeVerify { ref zip, ref against } if zip == "peon.zip" && against == "src"));
+    }
+
+    #[test]
+    fn parse_theme_check_and_install_check() {
+        let args: Vec<String> = ["ringring", "theme", "install", "--check", "t.zip"].map(String::from).to_vec();
+        assert!(matches!(parse_args(&args), Cmd::ThemeInstall { ref source, force: false, check: true } if source == "t.zip"));
+        let args: Vec<String> = ["ringring", "theme", "check", "peon"].map(String::from).to_vec();
+        assert!(matches!(parse_args(&args), Cmd::ThemeCheck { ref theme } if theme == "peon"));
+    }
+
+    #[test]
+    fn parse_theme_use() {
+        let args: Vec<String> = ["ringring", "theme", "use", "--session", "abc", "peon", "--pin"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(matches!(
+            parse_args(&args),
+            Cmd::ThemeUse { theme, session: Some(s), here: false, pin: true } if theme == "peon" && s == "abc"
+        ));
+        let args: Vec<String> = ["ringring", "theme", "use", "icq", "--here"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(
+            parse_args(&args),
+            Cmd::ThemeUse { theme, session: None, here: true, pin: false } if theme == "icq"
+        ));
+    }
+
+    #[test]
+    fn parse_mute_session() {
+        let args: Vec<String> = ["ringring", "mute", "--session", "abc"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::SessionMute { session_id } if session_id == "abc"));
+        let args: Vec<String> = ["ringring", "unmute", "--session", "abc"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::SessionUnmute { session_id } if session_id == "abc"));
+    }
+
+    #[test]
+    fn parse_global_mute() {
+        let words = |line: &str| -> Vec<String> { line.split(' ').map(String::from).collect() };
+        assert!(matches!(parse_args(&words("ringring mute")), Cmd::Mute { duration: None, all: false }));
+        assert!(matches!(
+            parse_args(&words("ringring mute --for 30m --all")),
+            Cmd::Mute { duration: Some(d), all: true } if d.as_secs() == 1800
+        ));
+        assert!(matches!(parse_args(&words("ringring mute --for soon")), Cmd::Invalid(_)));
+        assert!(matches!(parse_args(&words("ringring mute --session abc --for 1h")), Cmd::Invalid(_)));
+        assert!(matches!(parse_args(&words("ringring unmute")), Cmd::Unmute));
+    }
+
+    #[test]
+    fn parse_forward_target() {
+        let args = vec!["ringring".to_string(), "forward".to_string(), "--to".to_string(), "host:7878".to_string()];
+        assert!(matches!(parse_args(&args), Cmd::Forward { ref to } if to == "host:7878"));
+    }
+
+    #[test]
+    fn parse_daemon_listen() {
+        let args = vec!["ringring".to_string(), "daemon".to_string()];
+        assert!(matches!(parse_args(&args), Cmd::Daemon { listen: None }));
+        let args = vec!["ringring".to_string(), "daemon".to_string(), "--listen".to_string(), "0.0.0.0:7878".to_string()];
+        assert!(matches!(parse_args(&args), Cmd::Daemon { listen: Some(ref l) } if l == "0.0.0.0:7878"));
+    }
+
+    #[test]
+    fn parse_daemon_control() {
+        let args = vec!["ringring".to_string(), "daemon".to_string(), "stop".to_string()];
+        assert!(matches!(parse_args(&args), Cmd::DaemonStop));
+        let args = vec!["ringring".to_string(), "daemon".to_string(), "reload".to_string()];
+        assert!(matches!(parse_args(&args), Cmd::DaemonReload));
+        let args = vec!["ringring".to_string(), "daemon".to_string(), "status".to_string()];
+        assert!(matches!(parse_args(&args), Cmd::DaemonStatus));
+    }
+
+    #[test]
+    fn parse_daemon_install_service() {
+        let args = vec!["ringring".to_string(), "daemon".to_string(), "install-service".to_string()];
+        assert!(matches!(parse_args(&args), Cmd::DaemonInstallService));
+    }
+
+    #[test]
+    fn parse_replay_defaults_to_last_event() {
+        let args = vec!["ringring".to_string(), "replay".to_string()];
+        assert!(matches!(parse_args(&args), Cmd::Replay { file: None, last: None, id: None, dry_run: false }));
+    }
+
+    #[test]
+    fn parse_simulate_builds_hook_input() {
+        let args: Vec<String> = ["ringring", "simulate", "Notification", "--type", "idle_prompt", "--session-id", "abc", "--cwd", "/w"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let Cmd::Simulate { simulation, dry_run: false } = parse_args(&args) else { panic!("not simulate") };
+        assert_eq!(
+            simulation.input(),
+            serde_json::json!({"hook_event_name": "Notification", "session_id": "abc", "cwd": "/w", "notification_type": "idle_prompt"})
+        );
+
+        let args: Vec<String> = ["ringring", "simulate", "SessionStart", "--cwd", "/w"].iter().map(|s| s.to_string()).collect();
+        let Cmd::Simulate { simulation, .. } = parse_args(&args) else { panic!("not simulate") };
+        assert_eq!(simulation.input()["source"], "startup");
+        assert!(matches!(parse_args(&["ringring".to_string(), "simulate".to_string()]), Cmd::Invalid(_)));
+    }
+
+    #[test]
+    fn parse_history_flags() {
+        let args: Vec<String> = ["ringring", "history"].map(String::from).to_vec();
+        assert!(matches!(parse_args(&args), Cmd::History { lines: 50, json: false }));
+        let args: Vec<String> = ["ringring", "history", "-n", "5", "--json"].map(String::from).to_vec();
+        assert!(matches!(parse_args(&args), Cmd::History { lines: 5, json: true }));
+    }
+
+    #[test]
+    fn parse_play_takes_a_volume() {
+        let args: Vec<String> = ["ringring", "play", "earcon:complete", "--volume", "0.5"].map(String::from).to_vec();
+        assert!(matches!(parse_args(&args), Cmd::Play { ref target, volume: Some(v) } if target == "earcon:complete" && v == 0.5));
+        let args: Vec<String> = ["ringring", "play", "ding.wav", "--volume", "loud"].map(String::from).to_vec();
+        assert!(matches!(parse_args(&args), Cmd::Invalid(_)));
+    }
+
+    #[test]
+    fn parse_replay_flags() {
+        let args: Vec<String> = ["ringring", "replay", "--last", "5", "--dry-run"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::Replay { file: None, last: Some(5), id: None, dry_run: true }));
+        let args: Vec<String> = ["ringring", "replay", "--id", "abc"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::Replay { id: Some(ref id), .. } if id == "abc"));
+        let args: Vec<String> = ["ringring", "replay", "hooks.jsonl"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::Replay { file: Some(ref file), last: None, .. } if file == "hooks.jsonl"));
+    }
+
+    #[test]
+    fn parse_stats_export() {
+        let args: Vec<String> = ["ringring", "stats", "export", "--format", "json", "--since", "30d"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::StatsExport { ref format, since: Some(ref since) } if format == "json" && since == "30d"));
+        let args: Vec<String> = ["ringring", "stats", "--suggest"].iter().map(|s| s.to_string()).collect();
+        assert!(matches!(parse_args(&args), Cmd::Stats { suggest: true, apply: false }));
+    }
+
+    #[test]
+    fn parse_theme_install_missing_source() {
+        let args = vec!["ringring".to_string(), "theme".to_string(), "install".to_string()];
+        assert!(matches!(parse_args(&args), Cmd::ThemeInstall { ref source, .. } if source.is_empty()));
+    }
+}
+
+/// Registry themes matching `query`, marking the ones already installed.
+fn run_theme_search(query: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let data_dir = paths::data_dir();
+    let cfg = config::Config::load(&data_dir);
+    let index = registry::fetch_index(&cfg.registry)?;
+    let found = registry::search(&index, query);
+    if found.is_empty() {
+        println!("no themes match '{query}'");
+        return Ok(());
+    }
+    println!("| Theme | Version | Description |");
+    println!("|-------|---------|-------------|");
+    for entry in found {
+        let installed = if data_dir.join(&entry.name).join("manifest.json").is_file() { " (installed)" } else { "" };
+        let description = entry.description.as_deref().unwrap_or(&entry.display_name);
+        println!("| {}{installed} | {} | {description} |", entry.name, entry.version.as_deref().unwrap_or("-"));
+    }
+    println!("install one with: ringring theme install <theme>");
+    Ok(())
+}
+
+/// Sounds per category of a freshly installed theme, and the hook
+/// categories it has nothing for.
+fn print_categories(manifest: &manifest::Manifest) {
+    if manifest.is_visual() {
+        let mut categories: Vec<&str> = manifest.categories.keys().map(String::as_str).collect();
+        categories.sort_unstable();
+        println!("visual theme, no sounds: notifications for {}", categories.join(", "));
+        return;
+    }
+    let mut categories: Vec<(&String, usize)> = manifest.categories.iter().map(|(name, c)| (name, c.sounds.len())).collect();
+    categories.sort();
+    println!("| Category | Sounds |");
+    println!("|----------|--------|");
+    for (name, sounds) in categories {
+        println!("| {name} | {sounds} |");
+    }
+    let missing: Vec<&str> =
+        event::CATEGORIES.into_iter().filter(|c| manifest.categories.get(*c).is_none_or(|c| c.sounds.is_empty())).collect();
+    if !missing.is_empty() {
+        ui::warn(&format!("no sounds for {}; those events will be silent", missing.join(", ")));
+    }
+}
+
+/// The theme directory `theme pack`/`publish` mean: a directory with a
+/// manifest, else the installed theme of that name.
+fn theme_source_dir(dir: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = PathBuf::from(dir);
+    if path.join("manifest.json").is_file() || config::validate_theme_name(dir).is_err() {
+        return Ok(path);
+    }
+    let installed = config::theme_dir(&paths::data_dir(), dir)?;
+    if installed.join("manifest.json").is_file() {
+        Ok(installed)
+    } else if dir == builtin::NAME {
+        Err(format!("'{dir}' is built in and has no files to pack").into())
+    } else {
+        Err(format!("no theme directory or installed theme '{dir}'").into())
+    }
+}
+
+/// Zip a theme source dir canonically, by default to `<name>.zip` here.
+/// Returns the theme name, the zip and where it was written.
+fn pack_theme(dir: &str, output: Option<&str>) -> Result<(String, Vec<u8>, PathBuf), Box<dyn std::error::Error>> {
+    let dir = theme_source_dir(dir)?;
+    let name = pack::theme_name(&dir)?;
+    let bytes = pack::pack(&dir)?;
+    let output = output.map(PathBuf::from).unwrap_or_else(|| PathBuf::from(format!("{name}.zip")));
+    fs::write(&output, &bytes)?;
+    Ok((name, bytes, output))
+}
+
+fn run_theme_pack(dir: &str, output: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    if dir.is_empty() {
+        return Err("usage: ringring theme pack <dir|theme> [--output <file>]".into());
+    }
+    let (_, bytes, output) = pack_theme(dir, output)?;
+    ui::ok(&format!("packed {dir} into {} ({} bytes)", output.display(), bytes.len()));
+    println!("sha256 {}", publish::sha256_hex(&bytes));
+    Ok(())
+}
+
+/// Pack a theme, write its registry index entry next to the zip, then upload
+/// both to `registry.endpoint` or open a pre-filled pull request on `registry.repo`.
+fn run_theme_publish(dir: &str, url: Option<&str>, output: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    if dir.is_empty() {
+        return Err("usage: ringring theme publish <dir|theme> [--url <zip url>] [--output <file>]".into());
+    }
+    let manifest = manifest::Manifest::load(&theme_source_dir(dir)?).ok_or("manifest.json does not parse")?;
+    let (name, bytes, zip_path) = pack_theme(dir, output)?;
+    let entry = publish::entry(&name, &manifest, &bytes, url);
+    let entry_path = zip_path.with_extension("json");
+    fs::write(&entry_path, serde_json::to_string_pretty(&entry)? + "\n")?;
+    ui::ok(&format!("packed {} (sha256 {})", zip_path.display(), entry["sha256"].as_str().unwrap_or_default()));
+    ui::ok(&format!("index entry in {}", entry_path.display()));
+
+    let registry = config::Config::load(&paths::data_dir()).registry;
+    if let Some(endpoint) = &registry.endpoint {
+        let token = registry.token().ok_or("registry.endpoint needs a token (registry.token or RINGRING_REGISTRY_TOKEN)")?;
+        let spinner = ui::Spinner::start(&format!("uploading to {endpoint}"));
+        publish::upload(endpoint, &token, &name, &bytes, &entry)?;
+        drop(spinner);
+        ui::ok(&format!("published '{name}' to {endpoint}"));
+    } else if let Some(repo) = &registry.repo {
+        if url.is_none() {
+            ui::warn("no --url given; add where the zip can be downloaded to the entry before merging");
+        }
+        let pr = publish::pr_url(repo, registry.branch.as_deref().unwrap_or("main"), &name, &entry);
+        println!("{pr}");
+        let opener = if cfg!(target_os = "macos") { "open" } else { "xdg-open" };
+        let _ = std::process::Command::new(opener)
+            .arg(&pr)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn();
+    } else {
+        ui::warn("set registry.endpoint or registry.repo in config.json to publish");
+    }
+    Ok(())
+}
+
+fn run_theme_verify(zip: &str, against: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if zip.is_empty() || against.is_empty() {
+        return Err("usage: ringring theme verify <zip> --against <dir>".into());
+    }
+    match pack::verify(&fs::read(zip)?, std::path::Path::new(against))? {
+        pack::Verdict::Identical => ui::ok(&format!("{zip} is the canonical pack of {against}")),
+        pack::Verdict::SameContent => {
+            ui::ok(&format!("{zip} has the same files as {against}"));
+            ui::warn("but it was not packed canonically; `ringring theme pack` gives a reproducible zip");
+        }
+        pack::Verdict::Differs { missing, extra, changed } => {
+            for (label, files) in [("missing from zip", &missing), ("only in zip", &extra), ("changed", &changed)] {
+                for file in files {
+                    eprintln!("{} {label}: {file}", ui::paint(ui::Style::Bad, "✗"));
+                }
+            }
+            return Err(format!("{zip} does not match {against}").into());
+        }
+    }
+    Ok(())
+}
+
+/// Decode every sound of an installed theme within the configured
+/// `playback_limits` (or the defaults), reporting each file.
+fn run_theme_check(theme: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if theme.is_empty() {
+        return Err("usage: ringring theme check <name>".into());
+    }
+    config::validate_theme_name(theme)?;
+    let sounds_dir = paths::data_dir();
+    let limits = config::Config::load(&sounds_dir).playback_limits.unwrap_or_default();
+    let (manifest, sounds) = builtin::load_theme(&sounds_dir, theme).ok_or_else(|| format!("theme '{theme}' not found"))?;
+    let builtin::Sounds::Dir(dir) = sounds else {
+        ui::ok(&format!("'{theme}' is built in"));
+        return Ok(());
+    };
+    check_sounds(&dir, &manifest, &limits)
+}
+
+/// Strictly check an installed theme, a theme directory or a zip: the
+/// manifest, its categories and values, and every sound it names.
+fn run_theme_validate(target: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if target.is_empty() {
+        return Err("usage: ringring theme validate <name|dir|zip>".into());
+    }
+    let sounds_dir = paths::data_dir();
+    let limits = config::Config::load(&sounds_dir).playback_limits.unwrap_or_default();
+    let path = std::path::Path::new(target);
+    let staged;
+    let dir = if path.is_file() {
+        staged = install::stage(&install::ZipSource(path.to_path_buf()), &sounds_dir)?;
+        staged.dir.clone()
+    } else if path.join("manifest.json").is_file() {
+        path.to_path_buf()
+    } else {
+        config::validate_theme_name(target)?;
+        let dir = config::theme_dir(&sounds_dir, target)?;
+        if !dir.join("manifest.json").is_file() {
+            if target == builtin::NAME {
+                ui::ok(&format!("'{target}' is built in"));
+                return Ok(());
+            }
+            return Err(format!("theme '{target}' not found").into());
+        }
+        dir
+    };
+
+    let report = validate::theme(&dir, &limits)?;
+    for (file, length) in &report.sounds {
+        ui::ok(&format!("{file} ({:.1}s)", length.as_secs_f32()));
+    }
+    for warning in &report.warnings {
+        ui::warn(warning);
+    }
+    for problem in &report.problems {
+        eprintln!("{} {problem}", ui::paint(ui::Style::Bad, "✗"));
+    }
+    match report.problems.len() {
+        0 => Ok(()),
+        1 => Err("1 problem".into()),
+        n => Err(format!("{n} problems").into()),
+    }
+}
+
+/// Plays per sound of a theme over the whole history.
+fn run_theme_stats(theme: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if theme.is_empty() {
+        return Err("usage: ringring theme stats <name>".into());
+    }
+    config::validate_theme_name(theme)?;
+    let (manifest, _) = builtin::load_theme(&paths::data_dir(), theme).ok_or_else(|| format!("theme '{theme}' not found"))?;
+    let rows = stats::sound_plays(&history::load(), theme, &manifest);
+    println!("| Category | Sound | Plays |");
+    println!("|----------|-------|-------|");
+    for row in &rows {
+        let plays = match (row.plays, row.category_events) {
+            (0, 0) => format!("never ({} never went off)", row.category),
+            (0, _) => "never".to_string(),
+            (n, _) => n.to_string(),
+        };
+        println!("| {} | {} | {plays} |", row.category, row.file);
+    }
+    let never = rows.iter().filter(|r| r.plays == 0).count();
+    println!();
+    if never == 0 {
+        ui::ok(&format!("every sound played at least once ({})", history::history_path().display()));
+    } else {
+        ui::warn(&format!("{never} of {} sounds never played ({})", rows.len(), history::history_path().display()));
+    }
+    Ok(())
+}
+
+/// Delete an installed theme and the pins and pool entry naming it.
+fn run_theme_remove(theme: &str, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if theme.is_empty() {
+        return Err("usage: ringring theme remove <name> [--force]".into());
+    }
+    let sounds_dir = paths::data_dir();
+    let dir = config::theme_dir(&sounds_dir, theme)?;
+    if !dir.join("manifest.json").is_file() {
+        return Err(if theme == builtin::NAME {
+            format!("'{theme}' is built in and can't be removed")
+        } else {
+            format!("theme '{theme}' is not installed")
+        }
+        .into());
+    }
+    let cfg = config::Config::load(&sounds_dir);
+    let is_default = cfg.theme.as_deref() == Some(theme);
+    if is_default && !force {
+        return Err(format!("'{theme}' is the configured theme; choose another first or use --force").into());
+    }
+
+    fs::remove_dir_all(&dir)?;
+    ui::ok(&format!("removed {}", dir.display()));
+    for pinned in config::forget_theme(&sounds_dir, theme)? {
+        ui::ok(&format!("unpinned {pinned}"));
+    }
+    if is_default {
+        ui::warn(&format!("'{theme}' is still the configured theme; sessions will use '{}' until you choose another", builtin::NAME));
+    }
+    Ok(())
+}
+
+/// Scaffold a theme in the data dir for an author to fill in.
+fn run_theme_new(theme: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if theme.is_empty() {
+        return Err("usage: ringring theme new <name>".into());
+    }
+    if theme == builtin::NAME {
+        return Err(format!("'{theme}' is the built-in theme's name").into());
+    }
+    let dir = config::theme_dir(&paths::data_dir(), theme)?;
+    manifest::scaffold(&dir, theme)?;
+    ui::ok(&format!("created {}", dir.join("manifest.json").display()));
+    println!("Put your sounds in {} and list them in manifest.json,", dir.join("sounds").display());
+    println!("replacing the placeholder <category>.wav entries, then try it with: ringring test {theme}");
+    Ok(())
+}
+
+/// Write `theme` to config.json. With `migrate`, the legacy theme file is
+/// removed, and supplies the theme when none is given.
+fn run_theme_set(theme: Option<&str>, migrate: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let sounds_dir = paths::data_dir();
+    let legacy_file = sounds_dir.join("theme");
+    let legacy = fs::read_to_string(&legacy_file).ok().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    let theme = match (theme, &legacy) {
+        (Some(theme), _) => theme.to_string(),
+        (None, Some(legacy)) if migrate => legacy.clone(),
+        (None, None) if migrate => return Err(format!("no legacy theme file at {}", legacy_file.display()).into()),
+        (None, _) => return Err("usage: ringring theme set <name> [--migrate] | theme set --migrate".into()),
+    };
+    config::validate_theme_name(&theme)?;
+    if builtin::load_theme(&sounds_dir, &theme).is_none() {
+        return Err(format!("theme '{theme}' has no manifest in {}", sounds_dir.display()).into());
+    }
+    config::edit_config(&sounds_dir, |root| {
+        root["theme"] = serde_json::Value::String(theme.clone());
+    })?;
+    ui::ok(&format!("default theme set to '{theme}'"));
+    if migrate && legacy_file.exists() {
+        fs::remove_file(&legacy_file)?;
+        ui::ok(&format!("removed legacy theme file {}", legacy_file.display()));
+    } else if legacy.is_some() {
+        ui::warn(&format!("{} is no longer used; --migrate removes it", legacy_file.display()));
+    }
+    Ok(())
+}
+
+/// Decode every sound of a theme dir within `limits`, listing each result.
+fn check_sounds(dir: &std::path::Path, manifest: &manifest::Manifest, limits: &sandbox::Limits) -> Result<(), Box<dyn std::error::Error>> {
+    let files = manifest.files();
+    let mut failed = 0;
+    for file in &files {
+        match sandbox::validate(&dir.join(file), limits) {
+            Ok(length) => ui::ok(&format!("{file} ({:.1}s)", length.as_secs_f32())),
+            Err(e) => {
+                failed += 1;
+                eprintln!("{} {file}: {e}", ui::paint(ui::Style::Bad, "✗"));
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{failed} of {} sounds failed the checks", files.len()).into());
+    }
+    Ok(())
+}
+
+fn run_session_theme(session_id: &str, theme: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if session_id.is_empty() {
+        return Err("usage: ringring session <session_id> theme <name>".into());
+    }
+    if theme.is_empty() {
+        return Err("usage: ringring session <session_id> theme <name>".into());
+    }
+    config::validate_theme_name(theme)?;
+    if builtin::load_theme(&paths::data_dir(), theme).is_none() {
+        return Err(format!("theme '{theme}' not found").into());
+    }
+    fs::write(paths::tmp_dir().join(format!(".claude-theme-{session_id}")), theme)?;
+    println!("session {session_id}: theme set to '{theme}'");
+    Ok(())
+}
+
+/// Switch a running session's theme. The target is `--session`, the newest
+/// session in the current directory (`--here`), or the detected session.
+fn run_theme_use(theme: &str, session: Option<&str>, here: bool, pin: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if theme.is_empty() {
+        return Err("usage: ringring theme use <name> [--session <id>|--here] [--pin]".into());
+    }
+    config::validate_theme_name(theme)?;
+    let sounds_dir = paths::data_dir();
+    if builtin::load_theme(&sounds_dir, theme).is_none() {
+        return Err(format!("theme '{theme}' not found").into());
+    }
+    let cwd = std::env::current_dir()?.to_string_lossy().into_owned();
+
+    let session_id = match session {
+        Some(id) => id.to_string(),
+        None if here => session::newest_in(&cwd)
+            .map(|s| s.id)
+            .ok_or_else(|| format!("no session found for {cwd}"))?,
+        None => detect_session_id().ok_or("cannot detect the session; use --session <id> or --here")?,
+    };
+    fs::write(paths::tmp_dir().join(format!(".claude-theme-{session_id}")), theme)?;
+    ui::ok(&format!("session {session_id}: theme set to '{theme}'"));
+
+    if !pin {
+        let workspace = paths::workspace_root(std::path::Path::new(&cwd)).to_string_lossy().into_owned();
+        if suggest::record(&workspace, theme) >= suggest::THRESHOLD {
+            ui::warn(&format!("you keep choosing '{theme}' here; `ringring pin {theme}` makes it stick"));
+        }
+    }
+
+    if pin {
+        let dir = pin_workspace(&sounds_dir, Some(theme))?;
+        ui::ok(&format!("pinned '{theme}' to {dir}"));
+    } else if let Some((dir, pinned)) = config::Config::load(&sounds_dir).workspace_pin(&cwd)
+        && pinned != theme
+    {
+        // Workspace pins outrank the session cache
+        ui::warn(&format!("{dir} is pinned to '{pinned}', which takes precedence; add --pin to change it"));
+    }
+    Ok(())
+}
+
+/// Set (or with `None`, remove) the pin for the current workspace root.
+/// Returns the pinned directory.
+fn pin_workspace(sounds_dir: &std::path::Path, theme: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
+    let dir = paths::workspace_root(&std::env::current_dir()?).to_string_lossy().into_owned();
+    config::write_pin(sounds_dir, &dir, theme)?;
+    Ok(dir)
+}
+
+/// The directory a pin applies to: `path` made absolute and normalized, or
+/// else the current git checkout or directory.
+fn pin_dir(path: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
+    let cwd = std::env::current_dir()?;
+    let Some(path) = path else {
+        return Ok(paths::workspace_root(&cwd).to_string_lossy().into_owned());
+    };
+    // Lexical only, like `config validate`: pins match the cwd Claude reports
+    let dir = repair::normalize(path, &paths::home_dir());
+    let dir = if dir.is_absolute() { dir } else { repair::normalize(&cwd.join(dir).to_string_lossy(), &paths::home_dir()) };
+    if !dir.is_dir() {
+        return Err(format!("{path} is not a directory").into());
+    }
+    Ok(dir.to_string_lossy().into_owned())
+}
+
+fn run_pin(theme: &str, quiet: bool, path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    if theme.is_empty() && !quiet {
+        return Err("usage: ringring pin <theme> [--quiet] [--path <dir>] | pin --quiet".into());
+    }
+    let sounds_dir = paths::data_dir();
+    let dir = pin_dir(path)?;
+    if !theme.is_empty() {
+        config::validate_theme_name(theme)?;
+        if builtin::load_theme(&sounds_dir, theme).is_none() {
+            return Err(format!("theme '{theme}' not found").into());
+        }
+        config::write_pin(&sounds_dir, &dir, Some(theme))?;
+        ui::ok(&format!("pinned '{theme}' to {dir}"));
+    }
+    if quiet {
+        config::write_quiet_pin(&sounds_dir, &dir)?;
+        ui::ok(&format!("{dir} is quiet: notifications only, no sounds"));
+    }
+    Ok(())
+}
+
+fn run_unpin(path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let sounds_dir = paths::data_dir();
+    let root = pin_dir(path)?;
+    let cfg = config::Config::load(&sounds_dir);
+    if cfg.workspaces.contains_key(&root) {
+        config::write_pin(&sounds_dir, &root, None)?;
+        ui::ok(&format!("unpinned {root}"));
+        return Ok(());
+    }
+    match cfg.workspace_pin(&root) {
+        Some((dir, _)) if dir == root => {
+            config::write_pin(&sounds_dir, &root, None)?;
+            ui::ok(&format!("unpinned {dir}"));
+            Ok(())
+        }
+        Some((dir, theme)) => Err(format!("{root} has no pin of its own; it inherits '{theme}' from {dir}").into()),
+        None => Err(format!("{root} is not pinned").into()),
+    }
+}
+
+fn run_pins() {
+    let sounds_dir = paths::data_dir();
+    let cfg = config::Config::load(&sounds_dir);
+    if cfg.workspaces.is_empty() {
+        println!("no workspace pins");
+        return;
+    }
+    let mut pins: Vec<(&String, &config::Pin)> = cfg.workspaces.iter().collect();
+    pins.sort_by_key(|(dir, _)| *dir);
+    println!("| Directory | Theme | State |");
+    println!("|-----------|-------|-------|");
+    for (dir, pin) in pins {
+        let theme = pin.theme();
+        let mut state = if !std::path::Path::new(dir).is_dir() {
+            ui::paint(ui::Style::Warn, "stale: directory missing")
+        } else if !theme.is_empty() && builtin::load_theme(&sounds_dir, theme).is_none() {
+            ui::paint(ui::Style::Warn, "theme not installed")
+        } else {
+            "ok".to_string()
+        };
+        if pin.quiet() {
+            state.push_str(", quiet");
+        }
+        let theme = if theme.is_empty() { "-" } else { theme };
+        println!("| `{dir}` | {theme} | {state} |");
+    }
+}
+
+fn run_session_mute(session_id: &str, mute: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if session_id.is_empty() {
+        return Err("usage: ringring mute|unmute [--session <id>]".into());
+    }
+    if session_id.contains('/') {
+        return Err(format!("invalid session id '{session_id}'").into());
+    }
+    let mute_file = session::mute_file(session_id);
+    if mute {
+        fs::write(&mute_file, "")?;
+        println!("session {session_id}: muted");
+    } else {
+        let _ = fs::remove_file(&mute_file);
+        println!("session {session_id}: unmuted");
+    }
+    Ok(())
+}
+
+/// Mute every session, for `duration` or until `unmute`.
+fn run_mute(duration: Option<std::time::Duration>, all: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let until = duration.map(|d| log::now_secs() + d.as_secs());
+    mute::set(&mute::Mute { until, all })?;
+    let what = if all { "sounds and notifications" } else { "sounds (notifications still arrive)" };
+    match duration {
+        Some(d) => ui::ok(&format!("muted {what} for {}", config::format_duration(d))),
+        None => ui::ok(&format!("muted {what} until `ringring unmute`")),
+    }
+    Ok(())
+}
+
+fn run_set_mode(mode: &str) -> Result<(), Box<dyn std::error::Error>> {
+    match mode {
+        "" => return Err("usage: ringring mode <random|sequential>".into()),
+        other if !config::MODES.contains(&other) => {
+            return Err(format!("unknown mode '{other}', expected: {}", config::MODES.join(", ")).into());
+        }
+        _ => {}
+    }
+    config::edit_config(&paths::data_dir(), |root| {
+        root["mode"] = serde_json::Value::String(mode.to_string());
+    })?;
+    println!("mode set to '{mode}'");
+    Ok(())
+}
+
+/// Print the master volume, or a theme's own, as a 0–100 level.
+fn run_volume_get(theme: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let cfg = config::Config::load(&paths::data_dir());
+    let level = match theme {
+        Some(theme) => {
+            config::validate_theme_name(theme)?;
+            cfg.theme_volumes.get(theme).copied()
+        }
+        None => cfg.volume,
+    };
+    println!("{}", level.unwrap_or(100).min(100));
+    Ok(())
+}
+
+fn run_volume_set(level: &str, theme: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    if level.is_empty() {
+        return Err("usage: ringring volume set <0-100> [--theme <name>]".into());
+    }
+    let level: u8 = level.parse().ok().filter(|l| *l <= 100).ok_or_else(|| format!("volume must be 0–100, got '{level}'"))?;
+    if let Some(theme) = theme {
+        config::validate_theme_name(theme)?;
+    }
+    config::edit_config(&paths::data_dir(), |root| match theme {
+        Some(theme) => {
+            if !root["theme_volumes"].is_object() {
+                root["theme_volumes"] = serde_json::json!({});
+            }
+            root["theme_volumes"][theme] = level.into();
+        }
+        None => root["volume"] = level.into(),
+    })?;
+    match theme {
+        Some(theme) => println!("volume of '{theme}' set to {level}"),
+        None => println!("volume set to {level}"),
+    }
+    Ok(())
+}
+
+/// Print `key` from config.json: strings as they are, anything else as JSON.
+fn run_config_get(key: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let root = config::raw_config(&paths::data_dir())?;
+    let value = match key {
+        Some(key) => config::get_key(&root, key)?.ok_or_else(|| format!("'{key}' is not set"))?,
+        None => &root,
+    };
+    match value.as_str() {
+        Some(text) => println!("{text}"),
+        None => println!("{}", serde_json::to_string_pretty(value)?),
     }
+    Ok(())
+}
 
-    #[test]
-    fn parse_theme_install_local() {
-        let args = vec!["ringring".to_string(), "theme".to_string(), "install".to_string(), "/tmp/foo.zip".to_string()];
-        assert!(matches!(parse_args(&args), Cmd::ThemeInstall { ref source, force: false } if source == "/tmp/foo.zip"));
+/// Set `key` to `value`, read as JSON or else taken as a string; `None`
+/// removes it.
+fn run_config_set(key: &str, value: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    if key.is_empty() {
+        return Err("usage: ringring config unset <key>".into());
+    }
+    let parsed = value.map(|raw| serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::String(raw.to_string())));
+    let shown = parsed.as_ref().map(|v| v.to_string());
+    let previous = config::set_key(&paths::data_dir(), key, parsed)?;
+    match shown {
+        Some(shown) => ui::ok(&format!("{key} = {shown}")),
+        None if previous.is_some() => ui::ok(&format!("{key} removed")),
+        None => ui::ok(&format!("{key} was not set")),
     }
+    Ok(())
+}
 
-    #[test]
-    fn parse_theme_install_force() {
-        let args = vec!["ringring".to_string(), "theme".to_string(), "install".to_string(), "--force".to_string(), "https://example.com/t.zip".to_string()];
-        assert!(matches!(parse_args(&args), Cmd::ThemeInstall { ref source, force: true } if source == "https://example.com/t.zip"));
+fn run_config_export(output: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let setup = setup::export(&paths::data_dir())?;
+    let json = serde_json::to_string_pretty(&setup)?;
+    let Some(output) = output else {
+        println!("{json}");
+        return Ok(());
+    };
+    fs::write(output, json + "\n")?;
+    let sources = setup.themes.iter().filter(|t| t.source.is_some()).count();
+    ui::ok(&format!("wrote {output}: config.json, {} includes, {} themes ({sources} with a source to install from)", setup.includes.len(), setup.themes.len()));
+    Ok(())
+}
+
+/// Restore a `config export` (a file, `-` for stdin): install the themes
+/// missing here from their sources, then write the includes and config.json
+/// after showing how config.json changes.
+fn run_config_import(file: &str, yes: bool, skip_themes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if file.is_empty() {
+        return Err("usage: ringring config import [--yes] [--skip-themes] <file|->".into());
     }
+    if file == "-" && !yes {
+        return Err("pass --yes when reading the export from stdin".into());
+    }
+    let text = if file == "-" { std::io::read_to_string(std::io::stdin())? } else { fs::read_to_string(file).map_err(|e| format!("{file}: {e}"))? };
+    let setup = setup::Setup::parse(&text)?;
+    let data_dir = paths::data_dir();
+    let missing = if skip_themes { Vec::new() } else { setup.missing_themes(&data_dir) };
 
-    #[test]
-    fn parse_theme_install_missing_source() {
-        let args = vec!["ringring".to_string(), "theme".to_string(), "install".to_string()];
-        assert!(matches!(parse_args(&args), Cmd::ThemeInstall { ref source, .. } if source.is_empty()));
+    let old = serde_json::to_string_pretty(&config::raw_config(&data_dir)?)?;
+    let new = serde_json::to_string_pretty(&setup.config)?;
+    for line in repair::diff(&old, &new) {
+        let style = if line.starts_with('+') { ui::Style::Good } else { ui::Style::Bad };
+        println!("{}", ui::paint(style, &line));
+    }
+    for include in setup.includes.keys() {
+        println!("include {include}");
+    }
+    for theme in &missing {
+        println!("theme {} from {}", theme.name, theme.source.as_deref().unwrap_or("(no source)"));
+    }
+    if !yes && !confirm(&format!("import into {}?", data_dir.display()))? {
+        println!("left {} unchanged", data_dir.display());
+        return Ok(());
     }
-}
 
-fn run_session_theme(session_id: &str, theme: &str) -> Result<(), Box<dyn std::error::Error>> {
-    if session_id.is_empty() {
-        return Err("usage: ringring session <session_id> theme <name>".into());
+    let mut failed = Vec::new();
+    for theme in missing {
+        let Some(source) = &theme.source else {
+            ui::warn(&format!("theme '{}' wasn't installed from a source; copy it over by hand", theme.name));
+            failed.push(theme.name.as_str());
+            continue;
+        };
+        if let Err(e) = run_theme_install(source, false, false) {
+            ui::warn(&format!("could not install theme '{}' from {source}: {e}", theme.name));
+            failed.push(theme.name.as_str());
+        }
     }
-    if theme.is_empty() {
-        return Err("usage: ringring session <session_id> theme <name>".into());
+    for path in setup.restore_config(&data_dir)? {
+        ui::ok(&format!("wrote {}", path.display()));
     }
-    let sounds_dir = paths::data_dir();
-    let theme_dir = config::theme_dir(&sounds_dir, theme);
-    if !theme_dir.join("manifest.json").exists() {
-        return Err(format!("theme '{theme}' not found").into());
+    if !setup.config["workspaces"].as_object().is_none_or(|pins| pins.is_empty()) {
+        println!("pins for directories missing on this machine show up in `ringring config validate`");
+    }
+    if !failed.is_empty() {
+        return Err(format!("missing themes: {}", failed.join(", ")).into());
     }
-    fs::write(format!("/tmp/.claude-theme-{session_id}"), theme)?;
-    println!("session {session_id}: theme set to '{theme}'");
     Ok(())
 }
 
-fn run_session_mute(session_id: &str, mute: bool) -> Result<(), Box<dyn std::error::Error>> {
-    if session_id.is_empty() {
-        return Err("usage: ringring session <session_id> mute|unmute".into());
-    }
-    let mute_file = format!("/tmp/.claude-mute-{session_id}");
-    if mute {
-        fs::write(&mute_file, "")?;
-        println!("session {session_id}: muted");
+/// Show which `routes` rule an event (a hook payload file, `-` for stdin)
+/// matches and which notification backends it would reach.
+fn run_routes_test(event_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let raw = if event_path == "-" { std::io::read_to_string(std::io::stdin())? } else { fs::read_to_string(event_path)? };
+    let input: event::HookInput = serde_json::from_str(&raw)?;
+    let cwd = input.cwd.clone().unwrap_or_else(|| std::env::current_dir().unwrap_or_default().to_string_lossy().into_owned());
+    let category = event::map_event(&input).category;
+    let cfg = config::Config::load(&paths::data_dir());
+    println!("{} in {cwd}, category {}", input.hook_event_name, category.as_deref().unwrap_or("none"));
+
+    let matched = routes::first_match(&cfg.routes, &cwd, category.as_deref(), &paths::home_dir());
+    if cfg.routes.is_empty() {
+        println!("no routes configured");
     } else {
-        let _ = fs::remove_file(&mute_file);
-        println!("session {session_id}: unmuted");
+        println!("| # | Workspace | Category | Backends | Match |");
+        println!("|---|-----------|----------|----------|-------|");
+        for (i, route) in cfg.routes.iter().enumerate() {
+            let state = match matched {
+                Some(m) if m == i => ui::paint(ui::Style::Good, "match"),
+                Some(m) if m < i => "not reached".to_string(),
+                _ => "no".to_string(),
+            };
+            let or_any = |s: String| if s.is_empty() { "any".to_string() } else { s };
+            let backends = if route.backends.is_empty() { "none".to_string() } else { route.backends.join(", ") };
+            println!(
+                "| {} | {} | {} | {backends} | {state} |",
+                i + 1,
+                or_any(route.workspace.clone().map(|w| format!("`{w}`")).unwrap_or_default()),
+                or_any(route.category.join(", ")),
+            );
+        }
+    }
+
+    let env = headless::detect(cfg.headless);
+    let dispatcher = notify::Dispatcher::new(&cfg, notify::Branding::from_config(&cfg), &env, None, None)
+        .haptic(&cfg, category.as_deref())
+        .light(&cfg, category.as_deref(), None)
+        .controllers(&cfg, category.as_deref());
+    let all = dispatcher.backend_names();
+    let sent = dispatcher.route(&cfg.routes, &cwd, category.as_deref()).backend_names();
+    let skipped: Vec<&str> = all.iter().filter(|b| !sent.contains(b)).copied().collect();
+    println!("sends to  {}", if sent.is_empty() { "nothing".to_string() } else { sent.join(", ") });
+    if !skipped.is_empty() {
+        println!("skips     {}", skipped.join(", "));
     }
     Ok(())
 }
 
-fn run_set_mode(mode: &str) -> Result<(), Box<dyn std::error::Error>> {
-    match mode {
-        "random" | "sequential" => {}
-        "" => return Err("usage: ringring mode <random|sequential>".into()),
-        other => return Err(format!("unknown mode '{other}', expected: random, sequential").into()),
-    }
+/// Report stale or duplicate config entries; with `fix`, show the diff and
+/// write the repaired config. Returns false when problems remain.
+fn run_config_validate(fix: bool) -> Result<bool, Box<dyn std::error::Error>> {
     let sounds_dir = paths::data_dir();
     let config_path = sounds_dir.join("config.json");
-    let content = fs::read_to_string(&config_path).unwrap_or_else(|_| "{}".to_string());
-    let mut root: serde_json::Value = serde_json::from_str(&content)?;
-    root["mode"] = serde_json::Value::String(mode.to_string());
-    fs::write(&config_path, serde_json::to_string_pretty(&root)?)?;
-    println!("mode set to '{mode}'");
-    Ok(())
+    let Ok(content) = fs::read_to_string(&config_path) else {
+        ui::ok(&format!("no config at {}, nothing to check", config_path.display()));
+        return Ok(true);
+    };
+    let root: serde_json::Value = serde_json::from_str(&content).map_err(|e| format!("{}: {e}", config_path.display()))?;
+    if let Err(e) = serde_json::from_value::<config::Config>(root.clone()) {
+        return Err(format!("{}: {e}", config_path.display()).into());
+    }
+
+    let home = PathBuf::from(std::env::var("HOME").unwrap_or_default());
+    let repair = repair::check(
+        &root,
+        &home,
+        |theme| builtin::load_theme(&sounds_dir, theme).is_some(),
+        |dir| dir.is_dir(),
+    );
+    if repair.problems.is_empty() {
+        ui::ok(&format!("{} is fine", config_path.display()));
+        return Ok(true);
+    }
+    for problem in &repair.problems {
+        ui::warn(problem);
+    }
+    if !fix {
+        eprintln!("run `ringring config validate --fix` to repair");
+        return Ok(false);
+    }
+
+    let old = serde_json::to_string_pretty(&root)?;
+    let new = serde_json::to_string_pretty(&repair.fixed)?;
+    for line in repair::diff(&old, &new) {
+        let style = if line.starts_with('+') { ui::Style::Good } else { ui::Style::Bad };
+        println!("{}", ui::paint(style, &line));
+    }
+    fs::write(&config_path, new)?;
+    ui::ok(&format!("wrote {}", config_path.display()));
+    Ok(true)
 }
 
 /// Walk up the process tree looking for a `.claude-ringring-cpid-{pid}` file
@@ -487,7 +2864,7 @@ fn run_set_mode(mode: &str) -> Result<(), Box<dyn std::error::Error>> {
 fn detect_session_id() -> Option<String> {
     let mut pid = std::process::id();
     for _ in 0..20 {
-        let path = format!("/tmp/.claude-ringring-cpid-{pid}");
+        let path = paths::tmp_dir().join(format!(".claude-ringring-cpid-{pid}"));
         if let Ok(sid) = fs::read_to_string(&path) {
             let sid = sid.trim().to_string();
             if !sid.is_empty() {
@@ -525,7 +2902,7 @@ fn read_ppid(pid: u32) -> Option<u32> {
 }
 
 fn detect_session_from_theme_files() -> Option<String> {
-    let entries: Vec<_> = fs::read_dir("/tmp")
+    let entries: Vec<_> = fs::read_dir(paths::tmp_dir())
         .ok()?
         .filter_map(|e| e.ok())
         .filter(|e| {
@@ -547,6 +2924,66 @@ fn detect_session_from_theme_files() -> Option<String> {
         .map(|(sid, _)| sid)
 }
 
+/// List sessions from the session state store, most recently active first.
+fn run_sessions() {
+    let sessions = session::load_all();
+    if sessions.is_empty() {
+        println!("no active sessions");
+        return;
+    }
+    println!("| Session | Project | Theme | Last event | Attention | Muted | Started |");
+    println!("|---------|---------|-------|------------|-----------|-------|---------|");
+    for s in &sessions {
+        println!(
+            "| `{}` | {} | {} | {} | {} | {} | {} |",
+            s.id,
+            s.project(),
+            s.theme.as_deref().unwrap_or("-"),
+            s.last_event.as_deref().unwrap_or("-"),
+            s.attention.as_deref().unwrap_or("-"),
+            if s.muted() { "yes" } else { "-" },
+            log::format_timestamp(s.started),
+        );
+    }
+}
+
+/// Sessions waiting on the user. `--json` is meant for status bars to poll.
+fn run_pending(json: bool) {
+    let pending = session::pending();
+    if json {
+        let items: Vec<serde_json::Value> = pending
+            .iter()
+            .map(|s| {
+                serde_json::json!({
+                    "session_id": s.id,
+                    "project": s.project(),
+                    "cwd": s.cwd,
+                    "reason": s.attention,
+                    "since": s.attention_since,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::Value::Array(items));
+        return;
+    }
+    if pending.is_empty() {
+        println!("nothing pending");
+        return;
+    }
+    let now = log::now_secs();
+    println!("| Session | Project | Waiting for | Since |");
+    println!("|---------|---------|-------------|-------|");
+    for s in &pending {
+        println!(
+            "| `{}` | {} | {} | {}s ago |",
+            s.id,
+            s.project(),
+            s.attention.as_deref().unwrap_or("-"),
+            now.saturating_sub(s.attention_since),
+        );
+    }
+}
+
 fn run_status(session_id: Option<&str>) {
     let sounds_dir = paths::data_dir();
     let cfg = config::Config::load(&sounds_dir);
@@ -555,75 +2992,604 @@ fn run_status(session_id: Option<&str>) {
     println!("|---------|-------|");
 
     if let Some(sid) = session_id {
-        let muted = PathBuf::from(format!("/tmp/.claude-mute-{sid}")).exists();
-
-        let cwd = std::env::current_dir().unwrap_or_default();
-        let resolver = config::ThemeResolver {
-            sounds_dir: &sounds_dir,
-            config: &cfg,
-            session_id: sid,
-            cwd: cwd.to_string_lossy().into_owned(),
-        };
-        let effective = resolver.resolve();
-
         println!("| Session | `{sid}` |");
-        println!("| Theme | {effective} |");
-        println!("| Muted | {muted} |");
+        println!("| Muted | {} |", session::is_muted(sid));
     }
 
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let resolver = config::ThemeResolver {
+        sounds_dir: &sounds_dir,
+        config: &cfg,
+        session_id: session_id.unwrap_or_default(),
+        cwd: cwd.to_string_lossy().into_owned(),
+    };
+    let (theme, source) = resolver.explain();
+    println!("| Directory | `{}` |", cwd.display());
+    println!("| Theme | {theme} |");
+    println!("| Chosen by | {source} |");
+    let (dir, manifest) = match builtin::load_theme(&sounds_dir, &theme) {
+        Some((_, builtin::Sounds::Dir(sounds))) => {
+            (format!("`{}`", sounds.parent().unwrap_or(&sounds).display()), ui::paint(ui::Style::Good, "loaded"))
+        }
+        Some((_, builtin::Sounds::Embedded)) => ("built in".to_string(), ui::paint(ui::Style::Good, "built in")),
+        None => match config::theme_dir(&sounds_dir, &theme) {
+            Ok(dir) => (format!("`{}`", dir.display()), ui::paint(ui::Style::Warn, &format!("missing or invalid, the hook plays {}", builtin::NAME))),
+            Err(e) => ("-".to_string(), ui::paint(ui::Style::Bad, &e)),
+        },
+    };
+    println!("| Theme dir | {dir} |");
+    println!("| Manifest | {manifest} |");
+
+    if let Some(muted) = mute::active(log::now_secs()) {
+        let what = if muted.all { "sounds and notifications" } else { "sounds" };
+        let until = match muted.until {
+            Some(until) => format!("for {}", config::format_duration(std::time::Duration::from_secs(until.saturating_sub(log::now_secs())))),
+            None => "until unmuted".to_string(),
+        };
+        println!("| Global mute | {} |", ui::paint(ui::Style::Warn, &format!("{what}, {until}")));
+    }
     println!("| Mode | {} |", cfg.mode.as_deref().unwrap_or("(default)"));
     println!("| Default theme | {} |", cfg.theme.as_deref().unwrap_or("(none)"));
     if !cfg.random_pool.is_empty() {
         println!("| Pool | {} |", cfg.random_pool.join(", "));
     }
     println!("| Data dir | `{}` |", sounds_dir.display());
+    if let Some(available) = updates::load().filter(|a| !a.is_empty()) {
+        println!("| Updates | {} |", ui::paint(ui::Style::Warn, &available.summary()));
+    }
 }
 
-fn handle_session_start(
-    hook_input: &event::HookInput,
-    _resolver: &config::ThemeResolver,
-    _theme: &str,
-    theme_dir: &std::path::Path,
-    manifest: &manifest::Manifest,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let source_type = hook_input.source.as_deref().unwrap_or("unknown");
-    let session_id = if hook_input.session_id.is_empty() {
-        "unknown"
+fn run_doctor() {
+    let sounds_dir = paths::data_dir();
+    let cfg = config::Config::load(&sounds_dir);
+
+    let good = |s: &str| ui::paint(ui::Style::Good, s);
+    let bad = |s: &str| ui::paint(ui::Style::Warn, s);
+
+    let dir_state = match degraded::problem(&sounds_dir) {
+        Some(reason) => ui::paint(ui::Style::Bad, &format!("{reason}; hooks use the built-in theme and terminal notifications")),
+        None if sounds_dir.is_dir() => good("ok"),
+        None => bad("missing"),
+    };
+    println!("{:<12} {} ({dir_state})", "data dir", sounds_dir.display());
+    if let Some(last) = degraded::last() {
+        println!("{:<12} {}", "degraded", bad(&format!("hook events since {}: {}", log::format_timestamp(last.timestamp), last.reason)));
+    }
+
+    let config_path = sounds_dir.join("config.json");
+    let config_state = match config::Config::load_strict(&sounds_dir) {
+        Ok(None) => "not found, using defaults".to_string(),
+        Ok(Some(_)) => good("ok"),
+        Err(e) => ui::paint(ui::Style::Bad, &format!("parse error: {e}")),
+    };
+    println!("{:<12} {} ({config_state})", "config", config_path.display());
+    if let Some(schema) = cfg.schema.filter(|s| *s > buildinfo::CONFIG_SCHEMA) {
+        println!("{:<12} {}", "", bad(&format!("config schema {schema} is newer than this build's {}; upgrade ringring", buildinfo::CONFIG_SCHEMA)));
+    }
+
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let resolver = config::ThemeResolver {
+        sounds_dir: &sounds_dir,
+        config: &cfg,
+        session_id: "",
+        cwd: cwd.to_string_lossy().into_owned(),
+    };
+    let theme = resolver.resolve();
+    let manifest_state = match builtin::load_theme(&sounds_dir, &theme) {
+        Some((_, builtin::Sounds::Embedded)) => "built-in".to_string(),
+        Some(_) => good("manifest ok"),
+        None => bad("manifest missing or invalid, using built-in earcon"),
+    };
+    println!("{:<12} {theme} ({manifest_state})", "theme");
+
+    let incompatible: Vec<(String, Vec<String>)> = installed_themes(&sounds_dir)
+        .iter()
+        .map(|(name, manifest)| (name.clone(), buildinfo::manifest_problems(manifest)))
+        .filter(|(_, problems)| !problems.is_empty())
+        .collect();
+    if incompatible.is_empty() {
+        println!("{:<12} {}", "themes", good(&format!("all compatible with ringring {}", buildinfo::VERSION)));
+    }
+    for (name, problems) in incompatible {
+        println!("{:<12} {}", "themes", bad(&format!("{name}: {}", problems.join("; "))));
+    }
+
+    let env = headless::detect(cfg.headless);
+    let yes_no = |b: bool| if b { good("yes") } else { bad("no") };
+    println!("{:<12} {}", "display", yes_no(env.display));
+    println!("{:<12} {}", "d-bus", yes_no(env.dbus));
+    println!("{:<12} {}", "audio", yes_no(env.audio));
+    println!("{:<12} {}", "player", audio::player(&cfg).name());
+    let mode = match (env.is_headless(), cfg.headless) {
+        (true, Some(true)) => "headless (forced by config)",
+        (true, _) => "headless (auto-detected)",
+        (false, Some(false)) => "desktop (forced by config)",
+        (false, _) => "desktop",
+    };
+    println!("{:<12} {mode}", "mode");
+
+    let notifier = notify::Dispatcher::new(&cfg, notify::Branding::from_config(&cfg), &env, None, None);
+    println!("{:<12} {}", "notifiers", notifier.backend_names().join(", "));
+    println!("{:<12} {}", "log", log::log_path().display());
+}
+
+/// Non-interactive self-test for timers and scripts. Returns false if any
+/// check failed.
+fn run_healthcheck() -> bool {
+    let sounds_dir = paths::data_dir();
+    let mut failures = 0;
+    let mut report = |name: &str, result: Result<String, String>| match result {
+        Ok(detail) => println!("ok    {name}: {detail}"),
+        Err(e) => {
+            failures += 1;
+            println!("FAIL  {name}: {e}");
+        }
+    };
+
+    let cfg = match config::Config::load_strict(&sounds_dir) {
+        Ok(cfg) => {
+            let detail = if cfg.is_some() { "parsed" } else { "not found, using defaults" };
+            report("config", Ok(detail.to_string()));
+            cfg.unwrap_or_default()
+        }
+        Err(e) => {
+            report("config", Err(e));
+            config::Config::default()
+        }
+    };
+
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let resolver = config::ThemeResolver {
+        sounds_dir: &sounds_dir,
+        config: &cfg,
+        session_id: "",
+        cwd: cwd.to_string_lossy().into_owned(),
+    };
+    let theme = resolver.resolve();
+    report(
+        "theme",
+        builtin::load_theme(&sounds_dir, &theme)
+            .map(|_| theme.clone())
+            .ok_or_else(|| format!("'{theme}' has no usable manifest")),
+    );
+
+    let env = headless::detect(cfg.headless);
+    if cfg.sound_enabled() && audio::player(&cfg).needs_device() {
+        report("audio", audio::probe().map(|_| "played 20 ms of silence".to_string()).map_err(|e| e.to_string()));
+    }
+    if cfg.notifications_enabled() && !env.is_headless() {
+        report("notifications", notify::ping_server().map_err(|e| e.to_string()));
+    }
+
+    if failures > 0 {
+        println!("{failures} check(s) failed");
+    }
+    failures == 0
+}
+
+fn run_forward(to: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if to.is_empty() {
+        return Err("usage: ringring forward --to <host:port|unix-socket>".into());
+    }
+    let payload = std::io::read_to_string(std::io::stdin())?;
+    let cfg = config::Config::load(&paths::data_dir());
+    daemon::forward(&daemon::Endpoint::parse(to), cfg.forward_token().as_deref(), &payload)
+}
+
+fn run_daemon(listen: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let endpoint = listen.map(daemon::Endpoint::parse).unwrap_or_else(daemon::Endpoint::default_socket);
+    // Before the cache watcher and queue workers start reading the environment
+    daemon::claim_activation();
+    audio::set_in_process(true);
+    let cache = DAEMON_CACHE.get_or_init(|| cache::Cache::new(paths::data_dir()));
+    cache.watch();
+    // `daemon reload` re-reads the token; pick up every other change right away too
+    let load_token = || {
+        if let Some(cache) = DAEMON_CACHE.get() {
+            cache.refresh();
+        }
+        config::Config::load(&paths::data_dir()).forward_token()
+    };
+    let queues = queue::Queues::new(cache.config().0.daemon_queue, handle_forwarded);
+    #[cfg(feature = "async")]
+    let served = rust_ringring::daemon_async::serve(&endpoint, load_token, queues);
+    #[cfg(not(feature = "async"))]
+    let served = daemon::serve(&endpoint, load_token, queues);
+    cache.retire();
+    served
+}
+
+/// Print daemon state. Returns false when it isn't running (exit 3, as
+/// `systemctl status` does).
+fn run_daemon_status() -> bool {
+    match daemon::running_pid() {
+        Some(pid) => {
+            println!("running (pid {pid})");
+            if let daemon::Endpoint::Unix(socket) = daemon::Endpoint::default_socket()
+                && socket.exists()
+            {
+                println!("socket  {}", socket.display());
+            }
+            for (category, stats) in queue::load_stats().unwrap_or_default() {
+                println!(
+                    "queue   {category}: {} waiting, {} played, {} dropped",
+                    stats.queued, stats.handled, stats.dropped
+                );
+            }
+            if let Some(counts) = cache::load_counts() {
+                println!("cache   {} hits, {} misses, {} reloads", counts.hits, counts.misses, counts.reloads);
+            }
+            true
+        }
+        None => {
+            println!("not running");
+            false
+        }
+    }
+}
+
+/// The last `lines` events of the history store, oldest first.
+fn run_history(lines: usize, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let records = history::load();
+    let rows = stats::export_rows(&records[records.len().saturating_sub(lines)..], 0);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+    if rows.is_empty() {
+        println!("no events recorded in {}", history::history_path().display());
+        return Ok(());
+    }
+    let dash = |s: &str| if s.is_empty() { "-".to_string() } else { s.to_string() };
+    println!("| Time | Event | Type | Theme | Category | Sound | Result |");
+    println!("|------|-------|------|-------|----------|-------|--------|");
+    for row in &rows {
+        let result = match (row.skipped.as_str(), row.notified) {
+            ("", true) => "notified".to_string(),
+            ("", false) => "-".to_string(),
+            (reason, _) => format!("skipped ({reason})"),
+        };
+        println!(
+            "| {} | {} | {} | {} | {} | {} | {result} |",
+            row.time,
+            row.event,
+            dash(&row.notification_type),
+            dash(&row.theme),
+            dash(&row.category),
+            dash(&row.sound),
+        );
+    }
+    Ok(())
+}
+
+/// How often `tail` looks for new lines.
+const TAIL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Print the last `lines` events, then every new event (and with `show_log`
+/// every log line) as it is written, until interrupted.
+fn run_tail(lines: usize, show_log: bool, follow: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let print_event = |record: &history::Record| {
+        let mut described = tail::describe(record).into_iter();
+        println!("{}", ui::paint(ui::Style::Bold, &described.next().unwrap_or_default()));
+        for line in described {
+            let style = if line.trim_start().starts_with("skipped") { ui::Style::Warn } else { ui::Style::Good };
+            println!("{}", ui::paint(style, &line));
+        }
+    };
+    let records = history::load();
+    records[records.len().saturating_sub(lines)..].iter().for_each(print_event);
+    if !follow {
+        return Ok(());
+    }
+
+    let mut events = tail::Follower::from_end(&history::history_path());
+    let mut log = tail::Follower::from_end(&log::log_path());
+    eprintln!("following {}{} (Ctrl-C to stop)", history::history_path().display(), if show_log { format!(" and {}", log::log_path().display()) } else { String::new() });
+    loop {
+        if show_log {
+            for line in log.read_new() {
+                println!("{line}");
+            }
+        }
+        for line in events.read_new() {
+            if let Ok(record) = serde_json::from_str::<history::Record>(&line) {
+                print_event(&record);
+            }
+        }
+        std::thread::sleep(TAIL_INTERVAL);
+    }
+}
+
+fn run_replay(last: Option<usize>, id: Option<&str>, dry_run: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let last = last.unwrap_or(1);
+    let records = history::load();
+    let selected: Vec<&history::Record> = match id {
+        Some(id) => {
+            let record = records.iter().find(|r| r.id == id)
+                .ok_or_else(|| format!("no event with id '{id}' in {}", history::history_path().display()))?;
+            vec![record]
+        }
+        None => records.iter().rev().take(last).rev().collect(),
+    };
+    if selected.is_empty() {
+        return Err(format!("no events recorded in {}", history::history_path().display()).into());
+    }
+
+    for record in selected {
+        let hook_input: event::HookInput = serde_json::from_value(record.input.clone())?;
+        println!(
+            "{} {} {}",
+            record.id,
+            log::format_timestamp(record.timestamp),
+            hook_input.hook_event_name,
+        );
+        println!("  recorded: {}", describe_outcome(&record.outcome));
+        let outcome = pipeline::handle(&hook_input, dry_run)?;
+        let label = if dry_run { "would do" } else { "replayed" };
+        println!("  {label}: {}", describe_outcome(&outcome));
+    }
+    Ok(())
+}
+
+/// Re-run the payloads of a `record_events` or capture file (or of bare hook
+/// payloads, one per line), the last `last` of them when given.
+fn run_replay_file(path: &str, last: Option<usize>, dry_run: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let payloads = capture::load_file(std::path::Path::new(path)).map_err(|e| format!("{path}: {e}"))?;
+    if payloads.is_empty() {
+        return Err(format!("no hook payloads in {path}").into());
+    }
+    let skip = payloads.len().saturating_sub(last.unwrap_or(payloads.len()));
+    for (n, payload) in payloads.iter().enumerate().skip(skip) {
+        let hook_input: event::HookInput = serde_json::from_value(payload.input.clone()).map_err(|e| format!("{path}: payload {}: {e}", n + 1))?;
+        let when = if payload.timestamp > 0 { log::format_timestamp(payload.timestamp) } else { "-".to_string() };
+        println!("{} {when} {}", n + 1, hook_input.hook_event_name);
+        let outcome = pipeline::handle(&hook_input, dry_run)?;
+        let label = if dry_run { "would do" } else { "replayed" };
+        println!("  {label}: {}", describe_outcome(&outcome));
+    }
+    Ok(())
+}
+
+/// Run a synthesized hook event through the pipeline, in this process and
+/// without recording it in the history.
+fn run_simulate(simulation: &Simulation, dry_run: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if !install::HOOK_EVENTS.contains(&simulation.event.as_str()) && !install::OPTIONAL_EVENTS.contains(&simulation.event.as_str()) {
+        ui::warn(&format!("Claude Code doesn't send {}; handling it like an unknown event", simulation.event));
+    }
+    let raw = simulation.input();
+    println!("{raw}");
+    let hook_input: event::HookInput = serde_json::from_value(raw)?;
+    let outcome = pipeline::handle(&hook_input, dry_run)?;
+    let label = if dry_run { "would do" } else { "did" };
+    println!("{label}: {}", describe_outcome(&outcome));
+    Ok(())
+}
+
+/// Count the captured events by name and notification type, flagging the
+/// ones ringring has no mapping for.
+fn run_events_summarize() -> Result<(), Box<dyn std::error::Error>> {
+    let path = capture::capture_path();
+    let summary = capture::summarize(&capture::load());
+    let Some((first, last)) = summary.span else {
+        let hint = if config::Config::load(&paths::data_dir()).capture_events { "" } else { "; set \"capture_events\": true in config.json" };
+        return Err(format!("no events captured in {}{hint}", path.display()).into());
+    };
+    println!(
+        "{} events in {}, {} to {}",
+        summary.total,
+        path.display(),
+        log::format_timestamp(first),
+        log::format_timestamp(last)
+    );
+    let new_events = summary.new_events();
+    let new_types = summary.new_notification_types();
+    let mark = |new: bool| if new { ui::paint(ui::Style::Warn, "  new") } else { String::new() };
+    println!("\n{}", ui::paint(ui::Style::Bold, "Events"));
+    for (name, n) in &summary.events {
+        println!("  {name:<24} {n:>5}{}", mark(new_events.contains(&name.as_str())));
+    }
+    if !summary.notification_types.is_empty() {
+        println!("\n{}", ui::paint(ui::Style::Bold, "Notification types"));
+        for (kind, n) in &summary.notification_types {
+            println!("  {kind:<24} {n:>5}{}", mark(new_types.contains(&kind.as_str())));
+        }
+    }
+    println!();
+    if new_events.is_empty() && new_types.is_empty() {
+        ui::ok("every captured event and notification type has a mapping");
     } else {
-        &hook_input.session_id
-    };
-    let startup_flag = PathBuf::from(format!("/tmp/.claude-ringring-{session_id}"));
-
-    match source_type {
-        "startup" => {
-            fs::write(&startup_flag, "startup")?;
-
-            // Deferred startup sound: sleep, then play if flag still exists
-            let theme_dir = theme_dir.to_path_buf();
-            let flag = startup_flag;
-
-            // Pick sound now, move only what we need into the thread
-            let pick = manifest::pick_sound(manifest, "greeting");
-            let volume = manifest.volume;
-
-            std::thread::spawn(move || {
-                std::thread::sleep(std::time::Duration::from_secs(1));
-                if flag.exists() {
-                    if let Some(pick) = pick {
-                        let sound_path = theme_dir.join("sounds").join(&pick.file);
-                        let _ = audio::play_sound(&sound_path, volume);
-                    }
-                    let _ = fs::remove_file(&flag);
-                }
-            })
-            .join()
-            .ok();
+        ui::warn("events marked new get the generic mapping; please report them so ringring can learn them");
+    }
+    Ok(())
+}
+
+/// Download the newest release's binary for this platform, check it against
+/// the release's `SHA256SUMS` and swap it in for the installed one.
+fn run_update(force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let current = env!("CARGO_PKG_VERSION");
+    let release = updates::fetch_release(updates::RELEASES_URL)?;
+    if !force && !updates::newer(&release.tag, current) {
+        ui::ok(&format!("ringring {current} is the latest release"));
+        return Ok(());
+    }
+    let dest = install::update_target()?;
+    let home = PathBuf::from(std::env::var("HOME").unwrap_or_default());
+    if install::packaged(&dest, &home) {
+        return Err(format!("{} was installed by a package manager; update it there", dest.display()).into());
+    }
+
+    let (os, arch) = (std::env::consts::OS, std::env::consts::ARCH);
+    let asset = updates::asset_for(os, arch).ok_or_else(|| format!("releases have no binary for {os} {arch}; build from source"))?;
+    let url = release.asset_url(&asset).ok_or_else(|| format!("release {} has no {asset}", release.tag))?;
+    let sums = updates::CHECKSUMS_ASSET;
+    let sums_url = release
+        .asset_url(sums)
+        .ok_or_else(|| format!("release {} has no {sums}, so {asset} can't be checked", release.tag))?;
+    let expected = updates::expected_sha256(&String::from_utf8(install::download(sums_url)?)?, &asset)
+        .ok_or_else(|| format!("{sums} of {} doesn't list {asset}", release.tag))?;
+    let bytes = install::download(url)?;
+    let actual = publish::sha256_hex(&bytes);
+    if actual != expected {
+        return Err(format!("{asset} has sha256 {actual}, but {sums} says {expected}; not installed").into());
+    }
+
+    install::replace_binary(&dest, &bytes)?;
+    ui::ok(&format!("updated {} from {current} to {}", dest.display(), release.tag));
+    if let Some(mut available) = updates::load().filter(|a| a.binary.is_some()) {
+        available.binary = None;
+        let _ = updates::save(&available);
+    }
+    Ok(())
+}
+
+/// Look for a newer ringring release and newer versions of installed
+/// themes. Meant for a daily timer: the result is kept for `status`, and a
+/// notification goes out only when the set of updates changed.
+fn run_check_updates(notify: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let data_dir = paths::data_dir();
+    let cfg = config::Config::load(&data_dir);
+    let current = env!("CARGO_PKG_VERSION");
+    let mut available = updates::Available { checked: log::now_secs(), ..Default::default() };
+
+    match updates::latest_release(updates::RELEASES_URL) {
+        Ok(tag) if updates::newer(&tag, current) => {
+            ui::warn(&format!("ringring {tag} is available (you have {current}); `ringring update` installs it"));
+            available.binary = Some(tag);
         }
-        "resume" => {
-            let _ = fs::remove_file(&startup_flag);
+        Ok(_) => ui::ok(&format!("ringring {current} is the latest release")),
+        Err(e) => ui::warn(&format!("could not check for a new release: {e}")),
+    }
+
+    let receipts = updates::load_receipts(&data_dir);
+    let (outdated, errors) = updates::outdated_themes(&receipts, |receipt| {
+        install::source_for(&receipt.source, &cfg.registry).latest_sha256()
+    });
+    for error in errors {
+        ui::warn(&format!("could not check theme {error}"));
+    }
+    for theme in &outdated {
+        let source = &receipts[theme].source;
+        ui::warn(&format!("theme '{theme}' has an update: ringring theme install --force {source}"));
+    }
+    if outdated.is_empty() && !receipts.is_empty() {
+        ui::ok("installed themes are up to date");
+    }
+    available.themes = outdated;
+
+    let previous = updates::load();
+    updates::save(&available)?;
+    let news = !available.is_empty() && previous.is_none_or(|p| !p.same_as(&available));
+    if notify && news {
+        let cwd = std::env::current_dir().unwrap_or_default().to_string_lossy().into_owned();
+        let env = headless::detect(cfg.headless);
+        notify::Dispatcher::new(&cfg, notify::Branding::from_config(&cfg), &env, None, None)
+            .route(&cfg.routes, &cwd, Some("meta"))
+            .send("Updates available", &available.summary());
+    }
+    Ok(())
+}
+
+fn run_stats(suggest: bool, apply: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let since = log::now_secs().saturating_sub(stats::WEEK.as_secs());
+    let tallies = stats::tally(&history::load(), since);
+    if tallies.is_empty() {
+        println!("no events in the last week ({})", history::history_path().display());
+        return Ok(());
+    }
+    let mut rows: Vec<(&String, &stats::Tally)> = tallies.iter().collect();
+    rows.sort_by_key(|(_, tally)| std::cmp::Reverse(tally.events));
+    println!("| Category | Events | Played | Notified | Skipped | Mostly |");
+    println!("|----------|--------|--------|----------|---------|--------|");
+    for (category, tally) in rows {
+        println!(
+            "| {category} | {} | {} | {} | {} | {} |",
+            tally.events,
+            tally.played,
+            tally.notified,
+            tally.skipped,
+            tally.top_trigger(),
+        );
+    }
+    if !suggest {
+        return Ok(());
+    }
+
+    let sounds_dir = paths::data_dir();
+    let suggestions = stats::suggest(&tallies, &config::Config::load(&sounds_dir));
+    println!();
+    if suggestions.is_empty() {
+        ui::ok("nothing to suggest");
+        return Ok(());
+    }
+    for suggestion in &suggestions {
+        println!("- {}", suggestion.message());
+    }
+    if !apply {
+        println!("run `ringring stats --apply` to add these cooldowns to config.json");
+        return Ok(());
+    }
+    if !confirm("apply these to config.json?")? {
+        println!("left config.json unchanged");
+        return Ok(());
+    }
+    stats::apply(&sounds_dir, &suggestions)?;
+    ui::ok(&format!("wrote {}", sounds_dir.join("config.json").display()));
+    Ok(())
+}
+
+fn run_stats_export(format: &str, since: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let since = match since {
+        Some(age) => {
+            let age = config::parse_duration(age).ok_or_else(|| format!("invalid --since '{age}' (e.g. 30d, 12h)"))?;
+            log::now_secs().saturating_sub(age.as_secs())
         }
-        _ => {}
+        None => 0,
+    };
+    let rows = stats::export_rows(&history::load(), since);
+    match format {
+        "csv" => print!("{}", stats::to_csv(&rows)),
+        "json" => println!("{}", serde_json::to_string_pretty(&rows)?),
+        other => return Err(format!("unknown format '{other}' (csv or json)").into()),
+    }
+    Ok(())
+}
+
+/// Ask a yes/no question on stdin; anything but y/yes is no.
+fn confirm(question: &str) -> std::io::Result<bool> {
+    use std::io::Write;
+    print!("{question} [y/N] ");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes"))
+}
+
+fn describe_outcome(outcome: &history::Outcome) -> String {
+    if let Some(ref reason) = outcome.skipped {
+        return format!("skipped ({reason})");
     }
+    format!(
+        "theme={} category={} sound={} notified={}",
+        outcome.theme.as_deref().unwrap_or("-"),
+        outcome.category.as_deref().unwrap_or("-"),
+        outcome.sound.as_deref().unwrap_or("-"),
+        outcome.notified,
+    )
+}
 
+#[cfg(target_os = "linux")]
+fn run_daemon_install_service() -> Result<(), Box<dyn std::error::Error>> {
+    let installed = PathBuf::from(std::env::var("HOME").map_err(|_| "HOME not set")?).join(".local/bin/ringring");
+    let exe = if installed.exists() { installed } else { std::env::current_exe()? };
+    for path in install::install_service_units(&paths::systemd_user_dir(), &exe)? {
+        println!("wrote {}", path.display());
+    }
+    install::enable_service()?;
+    println!("enabled ringring.socket; the daemon starts on the first hook event");
     Ok(())
 }
+
+#[cfg(not(target_os = "linux"))]
+fn run_daemon_install_service() -> Result<(), Box<dyn std::error::Error>> {
+    Err("systemd services are only supported on Linux".into())
+}
@@ -1,25 +1,90 @@
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
 
-#[derive(Debug, Deserialize)]
+/// Directory of split category files, `categories/<name>.json`, each one
+/// `Category`. A theme with thousands of sounds keeps manifest.json small
+/// this way, and the hook only reads the category an event plays.
+pub const SPLIT_DIR: &str = "categories";
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct Manifest {
     #[allow(dead_code)]
     pub name: String,
     #[allow(dead_code)]
     pub display_name: String,
+    /// One line about the theme, for the registry.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The theme's own version, for the registry.
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Manifest format version; themes written for a newer ringring set a
+    /// higher one (see `buildinfo::MANIFEST_SCHEMA`).
+    #[serde(default = "default_schema")]
+    pub schema: u32,
     /// Volume 0.0–1.0. Defaults to 1.0 if omitted.
     #[serde(default = "default_volume")]
     pub volume: f32,
+    /// Language of the voice lines, e.g. `cs`; built-in notification texts
+    /// follow it (see `locale`).
+    #[serde(default)]
+    pub language: Option<String>,
+    /// `"visual"` for a theme of notification texts, icons and colors only.
+    #[serde(default, rename = "type")]
+    pub kind: Kind,
+    /// Inline categories; split ones from `SPLIT_DIR` are merged in on load.
+    #[serde(default)]
     pub categories: HashMap<String, Category>,
+    /// Optional looping background track, played by the daemon during a session.
+    #[serde(default)]
+    pub ambience: Option<Ambience>,
+    /// Sounds played around every voice line of this theme.
+    #[serde(default)]
+    pub stinger: Stinger,
+}
+
+/// What a theme provides.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    #[default]
+    Sound,
+    /// Notifications only: its categories have no sounds, on purpose.
+    Visual,
+}
+
+/// Short sounds played right before (`prefix`) and after (`suffix`) a line.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Stinger {
+    #[serde(default)]
+    pub prefix: Option<String>,
+    #[serde(default)]
+    pub suffix: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Ambience {
+    pub file: String,
+    #[serde(default = "default_ambience_volume")]
+    pub volume: f32,
+}
+
+fn default_ambience_volume() -> f32 {
+    0.1
+}
+
+fn default_schema() -> u32 {
+    1
 }
 
 fn default_volume() -> f32 {
     1.0
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Category {
     #[serde(default)]
     pub title: Option<String>,
@@ -27,40 +92,164 @@ pub struct Category {
     pub body: Option<String>,
     #[serde(default)]
     pub sounds: Vec<Sound>,
+    /// Probability (0.0–1.0) that an event here plays a sound at all; the
+    /// notification goes out regardless. Unset means always.
+    #[serde(default)]
+    pub chance: Option<f32>,
+    /// Moods that replace `sounds` once the session has waited on the user
+    /// long enough, e.g. `{"impatient": {"after": "2m", "sounds": [...]}}`.
+    #[serde(default)]
+    pub variants: BTreeMap<String, Variant>,
+    /// Notification icon for events here, a file in the theme dir.
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// `#rrggbb` the configured light flashes in for events here, unless
+    /// config.json `light.colors` sets one.
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+/// Sounds for a session that has been waiting at least `after`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Variant {
+    /// Duration such as `"90s"` or `"5m"`; unparseable values never apply.
+    pub after: String,
+    #[serde(default)]
+    pub sounds: Vec<Sound>,
 }
 
-#[derive(Debug, Deserialize)]
+impl Category {
+    /// The variant for a session waiting `waited`: the one with the longest
+    /// `after` that has passed, if it has sounds.
+    pub fn variant(&self, waited: Duration) -> Option<(&str, &Variant)> {
+        self.variants
+            .iter()
+            .filter(|(_, v)| !v.sounds.is_empty())
+            .filter_map(|(name, v)| Some((name.as_str(), v, crate::config::parse_duration(&v.after)?)))
+            .filter(|(_, _, after)| *after <= waited)
+            .max_by_key(|(_, _, after)| *after)
+            .map(|(name, v, _)| (name, v))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct Sound {
     pub file: String,
     #[serde(default)]
     pub line: Option<String>,
+    /// Probability (0.0–1.0) that this sound plays once picked, on top of
+    /// the category's `chance`.
+    #[serde(default)]
+    pub chance: Option<f32>,
 }
 
 impl Manifest {
+    /// manifest.json with every split category.
     pub fn load(theme_dir: &Path) -> Option<Self> {
+        let mut manifest = Self::load_inline(theme_dir)?;
+        for (name, path) in split_categories(theme_dir) {
+            manifest.add_split(name, &path);
+        }
+        Some(manifest)
+    }
+
+    /// manifest.json with only the split categories named in `only`, for an
+    /// event that needs just those. Inline categories are always there.
+    pub fn load_categories(theme_dir: &Path, only: &[&str]) -> Option<Self> {
+        let mut manifest = Self::load_inline(theme_dir)?;
+        for name in only {
+            let path = theme_dir.join(SPLIT_DIR).join(format!("{name}.json"));
+            if path.is_file() {
+                manifest.add_split(name.to_string(), &path);
+            }
+        }
+        Some(manifest)
+    }
+
+    fn load_inline(theme_dir: &Path) -> Option<Self> {
         let path = theme_dir.join("manifest.json");
         let content = fs::read_to_string(&path).ok()?;
         serde_json::from_str(&content).ok()
     }
+
+    /// Add a split category unless manifest.json has it inline. Files that
+    /// don't parse are skipped; `theme validate` reports them.
+    fn add_split(&mut self, name: String, path: &Path) {
+        if self.categories.contains_key(&name) {
+            return;
+        }
+        if let Some(category) = fs::read_to_string(path).ok().and_then(|c| serde_json::from_str(&c).ok()) {
+            self.categories.insert(name, category);
+        }
+    }
+
+    pub fn is_visual(&self) -> bool {
+        self.kind == Kind::Visual
+    }
+
+    /// Path of `category`'s icon in `theme_dir`, unless it points outside it.
+    pub fn icon(&self, theme_dir: &Path, category: &str) -> Option<PathBuf> {
+        let icon = Path::new(self.categories.get(category)?.icon.as_deref()?);
+        icon.components().all(|c| matches!(c, Component::Normal(_))).then(|| theme_dir.join(icon))
+    }
+
+    /// Every sound file the theme refers to (relative to `sounds/`), sorted.
+    pub fn files(&self) -> Vec<&str> {
+        let mut files: Vec<&str> = self
+            .categories
+            .values()
+            .flat_map(|cat| cat.sounds.iter().chain(cat.variants.values().flat_map(|v| &v.sounds)))
+            .map(|s| s.file.as_str())
+            .chain(self.ambience.iter().map(|a| a.file.as_str()))
+            .chain(self.stinger.prefix.as_deref())
+            .chain(self.stinger.suffix.as_deref())
+            .collect();
+        files.sort_unstable();
+        files.dedup();
+        files
+    }
+}
+
+/// The theme's split category files by category name, sorted.
+pub fn split_categories(theme_dir: &Path) -> Vec<(String, PathBuf)> {
+    let mut files: Vec<(String, PathBuf)> = fs::read_dir(theme_dir.join(SPLIT_DIR))
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json") && p.is_file())
+        .filter_map(|p| Some((p.file_stem()?.to_str()?.to_string(), p)))
+        .collect();
+    files.sort();
+    files
 }
 
 pub struct SoundPick {
     pub file: String,
     pub line: Option<String>,
+    pub chance: Option<f32>,
 }
 
 /// Pick a random sound from a category. Returns None if category missing or empty.
 pub fn pick_sound(manifest: &Manifest, category: &str) -> Option<SoundPick> {
+    pick_sound_after(manifest, category, Duration::ZERO)
+}
+
+/// Like `pick_sound`, from the category's variant for a session that has
+/// been waiting on the user for `waited`.
+pub fn pick_sound_after(manifest: &Manifest, category: &str, waited: Duration) -> Option<SoundPick> {
     let cat = manifest.categories.get(category)?;
-    if cat.sounds.is_empty() {
+    let sounds = cat.variant(waited).map_or(&cat.sounds, |(_, v)| &v.sounds);
+    if sounds.is_empty() {
         return None;
     }
     use rand::Rng;
-    let idx = rand::rng().random_range(0..cat.sounds.len());
-    let sound = &cat.sounds[idx];
+    let idx = rand::rng().random_range(0..sounds.len());
+    let sound = &sounds[idx];
     Some(SoundPick {
         file: sound.file.clone(),
         line: sound.line.clone(),
+        chance: sound.chance,
     })
 }
 
@@ -73,6 +262,42 @@ pub fn category_text(manifest: &Manifest, category: &str) -> (Option<String>, Op
     )
 }
 
+/// Categories `theme new` starts a manifest with, each with a placeholder
+/// title and body: every category a hook event plays.
+pub const SKELETON_CATEGORIES: [(&str, &str, &str); 6] = [
+    ("greeting", "Ready to work", "Session started"),
+    ("complete", "Done", "Task complete"),
+    ("permission", "Permission needed", "Something needs your approval"),
+    ("annoyed", "Waiting for you", "Claude is waiting for input"),
+    ("acknowledge", "Signed in", "Authentication succeeded"),
+    ("resource_limit", "Heads up", "Something unexpected happened"),
+];
+
+/// Create `theme_dir` with an empty `sounds/` and a manifest.json listing
+/// `SKELETON_CATEGORIES`, each with one placeholder sound named after it.
+/// Refuses to touch an existing directory.
+pub fn scaffold(theme_dir: &Path, name: &str) -> std::io::Result<()> {
+    if theme_dir.exists() {
+        return Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, format!("{} already exists", theme_dir.display())));
+    }
+    let categories: serde_json::Map<String, serde_json::Value> = SKELETON_CATEGORIES
+        .iter()
+        .map(|(category, title, body)| {
+            let sounds = [serde_json::json!({"file": format!("{category}.wav"), "line": body})];
+            (category.to_string(), serde_json::json!({"title": title, "body": body, "sounds": sounds}))
+        })
+        .collect();
+    let manifest = serde_json::json!({
+        "name": name,
+        "display_name": name,
+        "schema": crate::buildinfo::MANIFEST_SCHEMA,
+        "volume": 1.0,
+        "categories": categories,
+    });
+    fs::create_dir_all(theme_dir.join("sounds"))?;
+    fs::write(theme_dir.join("manifest.json"), serde_json::to_string_pretty(&manifest)? + "\n")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,6 +325,18 @@ mod tests {
         .unwrap()
     }
 
+    #[test]
+    fn scaffold_lists_every_category() {
+        let dir = tempfile::tempdir().unwrap();
+        let theme_dir = dir.path().join("mine");
+        scaffold(&theme_dir, "mine").unwrap();
+        assert!(theme_dir.join("sounds").is_dir());
+        let manifest = Manifest::load(&theme_dir).unwrap();
+        assert_eq!(manifest.categories.len(), SKELETON_CATEGORIES.len());
+        assert_eq!(manifest.categories["annoyed"].sounds[0].file, "annoyed.wav");
+        assert!(scaffold(&theme_dir, "mine").is_err());
+    }
+
     #[test]
     fn pick_from_valid_category() {
         let manifest = sample_manifest();
@@ -121,6 +358,27 @@ mod tests {
         assert!(pick_sound(&manifest, "nonexistent").is_none());
     }
 
+    #[test]
+    fn variants_escalate_with_waiting_time() {
+        let manifest: Manifest = serde_json::from_str(
+            r#"{"name": "t", "display_name": "T", "categories": {"annoyed": {
+                "sounds": [{"file": "polite.wav"}],
+                "variants": {
+                    "impatient": {"after": "1m", "sounds": [{"file": "hurry.wav"}]},
+                    "furious": {"after": "5m", "sounds": [{"file": "busy.wav", "line": "Me busy, leave me alone!"}]},
+                    "broken": {"after": "soon", "sounds": [{"file": "never.wav"}]}
+                }
+            }}}"#,
+        )
+        .unwrap();
+        let file = |secs| pick_sound_after(&manifest, "annoyed", Duration::from_secs(secs)).unwrap().file;
+        assert_eq!(file(0), "polite.wav");
+        assert_eq!(file(90), "hurry.wav");
+        assert_eq!(file(600), "busy.wav");
+        assert_eq!(manifest.categories["annoyed"].variant(Duration::from_secs(300)).map(|(name, _)| name), Some("furious"));
+        assert_eq!(manifest.files(), ["busy.wav", "hurry.wav", "never.wav", "polite.wav"]);
+    }
+
     #[test]
     fn category_text_returns_title() {
         let manifest = sample_manifest();
@@ -142,6 +400,32 @@ mod tests {
         assert_eq!(m.unwrap().name, "t");
     }
 
+    #[test]
+    fn split_categories_load_whole_or_on_demand() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("manifest.json"),
+            r#"{"name": "t", "display_name": "T", "categories": {"complete": {"sounds": [{"file": "inline.wav"}]}}}"#,
+        )
+        .unwrap();
+        let split = dir.path().join(SPLIT_DIR);
+        std::fs::create_dir(&split).unwrap();
+        std::fs::write(split.join("complete.json"), r#"{"sounds": [{"file": "split.wav"}]}"#).unwrap();
+        std::fs::write(split.join("annoyed.json"), r#"{"title": "Waiting", "sounds": [{"file": "a.wav"}]}"#).unwrap();
+        std::fs::write(split.join("greeting.json"), r#"{"sounds": "#).unwrap();
+
+        let all = Manifest::load(dir.path()).unwrap();
+        let mut names: Vec<&String> = all.categories.keys().collect();
+        names.sort();
+        assert_eq!(names, ["annoyed", "complete"]);
+        assert_eq!(all.categories["complete"].sounds[0].file, "inline.wav");
+
+        let some = Manifest::load_categories(dir.path(), &["annoyed", "permission"]).unwrap();
+        assert_eq!(some.categories.len(), 2);
+        assert_eq!(some.categories["annoyed"].title.as_deref(), Some("Waiting"));
+        assert_eq!(Manifest::load_categories(dir.path(), &[]).unwrap().categories.len(), 1);
+    }
+
     #[test]
     fn load_missing_manifest_returns_none() {
         let dir = tempfile::tempdir().unwrap();
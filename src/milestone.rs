@@ -0,0 +1,102 @@
+//! Milestones: a celebration instead of the usual `complete` sound when
+//! today's finished tasks reach a configured count ("first of the day",
+//! "10 done today"), counted from the history store.
+
+use serde::Deserialize;
+
+use crate::history::{self, Record};
+use crate::stats;
+
+/// Category of the celebration, for its sound, `routes` and `badges`.
+pub const CATEGORY: &str = "milestone";
+
+/// Config.json `milestones`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MilestoneConfig {
+    /// Completed tasks today worth celebrating; 1 is the first of the day.
+    #[serde(default = "default_counts")]
+    pub counts: Vec<usize>,
+    /// Notification text; `{count}` is filled in.
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+fn default_counts() -> Vec<usize> {
+    vec![1, 10]
+}
+
+impl MilestoneConfig {
+    pub fn message(&self, count: usize) -> String {
+        match (&self.message, count) {
+            (Some(message), _) => message.replace("{count}", &count.to_string()),
+            (None, 1) => "First task of the day done".to_string(),
+            (None, _) => format!("{count} tasks done today"),
+        }
+    }
+}
+
+/// The milestone a `complete` event at `now` reaches, if any. The event
+/// isn't in the history yet, so it counts on top of today's.
+pub fn reached(cfg: &MilestoneConfig, now: u64) -> Option<usize> {
+    reached_in(&history::load(), cfg, day_start(now))
+}
+
+/// Only tasks whose event went through count: muted, cooled down or
+/// duplicate ones don't.
+fn reached_in(records: &[Record], cfg: &MilestoneConfig, since: u64) -> Option<usize> {
+    let count = 1 + records
+        .iter()
+        .filter(|r| r.timestamp >= since && r.outcome.skipped.is_none())
+        .filter(|r| stats::classify(r).is_some_and(|(category, _)| category == "complete"))
+        .count();
+    cfg.counts.contains(&count).then_some(count)
+}
+
+/// Unix time of the last local midnight at or before `now`.
+fn day_start(now: u64) -> u64 {
+    let time = now as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return now - now % 86_400;
+    }
+    let since_midnight = tm.tm_hour as u64 * 3600 + tm.tm_min as u64 * 60 + tm.tm_sec as u64;
+    now.saturating_sub(since_midnight)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::Outcome;
+
+    fn stop(timestamp: u64, skipped: Option<&str>) -> Record {
+        Record {
+            id: timestamp.to_string(),
+            timestamp,
+            input: serde_json::json!({"hook_event_name": "Stop"}),
+            outcome: Outcome { skipped: skipped.map(Into::into), ..Default::default() },
+        }
+    }
+
+    #[test]
+    fn counts_todays_completed_tasks() {
+        let cfg: MilestoneConfig = serde_json::from_str(r#"{"counts": [1, 3]}"#).unwrap();
+        let since = 1_000_000;
+        assert_eq!(reached_in(&[stop(since - 10, None)], &cfg, since), Some(1));
+
+        let mut records = vec![stop(since - 10, None), stop(since + 10, None), stop(since + 20, Some("session muted"))];
+        assert_eq!(reached_in(&records, &cfg, since), None);
+        records.push(stop(since + 30, None));
+        assert_eq!(reached_in(&records, &cfg, since), Some(3));
+    }
+
+    #[test]
+    fn messages_and_defaults() {
+        let cfg: MilestoneConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(cfg.counts, [1, 10]);
+        assert_eq!(cfg.message(1), "First task of the day done");
+        assert_eq!(cfg.message(10), "10 tasks done today");
+        let custom = MilestoneConfig { message: Some("{count} down!".into()), ..cfg };
+        assert_eq!(custom.message(25), "25 down!");
+        assert!(day_start(1_771_590_896) <= 1_771_590_896);
+    }
+}
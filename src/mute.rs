@@ -0,0 +1,73 @@
+//! Global mute: `ringring mute [--for 30m] [--all]` silences every session
+//! until `ringring unmute` or until the time runs out. Per session mutes are
+//! flag files in `session`.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mute {
+    /// Unix seconds the mute ends at; `None` lasts until `unmute`.
+    #[serde(default)]
+    pub until: Option<u64>,
+    /// Hold back notifications too, not just sounds.
+    #[serde(default)]
+    pub all: bool,
+}
+
+pub fn mute_path() -> PathBuf {
+    paths::runtime_dir().join("mute.json")
+}
+
+pub fn set(mute: &Mute) -> Result<(), Box<dyn std::error::Error>> {
+    let path = mute_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string(mute)?)?;
+    Ok(())
+}
+
+/// Lift the mute. Returns whether one was in effect.
+pub fn clear(now: u64) -> bool {
+    let was = active(now).is_some();
+    let _ = fs::remove_file(mute_path());
+    was
+}
+
+/// The mute in effect at `now`, removing it once it has run out.
+pub fn active(now: u64) -> Option<Mute> {
+    active_in(&mute_path(), now)
+}
+
+fn active_in(path: &Path, now: u64) -> Option<Mute> {
+    let mute: Mute = serde_json::from_slice(&fs::read(path).ok()?).ok()?;
+    if mute.until.is_some_and(|until| until <= now) {
+        let _ = fs::remove_file(path);
+        return None;
+    }
+    Some(mute)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expired_mutes_clear_themselves() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mute.json");
+        assert_eq!(active_in(&path, 100), None);
+
+        fs::write(&path, r#"{"until": 200, "all": true}"#).unwrap();
+        assert_eq!(active_in(&path, 199), Some(Mute { until: Some(200), all: true }));
+        assert_eq!(active_in(&path, 200), None);
+        assert!(!path.exists());
+
+        fs::write(&path, "{}").unwrap();
+        assert_eq!(active_in(&path, u64::MAX), Some(Mute { until: None, all: false }));
+    }
+}
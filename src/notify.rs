@@ -1,19 +1,60 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::OnceLock;
 
+use crate::config::Config;
+use crate::controller::{Midi, StreamDeck};
+use crate::haptic::Haptic;
+use crate::headless::Environment;
+use crate::light::Light;
+use crate::routes::{self, Route};
+use crate::{log, paths, remote};
+
 static ICON_BYTES: &[u8] = include_bytes!("../clawd.png");
 
-#[cfg(target_os = "linux")]
-const APP_ID: &str = "claude-code";
-#[cfg(target_os = "linux")]
-const DESKTOP_ENTRY: &str = "[Desktop Entry]
-Name=Claude Code
-Icon=claude-code
-Type=Application
-NoDisplay=true
-";
-
-fn icon_path() -> &'static PathBuf {
+const DEFAULT_APP_ID: &str = "claude-code";
+const DEFAULT_APP_NAME: &str = "Claude Code";
+
+/// How notifications identify themselves to the desktop: app id (desktop
+/// entry and icon name), display name, and icon.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Branding {
+    pub app_id: String,
+    pub app_name: String,
+    /// Custom icon path; `None` uses the embedded Claude icon.
+    pub icon: Option<PathBuf>,
+}
+
+impl Default for Branding {
+    fn default() -> Self {
+        Self {
+            app_id: DEFAULT_APP_ID.to_string(),
+            app_name: DEFAULT_APP_NAME.to_string(),
+            icon: None,
+        }
+    }
+}
+
+impl Branding {
+    pub fn from_config(cfg: &Config) -> Self {
+        let defaults = Self::default();
+        Self {
+            app_id: cfg.app_id.clone().filter(|s| !s.is_empty()).unwrap_or(defaults.app_id),
+            app_name: cfg.app_name.clone().filter(|s| !s.is_empty()).unwrap_or(defaults.app_name),
+            icon: cfg.icon.as_deref().filter(|s| !s.is_empty()).map(PathBuf::from),
+        }
+    }
+
+    /// Path of the icon passed to notifications.
+    fn icon_path(&self) -> PathBuf {
+        match &self.icon {
+            Some(path) if path.exists() => path.clone(),
+            _ => embedded_icon_path().clone(),
+        }
+    }
+}
+
+fn embedded_icon_path() -> &'static PathBuf {
     static PATH: OnceLock<PathBuf> = OnceLock::new();
     PATH.get_or_init(|| {
         let path = PathBuf::from("/tmp/.claude-ringring-icon.png");
@@ -22,48 +63,385 @@ fn icon_path() -> &'static PathBuf {
     })
 }
 
+#[cfg(target_os = "linux")]
+fn desktop_entry(branding: &Branding) -> String {
+    format!(
+        "[Desktop Entry]\nName={}\nIcon={}\nType=Application\nNoDisplay=true\n",
+        branding.app_name, branding.app_id,
+    )
+}
+
+/// Write `contents` to `path` unless it already holds exactly those bytes.
+/// Returns whether it wrote.
+#[cfg(target_os = "linux")]
+fn write_if_changed(path: &std::path::Path, contents: &[u8]) -> bool {
+    if std::fs::read(path).is_ok_and(|existing| existing == contents) {
+        return false;
+    }
+    std::fs::write(path, contents).is_ok()
+}
+
+/// Write the .desktop entry and icon for `branding` under `home`'s
+/// `.local/share`, returning the files that were missing or out of date.
+#[cfg(target_os = "linux")]
+pub fn install_desktop_entry(branding: &Branding, home: &std::path::Path) -> Vec<PathBuf> {
+    let mut written = Vec::new();
+    let apps_dir = home.join(".local/share/applications");
+    let _ = std::fs::create_dir_all(&apps_dir);
+    let desktop_path = apps_dir.join(format!("{}.desktop", branding.app_id));
+    if write_if_changed(&desktop_path, desktop_entry(branding).as_bytes()) {
+        written.push(desktop_path);
+    }
+
+    // Also install the icon so GNOME can find it
+    let icons_dir = home.join(".local/share/icons/hicolor/128x128/apps");
+    let _ = std::fs::create_dir_all(&icons_dir);
+    let icon_dest = icons_dir.join(format!("{}.png", branding.app_id));
+    let icon_bytes = branding
+        .icon
+        .as_ref()
+        .and_then(|p| std::fs::read(p).ok())
+        .unwrap_or_else(|| ICON_BYTES.to_vec());
+    if write_if_changed(&icon_dest, &icon_bytes) {
+        written.push(icon_dest);
+    }
+    written
+}
+
 #[cfg(target_os = "linux")]
 /// Ensure the .desktop file exists so GNOME can identify our app for stacking.
-fn ensure_desktop_entry() {
+/// Rewritten whenever the configured branding changes.
+fn ensure_desktop_entry(branding: &Branding) {
     static DONE: OnceLock<()> = OnceLock::new();
     DONE.get_or_init(|| {
         let home = std::env::var("HOME").unwrap_or_default();
-        if home.is_empty() {
-            return;
+        if !home.is_empty() {
+            install_desktop_entry(branding, std::path::Path::new(&home));
+        }
+    });
+}
+
+/// Which API desktop notifications use on Linux (`notification_backend`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DesktopBackend {
+    /// org.gtk.Notifications on GNOME, freedesktop elsewhere.
+    #[default]
+    Auto,
+    /// org.gtk.Notifications first, freedesktop if that fails.
+    Gtk,
+    Freedesktop,
+    /// No desktop notifications; remote backends still get them.
+    None,
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+impl DesktopBackend {
+    /// Whether to try org.gtk.Notifications, which only GNOME Shell serves;
+    /// elsewhere the gdbus call is a wasted process that fails.
+    fn uses_gtk(self) -> bool {
+        static GNOME: OnceLock<bool> = OnceLock::new();
+        match self {
+            DesktopBackend::Gtk => true,
+            DesktopBackend::Auto => *GNOME.get_or_init(|| is_gnome(std::env::var("XDG_CURRENT_DESKTOP").ok().as_deref())),
+            DesktopBackend::Freedesktop | DesktopBackend::None => false,
+        }
+    }
+}
+
+/// Whether an `XDG_CURRENT_DESKTOP` value (`ubuntu:GNOME`, `KDE`, ...)
+/// names GNOME.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn is_gnome(current_desktop: Option<&str>) -> bool {
+    current_desktop.is_some_and(|desktops| desktops.split(':').any(|d| d.eq_ignore_ascii_case("gnome")))
+}
+
+/// A destination for notifications (desktop, terminal, remote service, ...).
+pub trait Notifier {
+    fn name(&self) -> &'static str;
+    fn notify(&self, title: &str, body: &str) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// Native desktop notifications.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub struct Desktop {
+    pub branding: Branding,
+    /// Notifications sharing a group (the session's workspace) replace each
+    /// other instead of piling up, where the server supports it.
+    pub group: Option<String>,
+    /// Session whose terminal is focused when the notification is clicked.
+    /// Only set in the daemon, which outlives the notification.
+    pub focus: Option<String>,
+    /// `focus_command` from config.
+    pub focus_command: Option<String>,
+    pub backend: DesktopBackend,
+}
+
+impl Notifier for Desktop {
+    fn name(&self) -> &'static str {
+        "desktop"
+    }
+
+    fn notify(&self, title: &str, body: &str) -> Result<(), Box<dyn std::error::Error>> {
+        #[cfg(target_os = "linux")]
+        if let Some(session_id) = &self.focus {
+            return send_clickable(self, session_id, title, body);
+        }
+        send_desktop(&self.branding, self.backend, self.group.as_deref(), title, body)
+    }
+}
+
+/// freedesktop notification with a default action that focuses the session's
+/// terminal. Notifications of one group replace each other by id.
+#[cfg(target_os = "linux")]
+fn send_clickable(desktop: &Desktop, session_id: &str, title: &str, body: &str) -> Result<(), Box<dyn std::error::Error>> {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    static IDS: Mutex<Option<HashMap<String, u32>>> = Mutex::new(None);
+
+    let mut notification = notify_rust::Notification::new();
+    notification
+        .summary(title)
+        .body(body)
+        .icon(&desktop.branding.icon_path().to_string_lossy())
+        .appname(&desktop.branding.app_name)
+        .action("default", "Show terminal");
+    let mut ids = IDS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(id) = desktop.group.as_ref().and_then(|g| ids.as_ref()?.get(g)) {
+        notification.id(*id);
+    }
+    let handle = notification.show()?;
+    if let Some(group) = &desktop.group {
+        ids.get_or_insert_default().insert(group.clone(), handle.id());
+    }
+    drop(ids);
+
+    let session_id = session_id.to_string();
+    let command = desktop.focus_command.clone();
+    std::thread::spawn(move || {
+        handle.wait_for_action(|action| {
+            if action == "default" {
+                crate::focus::activate(&session_id, command.as_deref());
+            }
+        });
+    });
+    Ok(())
+}
+
+/// OSC 9 escape written to the controlling terminal. Understood by iTerm2,
+/// kitty, WezTerm, foot, Windows Terminal and others; ignored elsewhere.
+pub struct Terminal;
+
+impl Notifier for Terminal {
+    fn name(&self) -> &'static str {
+        "terminal"
+    }
+
+    fn notify(&self, title: &str, body: &str) -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+        let mut tty = std::fs::OpenOptions::new().write(true).open("/dev/tty")?;
+        write!(tty, "{}", osc9(title, body))?;
+        Ok(())
+    }
+}
+
+fn osc9(title: &str, body: &str) -> String {
+    // Control characters would terminate the sequence early
+    let clean = |s: &str| s.chars().filter(|c| !c.is_control()).collect::<String>();
+    format!("\x1b]9;{}: {}\x07", clean(title), clean(body))
+}
+
+/// Records notifications in the ringring log.
+pub struct Log;
+
+impl Notifier for Log {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    fn notify(&self, title: &str, body: &str) -> Result<(), Box<dyn std::error::Error>> {
+        log::write(&format!("notification: {title}: {body}"));
+        Ok(())
+    }
+}
+
+/// Body length a backend can show comfortably, when not configured.
+fn default_body_limit(backend: &str) -> Option<usize> {
+    match backend {
+        "desktop" | "terminal" => Some(200),
+        "slack" => Some(3000),
+        "telegram" => Some(4096),
+        _ => None,
+    }
+}
+
+/// Shorten `text` to at most `max` characters, cutting at a word boundary
+/// when one is reasonably close and ending with an ellipsis.
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    if max == 0 {
+        return String::new();
+    }
+    let cut: String = text.chars().take(max - 1).collect();
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(i) if cut[..i].chars().count() >= max / 2 => &cut[..i],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end_matches(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | ':' | '.')))
+}
+
+/// Fans a notification out to every active backend.
+pub struct Dispatcher {
+    backends: Vec<Box<dyn Notifier>>,
+    max_body_length: Option<usize>,
+    body_limits: HashMap<String, usize>,
+}
+
+impl Dispatcher {
+    /// Desktop notifications normally; log + terminal escape when headless.
+    /// Configured remote backends are always added.
+    /// `focus` makes desktop notifications focus that session's terminal when clicked.
+    pub fn new(cfg: &Config, branding: Branding, env: &Environment, group: Option<String>, focus: Option<String>) -> Self {
+        let mut backends: Vec<Box<dyn Notifier>> = if env.is_headless() {
+            vec![Box::new(Log), Box::new(Terminal)]
+        } else if cfg.notification_backend == DesktopBackend::None {
+            Vec::new()
+        } else {
+            let focus_command = cfg.focus_command.clone();
+            vec![Box::new(Desktop { branding, group, focus, focus_command, backend: cfg.notification_backend })]
+        };
+        backends.extend(remote::backends(&cfg.remote));
+        Self { backends, max_body_length: cfg.max_body_length, body_limits: cfg.body_limits.clone() }
+    }
+
+    /// Body limit for a backend: its `body_limits` entry, else `max_body_length`,
+    /// else the backend's built-in default.
+    fn body_limit(&self, backend: &str) -> Option<usize> {
+        self.body_limits.get(backend).copied().or(self.max_body_length).or_else(|| default_body_limit(backend))
+    }
+
+    pub fn backend_names(&self) -> Vec<&'static str> {
+        self.backends.iter().map(|b| b.name()).collect()
+    }
+
+    /// Add a rumble on the configured `haptic` device when `category` has a pattern.
+    pub fn haptic(mut self, cfg: &Config, category: Option<&str>) -> Self {
+        if let Some(haptic) = &cfg.haptic
+            && let Some(pattern) = category.and_then(|c| haptic.pattern(c))
+        {
+            self.backends.push(Box::new(Haptic { device: haptic.device.clone(), strength: haptic.strength, pattern }));
         }
+        self
+    }
+
+    /// Add a flash of the configured `light` when `category` has a color,
+    /// from config, the theme (`theme_color`) or the built-in ones.
+    pub fn light(mut self, cfg: &Config, category: Option<&str>, theme_color: Option<&str>) -> Self {
+        if let Some(light) = cfg.light.as_ref().zip(category).and_then(|(light, c)| Light::for_theme_category(light, c, theme_color)) {
+            self.backends.push(Box::new(light));
+        }
+        self
+    }
 
-        let apps_dir = PathBuf::from(&home).join(".local/share/applications");
-        let _ = std::fs::create_dir_all(&apps_dir);
-        let desktop_path = apps_dir.join("claude-code.desktop");
-        let _ = std::fs::write(&desktop_path, DESKTOP_ENTRY);
-
-        // Also install the icon so GNOME can find it
-        let icons_dir = PathBuf::from(&home).join(".local/share/icons/hicolor/128x128/apps");
-        let _ = std::fs::create_dir_all(&icons_dir);
-        let icon_dest = icons_dir.join("claude-code.png");
-        if !icon_dest.exists() {
-            let _ = std::fs::write(&icon_dest, ICON_BYTES);
+    /// Add the configured Stream Deck key and MIDI pad for `category`.
+    pub fn controllers(mut self, cfg: &Config, category: Option<&str>) -> Self {
+        let Some(category) = category else { return self };
+        if let Some(deck) = cfg.streamdeck.as_ref().and_then(|deck| StreamDeck::for_category(deck, category)) {
+            self.backends.push(Box::new(deck));
         }
+        if let Some(midi) = cfg.midi.as_ref().and_then(|midi| Midi::for_category(midi, category)) {
+            self.backends.push(Box::new(midi));
+        }
+        self
+    }
+
+    /// Keep only the backends allowed by the first `routes` rule matching
+    /// the event; all of them when no rule matches.
+    pub fn route(mut self, routes: &[Route], cwd: &str, category: Option<&str>) -> Self {
+        if let Some(i) = routes::first_match(routes, cwd, category, &paths::home_dir()) {
+            self.backends.retain(|backend| routes[i].allows(backend.name()));
+        }
+        self
+    }
+
+    /// Send to all backends. Failures are logged, never propagated.
+    pub fn send(&self, title: &str, body: &str) {
+        for backend in &self.backends {
+            let body = match self.body_limit(backend.name()) {
+                Some(max) => truncate(body, max),
+                None => body.to_string(),
+            };
+            if let Err(e) = backend.notify(title, &body) {
+                log::write(&format!("{} notification failed: {e}", backend.name()));
+            }
+        }
+    }
+}
+
+/// Desktop notification with one action button; `on_accept` runs if it is
+/// clicked. Only the daemon lives long enough to hear the answer.
+#[cfg(target_os = "linux")]
+pub fn offer(
+    branding: &Branding,
+    title: &str,
+    body: &str,
+    label: &str,
+    on_accept: impl FnOnce() + Send + 'static,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let handle = notify_rust::Notification::new()
+        .summary(title)
+        .body(body)
+        .icon(&branding.icon_path().to_string_lossy())
+        .appname(&branding.app_name)
+        .action("accept", label)
+        .show()?;
+    std::thread::spawn(move || {
+        handle.wait_for_action(|action| {
+            if action == "accept" {
+                on_accept();
+            }
+        });
     });
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn offer(
+    _branding: &Branding,
+    _title: &str,
+    _body: &str,
+    _label: &str,
+    _on_accept: impl FnOnce() + Send + 'static,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Err("notification actions are only supported on Linux".into())
 }
 
 /// Send a desktop notification.
-/// On Linux, tries org.gtk.Notifications (stacks in GNOME) then freedesktop fallback.
+/// On Linux, tries org.gtk.Notifications (stacks in GNOME) when `backend`
+/// calls for it, then freedesktop.
 /// On macOS, uses native notification center via mac-notification-sys.
-pub fn send_notification(title: &str, body: &str) {
-    let icon = icon_path().to_string_lossy();
+#[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+fn send_desktop(branding: &Branding, backend: DesktopBackend, group: Option<&str>, title: &str, body: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let icon_path = branding.icon_path();
+    let icon = icon_path.to_string_lossy();
 
     #[cfg(target_os = "linux")]
-    {
+    if backend.uses_gtk() {
         use std::process::Command;
         use std::sync::atomic::{AtomicU32, Ordering};
 
         static NOTIFICATION_ID: AtomicU32 = AtomicU32::new(0);
 
-        ensure_desktop_entry();
+        ensure_desktop_entry(branding);
 
-        let notif_id = NOTIFICATION_ID.fetch_add(1, Ordering::Relaxed);
-        let id = format!("ringring-{}-{}", std::process::id(), notif_id);
+        // GTK replaces a notification when one with the same id arrives
+        let id = match group {
+            Some(group) => format!("ringring-{group}"),
+            None => format!("ringring-{}-{}", std::process::id(), NOTIFICATION_ID.fetch_add(1, Ordering::Relaxed)),
+        };
 
         let variant = format!(
             "{{'title': <'{}'>, 'body': <'{}'>, 'icon': <('file-icon', <'{}'>)>}}",
@@ -79,26 +457,130 @@ pub fn send_notification(title: &str, body: &str) {
                 "--dest", "org.gtk.Notifications",
                 "--object-path", "/org/gtk/Notifications",
                 "--method", "org.gtk.Notifications.AddNotification",
-                APP_ID,
+                &branding.app_id,
                 &id,
                 &variant,
             ])
             .output();
 
         if result.is_ok_and(|o| o.status.success()) {
-            return;
+            return Ok(());
         }
     }
 
-    let _ = notify_rust::Notification::new()
+    notify_rust::Notification::new()
         .summary(title)
         .body(body)
         .icon(&icon)
-        .appname("Claude Code")
-        .show();
+        .appname(&branding.app_name)
+        .show()?;
+    Ok(())
+}
+
+/// Check that a notification daemon is reachable. Returns its name.
+#[cfg(target_os = "linux")]
+pub fn ping_server() -> Result<String, Box<dyn std::error::Error>> {
+    let info = notify_rust::get_server_information()?;
+    Ok(format!("{} {}", info.name, info.version))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn ping_server() -> Result<String, Box<dyn std::error::Error>> {
+    Ok("notification center".to_string())
 }
 
 #[cfg(target_os = "linux")]
 fn escape_gvariant(s: &str) -> String {
     s.replace('\\', "\\\\").replace('\'', "'\\''")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn branding_defaults_when_unset() {
+        let b = Branding::from_config(&Config::default());
+        assert_eq!(b, Branding::default());
+        assert_eq!(b.app_id, "claude-code");
+        assert_eq!(b.app_name, "Claude Code");
+    }
+
+    #[test]
+    fn branding_from_config_overrides() {
+        let cfg = Config {
+            app_id: Some("ringring".to_string()),
+            app_name: Some("Ringring".to_string()),
+            icon: Some("/opt/icons/ring.png".to_string()),
+            ..Default::default()
+        };
+        let b = Branding::from_config(&cfg);
+        assert_eq!(b.app_id, "ringring");
+        assert_eq!(b.app_name, "Ringring");
+        assert_eq!(b.icon, Some(PathBuf::from("/opt/icons/ring.png")));
+    }
+
+    #[test]
+    fn osc9_strips_control_characters() {
+        assert_eq!(osc9("Done", "line\x07one"), "\x1b]9;Done: lineone\x07");
+    }
+
+    #[test]
+    fn truncate_at_word_boundary() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("Okie dokie, work complete and tested", 20), "Okie dokie, work…");
+        assert_eq!(truncate("Supercalifragilistic", 10), "Supercali…");
+        assert_eq!(truncate("Příliš žluťoučký kůň", 8), "Příliš…");
+    }
+
+    #[test]
+    fn body_limit_precedence() {
+        let cfg: Config = serde_json::from_str(r#"{"max_body_length": 80, "body_limits": {"slack": 500}}"#).unwrap();
+        let env = Environment { display: true, dbus: true, audio: true };
+        let d = Dispatcher::new(&cfg, Branding::default(), &env, None, None);
+        assert_eq!(d.body_limit("slack"), Some(500));
+        assert_eq!(d.body_limit("desktop"), Some(80));
+        let d = Dispatcher::new(&Config::default(), Branding::default(), &env, None, None);
+        assert_eq!(d.body_limit("telegram"), Some(4096));
+        assert_eq!(d.body_limit("webhook"), None);
+    }
+
+    #[test]
+    fn headless_dispatcher_skips_desktop() {
+        let env = Environment { display: false, dbus: false, audio: false };
+        let d = Dispatcher::new(&Config::default(), Branding::default(), &env, None, None);
+        assert_eq!(d.backend_names(), vec!["log", "terminal"]);
+
+        let env = Environment { display: true, dbus: true, audio: true };
+        let d = Dispatcher::new(&Config::default(), Branding::default(), &env, None, None);
+        assert_eq!(d.backend_names(), vec!["desktop"]);
+    }
+
+    #[test]
+    fn backend_none_drops_desktop_notifications() {
+        let cfg: Config = serde_json::from_str(r#"{"notification_backend": "none"}"#).unwrap();
+        let env = Environment { display: true, dbus: true, audio: true };
+        assert!(Dispatcher::new(&cfg, Branding::default(), &env, None, None).backend_names().is_empty());
+        assert!(serde_json::from_str::<Config>(r#"{"notification_backend": "kde"}"#).is_err());
+    }
+
+    #[test]
+    fn gtk_is_used_on_gnome_only() {
+        assert!(is_gnome(Some("ubuntu:GNOME")) && is_gnome(Some("gnome")));
+        assert!(!is_gnome(Some("KDE")) && !is_gnome(Some("sway")) && !is_gnome(None));
+        assert!(DesktopBackend::Gtk.uses_gtk() && !DesktopBackend::Freedesktop.uses_gtk());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn desktop_entry_uses_branding() {
+        let b = Branding {
+            app_id: "ringring".to_string(),
+            app_name: "Ringring".to_string(),
+            icon: None,
+        };
+        let entry = desktop_entry(&b);
+        assert!(entry.contains("Name=Ringring\n"));
+        assert!(entry.contains("Icon=ringring\n"));
+    }
+}
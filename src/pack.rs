@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+
+use zip::write::SimpleFileOptions;
+
+/// Files of a theme keyed by their `/`-separated path below the theme dir.
+pub(crate) type Tree = BTreeMap<String, Vec<u8>>;
+
+/// How a zip compares to a theme's source tree.
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    /// Byte for byte what `theme pack` produces.
+    Identical,
+    /// Same files and contents, but not packed canonically.
+    SameContent,
+    Differs { missing: Vec<String>, extra: Vec<String>, changed: Vec<String> },
+}
+
+/// Theme name for a source dir: its last component, which must be a valid
+/// theme name and contain a manifest.
+pub fn theme_name(dir: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let name = dir
+        .canonicalize()?
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .ok_or("theme directory has no name")?;
+    crate::config::validate_theme_name(&name)?;
+    if !dir.join("manifest.json").is_file() {
+        return Err(format!("{} has no manifest.json", dir.display()).into());
+    }
+    Ok(name)
+}
+
+/// Files and directories operating systems and tools leave behind, which
+/// have no place in a theme zip.
+pub fn is_junk(name: &str) -> bool {
+    matches!(name, ".DS_Store" | "Thumbs.db" | "desktop.ini" | "__MACOSX" | ".git") || name.starts_with("._") || name.ends_with('~')
+}
+
+/// Regular files below `dir`, without junk (`is_junk`). Symlinks are skipped
+/// so a pack never reaches outside the theme.
+pub(crate) fn read_tree(dir: &Path) -> Result<Tree, Box<dyn std::error::Error>> {
+    let mut tree = Tree::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(rel) = pending.pop() {
+        for entry in std::fs::read_dir(dir.join(&rel))? {
+            let entry = entry?;
+            if is_junk(&entry.file_name().to_string_lossy()) {
+                continue;
+            }
+            let kind = entry.file_type()?;
+            let path = rel.join(entry.file_name());
+            if kind.is_dir() {
+                pending.push(path);
+            } else if kind.is_file() {
+                let key = path.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+                tree.insert(key, std::fs::read(entry.path())?);
+            }
+        }
+    }
+    Ok(tree)
+}
+
+/// The canonical zip for a theme dir: one top-level directory named after
+/// the theme, files in sorted order, fixed timestamps and permissions, so the
+/// same tree always gives the same bytes.
+pub fn pack(dir: &Path) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let name = theme_name(dir)?;
+    let options = SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .compression_level(Some(6))
+        .last_modified_time(zip::DateTime::default())
+        .unix_permissions(0o644);
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (path, bytes) in read_tree(dir)? {
+        zip.start_file(format!("{name}/{path}"), options)?;
+        zip.write_all(&bytes)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Compare a published zip with the source tree it claims to be built from.
+pub fn verify(zip_bytes: &[u8], dir: &Path) -> Result<Verdict, Box<dyn std::error::Error>> {
+    let name = theme_name(dir)?;
+    let source = read_tree(dir)?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(zip_bytes))?;
+    let mut packed = Tree::new();
+    let mut foreign = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let entry_name = entry.name().to_string();
+        match entry_name.strip_prefix(&format!("{name}/")) {
+            Some(path) => {
+                let mut bytes = Vec::new();
+                entry.read_to_end(&mut bytes)?;
+                packed.insert(path.to_string(), bytes);
+            }
+            None => foreign.push(entry_name),
+        }
+    }
+
+    let missing: Vec<String> = source.keys().filter(|p| !packed.contains_key(*p)).cloned().collect();
+    let mut extra: Vec<String> = packed.keys().filter(|p| !source.contains_key(*p)).cloned().collect();
+    extra.extend(foreign);
+    let changed: Vec<String> =
+        source.iter().filter(|(p, bytes)| packed.get(*p).is_some_and(|b| b != *bytes)).map(|(p, _)| p.clone()).collect();
+    if !missing.is_empty() || !extra.is_empty() || !changed.is_empty() {
+        return Ok(Verdict::Differs { missing, extra, changed });
+    }
+    Ok(if pack(dir)? == zip_bytes { Verdict::Identical } else { Verdict::SameContent })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn theme(root: &Path) -> PathBuf {
+        let dir = root.join("mytheme");
+        std::fs::create_dir_all(dir.join("sounds/extra")).unwrap();
+        std::fs::write(dir.join("manifest.json"), "{}").unwrap();
+        std::fs::write(dir.join("sounds/b.wav"), b"bb").unwrap();
+        std::fs::write(dir.join("sounds/extra/a.wav"), b"aa").unwrap();
+        dir
+    }
+
+    #[test]
+    fn pack_is_deterministic_and_sorted() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = theme(tmp.path());
+        let first = pack(&dir).unwrap();
+        // Touching a file changes its mtime but not the archive
+        std::fs::write(dir.join("sounds/b.wav"), b"bb").unwrap();
+        assert_eq!(pack(&dir).unwrap(), first);
+
+        let archive = zip::ZipArchive::new(Cursor::new(&first)).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        assert_eq!(names.len(), 3);
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(names, sorted);
+        assert_eq!(names[0], "mytheme/manifest.json");
+
+        std::fs::write(dir.join(".DS_Store"), b"junk").unwrap();
+        std::fs::write(dir.join("sounds/._b.wav"), b"junk").unwrap();
+        std::fs::create_dir(dir.join("__MACOSX")).unwrap();
+        std::fs::write(dir.join("__MACOSX/x"), b"junk").unwrap();
+        assert_eq!(pack(&dir).unwrap(), first);
+    }
+
+    #[test]
+    fn verify_reports_differences() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = theme(tmp.path());
+        let zip = pack(&dir).unwrap();
+        assert_eq!(verify(&zip, &dir).unwrap(), Verdict::Identical);
+
+        std::fs::write(dir.join("sounds/b.wav"), b"changed").unwrap();
+        std::fs::write(dir.join("sounds/new.wav"), b"n").unwrap();
+        std::fs::remove_file(dir.join("sounds/extra/a.wav")).unwrap();
+        assert_eq!(
+            verify(&zip, &dir).unwrap(),
+            Verdict::Differs {
+                missing: vec!["sounds/new.wav".into()],
+                extra: vec!["sounds/extra/a.wav".into()],
+                changed: vec!["sounds/b.wav".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn same_files_packed_differently() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = theme(tmp.path());
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        for (path, bytes) in [("sounds/b.wav", &b"bb"[..]), ("manifest.json", b"{}"), ("sounds/extra/a.wav", b"aa")] {
+            zip.start_file(format!("mytheme/{path}"), options).unwrap();
+            zip.write_all(bytes).unwrap();
+        }
+        let bytes = zip.finish().unwrap().into_inner();
+        assert_eq!(verify(&bytes, &dir).unwrap(), Verdict::SameContent);
+    }
+}
@@ -1,4 +1,4 @@
-fn home_dir() -> std::path::PathBuf {
+pub fn home_dir() -> std::path::PathBuf {
     std::env::var("HOME")
         .map(std::path::PathBuf::from)
         .unwrap_or_else(|_| std::path::PathBuf::from("/tmp"))
@@ -14,6 +14,16 @@ fn platform_config_fallback() -> std::path::PathBuf {
     home_dir().join(".config")
 }
 
+#[cfg(target_os = "macos")]
+fn platform_state_fallback() -> std::path::PathBuf {
+    home_dir().join("Library/Logs")
+}
+
+#[cfg(not(target_os = "macos"))]
+fn platform_state_fallback() -> std::path::PathBuf {
+    home_dir().join(".local/state")
+}
+
 #[cfg(target_os = "macos")]
 fn platform_data_fallback() -> std::path::PathBuf {
     home_dir().join("Library/Application Support")
@@ -33,7 +43,78 @@ pub fn config_dir() -> std::path::PathBuf {
     platform_config_fallback().join("ringring")
 }
 
+/// Directory for logs and other persistent runtime state.
+pub fn state_dir() -> std::path::PathBuf {
+    if let Ok(base) = std::env::var("XDG_STATE_HOME")
+        && !base.is_empty()
+    {
+        return std::path::PathBuf::from(base).join("ringring");
+    }
+    platform_state_fallback().join("ringring")
+}
+
+/// systemd user unit directory (`$XDG_CONFIG_HOME/systemd/user`).
+pub fn systemd_user_dir() -> std::path::PathBuf {
+    if let Ok(base) = std::env::var("XDG_CONFIG_HOME")
+        && !base.is_empty()
+    {
+        return std::path::PathBuf::from(base).join("systemd/user");
+    }
+    home_dir().join(".config/systemd/user")
+}
+
+/// Directory for the per-session flag files (mute, theme cache, pid map). `RINGRING_TMP_DIR` overrides `/tmp`, e.g. to isolate tests.
+pub fn tmp_dir() -> std::path::PathBuf {
+    match std::env::var("RINGRING_TMP_DIR") {
+        Ok(dir) if !dir.is_empty() => std::path::PathBuf::from(dir),
+        _ => std::path::PathBuf::from("/tmp"),
+    }
+}
+
+/// Directory a workspace pin for `cwd` applies to: the enclosing git
+/// checkout's root, or `cwd` itself outside of git.
+pub fn workspace_root(cwd: &std::path::Path) -> std::path::PathBuf {
+    cwd.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .unwrap_or(cwd)
+        .to_path_buf()
+}
+
+/// Per-user directory for sockets, locks and other short-lived state.
+pub fn runtime_dir() -> std::path::PathBuf {
+    if let Ok(base) = std::env::var("XDG_RUNTIME_DIR")
+        && !base.is_empty()
+    {
+        return std::path::PathBuf::from(base).join("ringring");
+    }
+    let uid = unsafe { libc::getuid() };
+    std::path::PathBuf::from(format!("/tmp/ringring-{uid}"))
+}
+
+/// Whether `dir` is a data directory ringring created and may delete whole:
+/// one named `ringring`, or the legacy `~/.claude/sounds`. Anything else was
+/// picked by the user and may hold their own files.
+pub fn owns_data_dir(dir: &std::path::Path) -> bool {
+    dir.file_name().is_some_and(|name| name == "ringring") || dir == home_dir().join(".claude/sounds")
+}
+
+/// Explicitly chosen data directory: `RINGRING_DATA_DIR` (also set by the
+/// global `--data-dir` flag), else a path in `config_dir()/data-dir`.
+pub fn data_dir_override() -> Option<std::path::PathBuf> {
+    if let Ok(dir) = std::env::var("RINGRING_DATA_DIR")
+        && !dir.is_empty()
+    {
+        return Some(std::path::PathBuf::from(dir));
+    }
+    let pointer = std::fs::read_to_string(config_dir().join("data-dir")).ok()?;
+    let dir = pointer.trim();
+    (!dir.is_empty()).then(|| std::path::PathBuf::from(dir))
+}
+
 pub fn data_dir() -> std::path::PathBuf {
+    if let Some(dir) = data_dir_override() {
+        return dir;
+    }
     if let Ok(base) = std::env::var("XDG_DATA_HOME")
         && !base.is_empty()
     {
@@ -75,6 +156,32 @@ mod tests {
         assert_eq!(result, std::path::PathBuf::from("/custom/config/ringring"));
     }
 
+    #[test]
+    fn state_dir_uses_xdg_when_set() {
+        unsafe { std::env::set_var("XDG_STATE_HOME", "/custom/state") };
+        let result = state_dir();
+        unsafe { std::env::remove_var("XDG_STATE_HOME") };
+        assert_eq!(result, std::path::PathBuf::from("/custom/state/ringring"));
+    }
+
+    #[test]
+    fn workspace_root_is_git_checkout() {
+        let tmp = tempfile::tempdir().unwrap();
+        let nested = tmp.path().join("repo/src/deep");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(workspace_root(&nested), nested);
+        std::fs::create_dir(tmp.path().join("repo/.git")).unwrap();
+        assert_eq!(workspace_root(&nested), tmp.path().join("repo"));
+    }
+
+    #[test]
+    fn runtime_dir_uses_xdg_when_set() {
+        unsafe { std::env::set_var("XDG_RUNTIME_DIR", "/run/user/1000") };
+        let result = runtime_dir();
+        unsafe { std::env::remove_var("XDG_RUNTIME_DIR") };
+        assert_eq!(result, std::path::PathBuf::from("/run/user/1000/ringring"));
+    }
+
     #[test]
     fn data_dir_prefers_xdg_with_data() {
         let tmp = tempfile::tempdir().unwrap();
@@ -88,6 +195,14 @@ mod tests {
         assert_eq!(result, ring_dir);
     }
 
+    #[test]
+    fn data_dir_env_override_wins() {
+        unsafe { std::env::set_var("RINGRING_DATA_DIR", "/sandbox/themes") };
+        let result = data_dir();
+        unsafe { std::env::remove_var("RINGRING_DATA_DIR") };
+        assert_eq!(result, std::path::PathBuf::from("/sandbox/themes"));
+    }
+
     #[test]
     fn data_dir_falls_back_to_legacy_when_xdg_empty() {
         // When XDG path has no data and ~/.claude/sounds exists, use legacy
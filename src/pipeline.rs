@@ -0,0 +1,754 @@
+//! The hook flow as explicit stages: parse → enrich → filter → map → pick →
+//! deliver. Parsing happens in the caller (stdin or daemon socket); the rest
+//! is `Pipeline::run`. Features that only decide whether an event goes
+//! through, or tweak what gets delivered, are `Middleware`.
+
+use std::cell::OnceCell;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::history::Outcome;
+use crate::{ambience, announce, audio, builtin, cache, config, cooldown, dedup, degraded, event, greeting, headless, locale, log, manifest, milestone, mute, notify, paths, routes, sandbox, session, suggest, threshold};
+
+/// Replacements for what events normally take from the environment, for
+/// embedding (see `RingringBuilder`). The defaults change nothing.
+#[derive(Clone, Default)]
+pub struct Overrides {
+    pub data_dir: Option<PathBuf>,
+    /// Used instead of the data dir's config.json.
+    pub config: Option<config::Config>,
+    /// Plays every category instead of the configured players.
+    pub player: Option<Arc<dyn audio::Player>>,
+    /// Receives notifications instead of the desktop/remote backends.
+    pub notifier: Option<Arc<dyn notify::Notifier + Send + Sync>>,
+    /// Leave session files, theme caches and dedup markers alone.
+    pub stateless: bool,
+    /// Parsed config and manifests kept in memory (the daemon's), used
+    /// instead of reading them from the data dir.
+    pub cache: Option<Arc<cache::Cache>>,
+}
+
+/// One event after enrichment: the config it runs under and where it came from.
+pub struct Ctx<'a> {
+    pub input: &'a event::HookInput,
+    /// Resolve everything but play, send and persist nothing.
+    pub dry_run: bool,
+    /// Whether per-session state may be written (not in dry runs or stateless engines).
+    pub persist: bool,
+    pub sounds_dir: PathBuf,
+    pub cfg: config::Config,
+    /// The session's working directory (ours when the event has none).
+    pub cwd: String,
+    pub project: String,
+    pub sound_on: bool,
+    pub notify_on: bool,
+    /// The workspace is pinned quiet: notifications only.
+    pub quiet: bool,
+    /// Why the data dir can't be read: the event runs on defaults, the
+    /// built-in theme and headless notifications.
+    pub degraded: Option<String>,
+    env: OnceCell<headless::Environment>,
+    overrides: &'a Overrides,
+}
+
+impl<'a> Ctx<'a> {
+    fn new(input: &'a event::HookInput, dry_run: bool, overrides: &'a Overrides) -> Self {
+        let sounds_dir = overrides.data_dir.clone().unwrap_or_else(paths::data_dir);
+        let (cfg, degraded) = match (&overrides.config, &overrides.cache) {
+            (Some(cfg), _) => (cfg.clone(), None),
+            (None, Some(cache)) => cache.config(),
+            (None, None) => match degraded::problem(&sounds_dir) {
+                Some(reason) => (config::Config { headless: Some(true), ..Default::default() }, Some(reason)),
+                None => (config::Config::load(&sounds_dir), None),
+            },
+        };
+        let cwd = input.cwd.clone().unwrap_or_else(|| {
+            std::env::current_dir().unwrap_or_default().to_string_lossy().into_owned()
+        });
+        let project = session::project_name(&cwd).to_string();
+        let quiet = cfg.workspace_quiet(&cwd);
+        let muted = mute::active(log::now_secs());
+        Self {
+            input,
+            dry_run,
+            persist: !dry_run && !overrides.stateless,
+            sound_on: cfg.sound_enabled() && !quiet && muted.is_none(),
+            quiet,
+            degraded,
+            notify_on: cfg.notifications_enabled() && !muted.is_some_and(|m| m.all),
+            sounds_dir,
+            cfg,
+            cwd,
+            project,
+            env: OnceCell::new(),
+            overrides,
+        }
+    }
+
+    pub fn event(&self) -> &str {
+        &self.input.hook_event_name
+    }
+
+    /// Display/audio availability, probed on first use.
+    pub fn env(&self) -> &headless::Environment {
+        self.env.get_or_init(|| headless::detect(self.cfg.headless))
+    }
+
+    pub fn player_for(&self, category: &str) -> Arc<dyn audio::Player> {
+        match &self.overrides.player {
+            Some(player) => Arc::clone(player),
+            None => audio::player_for(&self.cfg, category).into(),
+        }
+    }
+
+    /// Without an audio device, opening the output stream can only fail.
+    pub fn can_play(&self, player: &dyn audio::Player) -> bool {
+        self.sound_on && (self.env().audio || !player.needs_device())
+    }
+
+    fn resolver(&self) -> config::ThemeResolver<'_> {
+        config::ThemeResolver {
+            sounds_dir: &self.sounds_dir,
+            config: &self.cfg,
+            session_id: &self.input.session_id,
+            cwd: self.cwd.clone(),
+        }
+    }
+}
+
+/// The theme an event plays from.
+pub struct Theme {
+    pub name: String,
+    pub manifest: manifest::Manifest,
+    pub sounds: builtin::Sounds,
+}
+
+impl Theme {
+    /// Built-in `text` in the theme's language, if it declares one that has them.
+    fn text(&self, text: Option<locale::Text>) -> Option<(&'static str, &'static str)> {
+        locale::text(self.manifest.language.as_deref()?, text?)
+    }
+
+    /// `category`'s own notification icon, for an installed theme.
+    fn icon(&self, category: &str) -> Option<PathBuf> {
+        let builtin::Sounds::Dir(sounds) = &self.sounds else { return None };
+        self.manifest.icon(sounds.parent()?, category)
+    }
+
+    fn color(&self, category: &str) -> Option<&str> {
+        self.manifest.categories.get(category)?.color.as_deref()
+    }
+
+    /// The manifest's volume scaled by the configured master and per theme volume.
+    pub fn volume(&self, cfg: &config::Config) -> f32 {
+        self.manifest.volume * cfg.volume_scale(&self.name)
+    }
+}
+
+/// What the deliver stage is about to do. Middleware may rewrite it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delivery {
+    pub category: Option<String>,
+    pub title: String,
+    pub body: String,
+    /// Whether to send a notification at all.
+    pub notify: bool,
+    /// Sound file (relative to the theme's sounds/ dir) to play.
+    pub sound: Option<String>,
+}
+
+pub enum Flow {
+    Continue,
+    /// Drop the event, recording why.
+    Skip(String),
+}
+
+/// A pluggable step. `filter` runs in order after enrichment and can stop
+/// the event; `shape` runs after a sound and text were picked.
+pub trait Middleware {
+    fn filter(&self, _ctx: &Ctx) -> Flow {
+        Flow::Continue
+    }
+
+    fn shape(&self, _ctx: &Ctx, _delivery: &mut Delivery) {}
+}
+
+pub struct Pipeline {
+    middleware: Vec<Box<dyn Middleware + Send + Sync>>,
+    overrides: Overrides,
+}
+
+impl Pipeline {
+    /// The middleware every hook event goes through, in order.
+    pub fn standard() -> Self {
+        Self::with_overrides(Overrides::default())
+    }
+
+    pub fn with_overrides(overrides: Overrides) -> Self {
+        Self {
+            overrides,
+            middleware: vec![
+                Box::new(EventEnabled),
+                Box::new(TrackSession),
+                Box::new(OutputSwitches),
+                Box::new(Muted),
+                Box::new(Dedup),
+                Box::new(Cooldown),
+                Box::new(Badges),
+                Box::new(UnseenCount),
+            ],
+        }
+    }
+
+    /// Add middleware after the standard ones.
+    pub fn push(&mut self, middleware: Box<dyn Middleware + Send + Sync>) {
+        self.middleware.push(middleware);
+    }
+
+    pub fn run(&self, input: &event::HookInput, dry_run: bool) -> Result<Outcome, Box<dyn std::error::Error>> {
+        let ctx = Ctx::new(input, dry_run, &self.overrides);
+        if ctx.persist {
+            if let Some(reason) = &ctx.degraded {
+                log::write(&format!("degraded: {reason}; using the built-in theme and terminal notifications"));
+            }
+            degraded::record(ctx.degraded.as_deref());
+        }
+        for m in &self.middleware {
+            if let Flow::Skip(reason) = m.filter(&ctx) {
+                return Ok(Outcome::skipped(reason));
+            }
+        }
+
+        let action = event::map_event(input);
+        let wanted: Vec<&str> = action.category.as_deref().into_iter().chain([milestone::CATEGORY]).collect();
+        let (theme, source) = resolve_theme(&ctx, &wanted);
+        let mut outcome = Outcome { theme: Some(theme.name.clone()), theme_source: Some(source), ..Default::default() };
+
+        if audio::in_process() && ctx.persist {
+            let rodio = audio::player(&ctx.cfg);
+            let sound_ok = rodio.name() == "rodio" && ctx.can_play(&*rodio);
+            update_ambience(input, &theme, ctx.cfg.playback_limits.as_ref(), sound_ok);
+        }
+
+        if ctx.event() == "SessionStart" {
+            return greet(&ctx, &theme, outcome);
+        }
+
+        outcome.category = action.category.clone();
+        let mut delivery = pick(&ctx, &theme, action);
+        celebrate(&ctx, &theme, &mut delivery);
+        for m in &self.middleware {
+            m.shape(&ctx, &mut delivery);
+        }
+        deliver(&ctx, &theme, &delivery, &mut outcome);
+        if let Some(category) = &delivery.category {
+            alert_thresholds(&ctx, category);
+        }
+        Ok(outcome)
+    }
+}
+
+/// Run one hook event through the standard pipeline.
+pub fn handle(input: &event::HookInput, dry_run: bool) -> Result<Outcome, Box<dyn std::error::Error>> {
+    Pipeline::standard().run(input, dry_run)
+}
+
+/// Events switched off in config.json.
+struct EventEnabled;
+
+impl Middleware for EventEnabled {
+    fn filter(&self, ctx: &Ctx) -> Flow {
+        if ctx.cfg.event_enabled(ctx.event()) { Flow::Continue } else { Flow::Skip("event disabled".into()) }
+    }
+}
+
+/// Session state is tracked even when both outputs are off, for `pending`.
+struct TrackSession;
+
+impl Middleware for TrackSession {
+    fn filter(&self, ctx: &Ctx) -> Flow {
+        if ctx.persist {
+            let id = &ctx.input.session_id;
+            if ctx.event() == "SessionEnd" {
+                session::remove(id);
+            } else {
+                session::touch(id, &ctx.cwd, ctx.event(), event::attention_reason(ctx.input));
+            }
+        }
+        Flow::Continue
+    }
+}
+
+/// Global switches: bail out before any theme work if both outputs are off.
+struct OutputSwitches;
+
+impl Middleware for OutputSwitches {
+    fn filter(&self, ctx: &Ctx) -> Flow {
+        if ctx.sound_on || ctx.notify_on {
+            Flow::Continue
+        } else {
+            Flow::Skip("sound and notifications disabled".into())
+        }
+    }
+}
+
+struct Muted;
+
+impl Middleware for Muted {
+    fn filter(&self, ctx: &Ctx) -> Flow {
+        if !session::is_muted(&ctx.input.session_id) {
+            return Flow::Continue;
+        }
+        if audio::in_process() {
+            ambience::stop(&ctx.input.session_id);
+        }
+        Flow::Skip("session muted".into())
+    }
+}
+
+/// Claude occasionally fires the same `Notification` twice in a row.
+struct Dedup;
+
+impl Middleware for Dedup {
+    fn filter(&self, ctx: &Ctx) -> Flow {
+        if ctx.persist && ctx.event() == "Notification" && dedup::is_repeat(ctx.input) {
+            Flow::Skip("duplicate event".into())
+        } else {
+            Flow::Continue
+        }
+    }
+}
+
+/// Per category cooldowns from config.json `cooldowns`.
+struct Cooldown;
+
+impl Middleware for Cooldown {
+    fn filter(&self, ctx: &Ctx) -> Flow {
+        if !ctx.persist {
+            return Flow::Continue;
+        }
+        let Some(category) = event::map_event(ctx.input).category else {
+            return Flow::Continue;
+        };
+        match ctx.cfg.cooldown(&category) {
+            Some(cooldown) if cooldown::cooling(&category, cooldown) => Flow::Skip(format!("{category} cooling down")),
+            _ => Flow::Continue,
+        }
+    }
+}
+
+/// Per category title prefixes from config.json `badges`.
+struct Badges;
+
+impl Middleware for Badges {
+    fn shape(&self, ctx: &Ctx, delivery: &mut Delivery) {
+        if let Some(category) = &delivery.category {
+            delivery.title = ctx.cfg.badged_title(category, &delivery.title);
+        }
+    }
+}
+
+/// Prefix the title with the number of notifications since the user last
+/// typed a prompt, e.g. "[3] ", so a replaced notification still shows
+/// how many came before it. The first one goes out without a count.
+struct UnseenCount;
+
+impl Middleware for UnseenCount {
+    fn shape(&self, ctx: &Ctx, delivery: &mut Delivery) {
+        if !ctx.persist || !delivery.notify || ctx.input.session_id.is_empty() {
+            return;
+        }
+        let unseen = session::bump_unseen(&ctx.input.session_id);
+        if unseen > 1 {
+            delivery.title = format!("[{unseen}] {}", delivery.title);
+        }
+    }
+}
+
+/// Resolve the session's theme, reading only the `wanted` split categories
+/// from disk, and say why it was chosen. A missing theme falls back to the
+/// built-in one so zero-config setups still work.
+fn resolve_theme(ctx: &Ctx, wanted: &[&str]) -> (Theme, String) {
+    if let Some(reason) = &ctx.degraded {
+        let theme = Theme { name: builtin::NAME.to_string(), manifest: builtin::manifest(), sounds: builtin::Sounds::Embedded };
+        return (theme, format!("degraded: {reason}"));
+    }
+    let resolver = ctx.resolver();
+    let (name, source) = resolver.resolve_with_source();
+    let mut source = source.to_string();
+    if ctx.persist {
+        resolver.persist_session_theme(&name);
+    }
+    let loaded = match &ctx.overrides.cache {
+        Some(cache) => cache.theme(&name),
+        None => builtin::load_theme_categories(&ctx.sounds_dir, &name, wanted),
+    };
+    let theme = match loaded {
+        Some((manifest, sounds)) => Theme { name, manifest, sounds },
+        None => {
+            source = format!("{source}; '{name}' is not installed");
+            Theme { name: builtin::NAME.to_string(), manifest: builtin::manifest(), sounds: builtin::Sounds::Embedded }
+        }
+    };
+    if ctx.persist && ctx.event() != "SessionEnd" {
+        session::record_theme(&ctx.input.session_id, &theme.name);
+    }
+    (theme, source)
+}
+
+/// Choose the sound and the notification text for a mapped event.
+fn pick(ctx: &Ctx, theme: &Theme, action: event::EventAction) -> Delivery {
+    let notify = ctx.notify_on && !action.skip_notify;
+    let (fallback_title, fallback_body) = match theme.text(action.text) {
+        Some((title, body)) => (title.to_string(), body.to_string()),
+        None => (action.title, action.body),
+    };
+    let Some(category) = action.category else {
+        return Delivery { category: None, title: fallback_title, body: fallback_body, notify, sound: None };
+    };
+    // Repeated prompts escalate through the category's variants
+    let waited = session::load(&ctx.input.session_id).map_or(Duration::ZERO, |s| s.waiting(log::now_secs()));
+    let pick = manifest::pick_sound_after(&theme.manifest, &category, waited);
+    let (cat_title, cat_body) = manifest::category_text(&theme.manifest, &category);
+    let title = cat_title.unwrap_or(fallback_title);
+    let body = pick.as_ref().and_then(|p| p.line.clone()).or(cat_body).unwrap_or(fallback_body);
+    let sound = pick.filter(|p| roll_chance(ctx, theme, &category, p)).map(|p| p.file);
+    Delivery { category: Some(category), title, body, notify, sound }
+}
+
+/// Whether a picked sound plays: config.json `chances` for the category (else
+/// the theme's category `chance`) times the sound's own `chance`. Anything
+/// short of certain is rolled and the result logged.
+fn roll_chance(ctx: &Ctx, theme: &Theme, category: &str, pick: &manifest::SoundPick) -> bool {
+    let category_chance = ctx.cfg.chances.get(category).copied().or_else(|| theme.manifest.categories.get(category)?.chance);
+    let chance = (category_chance.unwrap_or(1.0) * pick.chance.unwrap_or(1.0)).clamp(0.0, 1.0);
+    if chance >= 1.0 {
+        return true;
+    }
+    let plays = rand::random::<f32>() < chance;
+    let verdict = if plays { "plays" } else { "stays silent" };
+    log::write(&format!("chance: {category} {} {verdict} ({:.0}% chance)", pick.file, chance * 100.0));
+    plays
+}
+
+/// A `complete` event that reaches one of the config `milestones` counts
+/// comes with a celebration: the theme's `milestone` sound (when it has
+/// one, regardless of `chance`) and a notification saying so. Dry runs
+/// and stateless engines leave no history to count, so they never do.
+fn celebrate(ctx: &Ctx, theme: &Theme, delivery: &mut Delivery) {
+    let Some(cfg) = &ctx.cfg.milestones else {
+        return;
+    };
+    if !ctx.persist {
+        return;
+    }
+    if delivery.category.as_deref() != Some("complete") {
+        return;
+    }
+    let Some(count) = milestone::reached(cfg, log::now_secs()) else {
+        return;
+    };
+    log::write(&format!("milestone: {count} completed today"));
+    let (title, _) = manifest::category_text(&theme.manifest, milestone::CATEGORY);
+    delivery.category = Some(milestone::CATEGORY.into());
+    delivery.title = title.unwrap_or_else(|| theme.text(Some(locale::Text::Milestone)).map_or("Milestone", |(t, _)| t).to_string());
+    delivery.body = cfg.message(count);
+    if let Some(pick) = manifest::pick_sound(&theme.manifest, milestone::CATEGORY) {
+        delivery.sound = Some(pick.file);
+    }
+}
+
+fn deliver(ctx: &Ctx, theme: &Theme, delivery: &Delivery, outcome: &mut Outcome) {
+    if delivery.notify {
+        if !ctx.dry_run {
+            send(ctx, Some(theme), delivery.category.as_deref(), &delivery.title, &delivery.body);
+        }
+        outcome.notified = true;
+    }
+
+    if let (Some(category), Some(file)) = (&delivery.category, &delivery.sound) {
+        let player = ctx.player_for(category);
+        if ctx.can_play(&*player) {
+            if !ctx.dry_run {
+                let _duck = ambience::duck();
+                let stingers = builtin::Stingers::resolve(&ctx.sounds_dir, &ctx.cfg, &theme.manifest, &theme.sounds);
+                let speech = ctx.cfg.announce.as_ref().and_then(|cfg| announce::for_project(cfg, &ctx.project));
+                let _ = match speech {
+                    Some(speech) => {
+                        let announced = announce::Announced { speech, inner: &*player };
+                        theme.sounds.play(&announced, file, &stingers, theme.volume(&ctx.cfg))
+                    }
+                    None => theme.sounds.play(&*player, file, &stingers, theme.volume(&ctx.cfg)),
+                };
+            }
+            outcome.sound = Some(file.clone());
+        }
+    }
+}
+
+/// Notify through the override notifier or the configured backends, as the
+/// routes allow for `category`, with the theme's icon and light color for it.
+fn send(ctx: &Ctx, theme: Option<&Theme>, category: Option<&str>, title: &str, body: &str) {
+    if let Some(notifier) = &ctx.overrides.notifier {
+        let routed = routes::first_match(&ctx.cfg.routes, &ctx.cwd, category, &paths::home_dir())
+            .is_none_or(|i| ctx.cfg.routes[i].allows(notifier.name()));
+        if routed && let Err(e) = notifier.notify(title, body) {
+            log::write(&format!("{} notification failed: {e}", notifier.name()));
+        }
+    } else {
+        // Click-to-focus needs a process that stays around to hear the click
+        let id = &ctx.input.session_id;
+        let focus = (audio::in_process() && !id.is_empty()).then(|| id.clone());
+        let mut branding = notify::Branding::from_config(&ctx.cfg);
+        let themed = theme.zip(category);
+        if let Some(icon) = themed.and_then(|(theme, category)| theme.icon(category)) {
+            branding.icon = Some(icon);
+        }
+        notify::Dispatcher::new(&ctx.cfg, branding, ctx.env(), Some(session::notification_group(&ctx.cwd)), focus)
+            .haptic(&ctx.cfg, category)
+            .light(&ctx.cfg, category, themed.and_then(|(theme, category)| theme.color(category)))
+            .controllers(&ctx.cfg, category)
+            .route(&ctx.cfg.routes, &ctx.cwd, category)
+            .send(title, body);
+    }
+}
+
+/// `meta` notifications for the `thresholds` rules this event tips over.
+fn alert_thresholds(ctx: &Ctx, category: &str) {
+    if !ctx.persist || !ctx.notify_on || !ctx.cfg.thresholds.iter().any(|rule| rule.category == category) {
+        return;
+    }
+    for message in threshold::crossed(&ctx.cfg.thresholds, category) {
+        log::write(&format!("threshold: {message}"));
+        send(ctx, None, Some(threshold::CATEGORY), &ctx.cfg.badged_title(threshold::CATEGORY, "ringring"), &message);
+    }
+}
+
+/// SessionStart: greetings are sound-only, and the daemon may offer a pin.
+fn greet(ctx: &Ctx, theme: &Theme, mut outcome: Outcome) -> Result<Outcome, Box<dyn std::error::Error>> {
+    if audio::in_process() && ctx.persist && ctx.notify_on && !ctx.env().is_headless() {
+        offer_pin(&ctx.cfg, &ctx.sounds_dir, &ctx.cwd);
+    }
+    let player = ctx.player_for("greeting");
+    if !ctx.can_play(&*player) {
+        let reason = if ctx.quiet { "greeting is sound-only and the workspace is quiet" } else { "greeting is sound-only and sound is disabled" };
+        outcome.skipped = Some(reason.into());
+        return Ok(outcome);
+    }
+    if theme.manifest.is_visual() {
+        outcome.skipped = Some(format!("greeting is sound-only and '{}' is a visual theme", theme.name));
+        return Ok(outcome);
+    }
+    outcome.category = Some("greeting".into());
+    let stingers = builtin::Stingers::resolve(&ctx.sounds_dir, &ctx.cfg, &theme.manifest, &theme.sounds);
+    outcome.sound = handle_session_start(ctx.input, theme, theme.volume(&ctx.cfg), &stingers, &*player, ctx.dry_run)?;
+    Ok(outcome)
+}
+
+/// Defer the greeting on startup (so a quick resume can cancel it), cancel a
+/// pending greeting on resume. Returns the greeting sound that was played.
+fn handle_session_start(
+    hook_input: &event::HookInput,
+    theme: &Theme,
+    volume: f32,
+    stingers: &builtin::Stingers,
+    player: &dyn audio::Player,
+    dry_run: bool,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let source_type = hook_input.source.as_deref().unwrap_or("unknown");
+    let session_id = if hook_input.session_id.is_empty() {
+        "unknown"
+    } else {
+        &hook_input.session_id
+    };
+    match source_type {
+        "startup" => {
+            // Pick sound now, move only what we need into the thread
+            let pick = manifest::pick_sound(&theme.manifest, "greeting");
+            if dry_run {
+                return Ok(pick.map(|p| p.file));
+            }
+
+            let Some(pending) = greeting::begin(session_id)? else {
+                return Ok(None);
+            };
+
+            // Deferred startup sound: sleep, then play if nothing cancelled it
+            let sounds = &theme.sounds;
+
+            let played = std::thread::scope(|scope| {
+                scope
+                    .spawn(move || {
+                        std::thread::sleep(std::time::Duration::from_secs(1));
+                        if !pending.claim() {
+                            return None;
+                        }
+                        let pick = pick?;
+                        let _duck = ambience::duck();
+                        let _ = sounds.play(player, &pick.file, stingers, volume);
+                        Some(pick.file)
+                    })
+                    .join()
+                    .ok()
+                    .flatten()
+            });
+            Ok(played)
+        }
+        "resume" => {
+            if !dry_run {
+                greeting::cancel(session_id);
+            }
+            Ok(None)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Daemon only: start the theme's ambience loop at SessionStart and stop it
+/// at Stop/SessionEnd.
+fn update_ambience(hook_input: &event::HookInput, theme: &Theme, limits: Option<&sandbox::Limits>, sound_ok: bool) {
+    match hook_input.hook_event_name.as_str() {
+        "SessionStart" if sound_ok => {
+            if let Some(amb) = &theme.manifest.ambience
+                && let builtin::Sounds::Dir(dir) = &theme.sounds
+                && let Err(e) = limits
+                    .map_or(Ok(()), |limits| sandbox::check_file(&dir.join(&amb.file), limits).map_err(Into::into))
+                    .and_then(|()| ambience::start(&hook_input.session_id, &dir.join(&amb.file), amb.volume))
+            {
+                log::write(&format!("ambience: cannot play {}: {e}", amb.file));
+            }
+        }
+        "Stop" | "SessionEnd" => {
+            ambience::stop(&hook_input.session_id);
+        }
+        _ => {}
+    }
+}
+
+/// After the same theme was picked by hand a few times in a workspace, offer
+/// (once) to pin it, via a notification action.
+fn offer_pin(cfg: &config::Config, sounds_dir: &Path, cwd: &str) {
+    let workspace = paths::workspace_root(Path::new(cwd)).to_string_lossy().into_owned();
+    let Some(theme) = suggest::due(&workspace) else {
+        return;
+    };
+    if cfg.workspace_pin(cwd).is_some_and(|(_, pinned)| pinned == theme) {
+        suggest::forget(&workspace);
+        return;
+    }
+    suggest::mark_offered(&workspace);
+    let project = session::project_name(&workspace).to_string();
+    let sounds_dir = sounds_dir.to_path_buf();
+    let accept = {
+        let (workspace, theme) = (workspace.clone(), theme.clone());
+        move || match config::write_pin(&sounds_dir, &workspace, Some(&theme)) {
+            Ok(()) => log::write(&format!("pinned '{theme}' to {workspace} from suggestion")),
+            Err(e) => log::write(&format!("pin suggestion: {e}")),
+        }
+    };
+    let body = format!("You keep choosing '{theme}' in {project}. Pin it to this workspace?");
+    if let Err(e) = notify::offer(&notify::Branding::from_config(cfg), "Pin theme?", &body, "Pin", accept) {
+        log::write(&format!("pin suggestion: {e}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Overrides that keep tests off the machine's own data dir and config.
+    fn sandboxed(dir: &tempfile::TempDir) -> Overrides {
+        Overrides { data_dir: Some(dir.path().to_path_buf()), config: Some(config::Config::default()), ..Default::default() }
+    }
+
+    struct DropAll;
+
+    impl Middleware for DropAll {
+        fn filter(&self, _ctx: &Ctx) -> Flow {
+            Flow::Skip("dropped by test".into())
+        }
+    }
+
+    #[test]
+    fn filter_middleware_stops_the_event() {
+        let input: event::HookInput = serde_json::from_str(r#"{"hook_event_name": "Stop"}"#).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let pipeline = Pipeline { middleware: vec![Box::new(DropAll)], overrides: sandboxed(&dir) };
+        assert_eq!(pipeline.run(&input, true).unwrap().skipped.as_deref(), Some("dropped by test"));
+    }
+
+    #[test]
+    fn pick_uses_category_text_and_badges_shape_it() {
+        let input: event::HookInput = serde_json::from_str(r#"{"hook_event_name": "Stop", "cwd": "/p/app"}"#).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let overrides = sandboxed(&dir);
+        let mut ctx = Ctx::new(&input, true, &overrides);
+        ctx.cfg = serde_json::from_str(r#"{"badges": {"complete": "✅"}}"#).unwrap();
+        ctx.notify_on = true;
+        let theme = Theme { name: builtin::NAME.into(), manifest: builtin::manifest(), sounds: builtin::Sounds::Embedded };
+
+        let mut delivery = pick(&ctx, &theme, event::map_event(&input));
+        assert_eq!(delivery.category.as_deref(), Some("complete"));
+        assert!(delivery.notify && delivery.sound.is_some());
+        Badges.shape(&ctx, &mut delivery);
+        assert!(delivery.title.starts_with("✅ "), "{}", delivery.title);
+    }
+
+    #[test]
+    fn zero_chance_keeps_the_notification_only() {
+        let input: event::HookInput = serde_json::from_str(r#"{"hook_event_name": "Stop", "cwd": "/p/app"}"#).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let overrides = sandboxed(&dir);
+        let mut ctx = Ctx::new(&input, true, &overrides);
+        ctx.cfg = serde_json::from_str(r#"{"chances": {"complete": 0.0}}"#).unwrap();
+        ctx.notify_on = true;
+        let theme = Theme { name: builtin::NAME.into(), manifest: builtin::manifest(), sounds: builtin::Sounds::Embedded };
+
+        let delivery = pick(&ctx, &theme, event::map_event(&input));
+        assert!(delivery.notify && delivery.sound.is_none());
+
+        ctx.cfg.chances.insert("complete".into(), 1.0);
+        assert!(pick(&ctx, &theme, event::map_event(&input)).sound.is_some());
+    }
+
+    #[test]
+    fn fallback_text_follows_the_theme_language() {
+        let input: event::HookInput = serde_json::from_str(r#"{"hook_event_name": "Stop", "cwd": "/p/app"}"#).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let overrides = sandboxed(&dir);
+        let ctx = Ctx::new(&input, true, &overrides);
+        let theme = |language: &str| {
+            let manifest = serde_json::json!({"name": "t", "display_name": "T", "language": language,
+                "categories": {"complete": {"sounds": [{"file": "a.wav"}]}}});
+            Theme { name: "t".into(), manifest: serde_json::from_value(manifest).unwrap(), sounds: builtin::Sounds::Embedded }
+        };
+
+        let delivery = pick(&ctx, &theme("cs"), event::map_event(&input));
+        assert_eq!((delivery.title.as_str(), delivery.body.as_str()), ("Hotovo", "Úkol dokončen"));
+        let delivery = pick(&ctx, &theme("en-US"), event::map_event(&input));
+        assert_eq!((delivery.title.as_str(), delivery.body.as_str()), ("Done", "Task complete"));
+        // Without built-in texts for the language, the defaults stay
+        let delivery = pick(&ctx, &theme("pl"), event::map_event(&input));
+        assert_eq!((delivery.title.as_str(), delivery.body.as_str()), ("Hotovo", "Okie dokie."));
+    }
+
+    #[test]
+    fn visual_theme_brings_text_icon_and_color_but_no_sound() {
+        let input: event::HookInput = serde_json::from_str(r#"{"hook_event_name": "Stop", "cwd": "/p/app"}"#).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let overrides = sandboxed(&dir);
+        let ctx = Ctx::new(&input, true, &overrides);
+        let manifest = serde_json::json!({"name": "v", "display_name": "V", "type": "visual", "categories": {
+            "complete": {"title": "All done", "body": "Go look", "icon": "icons/done.png", "color": "#00aa00"},
+            "permission": {"icon": "../../etc/passwd"}}});
+        let theme = Theme { name: "v".into(), manifest: serde_json::from_value(manifest).unwrap(), sounds: builtin::Sounds::Dir("/themes/v/sounds".into()) };
+
+        let delivery = pick(&ctx, &theme, event::map_event(&input));
+        assert_eq!((delivery.title.as_str(), delivery.body.as_str(), delivery.sound), ("All done", "Go look", None));
+        assert!(theme.manifest.is_visual());
+        assert_eq!(theme.icon("complete"), Some(PathBuf::from("/themes/v/icons/done.png")));
+        assert_eq!(theme.icon("permission"), None);
+        assert_eq!(theme.color("complete"), Some("#00aa00"));
+    }
+}
@@ -0,0 +1,131 @@
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+use crate::manifest::Manifest;
+
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Where `theme publish` sends themes: a registry endpoint that accepts
+/// uploads, or a GitHub repo holding the index to open a pull request on.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RegistryConfig {
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// API token for `endpoint`; `RINGRING_REGISTRY_TOKEN` takes precedence.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// `owner/name` of the index repository.
+    #[serde(default)]
+    pub repo: Option<String>,
+    #[serde(default)]
+    pub branch: Option<String>,
+}
+
+impl RegistryConfig {
+    pub fn token(&self) -> Option<String> {
+        std::env::var("RINGRING_REGISTRY_TOKEN")
+            .ok()
+            .or_else(|| self.token.clone())
+            .filter(|t| !t.is_empty())
+    }
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// The registry index entry for a packed theme. `url` is where the zip will
+/// be downloadable from, when known.
+pub fn entry(name: &str, manifest: &Manifest, zip: &[u8], url: Option<&str>) -> serde_json::Value {
+    let mut categories: Vec<&String> = manifest.categories.keys().collect();
+    categories.sort();
+    let mut entry = serde_json::json!({
+        "name": name,
+        "display_name": manifest.display_name,
+        "categories": categories,
+        "size": zip.len(),
+        "sha256": sha256_hex(zip),
+    });
+    if let Some(description) = &manifest.description {
+        entry["description"] = serde_json::Value::String(description.clone());
+    }
+    if let Some(version) = &manifest.version {
+        entry["version"] = serde_json::Value::String(version.clone());
+    }
+    if let Some(url) = url {
+        entry["url"] = serde_json::Value::String(url.to_string());
+    }
+    entry
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters.
+fn url_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// GitHub's "new file" page with the index entry filled in; committing it
+/// from a fork offers to open a pull request.
+pub fn pr_url(repo: &str, branch: &str, name: &str, entry: &serde_json::Value) -> String {
+    let body = serde_json::to_string_pretty(entry).unwrap_or_default() + "\n";
+    format!(
+        "https://github.com/{repo}/new/{branch}?filename={}&value={}",
+        url_encode(&format!("themes/{name}.json")),
+        url_encode(&body)
+    )
+}
+
+/// Upload the zip and then its index entry to a registry endpoint:
+/// `PUT {endpoint}/themes/{name}.zip` and `PUT {endpoint}/themes/{name}.json`.
+pub fn upload(endpoint: &str, token: &str, name: &str, zip: &[u8], entry: &serde_json::Value) -> Result<(), Box<dyn std::error::Error>> {
+    let agent = ureq::AgentBuilder::new().timeout(UPLOAD_TIMEOUT).build();
+    let base = format!("{}/themes/{name}", endpoint.trim_end_matches('/'));
+    let auth = format!("Bearer {token}");
+    agent
+        .put(&format!("{base}.zip"))
+        .set("Authorization", &auth)
+        .set("Content-Type", "application/zip")
+        .send_bytes(zip)?;
+    agent
+        .put(&format!("{base}.json"))
+        .set("Authorization", &auth)
+        .set("Content-Type", "application/json")
+        .send_string(&entry.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256_matches_known_digest() {
+        assert_eq!(sha256_hex(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+
+    #[test]
+    fn entry_describes_the_zip() {
+        let manifest: Manifest =
+            serde_json::from_str(r#"{"name": "x", "display_name": "Chime", "version": "1.0.0", "categories": {"greeting": {}, "complete": {}}}"#)
+                .unwrap();
+        let entry = entry("chime", &manifest, b"abc", Some("https://example.com/chime.zip"));
+        assert_eq!(entry["display_name"], "Chime");
+        assert_eq!(entry["categories"], serde_json::json!(["complete", "greeting"]));
+        assert_eq!(entry["size"], 3);
+        assert_eq!(entry["version"], "1.0.0");
+        assert!(entry.get("description").is_none());
+        assert_eq!(entry["url"], "https://example.com/chime.zip");
+    }
+
+    #[test]
+    fn pr_url_is_encoded() {
+        let url = pr_url("me/themes", "main", "chime", &serde_json::json!({"name": "chime"}));
+        assert!(url.starts_with("https://github.com/me/themes/new/main?filename=themes%2Fchime.json&value=%7B%0A"), "{url}");
+        assert!(!url.contains(' '));
+    }
+}
@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::{event, log, paths};
+
+/// Lane for events that map to no sound category.
+const UNCATEGORIZED: &str = "other";
+
+/// Which event to give up when a category's queue is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DropPolicy {
+    /// Drop the longest-waiting event; the latest state wins.
+    #[default]
+    Oldest,
+    /// Drop the event that just arrived.
+    Newest,
+}
+
+/// config.json `daemon_queue`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct QueueConfig {
+    /// Events waiting per category, not counting the one playing.
+    pub capacity: usize,
+    pub drop: DropPolicy,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self { capacity: 8, drop: DropPolicy::Oldest }
+    }
+}
+
+/// Counters for one category since the daemon started.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub queued: usize,
+    pub handled: u64,
+    pub dropped: u64,
+}
+
+#[derive(Default)]
+struct Lane {
+    pending: VecDeque<String>,
+    /// Whether a worker thread is draining this lane.
+    busy: bool,
+    stats: Stats,
+}
+
+/// Per-category FIFO queues in front of the daemon's handler. Each category
+/// plays its events one at a time on its own worker thread, which exits when
+/// the queue runs dry, so a burst costs at most one thread per category.
+pub struct Queues {
+    config: QueueConfig,
+    handler: fn(&str),
+    lanes: Mutex<BTreeMap<String, Lane>>,
+    /// Where `stats` are mirrored for `daemon status`.
+    stats_path: Option<PathBuf>,
+}
+
+impl Queues {
+    pub fn new(config: QueueConfig, handler: fn(&str)) -> Arc<Self> {
+        Arc::new(Self { config, handler, lanes: Mutex::default(), stats_path: Some(stats_path()) })
+    }
+
+    /// Queue a forwarded payload behind others of its category.
+    pub fn submit(self: &Arc<Self>, payload: String) {
+        let category = category_of(&payload);
+        let spawn = {
+            let mut lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+            let lane = lanes.entry(category.clone()).or_default();
+            if lane.pending.len() >= self.config.capacity.max(1) {
+                lane.stats.dropped += 1;
+                match self.config.drop {
+                    DropPolicy::Oldest => {
+                        log::write(&format!("daemon: {category} queue full, dropping the oldest event"));
+                        lane.pending.pop_front();
+                        lane.pending.push_back(payload);
+                    }
+                    DropPolicy::Newest => log::write(&format!("daemon: {category} queue full, dropping the new event")),
+                }
+            } else {
+                lane.pending.push_back(payload);
+            }
+            lane.stats.queued = lane.pending.len();
+            !std::mem::replace(&mut lane.busy, true)
+        };
+        self.publish();
+        if spawn {
+            let queues = Arc::clone(self);
+            std::thread::spawn(move || queues.work(&category));
+        }
+    }
+
+    fn work(&self, category: &str) {
+        loop {
+            let next = {
+                let mut lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+                let lane = lanes.entry(category.to_string()).or_default();
+                let next = lane.pending.pop_front();
+                lane.stats.queued = lane.pending.len();
+                lane.busy = next.is_some();
+                next
+            };
+            let Some(payload) = next else {
+                self.publish();
+                return;
+            };
+            (self.handler)(&payload);
+            if let Some(lane) = self.lanes.lock().unwrap_or_else(|e| e.into_inner()).get_mut(category) {
+                lane.stats.handled += 1;
+            }
+            self.publish();
+        }
+    }
+
+    /// Whether every queue is empty and nothing is playing.
+    pub fn idle(&self) -> bool {
+        self.lanes.lock().unwrap_or_else(|e| e.into_inner()).values().all(|lane| !lane.busy)
+    }
+
+    pub fn stats(&self) -> BTreeMap<String, Stats> {
+        let lanes = self.lanes.lock().unwrap_or_else(|e| e.into_inner());
+        lanes.iter().map(|(category, lane)| (category.clone(), lane.stats.clone())).collect()
+    }
+
+    fn publish(&self) {
+        if let Some(path) = &self.stats_path
+            && let Ok(json) = serde_json::to_string(&self.stats())
+        {
+            let _ = std::fs::write(path, json);
+        }
+    }
+
+    /// Remove the stats file when the daemon stops.
+    pub fn retire(&self) {
+        if let Some(path) = &self.stats_path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn stats_path() -> PathBuf {
+    paths::runtime_dir().join("daemon-queues.json")
+}
+
+/// Queue stats of the running daemon, as of its last change.
+pub fn load_stats() -> Option<BTreeMap<String, Stats>> {
+    serde_json::from_str(&std::fs::read_to_string(stats_path()).ok()?).ok()
+}
+
+/// Sound category a payload will play in; unparsable payloads go with the
+/// silent events.
+pub fn category_of(payload: &str) -> String {
+    serde_json::from_str::<event::HookInput>(payload)
+        .ok()
+        .and_then(|input| event::map_event(&input).category)
+        .unwrap_or_else(|| UNCATEGORIZED.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    static GATE: Mutex<Option<mpsc::Receiver<()>>> = Mutex::new(None);
+    static HANDLED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    /// Blocks on the gate before recording, so tests control when playback ends.
+    fn gated(payload: &str) {
+        if let Some(gate) = GATE.lock().unwrap().as_ref() {
+            let _ = gate.recv_timeout(Duration::from_secs(5));
+        }
+        HANDLED.lock().unwrap().push(payload.to_string());
+    }
+
+    fn stop(n: u32) -> String {
+        format!(r#"{{"hook_event_name": "Stop", "session_id": "{n}"}}"#)
+    }
+
+    fn wait_idle(queues: &Queues) {
+        for _ in 0..200 {
+            if queues.idle() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("queues never drained");
+    }
+
+    #[test]
+    fn full_queue_applies_drop_policy() {
+        for (drop, kept) in [(DropPolicy::Oldest, ["0", "3", "4"]), (DropPolicy::Newest, ["0", "1", "2"])] {
+            let (open, gate) = mpsc::channel();
+            *GATE.lock().unwrap() = Some(gate);
+            HANDLED.lock().unwrap().clear();
+            let queues = Arc::new(Queues {
+                config: QueueConfig { capacity: 2, drop },
+                handler: gated,
+                lanes: Mutex::default(),
+                stats_path: None,
+            });
+            // The first event starts playing (and holds the lane) while the rest queue up
+            queues.submit(stop(0));
+            std::thread::sleep(Duration::from_millis(50));
+            for n in 1..5 {
+                queues.submit(stop(n));
+            }
+            assert_eq!(queues.stats()["complete"], Stats { queued: 2, handled: 0, dropped: 2 });
+            for _ in 0..3 {
+                open.send(()).unwrap();
+            }
+            wait_idle(&queues);
+            let handled: Vec<String> = HANDLED.lock().unwrap().drain(..).collect();
+            let expected: Vec<String> = kept.iter().map(|n| stop(n.parse().unwrap())).collect();
+            assert_eq!(handled, expected, "{drop:?}");
+            assert_eq!(queues.stats()["complete"].handled, 3);
+        }
+        *GATE.lock().unwrap() = None;
+    }
+
+    #[test]
+    fn categories_come_from_the_event() {
+        assert_eq!(category_of(&stop(1)), "complete");
+        assert_eq!(category_of(r#"{"hook_event_name": "SessionEnd"}"#), "other");
+        assert_eq!(category_of("not json"), "other");
+    }
+}
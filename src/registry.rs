@@ -0,0 +1,122 @@
+//! The community theme registry: an index of every theme (`index.json`,
+//! `{"themes": [entry, ...]}`) and an entry per theme
+//! (`themes/<name>.json`, as `theme publish` writes it), at
+//! `registry.endpoint` or in `registry.repo` on GitHub. `theme search` reads
+//! the index, and `theme install <name>` resolves names through it.
+
+use serde::Deserialize;
+
+use crate::install;
+use crate::publish::RegistryConfig;
+
+/// One theme in the registry.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    #[serde(default)]
+    pub display_name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Where the zip downloads from.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// sha256 of the zip, which the download must match.
+    #[serde(default)]
+    pub sha256: Option<String>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub size: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct Index {
+    themes: Vec<Entry>,
+}
+
+/// URL of `path` in the registry.
+pub fn url(registry: &RegistryConfig, path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    match (&registry.endpoint, &registry.repo) {
+        (Some(endpoint), _) => Ok(format!("{}/{path}", endpoint.trim_end_matches('/'))),
+        (None, Some(repo)) => {
+            let branch = registry.branch.as_deref().unwrap_or("main");
+            Ok(format!("https://raw.githubusercontent.com/{repo}/{branch}/{path}"))
+        }
+        (None, None) => Err("no registry configured (set registry.endpoint or registry.repo)".into()),
+    }
+}
+
+/// Every theme in the registry's index.
+pub fn fetch_index(registry: &RegistryConfig) -> Result<Vec<Entry>, Box<dyn std::error::Error>> {
+    parse_index(&install::download(&url(registry, "index.json")?)?)
+}
+
+fn parse_index(bytes: &[u8]) -> Result<Vec<Entry>, Box<dyn std::error::Error>> {
+    Ok(serde_json::from_slice::<Index>(bytes).map_err(|e| format!("registry index.json: {e}"))?.themes)
+}
+
+/// The entry for `name`: from the index, or from `themes/<name>.json` in a
+/// registry that has no index.
+pub fn find(registry: &RegistryConfig, name: &str) -> Result<Entry, Box<dyn std::error::Error>> {
+    crate::config::validate_theme_name(name)?;
+    match fetch_index(registry) {
+        Ok(index) => index.into_iter().find(|e| e.name == name).ok_or_else(|| format!("no theme '{name}' in the registry").into()),
+        Err(_) => Ok(serde_json::from_slice(&install::download(&url(registry, &format!("themes/{name}.json"))?)?)?),
+    }
+}
+
+/// Entries with every word of `query` in their name, display name or
+/// description (ignoring case), best first: the exact name, names starting
+/// with the query, then the rest by name. An empty query matches all.
+pub fn search<'a>(entries: &'a [Entry], query: &str) -> Vec<&'a Entry> {
+    let query = query.to_lowercase();
+    let words: Vec<&str> = query.split_whitespace().collect();
+    let mut found: Vec<&Entry> = entries
+        .iter()
+        .filter(|e| {
+            let text = format!("{} {} {}", e.name, e.display_name, e.description.as_deref().unwrap_or_default()).to_lowercase();
+            words.iter().all(|w| text.contains(w))
+        })
+        .collect();
+    found.sort_by_key(|e| {
+        let name = e.name.to_lowercase();
+        (name != query, !name.starts_with(&query), e.name.clone())
+    });
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_prefers_endpoint() {
+        let mut registry = RegistryConfig { repo: Some("o/index".into()), ..Default::default() };
+        assert_eq!(url(&registry, "index.json").unwrap(), "https://raw.githubusercontent.com/o/index/main/index.json");
+        registry.endpoint = Some("https://themes.example.com/".into());
+        assert_eq!(url(&registry, "themes/chime.json").unwrap(), "https://themes.example.com/themes/chime.json");
+        assert!(url(&RegistryConfig::default(), "index.json").is_err());
+    }
+
+    #[test]
+    fn search_matches_every_word_best_first() {
+        let index = parse_index(
+            br#"{"themes": [
+                {"name": "peon-extra", "display_name": "Peon Extra", "description": "More Warcraft orcs"},
+                {"name": "chime", "display_name": "Chime", "description": "Soft bells", "version": "1.2.0"},
+                {"name": "peon", "display_name": "Warcraft Peon", "sha256": "abc", "url": "https://example.com/peon.zip"}
+            ]}"#,
+        )
+        .unwrap();
+        let names = |query: &str| search(&index, query).iter().map(|e| e.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names("peon"), ["peon", "peon-extra"]);
+        assert_eq!(names("WARCRAFT"), ["peon", "peon-extra"]);
+        assert_eq!(names("warcraft orcs"), ["peon-extra"]);
+        assert_eq!(names("bell"), ["chime"]);
+        assert_eq!(names(""), ["chime", "peon", "peon-extra"]);
+        assert_eq!(index[1].version.as_deref(), Some("1.2.0"));
+        assert!(parse_index(b"[]").unwrap_err().to_string().starts_with("registry index.json"));
+    }
+}
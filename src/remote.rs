@@ -0,0 +1,151 @@
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use crate::config::{RemoteConfig, SlackConfig, TelegramConfig, WebhookConfig};
+use crate::notify::Notifier;
+
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+fn post_json(url: &str, payload: &serde_json::Value) -> Result<(), Box<dyn std::error::Error>> {
+    ureq::AgentBuilder::new()
+        .timeout(TIMEOUT)
+        .build()
+        .post(url)
+        .set("Content-Type", "application/json")
+        .send_string(&payload.to_string())?;
+    Ok(())
+}
+
+/// Takes a failed remote send (backend, title, body) for another try.
+pub type RetryHook = Box<dyn Fn(Arc<dyn Notifier + Send + Sync>, String, String) + Send + Sync>;
+
+static RETRY: OnceLock<RetryHook> = OnceLock::new();
+
+/// Hand failed sends to `hook` from now on. The async daemon installs one;
+/// without it a failed send is final.
+#[cfg_attr(not(feature = "async"), allow(dead_code))]
+pub fn set_retry(hook: RetryHook) {
+    let _ = RETRY.set(hook);
+}
+
+/// A remote backend whose failures go to the retry hook, if any.
+struct Retrying(Arc<dyn Notifier + Send + Sync>);
+
+impl Notifier for Retrying {
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    fn notify(&self, title: &str, body: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.0.notify(title, body).inspect_err(|_| {
+            if let Some(retry) = RETRY.get() {
+                retry(Arc::clone(&self.0), title.to_string(), body.to_string());
+            }
+        })
+    }
+}
+
+/// Build a notifier for every configured remote backend.
+pub fn backends(cfg: &RemoteConfig) -> Vec<Box<dyn Notifier>> {
+    let mut out: Vec<Arc<dyn Notifier + Send + Sync>> = Vec::new();
+    if let Some(slack) = &cfg.slack {
+        out.push(Arc::new(Slack::from(slack)));
+    }
+    if let Some(telegram) = &cfg.telegram {
+        out.push(Arc::new(Telegram::from(telegram)));
+    }
+    if let Some(webhook) = &cfg.webhook {
+        out.push(Arc::new(Webhook::from(webhook)));
+    }
+    out.into_iter().map(|backend| Box::new(Retrying(backend)) as Box<dyn Notifier>).collect()
+}
+
+/// Slack incoming webhook.
+pub struct Slack {
+    webhook_url: String,
+}
+
+impl From<&SlackConfig> for Slack {
+    fn from(cfg: &SlackConfig) -> Self {
+        Self { webhook_url: cfg.webhook_url.clone() }
+    }
+}
+
+impl Notifier for Slack {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    fn notify(&self, title: &str, body: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let text = format!("*{title}*\n{body}");
+        post_json(&self.webhook_url, &serde_json::json!({ "text": text }))
+    }
+}
+
+/// Telegram bot `sendMessage`.
+pub struct Telegram {
+    bot_token: String,
+    chat_id: String,
+}
+
+impl From<&TelegramConfig> for Telegram {
+    fn from(cfg: &TelegramConfig) -> Self {
+        Self { bot_token: cfg.bot_token.clone(), chat_id: cfg.chat_id.clone() }
+    }
+}
+
+impl Notifier for Telegram {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    fn notify(&self, title: &str, body: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", self.bot_token);
+        post_json(&url, &serde_json::json!({
+            "chat_id": self.chat_id,
+            "text": format!("{title}\n{body}"),
+        }))
+    }
+}
+
+/// Generic JSON webhook: POSTs `{"title": ..., "body": ...}`.
+pub struct Webhook {
+    url: String,
+}
+
+impl From<&WebhookConfig> for Webhook {
+    fn from(cfg: &WebhookConfig) -> Self {
+        Self { url: cfg.url.clone() }
+    }
+}
+
+impl Notifier for Webhook {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn notify(&self, title: &str, body: &str) -> Result<(), Box<dyn std::error::Error>> {
+        post_json(&self.url, &serde_json::json!({ "title": title, "body": body }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_backends_when_unconfigured() {
+        assert!(backends(&RemoteConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn backends_follow_config() {
+        let cfg: RemoteConfig = serde_json::from_str(
+            r#"{"slack": {"webhook_url": "https://hooks.slack.test/x"},
+                "webhook": {"url": "http://localhost:9/notify"}}"#,
+        )
+        .unwrap();
+        let names: Vec<_> = backends(&cfg).iter().map(|b| b.name()).collect();
+        assert_eq!(names, vec!["slack", "webhook"]);
+    }
+}
@@ -0,0 +1,225 @@
+use serde_json::Value;
+use std::path::{Component, Path, PathBuf};
+
+/// A config.json with the stale and duplicate entries taken out.
+pub struct Repair {
+    pub fixed: Value,
+    /// One line per problem found, in the order they were fixed.
+    pub problems: Vec<String>,
+}
+
+/// Check a raw config.json. `theme_installed` and `dir_exists` are the only
+/// outside facts needed; unknown fields are left alone.
+pub fn check(root: &Value, home: &Path, theme_installed: impl Fn(&str) -> bool, dir_exists: impl Fn(&Path) -> bool) -> Repair {
+    let mut fixed = root.clone();
+    let mut problems = Vec::new();
+
+    if let Some(pins) = root["workspaces"].as_object() {
+        let mut kept = serde_json::Map::new();
+        for (dir, theme) in pins {
+            let normalized = normalize(dir, home);
+            let key = normalized.to_string_lossy().into_owned();
+            if !dir_exists(&normalized) {
+                problems.push(format!("workspace pin {dir} points at a missing directory"));
+            } else if kept.contains_key(&key) {
+                problems.push(format!("workspace pin {dir} duplicates {key}"));
+            } else {
+                if key != *dir {
+                    problems.push(format!("workspace pin {dir} normalized to {key}"));
+                }
+                kept.insert(key, theme.clone());
+            }
+        }
+        fixed["workspaces"] = Value::Object(kept);
+    }
+
+    if let Some(pool) = root["random_pool"].as_array() {
+        let mut kept: Vec<Value> = Vec::new();
+        for entry in pool {
+            let Some(theme) = entry.as_str() else {
+                problems.push(format!("random_pool entry {entry} is not a theme name"));
+                continue;
+            };
+            if kept.iter().any(|k| k.as_str() == Some(theme)) {
+                problems.push(format!("random_pool lists '{theme}' more than once"));
+            } else if !theme_installed(theme) {
+                problems.push(format!("random_pool theme '{theme}' is not installed"));
+            } else {
+                kept.push(entry.clone());
+            }
+        }
+        fixed["random_pool"] = Value::Array(kept);
+    }
+
+    Repair { fixed, problems }
+}
+
+/// Expand `~`, drop `.` components, duplicate and trailing slashes. Purely
+/// lexical: symlinks are left as they are, since pins match the reported cwd.
+pub fn normalize(dir: &str, home: &Path) -> PathBuf {
+    let expanded = match dir.strip_prefix("~") {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => home.join(rest.trim_start_matches('/')),
+        _ => PathBuf::from(dir),
+    };
+    let mut out = PathBuf::new();
+    for part in expanded.components() {
+        match part {
+            Component::CurDir => {}
+            Component::ParentDir if out.parent().is_some() => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// One line of an edit script.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Turn `old` into `new` line by line, keeping a longest common subsequence.
+fn edits<'a>(old: &'a str, new: &'a str) -> Vec<Edit<'a>> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    // Longest common subsequence table, filled from the end
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+    let (mut i, mut j, mut out) = (0, 0, Vec::new());
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            out.push(Edit::Same(a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            out.push(Edit::Removed(a[i]));
+            i += 1;
+        } else {
+            out.push(Edit::Added(b[j]));
+            j += 1;
+        }
+    }
+    out
+}
+
+/// Changed lines between two texts, prefixed with `-` or `+`.
+pub fn diff(old: &str, new: &str) -> Vec<String> {
+    edits(old, new)
+        .into_iter()
+        .filter_map(|edit| match edit {
+            Edit::Same(_) => None,
+            Edit::Removed(line) => Some(format!("-{line}")),
+            Edit::Added(line) => Some(format!("+{line}")),
+        })
+        .collect()
+}
+
+/// Unchanged lines shown around each change in `unified_diff`.
+const CONTEXT: usize = 3;
+
+/// `diff -u` style output, labelled `from` and `to`. Empty when the texts
+/// have the same lines.
+pub fn unified_diff(old: &str, new: &str, from: &str, to: &str) -> Vec<String> {
+    let edits = edits(old, new);
+    let changed: Vec<usize> = (0..edits.len()).filter(|&k| !matches!(edits[k], Edit::Same(_))).collect();
+    if changed.is_empty() {
+        return Vec::new();
+    }
+    // Hunks as edit ranges, merged when their context would touch
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &k in &changed {
+        let (start, end) = (k.saturating_sub(CONTEXT), (k + CONTEXT + 1).min(edits.len()));
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = vec![format!("--- {from}"), format!("+++ {to}")];
+    let counts = |range: &[Edit]| {
+        let old = range.iter().filter(|e| !matches!(e, Edit::Added(_))).count();
+        let new = range.iter().filter(|e| !matches!(e, Edit::Removed(_))).count();
+        (old, new)
+    };
+    for (start, end) in hunks {
+        let (old_before, new_before) = counts(&edits[..start]);
+        let (old_len, new_len) = counts(&edits[start..end]);
+        // An empty side names the line before it, as diff does
+        let old_start = if old_len == 0 { old_before } else { old_before + 1 };
+        let new_start = if new_len == 0 { new_before } else { new_before + 1 };
+        out.push(format!("@@ -{old_start},{old_len} +{new_start},{new_len} @@"));
+        out.extend(edits[start..end].iter().map(|edit| match edit {
+            Edit::Same(line) => format!(" {line}"),
+            Edit::Removed(line) => format!("-{line}"),
+            Edit::Added(line) => format!("+{line}"),
+        }));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn stale_and_duplicate_entries_removed() {
+        let root = json!({
+            "theme": "peon",
+            "workspaces": {"/p/app/": "peon", "/p/app": "icq", "/p/gone": "peon", "~/src": "aoe2"},
+            "random_pool": ["peon", "missing", "peon", 3],
+        });
+        let repair = check(&root, Path::new("/home/u"), |t| t == "peon", |d| d != Path::new("/p/gone"));
+        // Keys are visited in sorted order, so the exact "/p/app" pin wins
+        assert_eq!(repair.fixed["workspaces"], json!({"/p/app": "icq", "/home/u/src": "aoe2"}));
+        assert_eq!(repair.fixed["random_pool"], json!(["peon"]));
+        assert_eq!(repair.fixed["theme"], "peon");
+        assert_eq!(repair.problems.len(), 6, "{:?}", repair.problems);
+    }
+
+    #[test]
+    fn clean_config_has_no_problems() {
+        let root = json!({"workspaces": {"/p/app": "peon"}, "random_pool": ["peon"]});
+        let repair = check(&root, Path::new("/home/u"), |_| true, |_| true);
+        assert!(repair.problems.is_empty());
+        assert_eq!(repair.fixed, root);
+    }
+
+    #[test]
+    fn normalize_is_lexical() {
+        let home = Path::new("/home/u");
+        assert_eq!(normalize("/a//b/./c/", home), PathBuf::from("/a/b/c"));
+        assert_eq!(normalize("/a/b/../c", home), PathBuf::from("/a/c"));
+        assert_eq!(normalize("~", home), PathBuf::from("/home/u"));
+        assert_eq!(normalize("~bob/x", home), PathBuf::from("~bob/x"));
+    }
+
+    #[test]
+    fn diff_marks_changed_lines() {
+        assert_eq!(diff("a\nb\nc", "a\nc\nd"), vec!["-b", "+d"]);
+        assert!(diff("same", "same").is_empty());
+    }
+
+    #[test]
+    fn unified_diff_has_hunks_with_context() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12";
+        let new = "1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13";
+        assert_eq!(
+            unified_diff(old, new, "a", "b"),
+            vec![
+                "--- a", "+++ b", "@@ -1,6 +1,6 @@", " 1", " 2", "-3", "+three", " 4", " 5", " 6", "@@ -10,3 +10,4 @@", " 10", " 11", " 12",
+                "+13",
+            ]
+        );
+        assert_eq!(unified_diff("", "x", "a", "b")[2], "@@ -0,0 +1,1 @@");
+        assert!(unified_diff("same", "same", "a", "b").is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+use serde::Deserialize;
+use std::path::Path;
+
+/// Backend names the `local` and `remote` groups stand for.
+const LOCAL: &[&str] = &["desktop", "terminal", "log", "haptic", "light", "streamdeck", "midi"];
+const REMOTE: &[&str] = &["slack", "telegram", "webhook"];
+
+/// A config.json `routes` rule: where a notification may go for events from
+/// matching workspaces and categories. Rules are tried in order and the
+/// first match decides; with no match every backend gets it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Route {
+    /// Glob on the session's cwd: `*` matches within one path component,
+    /// `**` any number of them, a leading `~` is the home directory.
+    #[serde(default)]
+    pub workspace: Option<String>,
+    /// Categories the rule applies to; all when empty.
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub category: Vec<String>,
+    /// Backend names, or the groups `local` and `remote`. Empty sends nothing.
+    #[serde(default)]
+    pub backends: Vec<String>,
+}
+
+fn one_or_many<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(d)? {
+        OneOrMany::One(s) => vec![s],
+        OneOrMany::Many(v) => v,
+    })
+}
+
+impl Route {
+    pub fn matches(&self, cwd: &str, category: Option<&str>, home: &Path) -> bool {
+        let workspace_ok = self.workspace.as_deref().is_none_or(|pattern| {
+            let pattern = match pattern.strip_prefix('~') {
+                Some(rest) => format!("{}{rest}", home.display()),
+                None => pattern.to_string(),
+            };
+            glob_match(&pattern, cwd)
+        });
+        let category_ok = self.category.is_empty() || category.is_some_and(|c| self.category.iter().any(|r| r == c));
+        workspace_ok && category_ok
+    }
+
+    pub fn allows(&self, backend: &str) -> bool {
+        self.backends.iter().any(|allowed| match allowed.as_str() {
+            "local" => LOCAL.contains(&backend),
+            "remote" => REMOTE.contains(&backend),
+            name => name == backend,
+        })
+    }
+}
+
+/// Index of the first rule matching an event, if any.
+pub fn first_match(routes: &[Route], cwd: &str, category: Option<&str>, home: &Path) -> Option<usize> {
+    routes.iter().position(|route| route.matches(cwd, category, home))
+}
+
+/// Match `path` against `pattern` component by component.
+fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').filter(|c| !c.is_empty()).collect();
+    let path: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+    match_components(&pattern, &path)
+}
+
+fn match_components(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| match_components(rest, &path[skip..])),
+        Some((first, rest)) => {
+            path.split_first().is_some_and(|(component, path)| match_component(first, component) && match_components(rest, path))
+        }
+    }
+}
+
+/// `*` wildcards within a single component.
+fn match_component(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            let Some(text) = text.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=text.len()).filter(|&i| text.is_char_boundary(i)).any(|i| match_component(rest, &text[i..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn routes(json: &str) -> Vec<Route> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn globs() {
+        assert!(glob_match("/home/u/oss/**", "/home/u/oss"));
+        assert!(glob_match("/home/u/oss/**", "/home/u/oss/lib/src"));
+        assert!(!glob_match("/home/u/oss/**", "/home/u/ossify"));
+        assert!(glob_match("/home/u/*/app", "/home/u/work/app"));
+        assert!(!glob_match("/home/u/*/app", "/home/u/work/x/app"));
+        assert!(glob_match("/src/ring*", "/src/ringring"));
+        assert!(glob_match("**/scratch-*/**", "/tmp/scratch-42/x"));
+    }
+
+    #[test]
+    fn first_matching_rule_decides() {
+        let rules = routes(
+            r#"[{"workspace": "~/oss/**", "category": "complete", "backends": ["slack", "local"]},
+                {"workspace": "~/oss/**", "backends": ["local"]},
+                {"workspace": "~/work/**", "backends": []}]"#,
+        );
+        let home = Path::new("/home/u");
+        assert_eq!(first_match(&rules, "/home/u/oss/lib", Some("complete"), home), Some(0));
+        assert_eq!(first_match(&rules, "/home/u/oss/lib", Some("permission"), home), Some(1));
+        assert_eq!(first_match(&rules, "/home/u/work/mono", Some("complete"), home), Some(2));
+        assert_eq!(first_match(&rules, "/home/u/misc", Some("complete"), home), None);
+
+        assert!(rules[0].allows("slack") && rules[0].allows("desktop") && !rules[0].allows("webhook"));
+        assert!(!rules[1].allows("slack"));
+        assert!(!rules[2].allows("desktop"));
+    }
+}
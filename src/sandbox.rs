@@ -0,0 +1,159 @@
+use rodio::{Decoder, Source};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// File extensions a theme sound may have when limits are on.
+pub const ALLOWED_EXTENSIONS: &[&str] = &["wav", "mp3", "ogg", "flac"];
+
+/// Resource limits for sounds from themes you don't fully trust, so a
+/// crafted file can't tie up the hook path or the daemon.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    /// Largest sound file accepted, in bytes.
+    pub max_file_bytes: u64,
+    /// Longest playback; anything past it is cut off (and fails `theme check`).
+    pub max_seconds: f32,
+    /// How long opening (or, for `theme check`, fully decoding) a file may take.
+    pub decode_timeout_ms: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self { max_file_bytes: 10 * 1024 * 1024, max_seconds: 30.0, decode_timeout_ms: 2000 }
+    }
+}
+
+impl Limits {
+    fn timeout(&self) -> Duration {
+        Duration::from_millis(self.decode_timeout_ms)
+    }
+}
+
+/// Cheap checks that need no decoding: extension allowlist and file size.
+pub fn check_file(path: &Path, limits: &Limits) -> Result<(), String> {
+    let ext = path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).unwrap_or_default();
+    if !ALLOWED_EXTENSIONS.contains(&ext.as_str()) {
+        return Err(format!("file type '{ext}' is not allowed (use {})", ALLOWED_EXTENSIONS.join(", ")));
+    }
+    let size = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+    if size > limits.max_file_bytes {
+        return Err(format!("{size} bytes is over the {} byte limit", limits.max_file_bytes));
+    }
+    Ok(())
+}
+
+/// Run `f` on a helper thread, giving up after `timeout`. A decoder stuck on
+/// a hostile file is left behind; hook-mode players exit right after anyway.
+fn with_timeout<T: Send + 'static>(
+    timeout: Duration,
+    f: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(f());
+    });
+    rx.recv_timeout(timeout)
+        .map_err(|_| format!("decoding took longer than {} ms", timeout.as_millis()))?
+}
+
+/// Open a sound for playback within `limits`: checked, opened under the
+/// decode timeout and cut off after `max_seconds`.
+pub fn open(path: &Path, limits: &Limits) -> Result<Box<dyn Source + Send>, Box<dyn std::error::Error>> {
+    check_file(path, limits)?;
+    let owned = path.to_path_buf();
+    let decoder = with_timeout(limits.timeout(), move || {
+        let file = File::open(&owned).map_err(|e| e.to_string())?;
+        Decoder::new(BufReader::new(file)).map_err(|e| e.to_string())
+    })?;
+    Ok(Box::new(decoder.take_duration(Duration::from_secs_f32(limits.max_seconds.max(0.0)))))
+}
+
+/// Decode a whole file within `limits`, for `theme check`. Returns its length.
+pub fn validate(path: &Path, limits: &Limits) -> Result<Duration, String> {
+    check_file(path, limits)?;
+    let owned = path.to_path_buf();
+    let limits = *limits;
+    with_timeout(limits.timeout(), move || {
+        let file = File::open(&owned).map_err(|e| e.to_string())?;
+        let decoder = Decoder::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+        let per_second = decoder.sample_rate() as f64 * decoder.channels() as f64;
+        let budget = (limits.max_seconds.max(0.0) as f64 * per_second) as u64;
+        let deadline = Instant::now() + limits.timeout();
+        let mut samples = 0u64;
+        for _ in decoder {
+            samples += 1;
+            if samples > budget {
+                return Err(format!("plays longer than {}s", limits.max_seconds));
+            }
+            if samples.is_multiple_of(65_536) && Instant::now() > deadline {
+                return Err(format!("decoding took longer than {} ms", limits.decode_timeout_ms));
+            }
+        }
+        Ok(Duration::from_secs_f64(samples as f64 / per_second.max(1.0)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A mono 8 kHz 16-bit WAV of `secs` seconds of silence.
+    fn wav(path: &Path, secs: u32) {
+        let data_len = 8000 * 2 * secs;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&8000u32.to_le_bytes());
+        bytes.extend_from_slice(&16000u32.to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        bytes.resize(bytes.len() + data_len as usize, 0);
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn file_type_and_size_checked() {
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("boom.exe");
+        std::fs::write(&exe, b"MZ").unwrap();
+        assert!(check_file(&exe, &Limits::default()).unwrap_err().contains("not allowed"));
+
+        let big = dir.path().join("big.wav");
+        wav(&big, 1);
+        let tight = Limits { max_file_bytes: 100, ..Default::default() };
+        assert!(check_file(&big, &tight).unwrap_err().contains("byte limit"));
+        assert_eq!(check_file(&big, &Limits::default()), Ok(()));
+    }
+
+    #[test]
+    fn validate_enforces_length() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("long.WAV");
+        wav(&path, 3);
+        assert_eq!(validate(&path, &Limits::default()), Ok(Duration::from_secs(3)));
+        let short = Limits { max_seconds: 1.0, ..Default::default() };
+        assert!(validate(&path, &short).unwrap_err().contains("longer than 1s"));
+    }
+
+    #[test]
+    fn open_cuts_playback() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("long.wav");
+        wav(&path, 3);
+        let limits = Limits { max_seconds: 1.0, ..Default::default() };
+        // One second at 8 kHz, give or take rounding in take_duration
+        let samples = open(&path, &limits).unwrap().count();
+        assert!((7990..=8000).contains(&samples), "{samples}");
+    }
+}
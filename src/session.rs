@@ -0,0 +1,361 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::{log, paths, publish};
+
+/// What we know about a Claude Code session, updated on every hook event.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub id: String,
+    /// Working directory the session reported.
+    pub cwd: String,
+    /// Unix time of the first event seen.
+    #[serde(default)]
+    pub started: u64,
+    /// Unix time of the most recent event.
+    #[serde(default)]
+    pub updated: u64,
+    /// Theme the session last resolved to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    /// Hook event name of the most recent event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_event: Option<String>,
+    /// What the session is waiting on the user for ("permission", "input", ...).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attention: Option<String>,
+    /// Unix time the session started waiting.
+    #[serde(default)]
+    pub attention_since: u64,
+    /// Terminal the session runs in, captured at SessionStart (see `focus`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminal: Option<crate::focus::Terminal>,
+    /// Notifications sent since the user last typed a prompt.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub unseen: u32,
+    /// Controlling terminal (`tty_nr`) of the hook at SessionStart.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tty: Option<u64>,
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+impl Session {
+    /// Last component of the cwd, used as a display and grouping name.
+    pub fn project(&self) -> &str {
+        project_name(&self.cwd)
+    }
+
+    pub fn needs_attention(&self) -> bool {
+        self.attention.is_some()
+    }
+
+    /// How long the session has been waiting on the user at `now`.
+    pub fn waiting(&self, now: u64) -> std::time::Duration {
+        let secs = if self.needs_attention() { now.saturating_sub(self.attention_since) } else { 0 };
+        std::time::Duration::from_secs(secs)
+    }
+
+    /// Set or clear the attention flag for an event at `now`. A repeated
+    /// prompt keeps the time the session first started waiting.
+    fn observe(&mut self, event: &str, reason: Option<&str>, now: u64) {
+        self.last_event = Some(event.to_string());
+        if matches!(event, "UserPromptSubmit" | "SessionStart") {
+            self.unseen = 0;
+        }
+        match reason {
+            Some(reason) => {
+                if self.attention.is_none() {
+                    self.attention_since = now;
+                }
+                self.attention = Some(reason.to_string());
+            }
+            None => {
+                self.attention = None;
+                self.attention_since = 0;
+            }
+        }
+    }
+
+    pub fn muted(&self) -> bool {
+        is_muted(&self.id)
+    }
+}
+
+/// Sessions are muted by a flag file, checked on every hook event.
+pub fn mute_file(id: &str) -> PathBuf {
+    paths::tmp_dir().join(format!(".claude-mute-{id}"))
+}
+
+pub fn is_muted(id: &str) -> bool {
+    !id.is_empty() && mute_file(id).exists()
+}
+
+pub fn project_name(cwd: &str) -> &str {
+    let trimmed = cwd.trim_end_matches('/');
+    trimmed.rsplit('/').next().filter(|s| !s.is_empty()).unwrap_or(cwd)
+}
+
+/// Desktop notification group for the workspace `cwd` is in: a short hash
+/// of its root, so two checkouts with the same name don't replace each
+/// other's notifications.
+pub fn notification_group(cwd: &str) -> String {
+    let root = paths::workspace_root(Path::new(cwd));
+    publish::sha256_hex(root.to_string_lossy().as_bytes())[..16].to_string()
+}
+
+pub fn sessions_dir() -> PathBuf {
+    paths::runtime_dir().join("sessions")
+}
+
+/// Record that `id` is active in `cwd` and just sent `event`, which
+/// `reason` says is waiting on the user. Failures are ignored.
+pub fn touch(id: &str, cwd: &str, event: &str, reason: Option<&str>) {
+    update(id, |s| {
+        s.cwd = cwd.to_string();
+        let now = s.updated;
+        s.observe(event, reason, now);
+    });
+}
+
+/// Count one more notification the user hasn't seen; returns the new count.
+pub fn bump_unseen(id: &str) -> u32 {
+    let mut unseen = 0;
+    update(id, |s| {
+        s.unseen += 1;
+        unseen = s.unseen;
+    });
+    unseen
+}
+
+/// Remember the theme a session resolved to.
+pub fn record_theme(id: &str, theme: &str) {
+    update(id, |s| s.theme = Some(theme.to_string()));
+}
+
+/// Remember the terminal window a session started in.
+pub fn record_terminal(id: &str, terminal: crate::focus::Terminal) {
+    update(id, |s| s.terminal = Some(terminal));
+}
+
+/// Remember the controlling terminal a session started in.
+pub fn record_tty(id: &str, tty: u64) {
+    update(id, |s| s.tty = Some(tty));
+}
+
+/// This process's controlling terminal, as the `tty_nr` of
+/// `/proc/self/stat`. Hooks inherit Claude Code's even with piped stdio.
+pub fn controlling_tty() -> Option<u64> {
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    // The command name may contain spaces; fields resume after its ')'
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    fields.get(4)?.parse().ok().filter(|tty| *tty != 0)
+}
+
+pub fn load(id: &str) -> Option<Session> {
+    if !valid_id(id) {
+        return None;
+    }
+    let bytes = std::fs::read(sessions_dir().join(format!("{id}.json"))).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Ended sessions are kept this long for `previous_in_terminal`.
+const KEEP_ENDED: u64 = 86_400;
+
+fn ended_dir() -> PathBuf {
+    paths::runtime_dir().join("ended-sessions")
+}
+
+/// Forget a session (at SessionEnd). One with a known terminal is kept a
+/// while among the ended ones, so a restart there can pick up its theme.
+pub fn remove(id: &str) {
+    if valid_id(id) {
+        let _ = end_in(&sessions_dir(), &ended_dir(), id, log::now_secs());
+    }
+}
+
+fn end_in(dir: &Path, ended: &Path, id: &str, now: u64) -> std::io::Result<()> {
+    let path = dir.join(format!("{id}.json"));
+    let session: Option<Session> = std::fs::read(&path).ok().and_then(|bytes| serde_json::from_slice(&bytes).ok());
+    std::fs::remove_file(&path)?;
+    let Some(session) = session.filter(|s| s.tty.is_some()) else {
+        return Ok(());
+    };
+    for old in load_from(ended).iter().filter(|s| now.saturating_sub(s.updated) > KEEP_ENDED) {
+        let _ = std::fs::remove_file(ended.join(format!("{}.json", old.id)));
+    }
+    write_to(ended, &Session { updated: now, ..session })
+}
+
+/// The latest other session, running or ended, in the same terminal and
+/// directory as `current` and active within `within` seconds of `now`.
+pub fn previous_in_terminal(current: &Session, now: u64, within: u64) -> Option<Session> {
+    previous_in(&[sessions_dir(), ended_dir()], current, now, within)
+}
+
+fn previous_in(dirs: &[PathBuf], current: &Session, now: u64, within: u64) -> Option<Session> {
+    current.tty?;
+    dirs.iter()
+        .flat_map(|dir| load_from(dir))
+        .filter(|s| s.id != current.id && s.tty == current.tty && s.cwd == current.cwd)
+        .filter(|s| now.saturating_sub(s.updated) <= within)
+        .max_by_key(|s| s.updated)
+}
+
+/// Session ids become file names; refuse anything that could escape the dir.
+pub(crate) fn valid_id(id: &str) -> bool {
+    !id.is_empty() && !id.contains('/') && !id.starts_with('.')
+}
+
+fn update(id: &str, f: impl FnOnce(&mut Session)) {
+    if !valid_id(id) {
+        return;
+    }
+    let _ = update_in(&sessions_dir(), id, log::now_secs(), f);
+}
+
+fn update_in(dir: &Path, id: &str, now: u64, f: impl FnOnce(&mut Session)) -> std::io::Result<()> {
+    let mut session = std::fs::read(dir.join(format!("{id}.json")))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_else(|| Session { id: id.to_string(), started: now, ..Default::default() });
+    session.updated = now;
+    f(&mut session);
+    write_to(dir, &session)
+}
+
+/// All known sessions, most recently active first.
+pub fn load_all() -> Vec<Session> {
+    let mut sessions = load_from(&sessions_dir());
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.updated));
+    sessions
+}
+
+fn write_to(dir: &Path, session: &Session) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(dir.join(format!("{}.json", session.id)), serde_json::to_vec(session)?)
+}
+
+fn load_from(dir: &Path) -> Vec<Session> {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| std::fs::read(e.ok()?.path()).ok())
+        .filter_map(|bytes| serde_json::from_slice(&bytes).ok())
+        .collect()
+}
+
+/// Sessions waiting on the user, longest waiting first.
+pub fn pending() -> Vec<Session> {
+    let mut sessions: Vec<Session> = load_from(&sessions_dir()).into_iter().filter(Session::needs_attention).collect();
+    sessions.sort_by_key(|s| s.attention_since);
+    sessions
+}
+
+/// Sessions with an event in the last `within` seconds.
+pub fn active_count(within: u64) -> usize {
+    active_in(&sessions_dir(), log::now_secs(), within)
+}
+
+fn active_in(dir: &Path, now: u64, within: u64) -> usize {
+    load_from(dir).iter().filter(|s| now.saturating_sub(s.updated) <= within).count()
+}
+
+/// The most recently active session whose cwd is `cwd`.
+pub fn newest_in(cwd: &str) -> Option<Session> {
+    newest_in_dir(&sessions_dir(), cwd)
+}
+
+fn newest_in_dir(dir: &Path, cwd: &str) -> Option<Session> {
+    load_from(dir).into_iter().filter(|s| s.cwd == cwd).max_by_key(|s| s.updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notification_group_is_per_workspace_not_per_name() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (a, b) = (tmp.path().join("a/app"), tmp.path().join("b/app"));
+        std::fs::create_dir_all(a.join(".git")).unwrap();
+        std::fs::create_dir_all(a.join("src")).unwrap();
+        let group = |dir: &Path| notification_group(&dir.to_string_lossy());
+        assert_ne!(group(&a), group(&b));
+        assert_eq!(group(&a), group(&a.join("src")));
+    }
+
+    #[test]
+    fn ended_session_in_the_same_terminal_is_found() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (live, ended) = (tmp.path().join("sessions"), tmp.path().join("ended"));
+        let session = |id: &str, tty: Option<u64>, cwd: &str| Session { id: id.into(), cwd: cwd.into(), tty, theme: Some("peon".into()), ..Default::default() };
+        write_to(&live, &session("old", Some(34816), "/work/app")).unwrap();
+        write_to(&live, &session("notty", None, "/work/app")).unwrap();
+        write_to(&live, &session("elsewhere", Some(34816), "/work/lib")).unwrap();
+        end_in(&live, &ended, "old", 1000).unwrap();
+        end_in(&live, &ended, "notty", 1000).unwrap();
+        assert!(!live.join("old.json").exists());
+        assert!(!ended.join("notty.json").exists());
+
+        let dirs = [live, ended];
+        let current = session("new", Some(34816), "/work/app");
+        assert_eq!(previous_in(&dirs, &current, 1100, 600).map(|s| s.id), Some("old".to_string()));
+        assert_eq!(previous_in(&dirs, &current, 2000, 600), None);
+        assert_eq!(previous_in(&dirs, &Session { tty: Some(1), ..current.clone() }, 1100, 600), None);
+        assert_eq!(previous_in(&dirs, &Session { tty: None, ..current }, 1100, 600), None);
+    }
+
+    #[test]
+    fn newest_session_in_cwd_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        let session = |id: &str, cwd: &str, updated| Session { id: id.into(), cwd: cwd.into(), updated, ..Default::default() };
+        write_to(dir.path(), &session("old", "/p/app", 10)).unwrap();
+        write_to(dir.path(), &session("new", "/p/app", 20)).unwrap();
+        write_to(dir.path(), &session("other", "/p/lib", 30)).unwrap();
+        std::fs::write(dir.path().join("junk.json"), "not json").unwrap();
+
+        assert_eq!(newest_in_dir(dir.path(), "/p/app").map(|s| s.id), Some("new".to_string()));
+        assert_eq!(newest_in_dir(dir.path(), "/p/none"), None);
+        assert_eq!(load_from(dir.path()).len(), 3);
+        assert_eq!(active_in(dir.path(), 40, 20), 2);
+    }
+
+    #[test]
+    fn updates_keep_start_time() {
+        let dir = tempfile::tempdir().unwrap();
+        update_in(dir.path(), "s1", 100, |s| s.cwd = "/p/app".into()).unwrap();
+        update_in(dir.path(), "s1", 160, |s| s.theme = Some("peon".into())).unwrap();
+        let s = &load_from(dir.path())[0];
+        assert_eq!((s.started, s.updated), (100, 160));
+        assert_eq!(s.cwd, "/p/app");
+        assert_eq!(s.theme.as_deref(), Some("peon"));
+    }
+
+    #[test]
+    fn project_is_last_path_component() {
+        assert_eq!(project_name("/home/u/src/ringring"), "ringring");
+        assert_eq!(project_name("/home/u/src/ringring/"), "ringring");
+        assert_eq!(project_name("/"), "/");
+    }
+
+    #[test]
+    fn attention_set_by_prompt_and_cleared_by_activity() {
+        let mut s = Session::default();
+        s.observe("PermissionRequest", Some("permission"), 10);
+        s.observe("Notification", Some("permission"), 20);
+        assert_eq!((s.attention.as_deref(), s.attention_since), (Some("permission"), 10));
+        assert_eq!(s.waiting(25).as_secs(), 15);
+        s.unseen = 2;
+        s.observe("Stop", None, 25);
+        assert_eq!(s.unseen, 2);
+        s.observe("UserPromptSubmit", None, 30);
+        assert_eq!(s.unseen, 0);
+        assert_eq!(s.waiting(40).as_secs(), 0);
+        assert!(!s.needs_attention());
+        assert_eq!(s.last_event.as_deref(), Some("UserPromptSubmit"));
+    }
+}
@@ -0,0 +1,174 @@
+//! `config export`/`config import`: a whole setup in one JSON document, to
+//! carry to another machine. It holds config.json as written (workspace pins
+//! included), the files it `include`s, and the installed themes with the
+//! source each was installed from, so import can fetch them again.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::{buildinfo, config, updates};
+
+/// Version of the document; bumped when it changes incompatibly.
+pub const FORMAT: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Setup {
+    /// `FORMAT` the document was written in; also tells it apart from other JSON.
+    pub ringring_setup: u32,
+    /// ringring version that exported it.
+    #[serde(default)]
+    pub version: String,
+    pub config: serde_json::Value,
+    /// Included config files, by their path relative to the data dir.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub includes: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub themes: Vec<Theme>,
+}
+
+/// An installed theme.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    pub name: String,
+    /// The `theme install` argument, when it was installed with one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+/// The setup in `data_dir`. Includes that can't be read are left out.
+pub fn export(data_dir: &Path) -> Result<Setup, Box<dyn std::error::Error>> {
+    let config = config::raw_config(data_dir)?;
+    let mut includes = BTreeMap::new();
+    let mut pending = include_list(&config);
+    while let Some(include) = pending.pop() {
+        if includes.contains_key(&include) || relative_path(&include).is_none() {
+            continue;
+        }
+        let Some(value) = std::fs::read_to_string(data_dir.join(&include)).ok().and_then(|s| serde_json::from_str(&s).ok()) else {
+            continue;
+        };
+        pending.extend(include_list(&value));
+        includes.insert(include, value);
+    }
+
+    let receipts = updates::load_receipts(data_dir);
+    let mut themes: Vec<Theme> = std::fs::read_dir(data_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().join("manifest.json").is_file())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .map(|name| Theme { source: receipts.get(&name).map(|r| r.source.clone()), name })
+        .collect();
+    themes.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(Setup { ringring_setup: FORMAT, version: buildinfo::VERSION.to_string(), config, includes, themes })
+}
+
+fn include_list(config: &serde_json::Value) -> Vec<String> {
+    config.get("include").and_then(|v| serde_json::from_value(v.clone()).ok()).unwrap_or_default()
+}
+
+/// `path` when it stays inside the data dir: relative, without `..`.
+fn relative_path(path: &str) -> Option<&Path> {
+    let path = Path::new(path);
+    path.components().all(|c| matches!(c, Component::Normal(_))).then_some(path)
+}
+
+impl Setup {
+    /// Read an exported document, refusing newer formats and include paths
+    /// outside the data dir.
+    pub fn parse(text: &str) -> Result<Setup, Box<dyn std::error::Error>> {
+        let setup: Setup = serde_json::from_str(text).map_err(|e| format!("not a ringring config export: {e}"))?;
+        if setup.ringring_setup > FORMAT {
+            return Err(format!("exported by a newer ringring ({}); update this one first", setup.version).into());
+        }
+        serde_json::from_value::<config::Config>(setup.config.clone()).map_err(|e| format!("exported config.json: {e}"))?;
+        if let Some(bad) = setup.includes.keys().find(|path| relative_path(path).is_none()) {
+            return Err(format!("include '{bad}' is outside the data dir").into());
+        }
+        for theme in &setup.themes {
+            config::validate_theme_name(&theme.name)?;
+        }
+        Ok(setup)
+    }
+
+    /// Themes not installed in `data_dir` yet.
+    pub fn missing_themes(&self, data_dir: &Path) -> Vec<&Theme> {
+        self.themes.iter().filter(|t| !data_dir.join(&t.name).join("manifest.json").is_file()).collect()
+    }
+
+    /// Write the include files and config.json, keeping a config.json it
+    /// replaces as config.json.bak. Returns the files written.
+    pub fn restore_config(&self, data_dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let mut written = Vec::new();
+        for (include, value) in &self.includes {
+            let path = data_dir.join(relative_path(include).ok_or_else(|| format!("include '{include}' is outside the data dir"))?);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, serde_json::to_string_pretty(value)?)?;
+            written.push(path);
+        }
+        std::fs::create_dir_all(data_dir)?;
+        let path = data_dir.join("config.json");
+        if path.exists() {
+            std::fs::copy(&path, data_dir.join("config.json.bak"))?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(&self.config)?)?;
+        written.push(path);
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_round_trips_config_includes_and_themes() {
+        let from = tempfile::tempdir().unwrap();
+        std::fs::write(
+            from.path().join("config.json"),
+            r#"{"theme": "peon", "include": ["work.json"], "workspaces": {"/home/me/api": "chime"}}"#,
+        )
+        .unwrap();
+        std::fs::write(from.path().join("work.json"), r#"{"include": ["extra/volume.json"], "volume": 60}"#).unwrap();
+        std::fs::create_dir_all(from.path().join("extra")).unwrap();
+        std::fs::write(from.path().join("extra/volume.json"), r#"{"volume": 40}"#).unwrap();
+        for theme in ["peon", "chime"] {
+            std::fs::create_dir_all(from.path().join(theme)).unwrap();
+            std::fs::write(from.path().join(theme).join("manifest.json"), "{}").unwrap();
+        }
+        let receipt = updates::Receipt { source: "peon".into(), sha256: None, installed: 1 };
+        updates::record_receipt(from.path(), "peon", receipt).unwrap();
+
+        let setup = export(from.path()).unwrap();
+        assert_eq!(setup.includes.keys().collect::<Vec<_>>(), ["extra/volume.json", "work.json"]);
+        assert_eq!(
+            setup.themes,
+            [Theme { name: "chime".into(), source: None }, Theme { name: "peon".into(), source: Some("peon".into()) }]
+        );
+
+        let to = tempfile::tempdir().unwrap();
+        std::fs::write(to.path().join("config.json"), r#"{"theme": "old"}"#).unwrap();
+        let setup = Setup::parse(&serde_json::to_string(&setup).unwrap()).unwrap();
+        assert_eq!(setup.missing_themes(to.path()).len(), 2);
+        setup.restore_config(to.path()).unwrap();
+        let cfg = config::Config::load(to.path());
+        assert_eq!((cfg.theme.as_deref(), cfg.volume), (Some("peon"), Some(60)));
+        assert_eq!(cfg.workspaces.len(), 1);
+        assert!(std::fs::read_to_string(to.path().join("config.json.bak")).unwrap().contains("old"));
+    }
+
+    #[test]
+    fn parse_refuses_unsafe_or_newer_documents() {
+        let doc = |extra: &str| format!(r#"{{"ringring_setup": 1, "config": {{}}{extra}}}"#);
+        assert!(Setup::parse(&doc("")).is_ok());
+        assert!(Setup::parse(&doc(r#", "includes": {"../escape.json": {}}"#)).is_err());
+        assert!(Setup::parse(&doc(r#", "includes": {"/etc/x.json": {}}"#)).is_err());
+        assert!(Setup::parse(&doc(r#", "themes": [{"name": "../x"}]"#)).is_err());
+        assert!(Setup::parse(r#"{"ringring_setup": 2, "config": {}}"#).unwrap_err().to_string().contains("newer"));
+        assert!(Setup::parse(r#"{"theme": "peon"}"#).unwrap_err().to_string().starts_with("not a ringring config export"));
+    }
+}
@@ -0,0 +1,57 @@
+//! Caps how many hook processes handle events at once. Each holds one of
+//! `max_processes` lock files in `paths::runtime_dir()/slots`; a process
+//! that finds them all taken records its event to history and exits, so an
+//! event storm neither piles up players and notifications nor loses events
+//! (`ringring replay` can still run them).
+
+use std::fs::File;
+use std::os::fd::AsRawFd;
+use std::path::Path;
+
+use crate::paths;
+
+/// A held slot, released when dropped. A player forked while it is held
+/// keeps it until the sound ends.
+pub struct Slot {
+    _file: Option<File>,
+}
+
+/// Take one of `max` slots, or `None` when every one is held. With `max` 0,
+/// or lock files that can't be used at all, the event goes ahead unguarded.
+pub fn acquire(max: usize) -> Option<Slot> {
+    acquire_in(&paths::runtime_dir().join("slots"), max)
+}
+
+fn acquire_in(dir: &Path, max: usize) -> Option<Slot> {
+    if max == 0 || std::fs::create_dir_all(dir).is_err() {
+        return Some(Slot { _file: None });
+    }
+    let mut usable = false;
+    for i in 0..max {
+        let path = dir.join(format!("{i}.lock"));
+        let Ok(file) = std::fs::OpenOptions::new().create(true).truncate(false).write(true).open(path) else {
+            continue;
+        };
+        usable = true;
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+            return Some(Slot { _file: Some(file) });
+        }
+    }
+    (!usable).then_some(Slot { _file: None })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hands_out_at_most_max_slots() {
+        let tmp = tempfile::tempdir().unwrap();
+        let first = acquire_in(tmp.path(), 2).unwrap();
+        let _second = acquire_in(tmp.path(), 2).unwrap();
+        assert!(acquire_in(tmp.path(), 2).is_none());
+        drop(first);
+        assert!(acquire_in(tmp.path(), 2).is_some());
+        assert!(acquire_in(tmp.path(), 0).is_some());
+    }
+}
@@ -0,0 +1,374 @@
+//! What the history store says about recent events: which categories go
+//! off most, cooldowns that would quiet the noisiest ones, and flat exports
+//! for spreadsheets and dashboards.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::config::{self, Config};
+use crate::event::{self, HookInput};
+use crate::history::Record;
+use crate::manifest::Manifest;
+use crate::{log, session};
+
+/// How far back `ringring stats` looks.
+pub const WEEK: Duration = Duration::from_secs(7 * 86_400);
+
+/// A category going off more often than this in a week is worth a cooldown.
+const NOISY: usize = 50;
+
+/// Cooldowns to suggest, shortest first (seconds).
+const COOLDOWNS: [u64; 6] = [60, 120, 300, 600, 900, 1800];
+
+/// Events of one category.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Tally {
+    pub events: usize,
+    pub played: usize,
+    pub notified: usize,
+    pub skipped: usize,
+    /// Notification type (or hook event, for other events) → count.
+    pub triggers: BTreeMap<String, usize>,
+    /// Event timestamps, oldest first.
+    pub times: Vec<u64>,
+}
+
+impl Tally {
+    /// What set off most of the events.
+    pub fn top_trigger(&self) -> &str {
+        self.triggers.iter().max_by_key(|(_, n)| **n).map(|(t, _)| t.as_str()).unwrap_or("-")
+    }
+}
+
+/// The category a recorded event falls in and what set it off (the
+/// notification type, else the hook event). `None` for events that map to
+/// no category.
+pub fn classify(record: &Record) -> Option<(String, String)> {
+    let input: HookInput = serde_json::from_value(record.input.clone()).ok()?;
+    let category = record.outcome.category.clone().or_else(|| event::map_event(&input).category)?;
+    let trigger = input.notification_type.unwrap_or(input.hook_event_name);
+    Some((category, trigger))
+}
+
+/// Per category counts of the records at or after `since` (unix seconds).
+pub fn tally(records: &[Record], since: u64) -> BTreeMap<String, Tally> {
+    let mut tallies: BTreeMap<String, Tally> = BTreeMap::new();
+    for record in records.iter().filter(|r| r.timestamp >= since) {
+        let Some((category, trigger)) = classify(record) else { continue };
+        let tally = tallies.entry(category).or_default();
+        tally.events += 1;
+        tally.played += usize::from(record.outcome.sound.is_some());
+        tally.notified += usize::from(record.outcome.notified);
+        tally.skipped += usize::from(record.outcome.skipped.is_some());
+        *tally.triggers.entry(trigger).or_default() += 1;
+        tally.times.push(record.timestamp);
+    }
+    tallies
+}
+
+/// How often one sound of a theme played, for `theme stats`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundPlays {
+    pub category: String,
+    pub file: String,
+    pub plays: usize,
+    /// Events of the category with this theme, played or not.
+    pub category_events: usize,
+}
+
+/// Every sound `manifest` lists per category (variants included), with how
+/// often `theme` played it in `records`. Sorted by category, then file.
+pub fn sound_plays(records: &[Record], theme: &str, manifest: &Manifest) -> Vec<SoundPlays> {
+    let mut plays: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    let mut events: BTreeMap<String, usize> = BTreeMap::new();
+    let ours = records.iter().filter(|r| r.outcome.theme.as_deref() == Some(theme));
+    for record in ours.clone() {
+        if let Some((category, _)) = classify(record) {
+            *events.entry(category).or_default() += 1;
+        }
+    }
+    for record in ours {
+        if let (Some(category), Some(sound)) = (&record.outcome.category, &record.outcome.sound) {
+            *plays.entry((category, sound)).or_default() += 1;
+        }
+    }
+
+    let mut rows: Vec<SoundPlays> = manifest
+        .categories
+        .iter()
+        .flat_map(|(category, cat)| {
+            let mut files: Vec<&str> = cat.sounds.iter().chain(cat.variants.values().flat_map(|v| &v.sounds)).map(|s| s.file.as_str()).collect();
+            files.sort_unstable();
+            files.dedup();
+            files.into_iter().map(|file| SoundPlays {
+                category: category.clone(),
+                file: file.to_string(),
+                plays: plays.get(&(category.as_str(), file)).copied().unwrap_or(0),
+                category_events: events.get(category).copied().unwrap_or(0),
+            })
+        })
+        .collect();
+    rows.sort_by(|a, b| (&a.category, &a.file).cmp(&(&b.category, &b.file)));
+    rows
+}
+
+/// A config change `ringring stats --suggest` proposes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub category: String,
+    pub trigger: String,
+    pub events: usize,
+    pub cooldown: Duration,
+}
+
+impl Suggestion {
+    pub fn message(&self) -> String {
+        format!(
+            "{} triggered {} times this week — consider a cooldown of {} for {}",
+            self.trigger,
+            self.events,
+            config::format_duration(self.cooldown),
+            self.category,
+        )
+    }
+}
+
+/// Cooldowns for categories that went off more than `NOISY` times, sized to
+/// the median gap between their events so about half of them would still
+/// play. Categories with a cooldown already, or whose events are spread out
+/// too far for one to help, get none. Noisiest first.
+pub fn suggest(tallies: &BTreeMap<String, Tally>, cfg: &Config) -> Vec<Suggestion> {
+    let mut suggestions: Vec<Suggestion> = tallies
+        .iter()
+        .filter(|(category, tally)| tally.events > NOISY && cfg.cooldown(category).is_none())
+        .filter_map(|(category, tally)| {
+            let mut gaps: Vec<u64> = tally.times.windows(2).map(|w| w[1].saturating_sub(w[0])).collect();
+            gaps.sort_unstable();
+            let median = gaps[gaps.len() / 2];
+            let cooldown = COOLDOWNS.into_iter().find(|&c| c >= median)?;
+            Some(Suggestion {
+                category: category.clone(),
+                trigger: tally.top_trigger().to_string(),
+                events: tally.events,
+                cooldown: Duration::from_secs(cooldown),
+            })
+        })
+        .collect();
+    suggestions.sort_by_key(|s| std::cmp::Reverse(s.events));
+    suggestions
+}
+
+/// Write the suggested cooldowns to config.json.
+pub fn apply(sounds_dir: &Path, suggestions: &[Suggestion]) -> Result<(), Box<dyn std::error::Error>> {
+    config::edit_config(sounds_dir, |root| {
+        if !root["cooldowns"].is_object() {
+            root["cooldowns"] = serde_json::json!({});
+        }
+        for suggestion in suggestions {
+            root["cooldowns"][&suggestion.category] = config::format_duration(suggestion.cooldown).into();
+        }
+    })
+}
+
+/// One history record flattened for `stats export`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportRow {
+    pub id: String,
+    /// Unix seconds.
+    pub timestamp: u64,
+    /// The same instant as ISO-8601 UTC.
+    pub time: String,
+    pub event: String,
+    pub notification_type: String,
+    pub session_id: String,
+    pub project: String,
+    pub category: String,
+    pub theme: String,
+    pub sound: String,
+    pub notified: bool,
+    pub skipped: String,
+}
+
+/// Columns of `to_csv`, in order.
+const CSV_HEADER: &str = "id,timestamp,time,event,notification_type,session_id,project,category,theme,sound,notified,skipped";
+
+/// Export rows for the records at or after `since` (unix seconds). Missing
+/// values are empty strings so every row has the same shape.
+pub fn export_rows(records: &[Record], since: u64) -> Vec<ExportRow> {
+    records
+        .iter()
+        .filter(|r| r.timestamp >= since)
+        .map(|record| {
+            let input: HookInput = serde_json::from_value(record.input.clone()).unwrap_or_default();
+            let category = classify(record).map(|(category, _)| category).unwrap_or_default();
+            let outcome = &record.outcome;
+            ExportRow {
+                id: record.id.clone(),
+                timestamp: record.timestamp,
+                time: log::format_timestamp(record.timestamp),
+                project: input.cwd.as_deref().map(session::project_name).unwrap_or_default().to_string(),
+                event: input.hook_event_name,
+                notification_type: input.notification_type.unwrap_or_default(),
+                session_id: input.session_id,
+                category,
+                theme: outcome.theme.clone().unwrap_or_default(),
+                sound: outcome.sound.clone().unwrap_or_default(),
+                notified: outcome.notified,
+                skipped: outcome.skipped.clone().unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+/// RFC 4180 CSV with a header line.
+pub fn to_csv(rows: &[ExportRow]) -> String {
+    let mut out = format!("{CSV_HEADER}\r\n");
+    for row in rows {
+        let fields = [
+            row.id.clone(),
+            row.timestamp.to_string(),
+            row.time.clone(),
+            row.event.clone(),
+            row.notification_type.clone(),
+            row.session_id.clone(),
+            row.project.clone(),
+            row.category.clone(),
+            row.theme.clone(),
+            row.sound.clone(),
+            row.notified.to_string(),
+            row.skipped.clone(),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&line.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::Outcome;
+
+    fn record(timestamp: u64, input: serde_json::Value, outcome: Outcome) -> Record {
+        Record { id: timestamp.to_string(), timestamp, input, outcome }
+    }
+
+    /// `n` idle prompts `gap` seconds apart and a few Stops, starting at `start`.
+    fn week(start: u64, n: u64, gap: u64) -> Vec<Record> {
+        let idle = serde_json::json!({"hook_event_name": "Notification", "notification_type": "idle_prompt"});
+        let stop = serde_json::json!({"hook_event_name": "Stop"});
+        let mut records: Vec<Record> = (0..n)
+            .map(|i| record(start + i * gap, idle.clone(), Outcome { notified: true, ..Default::default() }))
+            .collect();
+        records.extend((0..5).map(|i| record(start + i, stop.clone(), Outcome::skipped("session muted"))));
+        records.push(record(start, serde_json::json!({"hook_event_name": "SessionEnd"}), Outcome::default()));
+        records.sort_by_key(|r| r.timestamp);
+        records
+    }
+
+    #[test]
+    fn tally_groups_by_category_and_trigger() {
+        let records = week(1000, 60, 200);
+        let tallies = tally(&records, 0);
+        assert_eq!(tallies.keys().collect::<Vec<_>>(), ["annoyed", "complete"]);
+        assert_eq!(tallies["annoyed"].events, 60);
+        assert_eq!(tallies["annoyed"].notified, 60);
+        assert_eq!(tallies["annoyed"].top_trigger(), "idle_prompt");
+        assert_eq!(tallies["complete"].skipped, 5);
+        assert_eq!(tally(&records, 1000 + 50 * 200)["annoyed"].events, 10);
+    }
+
+    #[test]
+    fn noisy_categories_get_a_cooldown() {
+        let tallies = tally(&week(1000, 94, 200), 0);
+        let suggestions = suggest(&tallies, &Config::default());
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].cooldown, Duration::from_secs(300));
+        assert_eq!(
+            suggestions[0].message(),
+            "idle_prompt triggered 94 times this week — consider a cooldown of 5m for annoyed"
+        );
+
+        // Already tuned, or too spread out for a cooldown to matter
+        let cfg: Config = serde_json::from_str(r#"{"cooldowns": {"annoyed": "2m"}}"#).unwrap();
+        assert!(suggest(&tallies, &cfg).is_empty());
+        assert!(suggest(&tally(&week(1000, 94, 3600), 0), &Config::default()).is_empty());
+    }
+
+    #[test]
+    fn apply_writes_cooldowns() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("config.json"), r#"{"theme": "peon"}"#).unwrap();
+        let suggestions = suggest(&tally(&week(1000, 60, 30), 0), &Config::default());
+        apply(dir.path(), &suggestions).unwrap();
+        let cfg = Config::load(dir.path());
+        assert_eq!(cfg.cooldown("annoyed"), Some(Duration::from_secs(60)));
+        assert_eq!(cfg.theme.as_deref(), Some("peon"));
+    }
+
+    #[test]
+    fn sound_plays_include_sounds_that_never_played() {
+        let manifest: Manifest = serde_json::from_str(
+            r#"{"name": "peon", "display_name": "Peon", "categories": {
+                "complete": {"sounds": [{"file": "done.wav"}, {"file": "yay.wav"}]},
+                "resource_limit": {"sounds": [{"file": "low.wav"}]}
+            }}"#,
+        )
+        .unwrap();
+        let stop = serde_json::json!({"hook_event_name": "Stop"});
+        let played = |theme: &str, sound: &str| Outcome {
+            theme: Some(theme.into()),
+            category: Some("complete".into()),
+            sound: Some(sound.into()),
+            ..Default::default()
+        };
+        let records = vec![
+            record(1, stop.clone(), played("peon", "done.wav")),
+            record(2, stop.clone(), played("peon", "done.wav")),
+            record(3, stop.clone(), played("other", "yay.wav")),
+            record(4, stop, Outcome { theme: Some("peon".into()), ..Outcome::skipped("session muted") }),
+        ];
+        let rows = sound_plays(&records, "peon", &manifest);
+        let summary: Vec<(&str, &str, usize, usize)> =
+            rows.iter().map(|r| (r.category.as_str(), r.file.as_str(), r.plays, r.category_events)).collect();
+        assert_eq!(summary, [("complete", "done.wav", 2, 3), ("complete", "yay.wav", 0, 3), ("resource_limit", "low.wav", 0, 0)]);
+    }
+
+    #[test]
+    fn export_flattens_records() {
+        let records = vec![
+            record(10, serde_json::json!({"hook_event_name": "Stop"}), Outcome::default()),
+            record(
+                1_771_590_896,
+                serde_json::json!({"hook_event_name": "Notification", "notification_type": "idle_prompt", "session_id": "s1", "cwd": "/work/app"}),
+                Outcome { theme: Some("peon".into()), skipped: Some("muted, \"really\"".into()), ..Default::default() },
+            ),
+        ];
+        let rows = export_rows(&records, 100);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].time, "2026-02-20T12:34:56Z");
+        assert_eq!(rows[0].project, "app");
+        assert_eq!(rows[0].category, "annoyed");
+
+        let csv = to_csv(&rows);
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        assert_eq!(
+            lines.next(),
+            Some(r#"1771590896,1771590896,2026-02-20T12:34:56Z,Notification,idle_prompt,s1,app,annoyed,peon,,false,"muted, ""really""""#)
+        );
+        assert_eq!(csv_field("plain"), "plain");
+    }
+}
@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+
+/// Same manual choice this many times in a row before a pin is suggested.
+pub const THRESHOLD: u32 = 3;
+
+/// The theme last chosen by hand for a workspace and how often in a row.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Choice {
+    theme: String,
+    count: u32,
+    /// A pin was already offered for this streak; don't ask again.
+    #[serde(default)]
+    offered: bool,
+}
+
+type Choices = HashMap<String, Choice>;
+
+fn choices_path() -> PathBuf {
+    paths::state_dir().join("choices.json")
+}
+
+fn load(path: &Path) -> Choices {
+    std::fs::read(path).ok().and_then(|bytes| serde_json::from_slice(&bytes).ok()).unwrap_or_default()
+}
+
+fn save(path: &Path, choices: &Choices) {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_vec(choices) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+fn record_in(choices: &mut Choices, workspace: &str, theme: &str) -> u32 {
+    let choice = choices.entry(workspace.to_string()).or_default();
+    if choice.theme != theme {
+        *choice = Choice { theme: theme.to_string(), ..Default::default() };
+    }
+    choice.count += 1;
+    choice.count
+}
+
+fn due_in(choices: &Choices, workspace: &str) -> Option<String> {
+    let choice = choices.get(workspace)?;
+    (choice.count >= THRESHOLD && !choice.offered).then(|| choice.theme.clone())
+}
+
+/// Note a manual theme choice (env var or `theme use`) for a workspace.
+/// Returns how many times in a row that theme has been chosen there.
+pub fn record(workspace: &str, theme: &str) -> u32 {
+    let path = choices_path();
+    let mut choices = load(&path);
+    let count = record_in(&mut choices, workspace, theme);
+    save(&path, &choices);
+    count
+}
+
+/// Theme worth offering as a pin for `workspace`, if the streak is long
+/// enough and it hasn't been offered yet.
+pub fn due(workspace: &str) -> Option<String> {
+    due_in(&load(&choices_path()), workspace)
+}
+
+/// Remember that the suggestion was made, so each streak is offered once.
+pub fn mark_offered(workspace: &str) {
+    let path = choices_path();
+    let mut choices = load(&path);
+    if let Some(choice) = choices.get_mut(workspace) {
+        choice.offered = true;
+        save(&path, &choices);
+    }
+}
+
+/// Drop the streak for a workspace (once it is pinned).
+pub fn forget(workspace: &str) {
+    let path = choices_path();
+    let mut choices = load(&path);
+    if choices.remove(workspace).is_some() {
+        save(&path, &choices);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streak_resets_on_different_theme() {
+        let mut choices = Choices::new();
+        assert_eq!(record_in(&mut choices, "/p/app", "icq"), 1);
+        assert_eq!(record_in(&mut choices, "/p/app", "icq"), 2);
+        assert_eq!(record_in(&mut choices, "/p/app", "peon"), 1);
+        assert_eq!(record_in(&mut choices, "/p/lib", "peon"), 1);
+    }
+
+    #[test]
+    fn offered_once_per_streak() {
+        let mut choices = Choices::new();
+        for _ in 0..THRESHOLD {
+            assert_eq!(due_in(&choices, "/p/app"), None);
+            record_in(&mut choices, "/p/app", "icq");
+        }
+        assert_eq!(due_in(&choices, "/p/app").as_deref(), Some("icq"));
+        choices.get_mut("/p/app").unwrap().offered = true;
+        assert_eq!(due_in(&choices, "/p/app"), None);
+        record_in(&mut choices, "/p/app", "peon");
+        assert!(!choices["/p/app"].offered);
+    }
+
+    #[test]
+    fn store_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state/choices.json");
+        let mut choices = load(&path);
+        record_in(&mut choices, "/p/app", "icq");
+        save(&path, &choices);
+        assert_eq!(load(&path)["/p/app"].theme, "icq");
+    }
+}
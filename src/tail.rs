@@ -0,0 +1,137 @@
+//! `ringring tail`: follow the history and the log as hook processes and
+//! the daemon write them, printing each handled event with what the
+//! pipeline decided (theme and why, category, sound, notification or the
+//! reason it was skipped).
+
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::history::Record;
+use crate::log;
+
+/// Reads the lines appended to a file since the last call. A file that
+/// shrank (rotated or truncated) is read again from the start.
+pub struct Follower {
+    path: PathBuf,
+    offset: u64,
+    partial: String,
+}
+
+impl Follower {
+    /// Follow `path` from its current end, so only new lines show up.
+    pub fn from_end(path: &Path) -> Self {
+        let offset = std::fs::metadata(path).map_or(0, |m| m.len());
+        Self { path: path.to_path_buf(), offset, partial: String::new() }
+    }
+
+    /// Complete lines appended since the last call. A line still being
+    /// written is kept for the next one. A missing file has none.
+    pub fn read_new(&mut self) -> Vec<String> {
+        let Ok(mut file) = std::fs::File::open(&self.path) else {
+            return Vec::new();
+        };
+        let len = file.metadata().map_or(0, |m| m.len());
+        if len < self.offset {
+            self.offset = 0;
+            self.partial.clear();
+        }
+        let mut bytes = Vec::new();
+        if file.seek(SeekFrom::Start(self.offset)).is_err() || file.read_to_end(&mut bytes).is_err() {
+            return Vec::new();
+        }
+        self.offset += bytes.len() as u64;
+        self.partial.push_str(&String::from_utf8_lossy(&bytes));
+        let Some(end) = self.partial.rfind('\n') else {
+            return Vec::new();
+        };
+        let rest = self.partial.split_off(end + 1);
+        let lines = std::mem::replace(&mut self.partial, rest);
+        lines.lines().filter(|l| !l.is_empty()).map(str::to_string).collect()
+    }
+}
+
+/// An event as `tail` prints it: a heading and one line per decision.
+pub fn describe(record: &Record) -> Vec<String> {
+    let input = &record.input;
+    let text = |key: &str| input[key].as_str().filter(|s| !s.is_empty());
+    let mut heading = format!("{} {}", log::format_timestamp(record.timestamp), text("hook_event_name").unwrap_or("(unknown event)"));
+    if let Some(kind) = text("notification_type") {
+        heading.push_str(&format!(" ({kind})"));
+    }
+    if let Some(session) = text("session_id") {
+        heading.push_str(&format!("  session {}", session.chars().take(8).collect::<String>()));
+    }
+    if let Some(cwd) = text("cwd") {
+        heading.push_str(&format!("  {cwd}"));
+    }
+
+    let outcome = &record.outcome;
+    let mut lines = vec![heading];
+    let mut detail = |label: &str, value: String| lines.push(format!("  {label:<9}{value}"));
+    if let Some(theme) = &outcome.theme {
+        match &outcome.theme_source {
+            Some(source) => detail("theme", format!("{theme} ({source})")),
+            None => detail("theme", theme.clone()),
+        }
+    }
+    if let Some(category) = &outcome.category {
+        detail("category", category.clone());
+    }
+    if let Some(sound) = &outcome.sound {
+        detail("sound", sound.clone());
+    }
+    match &outcome.skipped {
+        Some(reason) => detail("skipped", reason.clone()),
+        None => detail("notified", if outcome.notified { "yes" } else { "no" }.to_string()),
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn follower_returns_whole_new_lines_and_survives_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let mut follower = Follower::from_end(&path);
+        assert!(follower.read_new().is_empty());
+
+        std::fs::write(&path, "old\n").unwrap();
+        let mut follower = Follower::from_end(&path);
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "one\ntw").unwrap();
+        assert_eq!(follower.read_new(), ["one"]);
+        writeln!(file, "o").unwrap();
+        assert_eq!(follower.read_new(), ["two"]);
+
+        std::fs::write(&path, "new\n").unwrap();
+        assert_eq!(follower.read_new(), ["new"]);
+    }
+
+    #[test]
+    fn describes_decisions_and_skips() {
+        let record: Record = serde_json::from_value(serde_json::json!({
+            "id": "a", "timestamp": 0,
+            "input": {"hook_event_name": "Notification", "notification_type": "idle_prompt", "session_id": "0123456789", "cwd": "/work/app"},
+            "theme": "peon", "theme_source": "workspace pin for /work/app", "category": "annoyed", "sound": "what.wav", "notified": true
+        }))
+        .unwrap();
+        assert_eq!(
+            describe(&record),
+            [
+                "1970-01-01T00:00:00Z Notification (idle_prompt)  session 01234567  /work/app",
+                "  theme    peon (workspace pin for /work/app)",
+                "  category annoyed",
+                "  sound    what.wav",
+                "  notified yes",
+            ]
+        );
+
+        let record: Record =
+            serde_json::from_value(serde_json::json!({"id": "b", "timestamp": 0, "input": {"hook_event_name": "Stop"}, "skipped": "cooldown"})).unwrap();
+        assert_eq!(describe(&record), ["1970-01-01T00:00:00Z Stop", "  skipped  cooldown"]);
+    }
+}
@@ -0,0 +1,127 @@
+//! Threshold rules: a `meta` notification when a category goes off too often
+//! within a time window ("10 permission requests in 15m"), counted from the
+//! history store.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::history::{self, Record};
+use crate::{config, cooldown, paths, stats};
+
+/// Category of the alerts themselves, for `routes` and `badges`.
+pub const CATEGORY: &str = "meta";
+
+/// A config.json `thresholds` rule.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Threshold {
+    pub category: String,
+    /// Events within `window` that trigger the alert.
+    pub count: usize,
+    /// `15m`, `1h`, ...
+    pub window: String,
+    /// Alert text; `{count}`, `{category}` and `{window}` are filled in.
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+impl Threshold {
+    fn window(&self) -> Option<Duration> {
+        config::parse_duration(&self.window).filter(|d| !d.is_zero())
+    }
+
+    fn message(&self, count: usize) -> String {
+        let template = match (&self.message, self.category.as_str()) {
+            (Some(message), _) => message.as_str(),
+            (None, "permission") => "{count} permission requests in {window} — maybe adjust allowed tools",
+            (None, _) => "{count} {category} events in {window}",
+        };
+        template
+            .replace("{count}", &count.to_string())
+            .replace("{category}", &self.category)
+            .replace("{window}", &self.window)
+    }
+
+    /// Marker name, so editing a rule starts its clock over.
+    fn key(&self) -> String {
+        let key = format!("{}-{}-{}", self.category, self.count, self.window);
+        key.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' }).collect()
+    }
+}
+
+fn marker_dir() -> PathBuf {
+    paths::runtime_dir().join("thresholds")
+}
+
+/// Alerts for the rules an event of `category` tips over. The event isn't
+/// in the history yet, so it counts on top of what is. Each rule alerts at
+/// most once per window.
+pub fn crossed(rules: &[Threshold], category: &str) -> Vec<String> {
+    crossed_in(&marker_dir(), &history::load(), rules, category, SystemTime::now())
+}
+
+fn crossed_in(dir: &Path, records: &[Record], rules: &[Threshold], category: &str, now: SystemTime) -> Vec<String> {
+    let now_secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    rules
+        .iter()
+        .filter(|rule| rule.category == category && rule.count > 0)
+        .filter_map(|rule| {
+            let window = rule.window()?;
+            let since = now_secs.saturating_sub(window.as_secs());
+            let count = 1 + records
+                .iter()
+                .filter(|r| r.timestamp >= since)
+                .filter(|r| stats::classify(r).is_some_and(|(c, _)| c == category))
+                .count();
+            if count < rule.count || cooldown::cooling_in(dir, &rule.key(), window, now) {
+                return None;
+            }
+            Some(rule.message(count))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::Outcome;
+
+    fn permission(timestamp: u64) -> Record {
+        Record {
+            id: timestamp.to_string(),
+            timestamp,
+            input: serde_json::json!({"hook_event_name": "PermissionRequest"}),
+            outcome: Outcome::default(),
+        }
+    }
+
+    #[test]
+    fn alerts_once_per_window_when_crossed() {
+        let dir = tempfile::tempdir().unwrap();
+        let rules: Vec<Threshold> =
+            serde_json::from_str(r#"[{"category": "permission", "count": 10, "window": "15m"}]"#).unwrap();
+        let t0 = UNIX_EPOCH + Duration::from_secs(100_000);
+        let at = |secs: u64| t0 + Duration::from_secs(secs);
+        // Eight in the window plus two too old to count
+        let mut records: Vec<Record> = [0, 10].into_iter().chain((0..8).map(|i| 1000 + i * 60)).map(|s| permission(100_000 + s)).collect();
+
+        assert!(crossed_in(dir.path(), &records, &rules, "permission", at(1500)).is_empty());
+        records.push(permission(100_000 + 1500));
+        assert_eq!(
+            crossed_in(dir.path(), &records, &rules, "permission", at(1560)),
+            ["10 permission requests in 15m — maybe adjust allowed tools"]
+        );
+        records.push(permission(100_000 + 1560));
+        assert!(crossed_in(dir.path(), &records, &rules, "permission", at(1600)).is_empty());
+        assert!(crossed_in(dir.path(), &records, &rules, "complete", at(1600)).is_empty());
+    }
+
+    #[test]
+    fn custom_message_is_filled_in() {
+        let rule: Threshold =
+            serde_json::from_str(r#"{"category": "annoyed", "count": 3, "window": "1h", "message": "{category} x{count} in {window}"}"#).unwrap();
+        assert_eq!(rule.message(4), "annoyed x4 in 1h");
+        let generic = Threshold { message: None, ..rule };
+        assert_eq!(generic.message(4), "4 annoyed events in 1h");
+    }
+}
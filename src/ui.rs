@@ -0,0 +1,191 @@
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+static NO_COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Turn colors off for this process (`--no-color`).
+pub fn disable_color() {
+    NO_COLOR.store(true, Ordering::Relaxed);
+}
+
+/// Colors are used on a terminal unless `--no-color` or `NO_COLOR` says otherwise.
+fn color_enabled() -> bool {
+    !NO_COLOR.load(Ordering::Relaxed)
+        && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+        && unsafe { libc::isatty(libc::STDOUT_FILENO) } == 1
+}
+
+/// Spinners and progress bars are only drawn when stderr is a terminal.
+fn animate() -> bool {
+    unsafe { libc::isatty(libc::STDERR_FILENO) == 1 }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Style {
+    Good,
+    Warn,
+    Bad,
+    Bold,
+}
+
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Style::Good => "32",
+            Style::Warn => "33",
+            Style::Bad => "31",
+            Style::Bold => "1",
+        }
+    }
+}
+
+fn paint_with(enabled: bool, style: Style, text: &str) -> String {
+    if enabled { format!("\x1b[{}m{text}\x1b[0m", style.code()) } else { text.to_string() }
+}
+
+/// `text` in `style` when colors are on.
+pub fn paint(style: Style, text: &str) -> String {
+    paint_with(color_enabled(), style, text)
+}
+
+/// Status line for something that worked.
+pub fn ok(msg: &str) {
+    println!("{} {msg}", paint(Style::Good, "✓"));
+}
+
+/// Note about something that needs a look, on stderr.
+pub fn warn(msg: &str) {
+    eprintln!("{} {msg}", paint(Style::Warn, "!"));
+}
+
+fn clear_line() {
+    eprint!("\r\x1b[2K");
+    let _ = std::io::stderr().flush();
+}
+
+/// Animated "working" indicator on stderr, for waits of unknown length.
+pub struct Spinner {
+    done: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Spinner {
+    pub fn start(label: &str) -> Self {
+        let done = Arc::new(AtomicBool::new(false));
+        let thread = animate().then(|| {
+            let done = Arc::clone(&done);
+            let label = label.to_string();
+            std::thread::spawn(move || {
+                let frames = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+                for frame in frames.iter().cycle() {
+                    if done.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    eprint!("\r{frame} {label}");
+                    let _ = std::io::stderr().flush();
+                    std::thread::sleep(Duration::from_millis(80));
+                }
+                clear_line();
+            })
+        });
+        Self { done, thread }
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.done.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+const BAR_WIDTH: usize = 24;
+
+/// Progress bar on stderr for work with a known number of steps.
+pub struct Progress {
+    label: String,
+    total: usize,
+    done: usize,
+    draw: bool,
+    /// Counting bytes, shown in KiB.
+    bytes: bool,
+}
+
+impl Progress {
+    pub fn new(label: &str, total: usize) -> Self {
+        Self::start(label, total, false)
+    }
+
+    /// A bar for `total` bytes, e.g. a download of known length.
+    pub fn bytes(label: &str, total: usize) -> Self {
+        Self::start(label, total, true)
+    }
+
+    fn start(label: &str, total: usize, bytes: bool) -> Self {
+        let progress = Self { label: label.to_string(), total, done: 0, draw: animate() && total > 0, bytes };
+        progress.render();
+        progress
+    }
+
+    pub fn inc(&mut self) {
+        self.add(1);
+    }
+
+    pub fn add(&mut self, n: usize) {
+        self.done = (self.done + n).min(self.total);
+        self.render();
+    }
+
+    fn render(&self) {
+        if self.draw {
+            let bar = if self.bytes { byte_bar(self.done, self.total) } else { bar(self.done, self.total) };
+            eprint!("\r{} {bar}", self.label);
+            let _ = std::io::stderr().flush();
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if self.draw {
+            clear_line();
+        }
+    }
+}
+
+fn bar(done: usize, total: usize) -> String {
+    format!("{} {done}/{total}", fill(done, total))
+}
+
+fn byte_bar(done: usize, total: usize) -> String {
+    format!("{} {}/{} KiB", fill(done, total), done / 1024, total.div_ceil(1024))
+}
+
+fn fill(done: usize, total: usize) -> String {
+    let filled = (done * BAR_WIDTH).checked_div(total).unwrap_or(0);
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paint_respects_switch() {
+        assert_eq!(paint_with(true, Style::Good, "ok"), "\x1b[32mok\x1b[0m");
+        assert_eq!(paint_with(false, Style::Good, "ok"), "ok");
+    }
+
+    #[test]
+    fn bar_fills_proportionally() {
+        assert_eq!(bar(0, 4), format!("[{}] 0/4", "-".repeat(BAR_WIDTH)));
+        assert_eq!(bar(2, 4), format!("[{}{}] 2/4", "#".repeat(12), "-".repeat(12)));
+        assert_eq!(bar(0, 0), format!("[{}] 0/0", "-".repeat(BAR_WIDTH)));
+        assert_eq!(byte_bar(3072, 6000), format!("[{}{}] 3/6 KiB", "#".repeat(12), "-".repeat(12)));
+    }
+}
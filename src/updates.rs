@@ -0,0 +1,245 @@
+//! `ringring check-updates`: newer releases of the binary and of installed
+//! themes, remembered in the state dir for `status` and announced with one
+//! notification when something new turns up. `ringring update` installs the
+//! binary of the newest release, checked against its `SHA256SUMS`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::paths;
+
+/// GitHub API for the newest ringring release.
+pub const RELEASES_URL: &str = "https://api.github.com/repos/dontfreakout/rust-ringring/releases/latest";
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How a theme was installed, kept in the data dir's `receipts.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Receipt {
+    /// The `theme install` argument.
+    pub source: String,
+    /// sha256 of the installed archive, when it came from one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    pub installed: u64,
+}
+
+fn receipts_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("receipts.json")
+}
+
+pub fn load_receipts(data_dir: &Path) -> BTreeMap<String, Receipt> {
+    std::fs::read_to_string(receipts_path(data_dir))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+pub fn record_receipt(data_dir: &Path, theme: &str, receipt: Receipt) -> std::io::Result<()> {
+    let mut receipts = load_receipts(data_dir);
+    receipts.insert(theme.to_string(), receipt);
+    std::fs::write(receipts_path(data_dir), serde_json::to_string_pretty(&receipts)?)
+}
+
+/// Result of the last check.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Available {
+    pub checked: u64,
+    /// Newer ringring release, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binary: Option<String>,
+    /// Installed themes whose source has a newer version.
+    #[serde(default)]
+    pub themes: Vec<String>,
+}
+
+impl Available {
+    pub fn is_empty(&self) -> bool {
+        self.binary.is_none() && self.themes.is_empty()
+    }
+
+    /// The same updates as `other`, ignoring when each was checked.
+    pub fn same_as(&self, other: &Available) -> bool {
+        self.binary == other.binary && self.themes == other.themes
+    }
+
+    /// One line for `status` and the notification.
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(version) = &self.binary {
+            parts.push(format!("ringring {version}"));
+        }
+        match self.themes.as_slice() {
+            [] => {}
+            [theme] => parts.push(format!("theme {theme}")),
+            themes => parts.push(format!("themes {}", themes.join(", "))),
+        }
+        parts.join(", ")
+    }
+}
+
+fn state_path() -> PathBuf {
+    paths::state_dir().join("updates.json")
+}
+
+/// What the last `check-updates` found.
+pub fn load() -> Option<Available> {
+    serde_json::from_str(&std::fs::read_to_string(state_path()).ok()?).ok()
+}
+
+pub fn save(available: &Available) -> std::io::Result<()> {
+    let path = state_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string(available)?)
+}
+
+/// Whether release tag `latest` (`v1.2.0` or `1.2.0`) is newer than `current`.
+pub fn newer(latest: &str, current: &str) -> bool {
+    let parse = |v: &str| -> Vec<u64> {
+        v.trim_start_matches('v').split(['.', '-', '+']).map_while(|part| part.parse().ok()).collect()
+    };
+    parse(latest) > parse(current)
+}
+
+/// Release asset listing every other asset's sha256, as `sha256sum` writes it.
+pub const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+
+/// A GitHub release: its tag and downloadable files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    pub tag: String,
+    /// Asset names and their download URLs.
+    pub assets: Vec<(String, String)>,
+}
+
+impl Release {
+    fn parse(release: &serde_json::Value) -> Result<Self, Box<dyn std::error::Error>> {
+        let tag = release["tag_name"].as_str().ok_or("release has no tag_name")?.to_string();
+        let assets = release["assets"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|a| Some((a["name"].as_str()?.to_string(), a["browser_download_url"].as_str()?.to_string())))
+            .collect();
+        Ok(Self { tag, assets })
+    }
+
+    pub fn asset_url(&self, name: &str) -> Option<&str> {
+        self.assets.iter().find(|(n, _)| n == name).map(|(_, url)| url.as_str())
+    }
+}
+
+/// The newest release at `url` (a GitHub "latest release" endpoint).
+pub fn fetch_release(url: &str) -> Result<Release, Box<dyn std::error::Error>> {
+    let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+    let release: serde_json::Value = serde_json::from_reader(agent.get(url).call()?.into_reader())?;
+    Release::parse(&release)
+}
+
+/// Tag of the newest release at `url`.
+pub fn latest_release(url: &str) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(fetch_release(url)?.tag)
+}
+
+/// The release binary built for `os` and `arch` (`std::env::consts` names).
+pub fn asset_for(os: &str, arch: &str) -> Option<String> {
+    match (os, arch) {
+        ("linux", "x86_64" | "aarch64") => Some(format!("ringring-linux-{arch}")),
+        ("macos", "aarch64") => Some(format!("ringring-macos-{arch}")),
+        _ => None,
+    }
+}
+
+/// The sha256 `sums` (a `SHA256SUMS` file) lists for `asset`.
+pub fn expected_sha256(sums: &str, asset: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let (hash, name) = line.split_once(char::is_whitespace)?;
+        (name.trim_start().trim_start_matches('*') == asset).then(|| hash.to_ascii_lowercase())
+    })
+}
+
+/// Themes whose `latest` archive hash differs from their receipt's. Themes
+/// without a recorded hash, or whose source can't tell, are skipped; lookup
+/// failures are returned alongside.
+pub fn outdated_themes(
+    receipts: &BTreeMap<String, Receipt>,
+    latest: impl Fn(&Receipt) -> Result<Option<String>, Box<dyn std::error::Error>>,
+) -> (Vec<String>, Vec<String>) {
+    let mut outdated = Vec::new();
+    let mut errors = Vec::new();
+    for (theme, receipt) in receipts {
+        let Some(installed) = &receipt.sha256 else { continue };
+        match latest(receipt) {
+            Ok(Some(sha)) if &sha != installed => outdated.push(theme.clone()),
+            Ok(_) => {}
+            Err(e) => errors.push(format!("{theme}: {e}")),
+        }
+    }
+    (outdated, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_compare_numerically() {
+        assert!(newer("v0.10.0", "0.9.3"));
+        assert!(newer("1.0.0", "0.2.0"));
+        assert!(!newer("v0.2.0", "0.2.0"));
+        assert!(!newer("v0.1.9", "0.2.0"));
+        assert!(newer("0.2.1-rc1", "0.2.0"));
+    }
+
+    #[test]
+    fn release_assets_and_checksums() {
+        let release = Release::parse(&serde_json::json!({"tag_name": "v0.4.0", "assets": [
+            {"name": "ringring-linux-x86_64", "browser_download_url": "https://example.com/a"},
+            {"name": "SHA256SUMS", "browser_download_url": "https://example.com/sums"}
+        ]}))
+        .unwrap();
+        assert_eq!(release.tag, "v0.4.0");
+        let asset = asset_for("linux", "x86_64").unwrap();
+        assert_eq!(release.asset_url(&asset), Some("https://example.com/a"));
+        assert_eq!(release.asset_url(CHECKSUMS_ASSET), Some("https://example.com/sums"));
+        assert_eq!(asset_for("macos", "x86_64"), None);
+        assert_eq!(asset_for("windows", "x86_64"), None);
+
+        let sums = "ABC123  ringring-linux-aarch64\ndef456 *ringring-linux-x86_64\n";
+        assert_eq!(expected_sha256(sums, "ringring-linux-aarch64").as_deref(), Some("abc123"));
+        assert_eq!(expected_sha256(sums, "ringring-linux-x86_64").as_deref(), Some("def456"));
+        assert_eq!(expected_sha256(sums, "ringring-macos-aarch64"), None);
+    }
+
+    #[test]
+    fn receipts_roundtrip_and_outdated_themes() {
+        let dir = tempfile::tempdir().unwrap();
+        let receipt = |source: &str, sha: Option<&str>| Receipt { source: source.into(), sha256: sha.map(str::to_string), installed: 1 };
+        record_receipt(dir.path(), "chime", receipt("chime", Some("aaa"))).unwrap();
+        record_receipt(dir.path(), "bells", receipt("bells", Some("bbb"))).unwrap();
+        record_receipt(dir.path(), "local", receipt("./local", None)).unwrap();
+        record_receipt(dir.path(), "gone", receipt("gone", Some("ccc"))).unwrap();
+        let receipts = load_receipts(dir.path());
+        assert_eq!(receipts.len(), 4);
+
+        let (outdated, errors) = outdated_themes(&receipts, |r| match r.source.as_str() {
+            "chime" => Ok(Some("aaa".into())),
+            "bells" => Ok(Some("new".into())),
+            "gone" => Err("404".into()),
+            _ => panic!("no hash recorded, shouldn't be looked up"),
+        });
+        assert_eq!(outdated, ["bells"]);
+        assert_eq!(errors, ["gone: 404"]);
+    }
+
+    #[test]
+    fn summary_lists_everything_available() {
+        let available = Available { checked: 0, binary: Some("v0.3.0".into()), themes: vec!["a".into(), "b".into()] };
+        assert_eq!(available.summary(), "ringring v0.3.0, themes a, b");
+        assert_eq!(Available { themes: vec!["a".into()], ..Default::default() }.summary(), "theme a");
+    }
+}
@@ -0,0 +1,248 @@
+//! `theme validate`: what's wrong with a theme before anyone hears it. The
+//! manifest and its split category files are parsed strictly (errors with
+//! line and column, fields ringring would ignore), categories and values are
+//! checked against what the hook uses, and every sound file must exist and
+//! decode.
+
+use std::path::Path;
+use std::time::Duration;
+
+use crate::manifest::{self, Category, Manifest, Sound, Variant};
+use crate::{buildinfo, config, locale, milestone, sandbox};
+
+/// The result for one theme directory.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Decoded sound files and their lengths, sorted.
+    pub sounds: Vec<(String, Duration)>,
+    pub problems: Vec<String>,
+    /// Things that work but shouldn't be left as they are.
+    pub warnings: Vec<String>,
+}
+
+/// Sound files a theme can have before `validate` warns about its size.
+pub const MAX_SOUNDS: usize = 1000;
+
+/// Categories a theme may define: those hook events play, and `milestone`.
+pub fn known_categories() -> Vec<&'static str> {
+    manifest::SKELETON_CATEGORIES.iter().map(|(name, ..)| *name).chain([milestone::CATEGORY]).collect()
+}
+
+/// Check the theme in `dir`. An unreadable or unparsable manifest is an
+/// error; everything else ends up in the report.
+pub fn theme(dir: &Path, limits: &sandbox::Limits) -> Result<Report, String> {
+    let path = dir.join("manifest.json");
+    let content = std::fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let mut manifest: Manifest = serde_json::from_str(&content).map_err(|e| format!("manifest.json: {e}"))?;
+    let raw: serde_json::Value = serde_json::from_str(&content).map_err(|e| format!("manifest.json: {e}"))?;
+
+    let mut report = Report { problems: unknown_fields(&raw), ..Default::default() };
+    let split = manifest::split_categories(dir);
+    for (name, path) in &split {
+        let at = format!("{}/{name}.json", manifest::SPLIT_DIR);
+        if manifest.categories.contains_key(name) {
+            report.problems.push(format!("category '{name}' is in manifest.json and {at}; {at} is ignored"));
+            continue;
+        }
+        let content = std::fs::read_to_string(path).map_err(|e| format!("{at}: {e}"))?;
+        let parsed = serde_json::from_str::<Category>(&content)
+            .and_then(|category| serde_json::from_str(&content).map(|raw: serde_json::Value| (category, raw)));
+        match parsed {
+            Ok((category, raw)) => {
+                unknown_category_fields(&mut report.problems, name, &raw);
+                manifest.categories.insert(name.clone(), category);
+            }
+            Err(e) => report.problems.push(format!("{at}: {e}")),
+        }
+    }
+    report.problems.extend(value_problems(&manifest));
+    let mut icons: Vec<(&String, &String)> = manifest.categories.iter().filter_map(|(name, c)| Some((name, c.icon.as_ref()?))).collect();
+    icons.sort();
+    for (name, icon) in icons {
+        if !manifest.icon(dir, name).is_some_and(|path| path.is_file()) {
+            report.problems.push(format!("icon '{icon}' of category '{name}' is missing or outside the theme"));
+        }
+    }
+    let files = manifest.files().len();
+    if files > MAX_SOUNDS {
+        let hint = if split.is_empty() {
+            format!("; moving big categories to {}/<name>.json lets events read only the one they play", manifest::SPLIT_DIR)
+        } else {
+            String::new()
+        };
+        report.warnings.push(format!("{files} sound files is more than {MAX_SOUNDS}{hint}"));
+    }
+    if let Some(language) = manifest.language.as_deref().filter(|l| !locale::supported(l)) {
+        report.warnings.push(format!(
+            "language '{language}' has no built-in notification texts (known: {}); the default ones are used",
+            locale::LANGUAGES.join(", ")
+        ));
+    }
+    let sounds_dir = dir.join("sounds");
+    for file in manifest.files() {
+        let path = sounds_dir.join(file);
+        if !path.is_file() {
+            report.problems.push(format!("sounds/{file} is missing"));
+            continue;
+        }
+        match sandbox::validate(&path, limits) {
+            Ok(length) => report.sounds.push((file.to_string(), length)),
+            Err(e) => report.problems.push(format!("sounds/{file}: {e}")),
+        }
+    }
+    Ok(report)
+}
+
+/// Fields serde would skip without a word, e.g. `sound` for `sounds`.
+fn unknown_fields(raw: &serde_json::Value) -> Vec<String> {
+    let mut problems = Vec::new();
+    check_fields(&mut problems, raw, config::fields_of::<Manifest>(), "at the top level");
+    for (name, category) in raw["categories"].as_object().into_iter().flatten() {
+        unknown_category_fields(&mut problems, name, category);
+    }
+    problems
+}
+
+fn unknown_category_fields(problems: &mut Vec<String>, name: &str, category: &serde_json::Value) {
+    check_fields(problems, category, config::fields_of::<Category>(), &format!("in category '{name}'"));
+    for sound in category["sounds"].as_array().into_iter().flatten() {
+        check_fields(problems, sound, config::fields_of::<Sound>(), &format!("in a sound of '{name}'"));
+    }
+    for (variant_name, variant) in category["variants"].as_object().into_iter().flatten() {
+        check_fields(problems, variant, config::fields_of::<Variant>(), &format!("in variant '{variant_name}' of '{name}'"));
+        for sound in variant["sounds"].as_array().into_iter().flatten() {
+            check_fields(problems, sound, config::fields_of::<Sound>(), &format!("in a sound of '{name}' variant '{variant_name}'"));
+        }
+    }
+}
+
+fn check_fields(problems: &mut Vec<String>, value: &serde_json::Value, known: &[&str], at: &str) {
+    for key in value.as_object().into_iter().flat_map(|o| o.keys()) {
+        if !known.contains(&key.as_str()) {
+            problems.push(format!("unknown field '{key}' {at}"));
+        }
+    }
+}
+
+/// Values that parse but the hook can't use as meant.
+fn value_problems(manifest: &Manifest) -> Vec<String> {
+    let mut problems = Vec::new();
+    if manifest.schema > buildinfo::MANIFEST_SCHEMA {
+        problems.push(format!("manifest schema {} is newer than this build's {}", manifest.schema, buildinfo::MANIFEST_SCHEMA));
+    }
+    if !(0.0..=1.0).contains(&manifest.volume) {
+        problems.push(format!("volume {} is outside 0.0–1.0", manifest.volume));
+    }
+    let known = known_categories();
+    let mut categories: Vec<(&String, &Category)> = manifest.categories.iter().collect();
+    categories.sort_by_key(|(name, _)| *name);
+    for (name, category) in categories {
+        if !known.contains(&name.as_str()) {
+            problems.push(format!("unknown category '{name}' (known: {})", known.join(", ")));
+        }
+        match (manifest.is_visual(), category.sounds.is_empty()) {
+            (false, true) => problems.push(format!("category '{name}' has no sounds")),
+            (true, false) => problems.push(format!("category '{name}' has sounds, but a visual theme plays none")),
+            _ => {}
+        }
+        if let Some(color) = category.color.as_deref().filter(|c| crate::light::parse_hex(c).is_none()) {
+            problems.push(format!("category '{name}' has color '{color}', which is not #rrggbb"));
+        }
+        let sounds = category.sounds.iter().chain(category.variants.values().flat_map(|v| &v.sounds));
+        let chances = category.chance.iter().chain(sounds.filter_map(|s| s.chance.as_ref()));
+        if let Some(chance) = chances.into_iter().find(|c| !(0.0..=1.0).contains(*c)) {
+            problems.push(format!("category '{name}' has a chance of {chance}, outside 0.0–1.0"));
+        }
+        for (variant_name, variant) in &category.variants {
+            if config::parse_duration(&variant.after).is_none() {
+                problems.push(format!("variant '{variant_name}' of '{name}' waits for '{}', which is not a duration like 2m", variant.after));
+            }
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_typos_unknown_categories_and_missing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sounds")).unwrap();
+        std::fs::write(
+            dir.path().join("manifest.json"),
+            r#"{"name": "t", "display_name": "T", "colour": "red", "categories": {
+                "complete": {"sound": [], "sounds": [{"file": "done.wav", "chance": 2}]},
+                "compelte": {"sounds": [{"file": "done.wav"}], "variants": {"later": {"after": "soon", "sounds": []}}}
+            }}"#,
+        )
+        .unwrap();
+        let report = theme(dir.path(), &sandbox::Limits::default()).unwrap();
+        assert_eq!(
+            report.problems,
+            [
+                "unknown field 'colour' at the top level",
+                "unknown field 'sound' in category 'complete'",
+                "unknown category 'compelte' (known: greeting, complete, permission, annoyed, acknowledge, resource_limit, milestone)",
+                "variant 'later' of 'compelte' waits for 'soon', which is not a duration like 2m",
+                "category 'complete' has a chance of 2, outside 0.0–1.0",
+                "sounds/done.wav is missing",
+            ]
+        );
+        assert!(report.sounds.is_empty());
+    }
+
+    #[test]
+    fn checks_split_categories_and_warns_about_size() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("sounds")).unwrap();
+        std::fs::write(dir.path().join("manifest.json"), r#"{"name": "t", "display_name": "T", "language": "pl"}"#).unwrap();
+        let split = dir.path().join(manifest::SPLIT_DIR);
+        std::fs::create_dir(&split).unwrap();
+        let sounds: Vec<serde_json::Value> = (0..=MAX_SOUNDS).map(|i| serde_json::json!({"file": format!("{i}.wav")})).collect();
+        std::fs::write(split.join("complete.json"), serde_json::json!({"sounds": sounds, "titel": "x"}).to_string()).unwrap();
+        std::fs::write(split.join("annoyed.json"), "{\n  \"sounds\": [,]\n}").unwrap();
+
+        let report = theme(dir.path(), &sandbox::Limits::default()).unwrap();
+        assert!(report.problems[0].starts_with("categories/annoyed.json: expected value at line 2"), "{:?}", report.problems);
+        assert_eq!(report.problems[1], "unknown field 'titel' in category 'complete'");
+        assert!(report.problems[2].ends_with(".wav is missing"));
+        assert_eq!(report.warnings[0], format!("{} sound files is more than {MAX_SOUNDS}", MAX_SOUNDS + 1));
+        assert!(report.warnings[1].starts_with("language 'pl' has no built-in notification texts"));
+    }
+
+    #[test]
+    fn visual_themes_need_no_sounds() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("icons")).unwrap();
+        std::fs::write(dir.path().join("icons/done.png"), b"png").unwrap();
+        std::fs::write(
+            dir.path().join("manifest.json"),
+            r##"{"name": "v", "display_name": "V", "type": "visual", "categories": {
+                "complete": {"title": "Done", "icon": "icons/done.png", "color": "#00ff00"},
+                "permission": {"icon": "icons/ask.png", "color": "red"},
+                "annoyed": {"sounds": [{"file": "a.wav"}]}
+            }}"##,
+        )
+        .unwrap();
+        let report = theme(dir.path(), &sandbox::Limits::default()).unwrap();
+        assert_eq!(
+            report.problems,
+            [
+                "category 'annoyed' has sounds, but a visual theme plays none",
+                "category 'permission' has color 'red', which is not #rrggbb",
+                "icon 'icons/ask.png' of category 'permission' is missing or outside the theme",
+                "sounds/a.wav is missing",
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_errors_have_a_position() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("manifest.json"), "{\n  \"name\": \"t\",\n}").unwrap();
+        let err = theme(dir.path(), &sandbox::Limits::default()).unwrap_err();
+        assert!(err.contains("line 3 column 1"), "{err}");
+    }
+}
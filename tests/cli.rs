@@ -0,0 +1,1068 @@
+//! End-to-end tests: run the real binary against a throwaway home directory,
+//! with the null player and headless notifications.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+struct Sandbox {
+    dir: TempDir,
+}
+
+impl Sandbox {
+    fn new() -> Self {
+        let sandbox = Self { dir: tempfile::tempdir().unwrap() };
+        let data = sandbox.data_dir();
+        std::fs::create_dir_all(&data).unwrap();
+        std::fs::write(data.join("config.json"), r#"{"headless": true}"#).unwrap();
+        sandbox
+    }
+
+    fn path(&self, rel: &str) -> PathBuf {
+        self.dir.path().join(rel)
+    }
+
+    fn data_dir(&self) -> PathBuf {
+        self.path("data/ringring")
+    }
+
+    fn log(&self) -> String {
+        std::fs::read_to_string(self.path("state/ringring/ringring.log")).unwrap_or_default()
+    }
+
+    fn ringring(&self) -> Command {
+        let root: &Path = self.dir.path();
+        let mut cmd = Command::cargo_bin("ringring").unwrap();
+        cmd.env_clear()
+            .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+            .env("HOME", root)
+            .env("XDG_CONFIG_HOME", root.join("config"))
+            .env("XDG_DATA_HOME", root.join("data"))
+            .env("XDG_STATE_HOME", root.join("state"))
+            .env("XDG_RUNTIME_DIR", root.join("run"))
+            .env("RINGRING_TMP_DIR", root.join("tmp"))
+            .env("RINGRING_PLAYER", "null")
+            .env("NO_COLOR", "1");
+        std::fs::create_dir_all(root.join("tmp")).unwrap();
+        cmd
+    }
+
+    fn hook(&self, event: &str) {
+        self.ringring().write_stdin(event).assert().success();
+    }
+}
+
+#[test]
+fn list_includes_builtin_theme() {
+    let sb = Sandbox::new();
+    sb.ringring().arg("list").assert().success().stdout(predicate::str::contains("earcon"));
+}
+
+#[test]
+fn list_json_describes_every_theme() {
+    let sb = Sandbox::new();
+    let theme = sb.data_dir().join("mini");
+    std::fs::create_dir_all(&theme).unwrap();
+    std::fs::write(
+        theme.join("manifest.json"),
+        r#"{"name": "mini", "display_name": "Mini", "categories": {"complete": {"sounds": [{"file": "a.wav", "line": "Done"}, {"file": "b.wav"}],
+            "variants": {"impatient": {"after": "2m", "sounds": [{"file": "c.wav"}]}}}}}"#,
+    )
+    .unwrap();
+    let out = sb.ringring().args(["list", "--json"]).assert().success().get_output().stdout.clone();
+    let themes: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    let names: Vec<&str> = themes.as_array().unwrap().iter().map(|t| t["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["earcon", "mini"]);
+    assert_eq!(themes[0]["builtin"], true);
+    let mini = &themes[1];
+    assert_eq!((mini["display_name"].as_str(), mini["builtin"].as_bool(), mini["sound_count"].as_u64()), (Some("Mini"), Some(false), Some(3)));
+    let complete = &mini["categories"]["complete"];
+    assert_eq!(complete["sound_count"], 3);
+    assert_eq!(complete["sounds"][0], serde_json::json!({"file": "a.wav", "line": "Done"}));
+    assert_eq!(complete["variants"]["impatient"]["after"], "2m");
+}
+
+#[test]
+fn split_categories_are_listed_in_pages_and_played() {
+    let sb = Sandbox::new();
+    let theme = sb.data_dir().join("big");
+    std::fs::create_dir_all(theme.join("categories")).unwrap();
+    std::fs::write(theme.join("manifest.json"), r#"{"name": "big", "display_name": "Big"}"#).unwrap();
+    let sounds: Vec<serde_json::Value> = (1..=120).map(|i| serde_json::json!({"file": format!("{i}.wav")})).collect();
+    std::fs::write(theme.join("categories/complete.json"), serde_json::json!({"sounds": sounds}).to_string()).unwrap();
+
+    let out = sb.ringring().args(["list", "--debug"]).output().unwrap();
+    let out = String::from_utf8(out.stdout).unwrap();
+    assert!(out.contains("- 50.wav\n    (1–50 of 120, page 1 of 3; --page 2 for more)"), "{out}");
+    assert!(!out.contains("- 51.wav"));
+    let out = sb.ringring().args(["list", "--debug", "--page", "3"]).output().unwrap();
+    let out = String::from_utf8(out.stdout).unwrap();
+    assert!(out.contains("- 101.wav") && out.contains("(101–120 of 120, page 3 of 3)"), "{out}");
+
+    std::fs::write(sb.data_dir().join("config.json"), r#"{"headless": true, "theme": "big"}"#).unwrap();
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "s1"}"#);
+    assert!(sb.log().contains("big/sounds/"), "log: {}", sb.log());
+}
+
+#[test]
+fn version_verbose_and_doctor_check_compatibility() {
+    let sb = Sandbox::new();
+    sb.ringring()
+        .args(["version", "--verbose"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("ringring {}", env!("CARGO_PKG_VERSION"))))
+        .stdout(predicate::str::contains("codecs           wav, mp3, ogg, flac"))
+        .stdout(predicate::str::contains("manifest schema  1"));
+    sb.ringring().arg("doctor").assert().success().stdout(predicate::str::contains("all compatible"));
+
+    let theme = sb.data_dir().join("future");
+    std::fs::create_dir_all(&theme).unwrap();
+    std::fs::write(
+        theme.join("manifest.json"),
+        r#"{"name": "future", "display_name": "Future", "schema": 3, "categories": {"complete": {"sounds": [{"file": "done.opus"}]}}}"#,
+    )
+    .unwrap();
+    std::fs::write(sb.data_dir().join("config.json"), r#"{"headless": true, "schema": 9}"#).unwrap();
+    sb.ringring()
+        .arg("doctor")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("future: manifest schema 3 is newer than this build's 1; no codec for done.opus"))
+        .stdout(predicate::str::contains("config schema 9 is newer"));
+}
+
+#[test]
+fn theme_new_scaffolds_a_manifest() {
+    let sb = Sandbox::new();
+    sb.ringring().args(["theme", "new", "mine"]).assert().success().stdout(predicate::str::contains("ringring test mine"));
+    assert!(sb.data_dir().join("mine/sounds").is_dir());
+    sb.ringring().arg("list").assert().success().stdout(predicate::str::contains("mine"));
+
+    sb.ringring().args(["theme", "new", "mine"]).assert().code(1).stderr(predicate::str::contains("already exists"));
+    sb.ringring().args(["theme", "new", "earcon"]).assert().code(1).stderr(predicate::str::contains("built-in"));
+}
+
+#[test]
+fn theme_validate_reports_missing_sounds() {
+    let sb = Sandbox::new();
+    sb.ringring().args(["theme", "new", "mine"]).assert().success();
+    sb.ringring()
+        .args(["theme", "validate", "mine"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("sounds/complete.wav is missing").and(predicate::str::contains("6 problems")));
+    sb.ringring().args(["theme", "validate", "earcon"]).assert().success();
+    sb.ringring().args(["theme", "validate", "nope"]).assert().code(1).stderr(predicate::str::contains("not found"));
+}
+
+#[test]
+fn test_command_plays_through_null_player() {
+    let sb = Sandbox::new();
+    sb.ringring().args(["test", "earcon", "--category", "complete"]).assert().success();
+    assert!(sb.log().contains("null player: embedded:complete.wav"), "log: {}", sb.log());
+
+    sb.ringring()
+        .args(["test", "earcon", "--category", "complete", "--count", "3", "--gap", "0"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("3×  100%  complete.wav"));
+
+    sb.ringring()
+        .args(["test", "nosuchtheme"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("no manifest found"));
+
+    sb.ringring()
+        .args(["test", "../data"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("invalid theme name '../data'"));
+}
+
+#[test]
+fn play_takes_a_file_or_theme_category() {
+    let sb = Sandbox::new();
+    sb.ringring().args(["play", "earcon:complete"]).assert().success().stdout("[complete] complete.wav\n");
+    assert!(sb.log().contains("null player: embedded:complete.wav (volume 0.60)"), "log: {}", sb.log());
+
+    let file = sb.path("ding.wav");
+    std::fs::write(&file, b"RIFF").unwrap();
+    sb.ringring().args(["play", file.to_str().unwrap(), "--volume", "0.3"]).assert().success();
+    assert!(sb.log().contains("ding.wav (volume 0.30)"), "log: {}", sb.log());
+
+    sb.ringring().args(["play", "earcon:nope"]).assert().code(1).stderr(predicate::str::contains("category 'nope' not found"));
+    sb.ringring().args(["play", "missing.wav"]).assert().code(1).stderr(predicate::str::contains("no such file"));
+}
+
+#[test]
+fn volume_scales_theme_sounds() {
+    let sb = Sandbox::new();
+    sb.ringring().args(["volume", "get"]).assert().success().stdout("100\n");
+    sb.ringring().args(["volume", "set", "50"]).assert().success().stdout("volume set to 50\n");
+    sb.ringring().args(["volume", "set", "50", "--theme", "earcon"]).assert().success();
+    sb.ringring().args(["volume", "get", "--theme", "earcon"]).assert().success().stdout("50\n");
+    sb.ringring().args(["volume", "set", "150"]).assert().code(1).stderr(predicate::str::contains("volume must be 0–100"));
+
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "v1"}"#);
+    assert!(sb.log().contains("null player: embedded:complete.wav (volume 0.15)"), "log: {}", sb.log());
+}
+
+#[test]
+fn history_shows_what_the_hook_did() {
+    let sb = Sandbox::new();
+    sb.ringring().arg("history").assert().success().stdout(predicate::str::contains("no events recorded"));
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "h1"}"#);
+    sb.hook(r#"{"hook_event_name": "Notification", "notification_type": "idle_prompt", "session_id": "h1"}"#);
+
+    sb.ringring()
+        .arg("history")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("| Stop | - | earcon | complete | complete.wav | notified |"));
+    let out = sb.ringring().args(["history", "-n", "1", "--json"]).assert().success().get_output().stdout.clone();
+    let rows: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 1);
+    assert_eq!(rows[0]["notification_type"], "idle_prompt");
+}
+
+#[test]
+fn simulate_runs_the_hook_pipeline() {
+    let sb = Sandbox::new();
+    sb.ringring()
+        .args(["simulate", "Notification", "--type", "idle_prompt", "--session-id", "sim", "--cwd", "/work/app"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""notification_type":"idle_prompt""#))
+        .stdout(predicate::str::contains("did: theme=earcon category=annoyed sound=annoyed.wav notified=true"));
+    assert!(sb.log().contains("null player: embedded:annoyed.wav"), "log: {}", sb.log());
+
+    sb.ringring()
+        .args(["simulate", "Stop", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("would do: theme=earcon category=complete"));
+}
+
+#[test]
+fn first_completion_of_the_day_is_a_milestone() {
+    let sb = Sandbox::new();
+    std::fs::write(sb.data_dir().join("config.json"), r#"{"headless": true, "milestones": {"counts": [1]}}"#).unwrap();
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "m1", "cwd": "/work/app"}"#);
+    assert!(sb.log().contains("milestone: 1 completed today"), "log: {}", sb.log());
+    assert!(sb.log().contains("First task of the day done"), "log: {}", sb.log());
+
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "m1", "cwd": "/work/app", "message": "again"}"#);
+    assert_eq!(sb.log().matches("milestone:").count(), 1, "log: {}", sb.log());
+}
+
+#[test]
+fn captured_events_are_redacted_and_summarized() {
+    let sb = Sandbox::new();
+    sb.ringring().args(["events", "summarize"]).assert().code(1).stderr(predicate::str::contains("capture_events"));
+
+    std::fs::write(sb.data_dir().join("config.json"), r#"{"headless": true, "capture_events": true}"#).unwrap();
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "c1", "cwd": "/work/secret"}"#);
+    sb.hook(r#"{"hook_event_name": "Notification", "notification_type": "tea_break", "session_id": "c1", "message": "Brew time"}"#);
+    let capture = std::fs::read_to_string(sb.path("state/ringring/capture.jsonl")).unwrap();
+    assert!(capture.contains(r#""cwd":"<12 chars>""#) && !capture.contains("secret"), "capture: {capture}");
+
+    sb.ringring()
+        .args(["events", "summarize"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 events in"))
+        .stdout(predicate::str::is_match(r"tea_break +1  new").unwrap())
+        .stderr(predicate::str::contains("generic mapping"));
+}
+
+#[test]
+fn recorded_payloads_replay_through_the_pipeline() {
+    let sb = Sandbox::new();
+    std::fs::write(sb.data_dir().join("config.json"), r#"{"headless": true, "record_events": "hooks.jsonl"}"#).unwrap();
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "r1", "cwd": "/work/secret"}"#);
+    sb.hook(r#"{"hook_event_name": "Notification", "notification_type": "tea_break", "session_id": "r1", "message": "Brew time"}"#);
+    let record = sb.data_dir().join("hooks.jsonl");
+    let text = std::fs::read_to_string(&record).unwrap();
+    assert!(text.contains("/work/secret") && text.contains("Brew time"), "record: {text}");
+
+    let played = sb.log().matches("null player:").count();
+    sb.ringring()
+        .args(["replay", "--dry-run"])
+        .arg(&record)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 ").and(predicate::str::contains(" Stop\n")))
+        .stdout(predicate::str::contains("2 ").and(predicate::str::contains(" Notification\n")))
+        .stdout(predicate::str::contains("would do:"));
+    assert_eq!(sb.log().matches("null player:").count(), played);
+
+    sb.ringring()
+        .args(["replay", "--last", "1"])
+        .arg(&record)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 ").and(predicate::str::contains("replayed:")))
+        .stdout(predicate::str::contains(" Stop\n").not());
+    sb.ringring().args(["replay", "--id", "x"]).arg(&record).assert().code(1).stderr(predicate::str::contains("--id"));
+}
+
+#[test]
+fn tail_shows_recent_events_with_decisions() {
+    let sb = Sandbox::new();
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "t1", "cwd": "/work/app"}"#);
+    sb.ringring()
+        .args(["tail", "-n", "1", "--no-follow"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(" Stop  session t1  /work/app\n"))
+        .stdout(predicate::str::contains("  theme    earcon (fallback; 'peon' is not installed)\n"))
+        .stdout(predicate::str::contains("  category complete\n"))
+        .stderr(predicate::str::contains("following").not());
+}
+
+#[test]
+fn theme_stats_counts_plays_per_sound() {
+    let sb = Sandbox::new();
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "s1", "cwd": "/work/app"}"#);
+    sb.ringring()
+        .args(["theme", "stats", "earcon"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("| complete | complete.wav | 1 |"))
+        .stdout(predicate::str::contains("| permission | permission.wav | never (permission never went off) |"))
+        .stderr(predicate::str::contains("sounds never played"));
+}
+
+#[test]
+fn stop_event_plays_and_tracks_session() {
+    let sb = Sandbox::new();
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "s1", "cwd": "/work/app"}"#);
+    assert!(sb.log().contains("null player: embedded:complete.wav"), "log: {}", sb.log());
+    assert!(sb.log().contains("notification:"), "log: {}", sb.log());
+
+    sb.ringring()
+        .arg("sessions")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("`s1` | app | earcon | Stop"));
+
+    sb.hook(r#"{"hook_event_name": "SessionEnd", "session_id": "s1", "cwd": "/work/app"}"#);
+    sb.ringring().arg("sessions").assert().success().stdout("no active sessions\n");
+}
+
+#[test]
+fn muted_session_stays_silent() {
+    let sb = Sandbox::new();
+    sb.ringring().args(["mute", "--session", "s2"]).assert().success();
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "s2"}"#);
+    assert!(!sb.log().contains("null player"), "log: {}", sb.log());
+
+    sb.ringring().args(["unmute", "--session", "s2"]).assert().success();
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "s2"}"#);
+    assert!(sb.log().contains("null player"), "log: {}", sb.log());
+}
+
+#[test]
+fn global_mute_silences_every_session_until_unmuted() {
+    let sb = Sandbox::new();
+    sb.ringring().args(["mute", "--for", "1h"]).assert().success().stdout(predicate::str::contains("for 1h"));
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "s1"}"#);
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "s2"}"#);
+    assert!(!sb.log().contains("null player"), "log: {}", sb.log());
+    sb.ringring().arg("status").assert().success().stdout(predicate::str::contains("| Global mute | sounds, for "));
+
+    sb.ringring().arg("unmute").assert().success().stdout(predicate::str::contains("unmuted"));
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "s3"}"#);
+    assert!(sb.log().contains("null player"), "log: {}", sb.log());
+
+    // Already over: cleared on the next event
+    std::fs::write(sb.path("run/ringring/mute.json"), r#"{"until": 1}"#).unwrap();
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "s4"}"#);
+    assert!(!sb.path("run/ringring/mute.json").exists());
+}
+
+#[test]
+fn pending_lists_waiting_sessions() {
+    let sb = Sandbox::new();
+    sb.hook(r#"{"hook_event_name": "PermissionRequest", "session_id": "s3", "cwd": "/work/lib"}"#);
+    sb.ringring()
+        .args(["pending", "--json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""reason":"permission","session_id":"s3""#));
+
+    sb.hook(r#"{"hook_event_name": "UserPromptSubmit", "session_id": "s3", "cwd": "/work/lib"}"#);
+    sb.ringring().args(["pending", "--json"]).assert().success().stdout("[]\n");
+}
+
+#[test]
+fn unseen_notifications_are_counted_until_the_next_prompt() {
+    let sb = Sandbox::new();
+    let titles = || -> Vec<String> {
+        sb.log().lines().filter_map(|l| l.split_once("notification: ")).map(|(_, n)| n.to_string()).collect()
+    };
+    sb.hook(r#"{"hook_event_name": "Notification", "session_id": "s7", "notification_type": "permission_prompt"}"#);
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "s7"}"#);
+    sb.hook(r#"{"hook_event_name": "Notification", "session_id": "s7", "notification_type": "idle_prompt"}"#);
+    let seen = titles();
+    assert_eq!(seen.len(), 3, "log: {}", sb.log());
+    assert!(!seen[0].starts_with('[') && seen[1].starts_with("[2] ") && seen[2].starts_with("[3] "), "{seen:?}");
+
+    sb.hook(r#"{"hook_event_name": "UserPromptSubmit", "session_id": "s7"}"#);
+    sb.hook(r#"{"hook_event_name": "Notification", "session_id": "s7", "notification_type": "permission_prompt", "message": "again"}"#);
+    let seen = titles();
+    assert_eq!(seen.len(), 4, "log: {}", sb.log());
+    assert!(!seen[3].starts_with('['), "{seen:?}");
+}
+
+#[test]
+fn double_fired_notification_plays_once() {
+    let sb = Sandbox::new();
+    let event = r#"{"hook_event_name": "Notification", "session_id": "s6", "notification_type": "idle_prompt", "message": "Claude is waiting for your input"}"#;
+    sb.hook(event);
+    sb.hook(event);
+    assert_eq!(sb.log().matches("null player").count(), 1, "log: {}", sb.log());
+}
+
+#[test]
+fn disabled_event_is_ignored() {
+    let sb = Sandbox::new();
+    std::fs::write(sb.data_dir().join("config.json"), r#"{"headless": true, "events": {"Stop": false}}"#).unwrap();
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "s4"}"#);
+    assert!(sb.log().is_empty(), "log: {}", sb.log());
+}
+
+#[test]
+fn pin_applies_to_git_checkout() {
+    let sb = Sandbox::new();
+    let repo = sb.path("repo");
+    std::fs::create_dir_all(repo.join(".git")).unwrap();
+    std::fs::create_dir_all(repo.join("src")).unwrap();
+
+    sb.ringring().current_dir(repo.join("src")).args(["pin", "earcon"]).assert().success();
+    sb.ringring()
+        .arg("pins")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("| `{}` | earcon | ok |", repo.display())));
+
+    sb.ringring().current_dir(repo.join("src")).arg("unpin").assert().success();
+    sb.ringring().arg("pins").assert().success().stdout("no workspace pins\n");
+    sb.ringring().current_dir(&repo).arg("unpin").assert().failure().stderr(predicate::str::contains("not pinned"));
+}
+
+#[test]
+fn theme_pin_takes_a_path() {
+    let sb = Sandbox::new();
+    let lib = sb.path("code/lib");
+    std::fs::create_dir_all(&lib).unwrap();
+    std::fs::create_dir_all(sb.path("code/app")).unwrap();
+
+    sb.ringring()
+        .current_dir(sb.path("code/app"))
+        .args(["theme", "pin", "--path", "../lib/./", "earcon"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("pinned 'earcon' to {}\n", lib.display())));
+    sb.ringring().arg("pins").assert().success().stdout(predicate::str::contains(format!("| `{}` | earcon | ok |", lib.display())));
+    sb.ringring().args(["theme", "pin", "--path", "~/missing", "earcon"]).assert().failure().stderr(predicate::str::contains("not a directory"));
+
+    sb.ringring().args(["theme", "unpin", "--path", "~/code/lib"]).assert().success();
+    sb.ringring().arg("pins").assert().success().stdout("no workspace pins\n");
+}
+
+#[test]
+fn quiet_workspace_notifies_without_sound() {
+    let sb = Sandbox::new();
+    let repo = sb.path("work");
+    std::fs::create_dir_all(repo.join(".git")).unwrap();
+    sb.ringring().current_dir(&repo).args(["pin", "--quiet"]).assert().success();
+    sb.ringring().arg("pins").assert().success().stdout(predicate::str::contains("| - | ok, quiet |"));
+
+    let cwd = repo.display();
+    sb.hook(&format!(r#"{{"hook_event_name": "Stop", "session_id": "q1", "cwd": "{cwd}"}}"#));
+    assert!(sb.log().contains("notification:"), "log: {}", sb.log());
+    assert!(!sb.log().contains("null player"), "log: {}", sb.log());
+}
+
+#[test]
+fn routes_limit_backends_per_workspace() {
+    let sb = Sandbox::new();
+    let config = r#"{"headless": true, "routes": [{"workspace": "/work/**", "backends": ["log"]}, {"category": "complete", "backends": []}]}"#;
+    std::fs::write(sb.data_dir().join("config.json"), config).unwrap();
+    let event = sb.path("stop.json");
+    std::fs::write(&event, r#"{"hook_event_name": "Stop", "cwd": "/work/app"}"#).unwrap();
+    sb.ringring()
+        .args(["routes", "test", event.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("| 1 | `/work/**` | any | log | match |"))
+        .stdout(predicate::str::contains("| 2 | any | complete | none | not reached |"))
+        .stdout(predicate::str::contains("sends to  log\nskips     terminal"));
+
+    // Elsewhere the second rule swallows the notification
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "r1", "cwd": "/home/app"}"#);
+    assert!(!sb.log().contains("notification:"), "log: {}", sb.log());
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "r2", "cwd": "/work/app"}"#);
+    assert!(sb.log().contains("notification:"), "log: {}", sb.log());
+}
+
+#[test]
+fn stats_suggests_and_applies_cooldowns() {
+    let sb = Sandbox::new();
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+    let history: String = (0..60)
+        .map(|i| {
+            let record = serde_json::json!({
+                "id": i.to_string(),
+                "timestamp": now - 3600 * 24 + i * 200,
+                "input": {"hook_event_name": "Notification", "notification_type": "idle_prompt"},
+                "notified": true,
+            });
+            format!("{record}\n")
+        })
+        .collect();
+    std::fs::create_dir_all(sb.path("state/ringring")).unwrap();
+    std::fs::write(sb.path("state/ringring/history.jsonl"), history).unwrap();
+
+    sb.ringring()
+        .args(["stats", "--suggest"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("| annoyed | 60 | 0 | 60 | 0 | idle_prompt |"))
+        .stdout(predicate::str::contains("idle_prompt triggered 60 times this week — consider a cooldown of 5m for annoyed"));
+    sb.ringring().args(["stats", "--apply"]).write_stdin("n\n").assert().success().stdout(predicate::str::contains("unchanged"));
+    sb.ringring().args(["stats", "--apply"]).write_stdin("y\n").assert().success();
+    let config = std::fs::read_to_string(sb.data_dir().join("config.json")).unwrap();
+    assert!(config.contains(r#""annoyed": "5m""#), "config: {config}");
+
+    // Only the first idle prompt within the cooldown plays
+    for message in ["first", "second"] {
+        sb.hook(&format!(r#"{{"hook_event_name": "Notification", "session_id": "c1", "notification_type": "idle_prompt", "message": "{message}"}}"#));
+    }
+    assert_eq!(sb.log().matches("null player").count(), 1, "log: {}", sb.log());
+}
+
+#[test]
+fn threshold_sends_meta_alert_once() {
+    let sb = Sandbox::new();
+    let config = r#"{"headless": true, "thresholds": [{"category": "permission", "count": 2, "window": "15m"}]}"#;
+    std::fs::write(sb.data_dir().join("config.json"), config).unwrap();
+    for session in ["t1", "t2", "t3"] {
+        sb.hook(&format!(r#"{{"hook_event_name": "PermissionRequest", "session_id": "{session}"}}"#));
+    }
+    let alert = "2 permission requests in 15m — maybe adjust allowed tools";
+    assert_eq!(sb.log().matches(&format!("threshold: {alert}")).count(), 1, "log: {}", sb.log());
+    assert!(sb.log().contains(&format!("notification: ringring: {alert}")), "log: {}", sb.log());
+}
+
+#[test]
+fn data_dir_flag_isolates_config() {
+    let sb = Sandbox::new();
+    let other = sb.path("sandbox");
+    std::fs::create_dir_all(&other).unwrap();
+    std::fs::write(other.join("config.json"), r#"{"headless": true, "events": {"Stop": false}}"#).unwrap();
+
+    sb.ringring()
+        .args(["--data-dir", other.to_str().unwrap()])
+        .write_stdin(r#"{"hook_event_name": "Stop", "session_id": "s5"}"#)
+        .assert()
+        .success();
+    assert!(sb.log().is_empty(), "log: {}", sb.log());
+
+    sb.ringring().args(["--data-dir", other.to_str().unwrap(), "mode", "random"]).assert().success();
+    let config = std::fs::read_to_string(other.join("config.json")).unwrap();
+    assert!(config.contains(r#""mode": "random""#), "{config}");
+    let real = std::fs::read_to_string(sb.data_dir().join("config.json")).unwrap();
+    assert!(!real.contains("mode"), "{real}");
+}
+
+#[test]
+fn unreadable_data_dir_degrades_to_builtin_theme() {
+    let sb = Sandbox::new();
+    let missing = sb.path("gone");
+    sb.ringring()
+        .env("RINGRING_DATA_DIR", &missing)
+        .write_stdin(r#"{"hook_event_name": "Stop", "session_id": "d1"}"#)
+        .assert()
+        .success();
+    assert!(sb.log().contains("degraded: data dir"), "log: {}", sb.log());
+    assert!(sb.log().contains("null player: embedded:complete.wav"), "log: {}", sb.log());
+    sb.ringring()
+        .env("RINGRING_DATA_DIR", &missing)
+        .arg("doctor")
+        .assert()
+        .stdout(predicate::str::contains("does not exist; hooks use the built-in theme"))
+        .stdout(predicate::str::is_match(r"degraded +hook events since").unwrap());
+
+    // Back to normal clears it
+    sb.hook(r#"{"hook_event_name": "Stop", "session_id": "d1"}"#);
+    sb.ringring().arg("doctor").assert().stdout(predicate::str::contains("degraded").not());
+}
+
+#[test]
+fn config_export_imports_on_another_machine() {
+    let from = Sandbox::new();
+    let src = from.path("src/chime");
+    std::fs::create_dir_all(src.join("sounds")).unwrap();
+    std::fs::write(src.join("manifest.json"), r#"{"name": "chime", "display_name": "Chime", "categories": {}}"#).unwrap();
+    from.ringring().args(["theme", "install", src.to_str().unwrap()]).assert().success();
+    from.ringring().args(["pin", "chime", "--path", src.to_str().unwrap()]).assert().success();
+
+    let export = from.path("setup.json");
+    from.ringring()
+        .args(["config", "export", "--output", export.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 themes (1 with a source"));
+
+    let to = Sandbox::new();
+    to.ringring().args(["config", "import", "-"]).assert().code(1).stderr(predicate::str::contains("--yes"));
+    to.ringring()
+        .args(["config", "import", "--yes", export.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("theme chime from"))
+        .stdout(predicate::str::contains("installed theme 'chime'"));
+    assert!(to.data_dir().join("chime/manifest.json").is_file());
+    let config: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(to.data_dir().join("config.json")).unwrap()).unwrap();
+    assert_eq!(config["workspaces"].as_object().unwrap().values().collect::<Vec<_>>(), ["chime"]);
+}
+
+#[test]
+fn packed_theme_installs_and_verifies() {
+    let sb = Sandbox::new();
+    let src = sb.path("src/chime");
+    std::fs::create_dir_all(src.join("sounds")).unwrap();
+    std::fs::write(
+        src.join("manifest.json"),
+        r#"{"name": "chime", "display_name": "Chime", "categories": {"complete": {"sounds": [{"file": "sounds/ding.wav"}]}}}"#,
+    )
+    .unwrap();
+    std::fs::write(src.join("sounds/ding.wav"), b"RIFF").unwrap();
+
+    let zip = sb.path("chime.zip");
+    sb.ringring().args(["theme", "pack", src.to_str().unwrap(), "--output", zip.to_str().unwrap()]).assert().success();
+    sb.ringring()
+        .args(["theme", "verify", zip.to_str().unwrap(), "--against", src.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("canonical pack"));
+    sb.ringring()
+        .args(["theme", "install", zip.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("| complete | 1 |"))
+        .stderr(predicate::str::contains("no sounds for greeting, permission, annoyed"));
+    assert!(sb.data_dir().join("chime/sounds/ding.wav").exists());
+
+    // Packing the installed theme by name gives the same zip back
+    let repacked = sb.path("repacked.zip");
+    let sha256 = rust_ringring::publish::sha256_hex(&std::fs::read(&zip).unwrap());
+    sb.ringring()
+        .args(["theme", "pack", "chime", "--output", repacked.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("sha256 {sha256}")));
+    assert_eq!(std::fs::read(&repacked).unwrap(), std::fs::read(&zip).unwrap());
+
+    std::fs::write(src.join("sounds/ding.wav"), b"RIFF2").unwrap();
+    sb.ringring()
+        .args(["theme", "verify", zip.to_str().unwrap(), "--against", src.to_str().unwrap()])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("changed: sounds/ding.wav"));
+}
+
+#[test]
+fn theme_remove_guards_default_and_drops_pins() {
+    let sb = Sandbox::new();
+    let theme = sb.data_dir().join("chime");
+    std::fs::create_dir_all(&theme).unwrap();
+    std::fs::write(theme.join("manifest.json"), r#"{"name": "chime", "display_name": "Chime", "categories": {}}"#).unwrap();
+    let work = sb.path("work");
+    std::fs::create_dir_all(&work).unwrap();
+    sb.ringring().current_dir(&work).args(["pin", "chime"]).assert().success();
+    std::fs::write(
+        sb.data_dir().join("config.json"),
+        std::fs::read_to_string(sb.data_dir().join("config.json")).unwrap().replacen('{', r#"{"theme": "chime","#, 1),
+    )
+    .unwrap();
+
+    sb.ringring().args(["theme", "remove", "nope"]).assert().failure().stderr(predicate::str::contains("not installed"));
+    sb.ringring()
+        .args(["theme", "remove", "chime"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is the configured theme"));
+    assert!(theme.exists());
+
+    sb.ringring()
+        .args(["theme", "remove", "chime", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("unpinned {}", work.display())))
+        .stderr(predicate::str::contains("still the configured theme"));
+    assert!(!theme.exists());
+    sb.ringring().arg("pins").assert().success().stdout("no workspace pins\n");
+}
+
+#[test]
+fn theme_set_writes_config_and_migrates_legacy_file() {
+    let sb = Sandbox::new();
+    let config = || std::fs::read_to_string(sb.data_dir().join("config.json")).unwrap();
+    sb.ringring().args(["theme", "set", "missing"]).assert().failure().stderr(predicate::str::contains("no manifest"));
+
+    sb.ringring().args(["theme", "set", "earcon"]).assert().success();
+    assert!(config().contains(r#""theme": "earcon""#) && config().contains(r#""headless": true"#), "{}", config());
+
+    std::fs::write(sb.data_dir().join("theme"), "earcon\n").unwrap();
+    sb.ringring()
+        .args(["theme", "set", "--migrate"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("removed legacy theme file"));
+    assert!(!sb.data_dir().join("theme").exists());
+    sb.ringring().args(["theme", "set", "--migrate"]).assert().failure().stderr(predicate::str::contains("no legacy theme file"));
+}
+
+#[test]
+fn invalid_mode_fails() {
+    let sb = Sandbox::new();
+    sb.ringring().args(["mode", "loud"]).assert().failure().stderr(predicate::str::contains("ringring mode"));
+    sb.ringring().args(["mode", "sequential"]).assert().success();
+    let config = std::fs::read_to_string(sb.data_dir().join("config.json")).unwrap();
+    assert!(config.contains(r#""mode": "sequential""#), "{config}");
+}
+
+#[test]
+fn config_get_set_unset() {
+    let sb = Sandbox::new();
+    sb.ringring().args(["config", "set", "random_pool", r#"["earcon"]"#]).assert().success();
+    sb.ringring().args(["config", "set", "workspaces./src/api", "earcon"]).assert().success();
+    sb.ringring()
+        .args(["config", "set", "random_pool", "earcon"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("ringring config set: random_pool: invalid type"));
+    sb.ringring().args(["config", "set", "volumes", "1"]).assert().failure().stderr(predicate::str::contains("unknown key 'volumes'"));
+
+    sb.ringring().args(["config", "get", "workspaces./src/api"]).assert().success().stdout("earcon\n");
+    sb.ringring().args(["config", "get", "random_pool"]).assert().success().stdout("[\n  \"earcon\"\n]\n");
+    sb.ringring().args(["config", "unset", "random_pool"]).assert().success().stdout(predicate::str::contains("random_pool removed"));
+    sb.ringring().args(["config", "get", "random_pool"]).assert().failure().stderr(predicate::str::contains("'random_pool' is not set"));
+    let config = std::fs::read_to_string(sb.data_dir().join("config.json")).unwrap();
+    assert!(config.contains(r#""headless": true"#) && config.contains(r#""/src/api": "earcon""#), "{config}");
+}
+
+#[test]
+fn status_explains_the_theme() {
+    let sb = Sandbox::new();
+    let work = sb.path("work");
+    std::fs::create_dir_all(&work).unwrap();
+    sb.ringring().current_dir(&work).args(["pin", "earcon"]).assert().success();
+    sb.ringring()
+        .current_dir(&work)
+        .args(["status", "--session-id", "s1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("| Session | `s1` |"))
+        .stdout(predicate::str::contains("| Theme | earcon |"))
+        .stdout(predicate::str::contains(format!("| Chosen by | workspace pin for {} |", work.display())))
+        .stdout(predicate::str::contains("| Manifest | built in |"));
+
+    sb.ringring()
+        .args(["status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("| Chosen by | fallback |"))
+        .stdout(predicate::str::contains("| Manifest | missing or invalid, the hook plays earcon |"));
+}
+
+#[test]
+fn restart_in_the_same_terminal_keeps_the_random_theme() {
+    let sb = Sandbox::new();
+    sb.ringring().args(["theme", "new", "chime"]).assert().success();
+    std::fs::write(
+        sb.data_dir().join("config.json"),
+        r#"{"headless": true, "mode": "random", "random_pool": ["earcon", "chime"]}"#,
+    )
+    .unwrap();
+    let work = sb.path("work");
+    std::fs::create_dir_all(&work).unwrap();
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+    let session = |id: &str, theme: Option<&str>| {
+        serde_json::json!({"id": id, "cwd": work.to_str().unwrap(), "updated": now - 60, "theme": theme, "tty": 34816}).to_string()
+    };
+    let run = sb.path("run/ringring");
+    std::fs::create_dir_all(run.join("ended-sessions")).unwrap();
+    std::fs::create_dir_all(run.join("sessions")).unwrap();
+    std::fs::write(run.join("ended-sessions/old.json"), session("old", Some("chime"))).unwrap();
+    std::fs::write(run.join("sessions/new.json"), session("new", None)).unwrap();
+
+    sb.ringring()
+        .current_dir(&work)
+        .args(["status", "new"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("| Theme | chime |"))
+        .stdout(predicate::str::contains("| Chosen by | previous session in this terminal |"));
+
+    std::fs::write(
+        sb.data_dir().join("config.json"),
+        r#"{"headless": true, "mode": "random", "random_pool": ["earcon", "chime"], "sticky_theme_window": "0"}"#,
+    )
+    .unwrap();
+    sb.ringring().current_dir(&work).args(["status", "new"]).assert().success().stdout(predicate::str::contains("| Chosen by | random_pool |"));
+}
+
+#[test]
+fn unknown_command_is_an_error() {
+    let sb = Sandbox::new();
+    sb.ringring().arg("lsit").assert().code(2).stderr(predicate::str::contains("unknown command 'lsit'"));
+    sb.ringring().args(["list", "--help"]).assert().success().stdout(predicate::str::contains("--debug"));
+}
+
+#[test]
+fn install_honors_bin_dir_and_settings() {
+    let sb = Sandbox::new();
+    let bin = sb.path("opt/bin");
+    let settings = sb.path("claude/settings.json");
+    sb.ringring()
+        .args(["install", "--bin-dir", bin.to_str().unwrap(), "--settings", settings.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("+++ ").and(predicate::str::contains(r#"+    "Stop": ["#)))
+        .stderr(predicate::str::contains("pass --yes"));
+    assert!(!settings.exists());
+    sb.ringring()
+        .args(["install", "--yes", "--bin-dir", bin.to_str().unwrap(), "--settings", settings.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("registered hooks for SessionStart"));
+    assert!(sb.log().contains("install: changes to"), "log: {}", sb.log());
+    assert!(bin.join("ringring").is_file());
+    assert!(sb.path("claude/commands/ringring.md").is_file());
+    let v: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&settings).unwrap()).unwrap();
+    assert!(v["hooks"]["Stop"].is_array());
+    sb.ringring()
+        .args(["install", "--bin-dir", bin.to_str().unwrap(), "--settings", settings.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("hooks already registered"));
+}
+
+#[test]
+fn install_and_uninstall_reconcile_chosen_events() {
+    let sb = Sandbox::new();
+    let bin = sb.path("bin");
+    let settings = sb.path(".claude/settings.json");
+    let flags = ["--bin-dir", bin.to_str().unwrap()];
+    let hooked = || -> Vec<String> {
+        let v: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&settings).unwrap()).unwrap();
+        v["hooks"].as_object().unwrap().keys().cloned().collect()
+    };
+    let events = || -> serde_json::Value {
+        let v: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(sb.data_dir().join("config.json")).unwrap()).unwrap();
+        v["events"].clone()
+    };
+    sb.ringring().args(["install", "--yes", "--events", "Stop,Nope"]).args(flags).assert().code(2).stderr(predicate::str::contains("unknown hook event 'Nope'"));
+
+    sb.ringring().args(["install", "--yes", "--events", "Stop,SubagentStop,Notification"]).args(flags).assert().success();
+    assert_eq!(hooked(), ["Notification", "Stop", "SubagentStop"]);
+    assert_eq!(events()["SubagentStop"], true);
+    assert_eq!(events()["SessionStart"], false);
+    assert!(events().get("Stop").is_none());
+    // A plain re-install follows the recorded choice
+    sb.ringring().args(["install"]).args(flags).assert().success().stdout(predicate::str::contains("hooks already registered"));
+
+    sb.ringring().args(["uninstall", "--events", "SubagentStop"]).args(flags).assert().success();
+    assert_eq!(hooked(), ["Notification", "Stop"]);
+    assert_eq!(events()["SubagentStop"], false);
+    assert!(sb.path(".claude/commands/ringring.md").exists());
+}
+
+#[test]
+fn lost_hooks_are_reported_and_repaired() {
+    let sb = Sandbox::new();
+    let settings = sb.path(".claude/settings.json");
+    sb.ringring().args(["install", "--yes", "--bin-dir", sb.path("bin").to_str().unwrap()]).assert().success();
+    let mut v: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&settings).unwrap()).unwrap();
+    v["hooks"].as_object_mut().unwrap().remove("Stop");
+    std::fs::write(&settings, serde_json::to_string_pretty(&v).unwrap()).unwrap();
+
+    let event = r#"{"hook_event_name":"Notification","session_id":"s1","message":"hi"}"#;
+    sb.hook(event);
+    assert!(sb.log().contains("No ringring hook for Stop any more"), "log: {}", sb.log());
+    sb.hook(event);
+    assert_eq!(sb.log().matches("hooks: No ringring hook for Stop").count(), 1, "log: {}", sb.log());
+
+    sb.ringring().args(["install", "--repair", "--yes"]).assert().success().stdout(predicate::str::contains("re-added hooks for Stop"));
+    sb.ringring().args(["install", "--repair"]).assert().success().stdout(predicate::str::contains("no hooks missing"));
+}
+
+#[test]
+fn repair_restores_a_broken_install() {
+    use std::os::unix::fs::PermissionsExt;
+    let sb = Sandbox::new();
+    let bin_dir = sb.path("bin");
+    let bin = bin_dir.join("ringring");
+    let flags = ["--bin-dir", bin_dir.to_str().unwrap()];
+    sb.ringring().args(["install", "--yes"]).args(flags).assert().success();
+    sb.ringring().arg("repair").args(flags).assert().success();
+    sb.ringring().arg("repair").args(flags).assert().success().stdout(predicate::str::contains("nothing to repair"));
+
+    std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o644)).unwrap();
+    std::fs::remove_file(sb.path(".claude/commands/ringring.md")).unwrap();
+    std::fs::remove_file(sb.path(".local/share/applications/claude-code.desktop")).unwrap();
+    let settings = sb.path(".claude/settings.json");
+    let mut v: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&settings).unwrap()).unwrap();
+    v["hooks"].as_object_mut().unwrap().remove("Stop");
+    std::fs::write(&settings, serde_json::to_string_pretty(&v).unwrap()).unwrap();
+    sb.ringring()
+        .args(["repair", "--yes"])
+        .args(flags)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("made"))
+        .stdout(predicate::str::contains("re-added hooks for Stop"))
+        .stdout(predicate::str::contains("reinstalled /ringring command"))
+        .stdout(predicate::str::contains("claude-code.desktop"));
+    assert_eq!(std::fs::metadata(&bin).unwrap().permissions().mode() & 0o777, 0o755);
+
+    std::fs::write(&bin, "old build").unwrap();
+    sb.ringring().arg("repair").args(flags).assert().success().stdout(predicate::str::contains("replaced stale binary"));
+    std::fs::remove_file(&bin).unwrap();
+    sb.ringring().arg("repair").args(flags).assert().success().stdout(predicate::str::contains("installed missing binary"));
+    sb.ringring().arg("repair").args(flags).assert().success().stdout(predicate::str::contains("nothing to repair"));
+}
+
+#[test]
+fn assets_install_sets_up_a_packaged_binary() {
+    let sb = Sandbox::new();
+    sb.ringring().arg("list").assert().success().stderr(predicate::str::contains("run `ringring assets install`"));
+    sb.ringring().arg("list").assert().success().stderr(predicate::str::contains("assets install").not());
+
+    std::fs::remove_file(sb.data_dir().join("config.json")).unwrap();
+    sb.ringring()
+        .args(["assets", "install", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("with the earcon theme"))
+        .stdout(predicate::str::contains("registered hooks for"));
+    assert!(!sb.path(".local/bin/ringring").exists());
+    assert!(sb.path(".claude/commands/ringring.md").exists());
+    assert!(sb.path(".local/share/applications/claude-code.desktop").exists());
+    // Without a config.json under XDG_DATA_HOME the data dir falls back to ~/.local/share
+    let cfg: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(sb.path(".local/share/ringring/config.json")).unwrap()).unwrap();
+    assert_eq!(cfg["theme"], "earcon");
+
+    let fresh = Sandbox::new();
+    fresh.ringring().args(["assets", "install", "--yes"]).assert().success();
+    fresh.ringring().arg("list").assert().success().stderr(predicate::str::contains("isn't set up").not());
+}
+
+#[test]
+fn uninstall_removes_hooks_and_purges_data() {
+    let sb = Sandbox::new();
+    let bin = sb.path("bin");
+    let settings = sb.path(".claude/settings.json");
+    let flags = ["--bin-dir", bin.to_str().unwrap()];
+    sb.ringring().args(["install", "--yes"]).args(flags).assert().success();
+    sb.ringring().args(["mute", "--session", "s1"]).assert().success();
+
+    sb.ringring().arg("uninstall").args(flags).assert().success().stdout(predicate::str::contains("removed hooks for"));
+    let v: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&settings).unwrap()).unwrap();
+    assert_eq!(v["hooks"], serde_json::json!({}));
+    assert!(!sb.path(".claude/commands/ringring.md").exists());
+    assert!(!sb.path("tmp/.claude-mute-s1").exists());
+    assert!(bin.join("ringring").exists() && sb.data_dir().exists());
+
+    sb.ringring().arg("uninstall").args(flags).arg("--purge").assert().success();
+    assert!(!bin.join("ringring").exists());
+    assert!(!sb.data_dir().exists());
+}
+
+#[test]
+fn purge_asks_before_deleting_a_chosen_data_dir() {
+    let sb = Sandbox::new();
+    let music = sb.path("Music/sounds");
+    std::fs::create_dir_all(&music).unwrap();
+    std::fs::write(music.join("song.mp3"), "").unwrap();
+    let bin = sb.path("bin");
+    let flags = ["--bin-dir", bin.to_str().unwrap(), "--data-dir", music.to_str().unwrap()];
+
+    sb.ringring()
+        .arg("uninstall")
+        .args(flags)
+        .arg("--purge")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("song.mp3").and(predicate::str::contains("kept")));
+    assert!(music.join("song.mp3").exists());
+
+    sb.ringring().arg("uninstall").args(flags).args(["--purge", "--yes"]).assert().success();
+    assert!(!music.exists());
+}
+
+#[test]
+fn env_overrides_are_not_lost_to_a_running_daemon() {
+    let sb = Sandbox::new();
+    std::fs::write(sb.data_dir().join("config.json"), r#"{"headless": true, "player": "null"}"#).unwrap();
+    let root = sb.dir.path();
+    let mut daemon = std::process::Command::new(assert_cmd::cargo::cargo_bin("ringring"))
+        .arg("daemon")
+        .env_clear()
+        .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+        .env("HOME", root)
+        .env("XDG_CONFIG_HOME", root.join("config"))
+        .env("XDG_DATA_HOME", root.join("data"))
+        .env("XDG_STATE_HOME", root.join("state"))
+        .env("XDG_RUNTIME_DIR", root.join("run"))
+        .env("RINGRING_TMP_DIR", root.join("tmp"))
+        .spawn()
+        .unwrap();
+    let socket = sb.path("run/ringring/daemon.sock");
+    let waited = |done: &dyn Fn() -> bool| {
+        let start = std::time::Instant::now();
+        while !done() && start.elapsed() < std::time::Duration::from_secs(20) {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+    };
+    waited(&|| socket.exists());
+
+    // Sound turned off for this invocation only: the daemon must not play it
+    sb.ringring()
+        .env_remove("RINGRING_PLAYER")
+        .env("CLAUDE_SOUND_ENABLED", "0")
+        .write_stdin(r#"{"hook_event_name": "Stop", "session_id": "quiet"}"#)
+        .assert()
+        .success();
+    sb.ringring().env_remove("RINGRING_PLAYER").write_stdin(r#"{"hook_event_name": "Stop", "session_id": "loud"}"#).assert().success();
+    let history = || std::fs::read_to_string(sb.path("state/ringring/history.jsonl")).unwrap_or_default();
+    waited(&|| history().lines().count() == 2);
+    daemon.kill().unwrap();
+    daemon.wait().unwrap();
+
+    assert_eq!(history().lines().count(), 2, "log:\n{}", sb.log());
+    assert_eq!(sb.log().matches("null player: ").count(), 1, "log:\n{}", sb.log());
+}
+
+#[test]
+fn only_repeated_notifications_are_collapsed() {
+    let sb = Sandbox::new();
+    let notification = r#"{"hook_event_name": "Notification", "session_id": "s1", "notification_type": "idle_prompt", "message": "waiting"}"#;
+    let stop = r#"{"hook_event_name": "Stop", "session_id": "s1"}"#;
+    for event in [notification, notification, stop, stop] {
+        sb.hook(event);
+    }
+    let history = std::fs::read_to_string(sb.path("state/ringring/history.jsonl")).unwrap();
+    let skipped: Vec<Option<String>> = history
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["skipped"].as_str().map(String::from))
+        .collect();
+    assert_eq!(skipped, [None, Some("duplicate event".into()), None, None]);
+}
@@ -0,0 +1,126 @@
+use std::sync::{Arc, Mutex};
+
+use rust_ringring::{Clip, HookInput, Notifier, Player, RingringBuilder};
+
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+impl Recorder {
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut self.0.lock().unwrap())
+    }
+}
+
+impl Player for Recorder {
+    fn name(&self) -> &'static str {
+        "recorder"
+    }
+
+    fn play(&self, clip: Clip, _volume: f32) -> Result<(), Box<dyn std::error::Error>> {
+        self.0.lock().unwrap().push(format!("play {clip}"));
+        Ok(())
+    }
+
+    fn detach(&self) -> bool {
+        false
+    }
+
+    fn needs_device(&self) -> bool {
+        false
+    }
+}
+
+impl Notifier for Recorder {
+    fn name(&self) -> &'static str {
+        "recorder"
+    }
+
+    fn notify(&self, title: &str, _body: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.0.lock().unwrap().push(format!("notify {title}"));
+        Ok(())
+    }
+}
+
+fn event(json: &str) -> HookInput {
+    serde_json::from_str(json).unwrap()
+}
+
+/// Point the state and runtime dirs at a throwaway directory, once for the
+/// whole binary and before any test reads them.
+fn isolate() {
+    static ONCE: std::sync::Once = std::sync::Once::new();
+    ONCE.call_once(|| {
+        let root = tempfile::tempdir().unwrap().keep();
+        unsafe {
+            std::env::set_var("XDG_STATE_HOME", root.join("state"));
+            std::env::set_var("XDG_RUNTIME_DIR", root.join("run"));
+            std::env::set_var("RINGRING_TMP_DIR", &root);
+        }
+    });
+}
+
+#[test]
+fn embedded_engine_uses_custom_player_and_notifier() {
+    isolate();
+    let dir = tempfile::tempdir().unwrap();
+    let recorder = Recorder::default();
+    let config = serde_json::from_str(r#"{"badges": {"complete": "[done]"}}"#).unwrap();
+    let engine = RingringBuilder::new()
+        .data_dir(dir.path())
+        .config(config)
+        .player(recorder.clone())
+        .notifier(recorder.clone())
+        .stateless(true)
+        .build();
+
+    let outcome = engine.handle_event(&event(r#"{"hook_event_name": "Stop", "session_id": "embed"}"#)).unwrap();
+    assert_eq!(outcome.category.as_deref(), Some("complete"));
+    let calls = recorder.take();
+    assert!(calls.iter().any(|c| c.starts_with("notify [done] ")), "{calls:?}");
+    assert!(calls.iter().any(|c| c.starts_with("play ")), "{calls:?}");
+
+    // Stateless: nothing written to the data dir, and a repeat is not collapsed
+    assert!(std::fs::read_dir(dir.path()).unwrap().next().is_none());
+    engine.handle_event(&event(r#"{"hook_event_name": "Stop", "session_id": "embed"}"#)).unwrap();
+    assert!(!recorder.take().is_empty());
+}
+
+#[test]
+fn preview_plays_nothing() {
+    isolate();
+    let recorder = Recorder::default();
+    let engine = RingringBuilder::new()
+        .config(serde_json::from_str(r#"{"events": {"Stop": false}}"#).unwrap())
+        .player(recorder.clone())
+        .notifier(recorder.clone())
+        .stateless(true)
+        .build();
+    let outcome = engine.preview(&event(r#"{"hook_event_name": "Notification", "message": "hi"}"#)).unwrap();
+    assert!(outcome.skipped.is_none());
+    assert!(recorder.take().is_empty());
+    let outcome = engine.handle_event(&event(r#"{"hook_event_name": "Stop"}"#)).unwrap();
+    assert_eq!(outcome.skipped.as_deref(), Some("event disabled"));
+}
+
+#[test]
+fn handled_events_count_towards_milestones_and_thresholds() {
+    isolate();
+    let dir = tempfile::tempdir().unwrap();
+    let recorder = Recorder::default();
+    let engine = RingringBuilder::new()
+        .data_dir(dir.path())
+        .config(serde_json::from_str(r#"{"milestones": {"counts": [1]}, "thresholds": [{"category": "complete", "count": 2, "window": "1h"}]}"#).unwrap())
+        .player(recorder.clone())
+        .notifier(recorder.clone())
+        .build();
+    let stop = event(r#"{"hook_event_name": "Stop", "session_id": "milestones"}"#);
+
+    let sent = |calls: &[String], title: &str| calls.iter().any(|c| c.starts_with("notify ") && c.ends_with(title));
+    engine.preview(&stop).unwrap();
+    engine.handle_event(&stop).unwrap();
+    let calls = recorder.take();
+    assert!(sent(&calls, " Milestone") && !sent(&calls, " ringring"), "{calls:?}");
+    engine.handle_event(&stop).unwrap();
+    let calls = recorder.take();
+    assert!(!sent(&calls, " Milestone") && sent(&calls, " ringring"), "{calls:?}");
+}